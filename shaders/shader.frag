@@ -23,6 +23,7 @@ void main() {
 	outColor = mix(colorOnly, texColor, mixFactor.mixValue);
 
     vec3 lightDir = normalize(vec3(0.5, 1.0, 0.5));
-    float diffuse = max(dot(normalize(fragNormal), lightDir), 0.3);
+    // Lines and points carry a zero normal and are drawn unlit
+    float diffuse = length(fragNormal) > 0.0001 ? max(dot(normalize(fragNormal), lightDir), 0.3) : 1.0;
     outColor.rgb *= diffuse;
 }
\ No newline at end of file
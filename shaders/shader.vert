@@ -5,6 +5,7 @@ layout(location = 0) in vec3 inPosition;
 layout(location = 1) in vec2 inTexCoords;
 layout(location = 2) in vec3 inNormal;
 layout(location = 3) in vec3 inColor;
+layout(location = 4) in vec4 inTangent;
 
 // Uniforms : matrices MVP
 layout(binding = 0) uniform UniformBufferObject {
@@ -13,6 +14,11 @@ layout(binding = 0) uniform UniformBufferObject {
 	mat4 proj;
 } ubo;
 
+// Push constants : per-draw primitive settings
+layout(push_constant) uniform PrimitiveSettings {
+	float pointSize;
+} settings;
+
 // Output : to fragment shader
 layout(location = 0) out vec3 fragColor;
 layout(location = 1) out vec2 fragTextCoords;
@@ -22,6 +28,9 @@ void main() {
 	// MVP transform
 	gl_Position = ubo.proj * ubo.view * ubo.model * vec4(inPosition, 1.0);
 
+	// Only read when drawing a point list
+	gl_PointSize = settings.pointSize;
+
 	// Pass-through to fragment shader
 	fragColor = inColor;
 	fragTextCoords = inTexCoords;
@@ -8,8 +8,14 @@ use crate::renderer::{
 	DepthBuffer, Renderer, VulkanDevice, VulkanInstance, VulkanPipeline, VulkanRenderPass, VulkanSwapchain
 };
 use ash::vk;
+use std::path::{Path, PathBuf};
 use crate::mesh::{Mesh, DominantAxis};
-use crate::parser::obj::load_obj;
+use crate::mesh::lod::{LodLevel, LodOptions};
+use crate::parser::format::{FormatRegistry, LoadOptions};
+use crate::writer::obj::{save_obj, ObjWriteOptions};
+
+/// Highest subdivision level `D` steps to before going back to the original.
+const MAX_SUBDIVISION_LEVEL: usize = 3;
 
 pub struct App {
 	window: Option<Window>,
@@ -22,9 +28,15 @@ pub struct App {
 	pipeline: Option<VulkanPipeline>,
 	renderer: Option<Renderer>,
 	mesh: Option<Mesh>,
+	/// Where `S` saves the mesh, next to the loaded model.
+	export_path: PathBuf,
+	/// The loaded mesh before subdivision.
+	base_mesh: Option<Mesh>,
+	subdivision_level: usize,
 	centroid: [f32; 3],
 	dominant_axis: DominantAxis,
 	depth_buffer: Option<DepthBuffer>,
+	selected_part: usize,
 }
 
 impl Default for App {
@@ -40,9 +52,13 @@ impl Default for App {
 			pipeline: None,
 			renderer: None,
 			mesh: None,
+			export_path: PathBuf::new(),
+			base_mesh: None,
+			subdivision_level: 0,
 			centroid: [0.0, 0.0, 0.0],
 			dominant_axis: DominantAxis::X,
 			depth_buffer: None,
+			selected_part: 0,
 		}
 	}
 }
@@ -104,24 +120,57 @@ impl ApplicationHandler for App {
 		)
 		.expect("Failed to create pipeline");
 
-		let mut renderer = Renderer::new(&vulkan_instance.instance, &device, &pipeline, "ressources/texture.png")
-			.expect("Failed to create renderer");
-
 		let mesh_path = std::env::args()
 			.nth(1)
 			.unwrap_or_else(|| "ressources/42.obj".to_string());
 
 		println!("Loading mesh: {}", mesh_path);
 
-		let mut mesh = load_obj(&mesh_path)
-			.expect(&format!("Failed to load mesh: {}", mesh_path));
+		// Lenient: a few broken faces should not keep the model from showing
+		let load_options = LoadOptions { lenient: true };
+		let mut last_percent = None;
+		let mut reported_progress = false;
+		let model = FormatRegistry::default().load(&mesh_path, &load_options, &mut |progress| {
+			let percent = progress.fraction().map(|f| (f * 100.0) as u32);
+			if percent != last_percent {
+				last_percent = percent;
+				reported_progress = true;
+				match percent {
+					Some(percent) => print!("\rLoading... {:3}%", percent),
+					None => print!("\rLoading... {} lines", progress.lines_read),
+				}
+				let _ = std::io::Write::flush(&mut std::io::stdout());
+			}
+		});
+		if reported_progress {
+			println!();
+		}
+		let model = model.unwrap_or_else(|e| panic!("Failed to load mesh: {}: {}", mesh_path, e));
+
+		for warning in &model.warnings {
+			eprintln!("Warning: {}", warning);
+		}
+
+		let mut mesh = model.mesh;
 
 		mesh.normalize();
+		let base_mesh = mesh.clone();
+		if !mesh.has_tangents() {
+			mesh.generate_tangents();
+		}
+
+		let texture_path = mesh.diffuse_texture()
+			.filter(|path| std::path::Path::new(path).exists())
+			.unwrap_or("ressources/texture.png")
+			.to_string();
+
+		let mut renderer = Renderer::new(&vulkan_instance.instance, &device, &pipeline, &texture_path)
+			.expect("Failed to create renderer");
 
 		let dominant_axis = mesh.compute_dominant_axis();
 		println!("Dominant axis: {:?}", dominant_axis);
 
-		renderer.load_mesh(&vulkan_instance.instance, &device, &mesh)
+		renderer.load_mesh(&vulkan_instance.instance, &device, &mesh, &build_lods(&mesh))
 			.expect("Failed to load mesh into GPU");
 
 		self.window = Some(window);
@@ -134,6 +183,9 @@ impl ApplicationHandler for App {
 		self.pipeline = Some(pipeline);
 		self.renderer = Some(renderer);
 		self.mesh = Some(mesh);
+		self.export_path = export_path(&mesh_path);
+		self.base_mesh = Some(base_mesh);
+		self.subdivision_level = 0;
 		self.centroid =[0.0, 0.0, 0.0];
 		self.dominant_axis = dominant_axis;
 		self.depth_buffer = Some(depth_buffer);
@@ -168,6 +220,16 @@ impl ApplicationHandler for App {
 					window.request_redraw();
 				}
 			}
+			WindowEvent::MouseWheel { delta, .. } => {
+				use winit::event::MouseScrollDelta;
+				let steps = match delta {
+					MouseScrollDelta::LineDelta(_, y) => y,
+					MouseScrollDelta::PixelDelta(position) => position.y as f32 / 50.0,
+				};
+				if let Some(renderer) = &mut self.renderer {
+					println!("Zoom: {:.2}", renderer.zoom(steps));
+				}
+			}
 			WindowEvent::KeyboardInput { event, .. } => {
 				if event.state == winit::event::ElementState::Pressed {
 					use winit::keyboard::{PhysicalKey, KeyCode};
@@ -179,6 +241,22 @@ impl ApplicationHandler for App {
 									renderer.toggle_texture();
 								}
 							}
+							KeyCode::Tab => self.select_next_part(),
+							KeyCode::KeyH => self.update_parts(|mesh, part| mesh.toggle_part(part)),
+							KeyCode::KeyI => self.update_parts(|mesh, part| mesh.isolate_part(part)),
+							KeyCode::KeyA => self.update_parts(|mesh, _| mesh.show_all_parts()),
+							KeyCode::KeyS => self.save_mesh(),
+							KeyCode::KeyD => self.step_subdivision(),
+							KeyCode::KeyL => {
+								if let Some(renderer) = &mut self.renderer {
+									println!("Line width: {}", renderer.cycle_line_width());
+								}
+							}
+							KeyCode::KeyP => {
+								if let Some(renderer) = &mut self.renderer {
+									println!("Point size: {}", renderer.cycle_point_size());
+								}
+							}
 							_ => {}
 						}
 					}
@@ -200,6 +278,73 @@ impl App {
 		}
 	}
 
+	fn select_next_part(&mut self) {
+		if let Some(mesh) = &self.mesh {
+			if mesh.parts.is_empty() {
+				return;
+			}
+			self.selected_part = (self.selected_part + 1) % mesh.parts.len();
+
+			let part = &mesh.parts[self.selected_part];
+			println!("Selected part {}/{}: {} ({})",
+				self.selected_part + 1, mesh.parts.len(), part.name,
+				if part.visible { "visible" } else { "hidden" });
+		}
+	}
+
+	fn save_mesh(&self) {
+		if let Some(mesh) = &self.mesh {
+			let path = std::path::absolute(&self.export_path).unwrap_or_else(|_| self.export_path.clone());
+			match save_obj(mesh, &path.to_string_lossy(), &ObjWriteOptions::default()) {
+				Ok(()) => println!("Mesh saved to {}", path.display()),
+				Err(e) => eprintln!("Failed to save mesh to {}: {}", path.display(), e),
+			}
+		}
+	}
+
+	/// Subdivides the loaded mesh one level further, back to the original
+	/// after `MAX_SUBDIVISION_LEVEL`.
+	fn step_subdivision(&mut self) {
+		let (Some(base_mesh), Some(instance), Some(device), Some(renderer)) =
+			(&self.base_mesh, &self.vulkan_instance, &self.device, &mut self.renderer)
+		else {
+			return;
+		};
+
+		let level = (self.subdivision_level + 1) % (MAX_SUBDIVISION_LEVEL + 1);
+		let mut mesh = base_mesh.clone();
+		let scheme = mesh.subdivision_scheme();
+		mesh.subdivide(scheme, level);
+		if !mesh.has_tangents() {
+			mesh.generate_tangents();
+		}
+		if let Some(current) = &self.mesh {
+			for (part, shown) in mesh.parts.iter_mut().zip(&current.parts) {
+				part.visible = shown.visible;
+			}
+		}
+		println!("Subdivision level {} ({:?}): {} triangles", level, scheme, mesh.indices.len() / 3);
+
+		// The buffers being replaced may still be in use by frames in flight
+		unsafe {
+			device.device.device_wait_idle().expect("Failed to wait for device idle");
+		}
+		if let Err(e) = renderer.load_mesh(&instance.instance, device, &mesh, &build_lods(&mesh)) {
+			eprintln!("Failed to load subdivided mesh: {}", e);
+			return;
+		}
+
+		self.subdivision_level = level;
+		self.mesh = Some(mesh);
+	}
+
+	fn update_parts(&mut self, update: impl FnOnce(&mut Mesh, usize)) {
+		if let (Some(mesh), Some(renderer)) = (&mut self.mesh, &mut self.renderer) {
+			update(mesh, self.selected_part);
+			renderer.set_visible_parts(mesh);
+		}
+	}
+
 	fn handle_resize(&mut self, width: u32, height: u32) {
 		unsafe {
 			if let Some(device) = &self.device {
@@ -297,6 +442,22 @@ impl App {
 	}
 }
 
+/// `<model>_export.obj` in the directory of `model_path`.
+fn export_path(model_path: &str) -> PathBuf {
+	let path = Path::new(model_path);
+	let stem = path.file_stem().map_or("scop".into(), |stem| stem.to_string_lossy());
+	path.with_file_name(format!("{}_export.obj", stem))
+}
+
+/// Levels of detail of `mesh`, reported as they are built.
+fn build_lods(mesh: &Mesh) -> Vec<LodLevel> {
+	let lods = mesh.lod_chain(&LodOptions::default());
+	for (i, lod) in lods.iter().enumerate() {
+		println!("LOD {}: {} triangles, error {}", i + 1, lod.mesh.indices.len() / 3, lod.error);
+	}
+	lods
+}
+
 impl Drop for App {
 	fn drop(&mut self) {
 		self.cleanup();
@@ -0,0 +1,89 @@
+use crate::mesh::half_edge::HalfEdgeMesh;
+use crate::mesh::Mesh;
+use crate::parser::format::load_model;
+use crate::writer::save_mesh;
+
+pub const USAGE: &str = "usage: scop --simplify <ratio> <input> <output>
+       scop --smooth <iterations> <input> <output>
+       scop --info <input>";
+
+/// Runs the command line mode selected by `args`, without the program name.
+/// Returns `None` when the arguments ask for the viewer instead.
+pub fn run(args: &[String]) -> Option<Result<(), String>> {
+	match args.first().map(String::as_str) {
+		Some("--simplify") => Some(simplify(&args[1..])),
+		Some("--smooth") => Some(smooth(&args[1..])),
+		Some("--info") => Some(info(&args[1..])),
+		_ => None,
+	}
+}
+
+/// `--simplify <ratio> <input> <output>`: keeps about `ratio` of the
+/// triangles of `input` and saves the result to `output`.
+fn simplify(args: &[String]) -> Result<(), String> {
+	let [ratio, input, output] = args else {
+		return Err(USAGE.to_string());
+	};
+	let ratio: f32 = ratio.parse()
+		.ok()
+		.filter(|ratio| (0.0..=1.0).contains(ratio))
+		.ok_or_else(|| format!("ratio must be between 0 and 1, got {}", ratio))?;
+
+	let mut mesh = load(input)?;
+
+	let report = mesh.simplify(ratio);
+	println!("Triangles: {} -> {}", report.triangles_before, report.triangles_after);
+	println!("Vertices: {} -> {}", report.vertices_before, report.vertices_after);
+	println!("Error: {}", report.error);
+	if report.triangles_after > report.target_triangles {
+		eprintln!("Warning: stopped short of the {} triangles asked for, sharp edges, seams or borders hold the rest", report.target_triangles);
+	}
+
+	save(&mesh, output)
+}
+
+/// `--smooth <iterations> <input> <output>`: smooths the surface of `input`
+/// and saves the result to `output`.
+fn smooth(args: &[String]) -> Result<(), String> {
+	let [iterations, input, output] = args else {
+		return Err(USAGE.to_string());
+	};
+	let iterations: usize = iterations.parse()
+		.map_err(|_| format!("iterations must be a whole number, got {}", iterations))?;
+
+	let mut mesh = load(input)?;
+	mesh.smooth(iterations);
+	save(&mesh, output)
+}
+
+/// `--info <input>`: prints the size and topology of the surface of `input`.
+fn info(args: &[String]) -> Result<(), String> {
+	let [input] = args else {
+		return Err(USAGE.to_string());
+	};
+
+	let mesh = load(input)?;
+	let topology = HalfEdgeMesh::from_mesh(&mesh);
+	println!("Triangles: {}", mesh.indices.len() / 3);
+	println!("Vertices: {} ({} positions)", mesh.vertices.len(), topology.vertex_count());
+	println!("Faces: {}", topology.face_count());
+	println!("Edges: {}", topology.edges().count());
+	println!("Border loops: {}", topology.border_loops().len());
+	println!("Manifold: {}", if topology.is_manifold() { "yes" } else { "no" });
+
+	Ok(())
+}
+
+fn load(input: &str) -> Result<Mesh, String> {
+	let model = load_model(input).map_err(|e| format!("Failed to load mesh: {}: {}", input, e))?;
+	for warning in &model.warnings {
+		eprintln!("Warning: {}", warning);
+	}
+	Ok(model.mesh)
+}
+
+fn save(mesh: &Mesh, output: &str) -> Result<(), String> {
+	save_mesh(mesh, output).map_err(|e| format!("Failed to save mesh to {}: {}", output, e))?;
+	println!("Mesh saved to {}", output);
+	Ok(())
+}
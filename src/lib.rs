@@ -1,12 +1,14 @@
 pub mod math;
 
 pub mod parser;
+pub mod writer;
 
 
 pub mod mesh;
-pub use mesh::{Mesh, Vertex};
+pub use mesh::{Mesh, MeshPart, Vertex, VertexAttribute};
 
 mod renderer;
 pub use renderer::instance::VulkanInstance;
 
-pub mod app;
\ No newline at end of file
+pub mod app;
+pub mod cli;
\ No newline at end of file
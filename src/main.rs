@@ -1,13 +1,24 @@
 mod app;
+mod cli;
 mod math;
 mod mesh;
 mod parser;
 mod renderer;
+mod writer;
 
 use winit::event_loop::{ControlFlow, EventLoop};
 use app::App;
 
 fn main() {
+	let args: Vec<String> = std::env::args().skip(1).collect();
+	if let Some(result) = cli::run(&args) {
+		if let Err(e) = result {
+			eprintln!("{}", e);
+			std::process::exit(1);
+		}
+		return;
+	}
+
 	println!("=== SCOP - Starting ===");
 
 	let event_loop = EventLoop::new().expect("Failed to create event loop");
@@ -0,0 +1,60 @@
+//! Meshes shared by the tests of the mesh operations.
+
+use crate::mesh::Mesh;
+use crate::parser::obj::{obj_to_mesh, parse_obj_from_string};
+
+/// Cube of quads with UV seams, split in a bottom, sides and top part. Every
+/// face has its own smoothing group, so every edge is creased.
+pub const CUBE: &str = "
+v -1 -1 -1
+v 1 -1 -1
+v 1 1 -1
+v -1 1 -1
+v -1 -1 1
+v 1 -1 1
+v 1 1 1
+v -1 1 1
+vt 0 0
+vt 1 0
+vt 1 1
+vt 0 1
+g bottom
+s 1
+f 1/1 4/2 3/3 2/4
+g sides
+s 2
+f 1/1 2/2 6/3 5/4
+s 3
+f 2/1 3/2 7/3 6/4
+s 4
+f 3/1 4/2 8/3 7/4
+s 5
+f 4/1 1/2 5/3 8/4
+g top
+s 6
+f 5/1 6/2 7/3 8/4
+";
+
+pub fn cube() -> Mesh {
+	obj_to_mesh(parse_obj_from_string(CUBE).unwrap())
+}
+
+/// Grid of `n` by `n` quads over the unit square, lifted by `height(u, v)`,
+/// with a line along one border.
+pub fn bumpy_grid(n: usize, height: impl Fn(f32, f32) -> f32) -> Mesh {
+	let mut obj = String::new();
+	for y in 0..=n {
+		for x in 0..=n {
+			let (u, v) = (x as f32 / n as f32, y as f32 / n as f32);
+			obj += &format!("v {} {} {}\n", u, v, height(u, v));
+		}
+	}
+	for y in 0..n {
+		for x in 0..n {
+			let a = y * (n + 1) + x + 1;
+			obj += &format!("f {} {} {} {}\n", a, a + 1, a + n + 2, a + n + 1);
+		}
+	}
+	obj += "l 1 2\n";
+	obj_to_mesh(parse_obj_from_string(&obj).unwrap())
+}
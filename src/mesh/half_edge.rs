@@ -0,0 +1,607 @@
+use std::collections::HashMap;
+
+use crate::mesh::{Crease, Mesh, Vertex};
+use crate::parser::triangulate::triangulate_polygon;
+
+/// One side of an edge, running around a face.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HalfEdge {
+	/// Vertex the half-edge leaves from.
+	pub vertex: u32,
+	/// Half-edge running the other way along the same edge, `None` on
+	/// borders and non-manifold edges.
+	pub twin: Option<u32>,
+	/// Next half-edge around the face.
+	pub next: u32,
+	/// Previous half-edge around the face.
+	pub prev: u32,
+	pub face: u32,
+	/// Mesh vertex of the face corner at `vertex`, holding its UV, normal and
+	/// other values.
+	pub corner: u32,
+}
+
+/// A polygon of a [`HalfEdgeMesh`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Face {
+	/// One of the half-edges around the face.
+	pub half_edge: u32,
+	/// Where the face comes from: the first mesh triangle of its polygon, or
+	/// its index among the faces given to [`HalfEdgeMesh::from_faces`].
+	pub source: u32,
+}
+
+/// Adjacency of a mesh surface, over vertices welded by position so UV and
+/// normal seams do not split it.
+#[derive(Debug, Clone)]
+pub struct HalfEdgeMesh {
+	/// Position of each vertex.
+	pub positions: Vec<[f32; 3]>,
+	half_edges: Vec<HalfEdge>,
+	faces: Vec<Face>,
+	/// An outgoing half-edge of each vertex, a border one when there is one,
+	/// `None` for vertices outside the faces.
+	outgoing: Vec<Option<u32>>,
+	/// Vertex of each mesh vertex.
+	welded: Vec<u32>,
+}
+
+impl HalfEdgeMesh {
+	/// Builds the faces from the source polygons of `mesh` when it kept them,
+	/// from its triangles otherwise.
+	///
+	/// Polygons that are not one simple loop are split back into triangles,
+	/// and faces going through a vertex twice are left out. Edges with more
+	/// than two faces, or two faces winding the same way, get no twins.
+	pub fn from_mesh(mesh: &Mesh) -> Self {
+		let triangle_count = mesh.indices.len() / 3;
+		if mesh.polygons.iter().map(|&count| count as usize).sum::<usize>() == triangle_count {
+			Self::build(mesh, &mesh.polygons)
+		} else {
+			Self::from_triangles(mesh)
+		}
+	}
+
+	/// Builds one face per triangle of `mesh`, ignoring its polygons.
+	pub fn from_triangles(mesh: &Mesh) -> Self {
+		Self::build(mesh, &vec![1; mesh.indices.len() / 3])
+	}
+
+	/// Builds faces over `positions` from the vertex loops
+	/// `vertices[face_starts[f]..face_starts[f + 1]]`, the last start being the
+	/// end of the last face.
+	///
+	/// The corner of each half-edge is its index in `vertices`, and faces
+	/// going through a vertex twice are left out.
+	pub fn from_faces(positions: Vec<[f32; 3]>, face_starts: &[usize], vertices: &[u32]) -> Self {
+		let mut half_edge_mesh = Self {
+			outgoing: vec![None; positions.len()],
+			welded: (0..positions.len() as u32).collect(),
+			positions,
+			half_edges: Vec::new(),
+			faces: Vec::new(),
+		};
+
+		for (f, ends) in face_starts.windows(2).enumerate() {
+			let corners: Vec<u32> = (ends[0] as u32..ends[1] as u32).collect();
+			half_edge_mesh.push_face(&vertices[ends[0]..ends[1]], &corners, f as u32);
+		}
+
+		half_edge_mesh.link_twins();
+		half_edge_mesh
+	}
+
+	fn build(mesh: &Mesh, polygons: &[u32]) -> Self {
+		let (positions, welded) = weld(&mesh.vertices);
+		let mut half_edge_mesh = Self {
+			outgoing: vec![None; positions.len()],
+			positions,
+			half_edges: Vec::new(),
+			faces: Vec::new(),
+			welded,
+		};
+
+		let mut first = 0;
+		for &count in polygons {
+			let triangles = &mesh.indices[first * 3..(first + count as usize) * 3];
+			match polygon_outline(triangles, &half_edge_mesh.welded) {
+				Some(outline) => half_edge_mesh.push_mesh_face(&outline, first as u32),
+				None => {
+					for triangle in triangles.chunks_exact(3) {
+						half_edge_mesh.push_mesh_face(triangle, first as u32);
+					}
+				}
+			}
+			first += count as usize;
+		}
+
+		half_edge_mesh.link_twins();
+		half_edge_mesh
+	}
+
+	fn push_mesh_face(&mut self, corners: &[u32], triangle: u32) {
+		let vertices: Vec<u32> = corners.iter().map(|&i| self.welded[i as usize]).collect();
+		self.push_face(&vertices, corners, triangle);
+	}
+
+	fn push_face(&mut self, vertices: &[u32], corners: &[u32], source: u32) {
+		let mut sorted = vertices.to_vec();
+		sorted.sort_unstable();
+		sorted.dedup();
+		if sorted.len() < 3 || sorted.len() != vertices.len() {
+			return;
+		}
+
+		let face = self.faces.len() as u32;
+		let start = self.half_edges.len() as u32;
+		let n = corners.len() as u32;
+		for (k, (&corner, &vertex)) in corners.iter().zip(vertices).enumerate() {
+			let k = k as u32;
+			self.half_edges.push(HalfEdge {
+				vertex,
+				twin: None,
+				next: start + (k + 1) % n,
+				prev: start + (k + n - 1) % n,
+				face,
+				corner,
+			});
+		}
+		self.faces.push(Face { half_edge: start, source });
+	}
+
+	fn link_twins(&mut self) {
+		let mut directed: HashMap<(u32, u32), Vec<u32>> = HashMap::new();
+		for h in 0..self.half_edges.len() as u32 {
+			directed.entry((self.half_edges[h as usize].vertex, self.destination(h))).or_default().push(h);
+		}
+
+		for (&(a, b), forward) in &directed {
+			if let ([h], Some([t])) = (forward.as_slice(), directed.get(&(b, a)).map(Vec::as_slice)) {
+				self.half_edges[*h as usize].twin = Some(*t);
+			}
+		}
+
+		// Starting at a border lets the walk around a vertex reach all its faces
+		for h in 0..self.half_edges.len() as u32 {
+			let half_edge = self.half_edges[h as usize];
+			let outgoing = &mut self.outgoing[half_edge.vertex as usize];
+			if outgoing.is_none() || half_edge.twin.is_none() {
+				*outgoing = Some(h);
+			}
+		}
+	}
+
+	pub fn vertex_count(&self) -> usize {
+		self.positions.len()
+	}
+
+	pub fn face_count(&self) -> usize {
+		self.faces.len()
+	}
+
+	pub fn half_edge_count(&self) -> usize {
+		self.half_edges.len()
+	}
+
+	pub fn half_edge(&self, h: u32) -> &HalfEdge {
+		&self.half_edges[h as usize]
+	}
+
+	pub fn face(&self, f: u32) -> &Face {
+		&self.faces[f as usize]
+	}
+
+	/// Vertex of a mesh vertex.
+	pub fn vertex_of(&self, mesh_vertex: u32) -> u32 {
+		self.welded[mesh_vertex as usize]
+	}
+
+	/// Vertex the half-edge `h` leads to.
+	pub fn destination(&self, h: u32) -> u32 {
+		self.half_edges[self.half_edges[h as usize].next as usize].vertex
+	}
+
+	pub fn vertices(&self) -> std::ops::Range<u32> {
+		0..self.positions.len() as u32
+	}
+
+	pub fn faces(&self) -> std::ops::Range<u32> {
+		0..self.faces.len() as u32
+	}
+
+	/// One half-edge of each edge.
+	pub fn edges(&self) -> impl Iterator<Item = u32> + '_ {
+		(0..self.half_edges.len() as u32).filter(|&h| self.half_edges[h as usize].twin.is_none_or(|twin| h < twin))
+	}
+
+	/// Half-edges around face `f`, in winding order.
+	pub fn face_half_edges(&self, f: u32) -> impl Iterator<Item = u32> + '_ {
+		let start = self.faces[f as usize].half_edge;
+		let mut current = Some(start);
+		std::iter::from_fn(move || {
+			let h = current?;
+			let next = self.half_edges[h as usize].next;
+			current = (next != start).then_some(next);
+			Some(h)
+		})
+	}
+
+	/// Vertices around face `f`, in winding order.
+	pub fn face_vertices(&self, f: u32) -> impl Iterator<Item = u32> + '_ {
+		self.face_half_edges(f).map(|h| self.half_edges[h as usize].vertex)
+	}
+
+	/// Half-edges leaving vertex `v`, turning from a border one if any.
+	///
+	/// Around a non-manifold vertex only the faces reachable across edges
+	/// from the first half-edge are visited.
+	pub fn outgoing(&self, v: u32) -> impl Iterator<Item = u32> + '_ {
+		let start = self.outgoing[v as usize];
+		let mut current = start;
+		std::iter::from_fn(move || {
+			let h = current?;
+			current = self.half_edges[self.half_edges[h as usize].prev as usize].twin
+				.filter(|&next| Some(next) != start);
+			Some(h)
+		})
+	}
+
+	/// One half-edge of each edge at vertex `v`: those leaving it, and at a
+	/// border the last one, running into it.
+	pub fn vertex_edges(&self, v: u32) -> Vec<u32> {
+		let mut edges: Vec<u32> = self.outgoing(v).collect();
+		if let Some(&last) = edges.last() {
+			let prev = self.half_edges[last as usize].prev;
+			if self.half_edges[prev as usize].twin.is_none() {
+				edges.push(prev);
+			}
+		}
+		edges
+	}
+
+	/// End of the edge of half-edge `h` other than vertex `v`.
+	pub fn other_end(&self, h: u32, v: u32) -> u32 {
+		let vertex = self.half_edges[h as usize].vertex;
+		if vertex == v { self.destination(h) } else { vertex }
+	}
+
+	/// Vertices sharing an edge with vertex `v`.
+	pub fn neighbors(&self, v: u32) -> Vec<u32> {
+		self.vertex_edges(v).into_iter().map(|h| self.other_end(h, v)).collect()
+	}
+
+	/// Faces around vertex `v`.
+	pub fn vertex_faces(&self, v: u32) -> impl Iterator<Item = u32> + '_ {
+		self.outgoing(v).map(|h| self.half_edges[h as usize].face)
+	}
+
+	pub fn is_border_edge(&self, h: u32) -> bool {
+		self.half_edges[h as usize].twin.is_none()
+	}
+
+	pub fn is_border_vertex(&self, v: u32) -> bool {
+		self.outgoing[v as usize].is_some_and(|h| self.is_border_edge(h))
+	}
+
+	/// Border half-edges chained around each hole or open side, in winding
+	/// order.
+	pub fn border_loops(&self) -> Vec<Vec<u32>> {
+		let mut visited = vec![false; self.half_edges.len()];
+		let mut loops = Vec::new();
+
+		for start in 0..self.half_edges.len() as u32 {
+			if visited[start as usize] || !self.is_border_edge(start) {
+				continue;
+			}
+
+			let mut border = Vec::new();
+			let mut h = start;
+			while !visited[h as usize] {
+				visited[h as usize] = true;
+				border.push(h);
+				// Turn around the end vertex until leaving it along a border
+				h = self.half_edges[h as usize].next;
+				while let Some(twin) = self.half_edges[h as usize].twin {
+					h = self.half_edges[twin as usize].next;
+				}
+			}
+			loops.push(border);
+		}
+
+		loops
+	}
+
+	/// Whether every edge has at most two faces winding opposite ways, and
+	/// the faces around every vertex form a single fan.
+	pub fn is_manifold(&self) -> bool {
+		let mut leaving = vec![0; self.positions.len()];
+		let mut border_edges = HashMap::new();
+		for h in 0..self.half_edges.len() as u32 {
+			let half_edge = &self.half_edges[h as usize];
+			leaving[half_edge.vertex as usize] += 1;
+			if half_edge.twin.is_none() {
+				let (a, b) = (half_edge.vertex, self.destination(h));
+				if border_edges.insert((a.min(b), a.max(b)), h).is_some() {
+					return false;
+				}
+			}
+		}
+
+		self.vertices().all(|v| self.outgoing(v).count() == leaving[v as usize])
+	}
+
+	/// Copy of `source`, the mesh this was built from, with its surface
+	/// replaced by the faces, at the current positions.
+	///
+	/// Corners keep the values of their mesh vertices and faces are
+	/// triangulated by ear clipping. Parts and face materials follow the triangles
+	/// they come from, and lines and points are kept.
+	pub fn to_mesh(&self, source: &Mesh) -> Mesh {
+		let mut mesh = source.clone();
+		let mut vertices: Vec<Vertex> = Vec::new();
+		let mut attributes: Vec<Vec<f32>> = vec![Vec::new(); source.attributes.len()];
+		let mut first_vertex: HashMap<u32, u32> = HashMap::new();
+		let mut cache: HashMap<(u32, u32), u32> = HashMap::new();
+		let corner_vertices: Vec<u32> = self.half_edges.iter()
+			.map(|half_edge| {
+				*cache.entry((half_edge.vertex, half_edge.corner)).or_insert_with(|| {
+					let mut vertex = source.vertices[half_edge.corner as usize];
+					vertex.position = self.positions[half_edge.vertex as usize];
+					vertices.push(vertex);
+					for (values, attribute) in attributes.iter_mut().zip(&source.attributes) {
+						values.push(attribute.values.get(half_edge.corner as usize).copied().unwrap_or(0.0));
+					}
+					let index = (vertices.len() - 1) as u32;
+					first_vertex.entry(half_edge.vertex).or_insert(index);
+					index
+				})
+			})
+			.collect();
+
+		let mut indices = Vec::new();
+		let mut origins = Vec::new();
+		let mut polygons = Vec::new();
+		for f in self.faces() {
+			let half_edges: Vec<u32> = self.face_half_edges(f).collect();
+			let points: Vec<[f32; 3]> = half_edges.iter().map(|&h| self.positions[self.half_edges[h as usize].vertex as usize]).collect();
+			let triangles = triangulate_face(&points);
+			for triangle in &triangles {
+				indices.extend(triangle.map(|k| corner_vertices[half_edges[k] as usize]));
+				origins.push(self.faces[f as usize].source);
+			}
+			polygons.push(triangles.len() as u32);
+		}
+
+		keep_loose_vertices(&mut mesh, &mut vertices, &mut attributes);
+		follow_origins(&mut mesh, &origins);
+
+		let mut creases: Vec<Crease> = source.creases.iter()
+			.filter_map(|crease| Some(Crease {
+				edge: [*first_vertex.get(&self.vertex_of(crease.edge[0]))?, *first_vertex.get(&self.vertex_of(crease.edge[1]))?],
+				sharpness: crease.sharpness,
+			}))
+			.collect();
+		creases.sort_by_key(|crease| crease.edge);
+		creases.dedup_by_key(|crease| crease.edge);
+
+		for (attribute, values) in mesh.attributes.iter_mut().zip(attributes) {
+			attribute.values = values;
+		}
+		mesh.vertices = vertices;
+		mesh.indices = indices;
+		mesh.polygons = polygons;
+		mesh.creases = creases;
+		mesh
+	}
+}
+
+/// Positions of `vertices` with duplicates merged, and the index of each
+/// vertex among them.
+pub fn weld(vertices: &[Vertex]) -> (Vec<[f32; 3]>, Vec<u32>) {
+	let mut by_position: HashMap<[u32; 3], u32> = HashMap::new();
+	let mut positions = Vec::new();
+	let welded = vertices.iter()
+		.map(|vertex| {
+			// Adding zero turns -0.0 into 0.0
+			let key = vertex.position.map(|c| (c + 0.0).to_bits());
+			*by_position.entry(key).or_insert_with(|| {
+				positions.push(vertex.position);
+				(positions.len() - 1) as u32
+			})
+		})
+		.collect();
+	(positions, welded)
+}
+
+/// Triangles of a face given its corner positions in winding order, by ear
+/// clipping, or as a fan when clipping fails.
+pub(crate) fn triangulate_face(points: &[[f32; 3]]) -> Vec<[usize; 3]> {
+	if points.len() > 3
+		&& let Some(triangles) = triangulate_polygon(points).filter(|triangles| !triangles.is_empty())
+	{
+		return triangles;
+	}
+	(1..points.len() - 1).map(|k| [0, k, k + 1]).collect()
+}
+
+/// Appends copies of the vertices of the lines and points of `mesh` to the
+/// new `vertices` and `attributes`, and points the lines and points at them.
+pub(crate) fn keep_loose_vertices(mesh: &mut Mesh, vertices: &mut Vec<Vertex>, attributes: &mut [Vec<f32>]) {
+	let mut kept: HashMap<u32, u32> = HashMap::new();
+	for i in mesh.line_indices.iter_mut().chain(&mut mesh.point_indices) {
+		*i = *kept.entry(*i).or_insert_with(|| {
+			vertices.push(mesh.vertices[*i as usize]);
+			for (values, attribute) in attributes.iter_mut().zip(&mesh.attributes) {
+				values.push(attribute.values.get(*i as usize).copied().unwrap_or(0.0));
+			}
+			(vertices.len() - 1) as u32
+		});
+	}
+}
+
+/// Moves the parts and face materials of `mesh` to new triangles, given the
+/// source triangle each comes from, in increasing order. Must run before the
+/// indices are replaced.
+pub(crate) fn follow_origins(mesh: &mut Mesh, origins: &[u32]) {
+	let start_of = |triangle: u32| origins.partition_point(|&origin| origin < triangle) as u32;
+	for part in &mut mesh.parts {
+		let first = start_of(part.first_index / 3);
+		let end = start_of((part.first_index + part.index_count) / 3);
+		part.first_index = first * 3;
+		part.index_count = (end - first) * 3;
+	}
+	if mesh.face_materials.len() == mesh.indices.len() / 3 {
+		mesh.face_materials = origins.iter().map(|&origin| mesh.face_materials[origin as usize]).collect();
+	}
+}
+
+/// Boundary of the triangles of one polygon, as the vertices of its corners
+/// in winding order, or `None` unless it is a single loop.
+fn polygon_outline(triangles: &[u32], welded: &[u32]) -> Option<Vec<u32>> {
+	if triangles.len() == 3 {
+		return Some(triangles.to_vec());
+	}
+
+	// Inner edges are walked both ways, border edges once
+	let directed: Vec<(u32, u32, u32)> = triangles.chunks_exact(3)
+		.flat_map(|t| (0..3).map(move |k| (t[k], t[(k + 1) % 3])))
+		.map(|(a, b)| (welded[a as usize], welded[b as usize], a))
+		.collect();
+	let border: Vec<(u32, u32, u32)> = directed.iter().copied()
+		.filter(|&(a, b, _)| !directed.iter().any(|&(c, d, _)| c == b && d == a))
+		.collect();
+
+	let mut outline = Vec::with_capacity(border.len());
+	let (start, mut next, vertex) = *border.first()?;
+	outline.push(vertex);
+	while next != start {
+		let mut leaving = border.iter().filter(|&&(a, _, _)| a == next);
+		let &(_, to, vertex) = leaving.next()?;
+		if leaving.next().is_some() || outline.len() >= border.len() {
+			return None;
+		}
+		outline.push(vertex);
+		next = to;
+	}
+
+	(outline.len() == border.len()).then_some(outline)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mesh::fixtures::{cube, CUBE};
+    use crate::parser::obj::{obj_to_mesh, parse_obj_from_string};
+
+    #[test]
+    fn closed_cube_has_no_border() {
+        let mesh = HalfEdgeMesh::from_mesh(&cube());
+
+        assert_eq!(mesh.vertex_count(), 8);
+        assert_eq!(mesh.face_count(), 6);
+        assert_eq!(mesh.edges().count(), 12);
+        assert!(mesh.faces().all(|f| mesh.face_vertices(f).count() == 4));
+        assert!(mesh.is_manifold());
+        assert!(mesh.border_loops().is_empty());
+        for v in mesh.vertices() {
+            assert!(!mesh.is_border_vertex(v));
+            assert_eq!(mesh.neighbors(v).len(), 3);
+            assert_eq!(mesh.vertex_faces(v).count(), 3);
+        }
+    }
+
+    #[test]
+    fn open_sides_form_border_loops() {
+        // The cube without its bottom and top
+        let source = CUBE.lines().filter(|line| !line.starts_with("f 1/1 4/2") && !line.starts_with("f 5/1")).collect::<Vec<_>>().join("\n");
+        let mesh = HalfEdgeMesh::from_mesh(&obj_to_mesh(parse_obj_from_string(&source).unwrap()));
+
+        assert!(mesh.is_manifold());
+        let loops = mesh.border_loops();
+        assert_eq!(loops.len(), 2);
+        for border in &loops {
+            assert_eq!(border.len(), 4);
+            // Each half-edge ends where the next one starts
+            for (k, &h) in border.iter().enumerate() {
+                let next = border[(k + 1) % border.len()];
+                assert_eq!(mesh.destination(h), mesh.half_edge(next).vertex);
+            }
+        }
+        for v in mesh.vertices() {
+            assert!(mesh.is_border_vertex(v));
+            assert_eq!(mesh.neighbors(v).len(), 3);
+            assert_eq!(mesh.vertex_faces(v).count(), 2);
+        }
+    }
+
+    #[test]
+    fn round_trip_keeps_polygons_and_parts() {
+        let source = cube();
+        let mut mesh = HalfEdgeMesh::from_mesh(&source);
+        for position in &mut mesh.positions {
+            *position = position.map(|c| c * 2.0);
+        }
+
+        let rebuilt = mesh.to_mesh(&source);
+
+        assert_eq!(rebuilt.indices.len(), source.indices.len());
+        assert_eq!(rebuilt.polygons, vec![2; 6]);
+        let parts: Vec<(u32, u32)> = rebuilt.parts.iter().map(|p| (p.first_index, p.index_count)).collect();
+        assert_eq!(parts, vec![(0, 6), (6, 24), (30, 6)]);
+        assert!(rebuilt.vertices.iter().all(|v| v.position.iter().all(|c| c.abs() == 2.0)));
+        // UV seams keep their split vertices
+        assert_eq!(rebuilt.vertices.len(), source.vertices.len());
+    }
+
+    #[test]
+    fn round_trip_keeps_concave_polygons_inside() {
+        // An L, which fans from most of its corners fold over the notch
+        let mut source = obj_to_mesh(parse_obj_from_string("
+v 0 0 0
+v 2 0 0
+v 2 1 0
+v 1 1 0
+v 1 2 0
+v 0 2 0
+f 1 2 3 4 5 6
+").unwrap());
+        // The outline starts at the first corner, (2, 0)
+        source.indices[0..3].rotate_left(1);
+        let mesh = HalfEdgeMesh::from_mesh(&source);
+        assert_eq!(mesh.face_count(), 1);
+        assert_eq!(mesh.positions[mesh.face_vertices(0).next().unwrap() as usize], [2.0, 0.0, 0.0]);
+
+        let rebuilt = mesh.to_mesh(&source);
+
+        assert_eq!(rebuilt.polygons, vec![4]);
+        let mut area = 0.0;
+        for triangle in rebuilt.indices.chunks_exact(3) {
+            let [a, b, c] = [0, 1, 2].map(|k| rebuilt.vertices[triangle[k] as usize].position);
+            let signed = ((b[0] - a[0]) * (c[1] - a[1]) - (b[1] - a[1]) * (c[0] - a[0])) / 2.0;
+            assert!(signed > 0.0, "{:?}", triangle);
+            area += signed;
+        }
+        assert_eq!(area, 3.0);
+    }
+
+    #[test]
+    fn shared_edges_of_three_faces_are_not_manifold() {
+        let mesh = obj_to_mesh(parse_obj_from_string("
+v 0 0 0
+v 1 0 0
+v 0 1 0
+v 0 -1 0
+v 0 0 1
+f 1 2 3
+f 2 1 4
+f 1 2 5
+f 1 1 2
+").unwrap());
+        let mesh = HalfEdgeMesh::from_mesh(&mesh);
+
+        // The degenerate face is left out
+        assert_eq!(mesh.face_count(), 3);
+        assert!(!mesh.is_manifold());
+        assert!(mesh.edges().all(|h| mesh.is_border_edge(h)));
+    }
+}
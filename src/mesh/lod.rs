@@ -0,0 +1,123 @@
+use crate::mesh::Mesh;
+
+/// Options of [`Mesh::lod_chain`].
+#[derive(Debug, Clone)]
+pub struct LodOptions {
+	/// Most levels generated below the full mesh.
+	pub max_levels: usize,
+	/// Fraction of the triangles of the previous level each level keeps.
+	pub ratio: f32,
+	/// Levels with fewer triangles than this are not simplified further.
+	pub min_triangles: usize,
+}
+
+impl Default for LodOptions {
+	fn default() -> Self {
+		Self {
+			max_levels: 4,
+			ratio: 0.5,
+			min_triangles: 64,
+		}
+	}
+}
+
+/// A simplified version of a mesh.
+#[derive(Debug)]
+pub struct LodLevel {
+	pub mesh: Mesh,
+	/// Estimated distance to the full mesh, in model units.
+	pub error: f32,
+}
+
+impl Mesh {
+	/// Builds levels of detail coarser than the mesh, each simplified from
+	/// the one before, with errors adding up along the chain.
+	///
+	/// Stops early once a level no longer shrinks much, as when seams hold
+	/// the triangles in place. Lines and points are left out of the levels.
+	pub fn lod_chain(&self, options: &LodOptions) -> Vec<LodLevel> {
+		let mut levels: Vec<LodLevel> = Vec::new();
+
+		while levels.len() < options.max_levels {
+			let previous = levels.last().map_or(self, |level| &level.mesh);
+			let triangles = previous.indices.len() / 3;
+			if triangles < options.min_triangles {
+				break;
+			}
+
+			let mut mesh = previous.clone();
+			mesh.line_indices.clear();
+			mesh.point_indices.clear();
+			let report = mesh.simplify(options.ratio);
+
+			// A level barely smaller than the previous is not worth its memory
+			if report.triangles_after as f32 > triangles as f32 * (1.0 + options.ratio) / 2.0 {
+				break;
+			}
+			let error = levels.last().map_or(0.0, |level| level.error) + report.error;
+			levels.push(LodLevel { mesh, error });
+		}
+
+		levels
+	}
+}
+
+/// Picks the coarsest level whose error spans at most `max_pixels` on
+/// screen.
+///
+/// `errors` lists the levels from the finest, and `pixels_per_unit` is the
+/// size on screen of one model unit at the distance of the mesh.
+pub fn select_lod(errors: &[f32], pixels_per_unit: f32, max_pixels: f32) -> usize {
+	errors.iter()
+		.rposition(|&error| error * pixels_per_unit <= max_pixels)
+		.unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mesh::fixtures;
+
+    fn bumpy_grid(n: usize) -> Mesh {
+        fixtures::bumpy_grid(n, |u, v| 0.1 * (u * 6.0).sin() * (v * 6.0).cos())
+    }
+
+    #[test]
+    fn levels_shrink_while_error_grows() {
+        let mesh = bumpy_grid(24);
+        let levels = mesh.lod_chain(&LodOptions::default());
+
+        assert_eq!(levels.len(), 4);
+        let mut triangles = mesh.indices.len();
+        let mut error = 0.0;
+        for level in &levels {
+            assert!(level.mesh.indices.len() < triangles);
+            assert!(level.error >= error);
+            assert!(level.mesh.line_indices.is_empty());
+            triangles = level.mesh.indices.len();
+            error = level.error;
+        }
+        assert!(error > 0.0);
+    }
+
+    #[test]
+    fn chain_stops_at_small_meshes() {
+        let mesh = bumpy_grid(8);
+        let options = LodOptions { min_triangles: 100, ..Default::default() };
+
+        let levels = mesh.lod_chain(&options);
+
+        assert_eq!(levels.len(), 1);
+        assert!(levels[0].mesh.indices.len() / 3 < 100);
+    }
+
+    #[test]
+    fn selection_follows_screen_size() {
+        let errors = [0.0, 0.001, 0.01, 0.1];
+
+        assert_eq!(select_lod(&errors, 100.0, 1.0), 2);
+        assert_eq!(select_lod(&errors, 1000.0, 1.0), 1);
+        assert_eq!(select_lod(&errors, 1.0, 1.0), 3);
+        assert_eq!(select_lod(&errors[..1], 1000.0, 1.0), 0);
+    }
+}
@@ -0,0 +1,182 @@
+#[cfg(test)]
+mod fixtures;
+pub mod half_edge;
+pub mod lod;
+pub mod normals;
+pub mod simplify;
+pub mod smooth;
+pub mod subdivide;
+pub mod tangent;
+
+use crate::parser::mtl::Material;
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct Vertex {
+	pub position: [f32; 3],
+	pub tex_coords: [f32; 2],
+	pub normal: [f32; 3],
+	pub color: [f32; 3],
+	/// Tangent with the bitangent sign in `w`, all zero until generated.
+	pub tangent: [f32; 4],
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum DominantAxis { X, Y, Z }
+
+impl Vertex {
+	pub fn default() -> Self {
+		Self {
+			position: [0.0, 0.0, 0.0],
+			tex_coords: [0.0, 0.0],
+			normal: [0.0, 1.0, 0.0],
+			color: [0.0, 0.0, 0.0],
+			tangent: [0.0, 0.0, 0.0, 0.0],
+		}
+	}
+    pub fn check_size() {
+        println!("Vertex size: {} bytes", std::mem::size_of::<Self>());
+        println!("  position offset: {}", 0);
+        println!("  tex_coords offset: {}", std::mem::offset_of!(Vertex, tex_coords));
+        println!("  normal offset: {}", std::mem::offset_of!(Vertex, normal));
+        println!("  color offset: {}", std::mem::offset_of!(Vertex, color));
+        println!("  tangent offset: {}", std::mem::offset_of!(Vertex, tangent));
+    }
+}
+
+/// A named range of the index buffer that can be shown or hidden on its own.
+#[derive(Debug, Clone)]
+pub struct MeshPart {
+	pub name: String,
+	pub first_index: u32,
+	pub index_count: u32,
+	pub visible: bool,
+}
+
+/// A per-vertex value without a slot in `Vertex`, such as a scanner
+/// confidence, kept for later visualization.
+#[derive(Debug, Clone, PartialEq)]
+pub struct VertexAttribute {
+	pub name: String,
+	/// One value per vertex.
+	pub values: Vec<f32>,
+}
+
+/// An edge subdivision keeps sharp.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Crease {
+	/// Vertex indices of the ends. Vertices at the same positions share the
+	/// crease.
+	pub edge: [u32; 2],
+	/// Subdivision levels the edge stays sharp for, `f32::INFINITY` for all.
+	pub sharpness: f32,
+}
+
+#[derive(Debug, Clone)]
+pub struct Mesh {
+	pub vertices: Vec<Vertex>,
+	pub indices: Vec<u32>,
+	pub materials: Vec<Material>,
+	/// Material id of each triangle, indexing into `materials`.
+	pub face_materials: Vec<Option<usize>>,
+	pub parts: Vec<MeshPart>,
+	/// Vertex pairs drawn as a line list.
+	pub line_indices: Vec<u32>,
+	/// Vertices drawn as a point list.
+	pub point_indices: Vec<u32>,
+	pub attributes: Vec<VertexAttribute>,
+	/// Triangle count of each source polygon, whose triangles follow each
+	/// other in `indices`. Empty when the polygons were not kept.
+	pub polygons: Vec<u32>,
+	pub creases: Vec<Crease>,
+}
+
+impl Mesh {
+	pub fn toggle_part(&mut self, part: usize) {
+		if let Some(p) = self.parts.get_mut(part) {
+			p.visible = !p.visible;
+		}
+	}
+
+	pub fn isolate_part(&mut self, part: usize) {
+		for (i, p) in self.parts.iter_mut().enumerate() {
+			p.visible = i == part;
+		}
+	}
+
+	pub fn show_all_parts(&mut self) {
+		for p in &mut self.parts {
+			p.visible = true;
+		}
+	}
+
+	pub fn attribute(&self, name: &str) -> Option<&VertexAttribute> {
+		self.attributes.iter().find(|a| a.name == name)
+	}
+
+	/// Returns the first diffuse texture referenced by the mesh materials.
+	pub fn diffuse_texture(&self) -> Option<&str> {
+		self.materials.iter().find_map(|m| m.diffuse_map.as_deref())
+	}
+
+	pub fn compute_bounding_box(&self) -> ([f32; 3], [f32; 3]) {
+		if self.vertices.is_empty() {
+			return ([0.0; 3], [0.0; 3]);
+		}
+
+		let mut min = self.vertices[0].position;
+		let mut max = self.vertices[0].position;
+
+		for vertex in &self.vertices {
+			for i in 0..3 {
+				min[i] = min[i].min(vertex.position[i]);
+				max[i] = max[i].max(vertex.position[i]);
+			}
+		}
+
+		(min, max)
+	}
+
+pub fn normalize(&mut self) {
+    let (min, max) = self.compute_bounding_box();
+
+    let center = [
+        (min[0] + max[0]) / 2.0,
+        (min[1] + max[1]) / 2.0,
+        (min[2] + max[2]) / 2.0,
+    ];
+
+    let max_half_extent = (0..3)
+        .map(|i| (max[i] - min[i]) / 2.0)
+        .fold(0.0_f32, f32::max);
+
+    for vertex in &mut self.vertices {
+        for i in 0..3 {
+            vertex.position[i] = (vertex.position[i] - center[i]) / max_half_extent;
+        }
+    }
+}
+
+	pub fn compute_dominant_axis(&self) -> DominantAxis {
+		let bounding_box = self.compute_bounding_box();
+		let mut min_value = 2.0;
+		let mut min_indice: usize = 0;
+
+		for i in 0..3 {
+			let min = bounding_box.0[i];
+			let max = bounding_box.1[i];
+			let dim = (max - min).abs();
+			if dim < min_value {
+				min_value = dim;
+				min_indice = i;
+			}
+		}
+
+		match min_indice {
+			0 => DominantAxis::X,
+			1 => DominantAxis::Y,
+			2 => DominantAxis::Z,
+			_ => DominantAxis::X,
+		}
+	}
+}
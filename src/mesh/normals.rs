@@ -0,0 +1,29 @@
+/// Area-weighted vertex normals. Vertices outside any triangle get a zero
+/// normal, which the shaders draw unlit.
+pub(crate) fn smooth_normals(positions: &[[f32; 3]], triangles: &[[u32; 3]]) -> Vec<[f32; 3]> {
+	let mut sums = vec![[0.0_f32; 3]; positions.len()];
+
+	for triangle in triangles {
+		let [a, b, c] = triangle.map(|i| positions[i as usize]);
+		let e1 = [b[0] - a[0], b[1] - a[1], b[2] - a[2]];
+		let e2 = [c[0] - a[0], c[1] - a[1], c[2] - a[2]];
+		let cross = [
+			e1[1] * e2[2] - e1[2] * e2[1],
+			e1[2] * e2[0] - e1[0] * e2[2],
+			e1[0] * e2[1] - e1[1] * e2[0],
+		];
+
+		for &i in triangle {
+			for axis in 0..3 {
+				sums[i as usize][axis] += cross[axis];
+			}
+		}
+	}
+
+	sums.into_iter()
+		.map(|n| {
+			let len = (n[0] * n[0] + n[1] * n[1] + n[2] * n[2]).sqrt();
+			if len > 1e-12 { [n[0] / len, n[1] / len, n[2] / len] } else { [0.0; 3] }
+		})
+		.collect()
+}
@@ -0,0 +1,757 @@
+use std::cmp::Ordering;
+use std::collections::{BinaryHeap, HashMap};
+
+use crate::mesh::half_edge::weld;
+use crate::mesh::Mesh;
+
+/// Weight of the planes holding borders and seams in place, relative to the
+/// planes of the surface itself.
+const BORDER_WEIGHT: f64 = 10.0;
+
+/// Options of [`Mesh::simplify_with`].
+#[derive(Debug, Clone)]
+pub struct SimplifyOptions {
+	/// Fraction of the triangles to keep, in `[0, 1]`.
+	pub target_ratio: f32,
+	/// Largest change, in degrees, a collapse may make to a face normal, and
+	/// largest angle between the normals of vertex copies merged across a
+	/// shading split.
+	pub max_normal_angle: f32,
+	/// Collapses whose estimated error exceeds this distance are not done,
+	/// even if the target is not reached.
+	pub max_error: f32,
+}
+
+impl Default for SimplifyOptions {
+	fn default() -> Self {
+		Self {
+			target_ratio: 0.5,
+			max_normal_angle: 45.0,
+			max_error: f32::INFINITY,
+		}
+	}
+}
+
+/// Outcome of a simplification.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SimplifyReport {
+	pub triangles_before: usize,
+	pub triangles_after: usize,
+	pub vertices_before: usize,
+	pub vertices_after: usize,
+	/// Triangle count asked for. Seams, borders and the limits of the options
+	/// can stop the collapses before it is reached.
+	pub target_triangles: usize,
+	/// Estimated distance between the simplified and the original surface,
+	/// in model units: the root mean square distance of the worst collapse
+	/// to the planes it merged.
+	pub error: f32,
+}
+
+impl Mesh {
+	/// Reduces the triangle count to about `target_ratio` of the original
+	/// with quadric error metrics. See [`Mesh::simplify_with`].
+	pub fn simplify(&mut self, target_ratio: f32) -> SimplifyReport {
+		self.simplify_with(&SimplifyOptions { target_ratio, ..Default::default() })
+	}
+
+	/// Simplifies the triangles by collapsing edges, cheapest first, where
+	/// the cost is the squared distance to the planes of the merged area.
+	///
+	/// Vertices only move onto a neighbor, so no attribute is interpolated.
+	/// Copies of a vertex with the same UV and normals within
+	/// `max_normal_angle` are merged first, with their normals averaged, so
+	/// flat shading does not hold the surface in place. Border vertices slide
+	/// along the border, and vertices split by a UV, sharper normal or
+	/// material seam slide along the seam with all their copies. Vertices
+	/// used by lines or points stay in place, and vertices left unreferenced
+	/// are dropped.
+	pub fn simplify_with(&mut self, options: &SimplifyOptions) -> SimplifyReport {
+		let triangles_before = self.indices.len() / 3;
+		let vertices_before = self.vertices.len();
+		let target = (triangles_before as f32 * options.target_ratio.clamp(0.0, 1.0)).round() as usize;
+		let min_cos = (options.max_normal_angle.clamp(0.0, 180.0) as f64).to_radians().cos();
+
+		if target < triangles_before {
+			self.merge_wedges(min_cos);
+		}
+		let mut simplifier = Simplifier::new(self, min_cos);
+		let max_cost = (options.max_error as f64).powi(2);
+		let mut error: f64 = 0.0;
+
+		while simplifier.alive_count > target {
+			let Some(candidate) = simplifier.heap.pop() else {
+				break;
+			};
+			if simplifier.is_stale(&candidate) {
+				continue;
+			}
+			if candidate.cost > max_cost {
+				break;
+			}
+			match simplifier.plan_collapse(candidate.from, candidate.to) {
+				Some(mapping) => {
+					simplifier.collapse(candidate.from, candidate.to, &mapping);
+					error = error.max(candidate.cost);
+				}
+				None => simplifier.push_fallback(candidate.from),
+			}
+		}
+
+		let (triangles, alive) = (simplifier.triangles, simplifier.alive);
+		self.rebuild(&triangles, &alive);
+
+		SimplifyReport {
+			triangles_before,
+			triangles_after: self.indices.len() / 3,
+			vertices_before,
+			vertices_after: self.vertices.len(),
+			target_triangles: target,
+			error: error.sqrt() as f32,
+		}
+	}
+
+	/// Points the triangles at one copy of each vertex among those sharing a
+	/// position and UV, with normals at most `acos(min_cos)` apart. The copies
+	/// kept get the average normal of the merged ones.
+	fn merge_wedges(&mut self, min_cos: f64) {
+		let (positions, welded) = weld(&self.vertices);
+		// Kept copy and normal sum of each group, by welded vertex
+		let mut groups: Vec<Vec<(u32, [f64; 3])>> = vec![Vec::new(); positions.len()];
+		let mut merged_into: Vec<u32> = (0..self.vertices.len() as u32).collect();
+
+		for (i, vertex) in self.vertices.iter().enumerate() {
+			let normal = vertex.normal.map(|c| c as f64);
+			let unit = normalize(normal);
+			let group = groups[welded[i] as usize].iter_mut().find(|(kept, _)| {
+				let other = &self.vertices[*kept as usize];
+				other.tex_coords == vertex.tex_coords && match (unit, normalize(other.normal.map(|c| c as f64))) {
+					(Some(a), Some(b)) => dot(a, b) >= min_cos,
+					_ => other.normal == vertex.normal,
+				}
+			});
+			match group {
+				Some((kept, sum)) => {
+					merged_into[i] = *kept;
+					*sum = add(*sum, unit.unwrap_or(normal));
+				}
+				None => groups[welded[i] as usize].push((i as u32, unit.unwrap_or(normal))),
+			}
+		}
+
+		for &(kept, sum) in groups.iter().flatten() {
+			if let Some(normal) = normalize(sum) {
+				self.vertices[kept as usize].normal = normal.map(|c| c as f32);
+			}
+		}
+		for i in &mut self.indices {
+			*i = merged_into[*i as usize];
+		}
+	}
+
+	/// Keeps the triangles flagged in `alive` and drops unreferenced vertices.
+	fn rebuild(&mut self, triangles: &[[u32; 3]], alive: &[bool]) {
+		let triangle_count = alive.len();
+
+		// Parts are index ranges, shifted by the triangles removed before them
+		let mut kept_before = vec![0; triangle_count + 1];
+		for (t, &keep) in alive.iter().enumerate() {
+			kept_before[t + 1] = kept_before[t] + keep as u32;
+		}
+		for part in &mut self.parts {
+			let first = (part.first_index / 3) as usize;
+			let end = ((part.first_index + part.index_count) / 3) as usize;
+			part.first_index = kept_before[first.min(triangle_count)] * 3;
+			part.index_count = (kept_before[end.min(triangle_count)] - kept_before[first.min(triangle_count)]) * 3;
+		}
+
+		// Collapsed polygons are no longer the source ones
+		if alive.iter().any(|&keep| !keep) {
+			self.polygons.clear();
+		}
+		if self.face_materials.len() == triangle_count {
+			let materials = std::mem::take(&mut self.face_materials);
+			self.face_materials = materials.into_iter().zip(alive).filter(|(_, keep)| **keep).map(|(m, _)| m).collect();
+		}
+		self.indices = triangles.iter().zip(alive)
+			.filter(|(_, keep)| **keep)
+			.flat_map(|(triangle, _)| triangle.iter().copied())
+			.collect();
+
+		let mut used = vec![false; self.vertices.len()];
+		for &i in self.indices.iter().chain(&self.line_indices).chain(&self.point_indices) {
+			used[i as usize] = true;
+		}
+		let mut remap = vec![u32::MAX; self.vertices.len()];
+		for (kept, (slot, _)) in remap.iter_mut().zip(&used).filter(|(_, used)| **used).enumerate() {
+			*slot = kept as u32;
+		}
+
+		let keep = |i: usize| used[i];
+		let vertices = std::mem::take(&mut self.vertices);
+		self.vertices = vertices.into_iter().enumerate().filter(|&(i, _)| keep(i)).map(|(_, v)| v).collect();
+		for attribute in &mut self.attributes {
+			let values = std::mem::take(&mut attribute.values);
+			attribute.values = values.into_iter().enumerate().filter(|&(i, _)| keep(i)).map(|(_, v)| v).collect();
+		}
+		for i in self.indices.iter_mut().chain(&mut self.line_indices).chain(&mut self.point_indices) {
+			*i = remap[*i as usize];
+		}
+		self.creases.retain(|crease| crease.edge.iter().all(|&i| used[i as usize]));
+		for crease in &mut self.creases {
+			crease.edge = crease.edge.map(|i| remap[i as usize]);
+		}
+	}
+}
+
+/// Symmetric 4x4 matrix summing squared distances to weighted planes, stored
+/// as its upper triangle.
+#[derive(Debug, Clone, Copy, Default)]
+struct Quadric {
+	m: [f64; 10],
+	weight: f64,
+}
+
+impl Quadric {
+	/// Plane `n.p + d = 0` with a unit normal.
+	fn plane(n: [f64; 3], d: f64, weight: f64) -> Self {
+		let [a, b, c] = n;
+		let m = [a * a, a * b, a * c, a * d, b * b, b * c, b * d, c * c, c * d, d * d];
+		Self { m: m.map(|v| v * weight), weight }
+	}
+
+	fn add(&mut self, other: &Quadric) {
+		for (a, b) in self.m.iter_mut().zip(&other.m) {
+			*a += b;
+		}
+		self.weight += other.weight;
+	}
+
+	/// Mean squared distance from `p` to the planes.
+	fn error(&self, [x, y, z]: [f64; 3]) -> f64 {
+		let m = &self.m;
+		let sum = m[0] * x * x + m[4] * y * y + m[7] * z * z
+			+ 2.0 * (m[1] * x * y + m[2] * x * z + m[5] * y * z)
+			+ 2.0 * (m[3] * x + m[6] * y + m[8] * z)
+			+ m[9];
+
+		if self.weight > 0.0 { sum.max(0.0) / self.weight } else { 0.0 }
+	}
+}
+
+/// Collapse of welded vertex `from` onto its neighbor `to`.
+struct Candidate {
+	cost: f64,
+	from: usize,
+	to: usize,
+	version: u32,
+}
+
+impl PartialEq for Candidate {
+	fn eq(&self, other: &Self) -> bool {
+		self.cost.total_cmp(&other.cost) == Ordering::Equal
+	}
+}
+
+impl Eq for Candidate {}
+
+impl PartialOrd for Candidate {
+	fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+		Some(self.cmp(other))
+	}
+}
+
+impl Ord for Candidate {
+	/// Reversed so the heap pops the cheapest collapse first.
+	fn cmp(&self, other: &Self) -> Ordering {
+		other.cost.total_cmp(&self.cost)
+	}
+}
+
+/// Edge collapse state. Vertices sharing a position are welded so that
+/// seams are seen as the interior edges they are; the mesh vertices of a
+/// welded vertex are its wedges.
+struct Simplifier<'a> {
+	mesh: &'a Mesh,
+	triangles: Vec<[u32; 3]>,
+	alive: Vec<bool>,
+	alive_count: usize,
+	original_normals: Vec<[f64; 3]>,
+	welded: Vec<usize>,
+	/// Mesh vertices of the triangles at each welded vertex, emptied once it
+	/// is collapsed.
+	wedges: Vec<Vec<u32>>,
+	/// Triangles of each mesh vertex, dead ones included.
+	vertex_triangles: Vec<Vec<usize>>,
+	quadrics: Vec<Quadric>,
+	/// Bumped when the neighborhood changes, to discard outdated candidates.
+	versions: Vec<u32>,
+	/// Welded vertices drawn by lines or points.
+	pinned: Vec<bool>,
+	min_cos: f64,
+	heap: BinaryHeap<Candidate>,
+}
+
+impl<'a> Simplifier<'a> {
+	fn new(mesh: &'a Mesh, min_cos: f64) -> Self {
+		let (positions, welded) = weld(&mesh.vertices);
+		let welded: Vec<usize> = welded.into_iter().map(|id| id as usize).collect();
+		// Copies merged away by `merge_wedges` are no longer wedges
+		let mut used = vec![false; mesh.vertices.len()];
+		for &i in &mesh.indices {
+			used[i as usize] = true;
+		}
+		let mut wedges: Vec<Vec<u32>> = vec![Vec::new(); positions.len()];
+		for (i, &id) in welded.iter().enumerate().filter(|&(i, _)| used[i]) {
+			wedges[id].push(i as u32);
+		}
+
+		let triangles: Vec<[u32; 3]> = mesh.indices.chunks_exact(3).map(|t| [t[0], t[1], t[2]]).collect();
+		let mut simplifier = Self {
+			mesh,
+			alive: vec![false; triangles.len()],
+			alive_count: 0,
+			original_normals: vec![[0.0; 3]; triangles.len()],
+			vertex_triangles: vec![Vec::new(); mesh.vertices.len()],
+			quadrics: vec![Quadric::default(); wedges.len()],
+			versions: vec![0; wedges.len()],
+			pinned: vec![false; wedges.len()],
+			min_cos,
+			heap: BinaryHeap::new(),
+			triangles,
+			welded,
+			wedges,
+		};
+		for &i in mesh.line_indices.iter().chain(&mesh.point_indices) {
+			simplifier.pinned[simplifier.welded[i as usize]] = true;
+		}
+
+		let mut edge_uses: HashMap<(u32, u32), (u32, usize)> = HashMap::new();
+		for t in 0..simplifier.triangles.len() {
+			let triangle = simplifier.triangles[t];
+			let [a, b, c] = triangle.map(|i| simplifier.welded[i as usize]);
+			let (normal, area) = simplifier.normal_and_area(t, None);
+			// Degenerate triangles are dropped
+			if a == b || b == c || a == c || area <= 0.0 {
+				continue;
+			}
+
+			simplifier.alive[t] = true;
+			simplifier.alive_count += 1;
+			simplifier.original_normals[t] = normal;
+			let p = simplifier.position(a);
+			let plane = Quadric::plane(normal, -dot(normal, p), area);
+			for id in [a, b, c] {
+				simplifier.quadrics[id].add(&plane);
+			}
+			for &i in &triangle {
+				simplifier.vertex_triangles[i as usize].push(t);
+			}
+			for k in 0..3 {
+				let (i, j) = (triangle[k], triangle[(k + 1) % 3]);
+				edge_uses.entry((i.min(j), i.max(j))).or_insert((0, t)).0 += 1;
+			}
+		}
+
+		// Edges of a single triangle are borders or seams, held by a plane
+		// through the edge perpendicular to the surface
+		for (&(i, j), &(uses, t)) in &edge_uses {
+			if uses != 1 {
+				continue;
+			}
+			let (a, b) = (simplifier.welded[i as usize], simplifier.welded[j as usize]);
+			let edge = sub(simplifier.position(b), simplifier.position(a));
+			let length_squared = dot(edge, edge);
+			if let Some(normal) = normalize(cross(edge, simplifier.original_normals[t])) {
+				let plane = Quadric::plane(normal, -dot(normal, simplifier.position(a)), length_squared * BORDER_WEIGHT);
+				simplifier.quadrics[a].add(&plane);
+				simplifier.quadrics[b].add(&plane);
+			}
+		}
+
+		for id in 0..simplifier.wedges.len() {
+			simplifier.push(id);
+		}
+
+		simplifier
+	}
+
+	fn position(&self, id: usize) -> [f64; 3] {
+		self.mesh.vertices[self.wedges[id][0] as usize].position.map(|c| c as f64)
+	}
+
+	/// Unit normal and area of triangle `t`, with welded vertex `moved.0`
+	/// placed at `moved.1` when given.
+	fn normal_and_area(&self, t: usize, moved: Option<(usize, [f64; 3])>) -> ([f64; 3], f64) {
+		let [p0, p1, p2] = self.triangles[t].map(|i| {
+			let id = self.welded[i as usize];
+			match moved {
+				Some((from, to)) if from == id => to,
+				_ => self.mesh.vertices[i as usize].position.map(|c| c as f64),
+			}
+		});
+		let n = cross(sub(p1, p0), sub(p2, p0));
+		let len = dot(n, n).sqrt();
+
+		if len > 1e-20 { (n.map(|c| c / len), len / 2.0) } else { ([0.0; 3], 0.0) }
+	}
+
+	fn triangles_of(&self, id: usize) -> Vec<usize> {
+		let mut triangles: Vec<usize> = self.wedges[id].iter()
+			.flat_map(|&w| self.vertex_triangles[w as usize].iter().copied())
+			.filter(|&t| self.alive[t])
+			.collect();
+		triangles.sort_unstable();
+		triangles.dedup();
+		triangles
+	}
+
+	fn contains(&self, t: usize, id: usize) -> bool {
+		self.triangles[t].iter().any(|&i| self.welded[i as usize] == id)
+	}
+
+	fn cost(&self, from: usize, to: usize) -> f64 {
+		let mut quadric = self.quadrics[from];
+		quadric.add(&self.quadrics[to]);
+		quadric.error(self.position(to))
+	}
+
+	/// Queues the cheapest collapse of `from`, one candidate per vertex.
+	fn push(&mut self, from: usize) {
+		let best = self.neighbors(from).into_iter()
+			.map(|to| (self.cost(from, to), to))
+			.min_by(|a, b| a.0.total_cmp(&b.0));
+		if let Some((cost, to)) = best {
+			self.heap.push(Candidate { cost, from, to, version: self.versions[from] });
+		}
+	}
+
+	/// Queues the cheapest collapse of `from` that passes the checks, after
+	/// the cheapest one failed them.
+	fn push_fallback(&mut self, from: usize) {
+		let mut options: Vec<(f64, usize)> = self.neighbors(from).into_iter()
+			.map(|to| (self.cost(from, to), to))
+			.collect();
+		options.sort_by(|a, b| a.0.total_cmp(&b.0));
+		if let Some(&(cost, to)) = options.iter().find(|&&(_, to)| self.plan_collapse(from, to).is_some()) {
+			self.heap.push(Candidate { cost, from, to, version: self.versions[from] });
+		}
+	}
+
+	/// A vertex is queued again whenever its neighborhood changes.
+	fn is_stale(&self, candidate: &Candidate) -> bool {
+		self.wedges[candidate.from].is_empty() || candidate.version != self.versions[candidate.from]
+	}
+
+	/// Checks that moving `from` onto `to` keeps the mesh sound, and returns
+	/// the wedge of `to` each wedge of `from` becomes.
+	fn plan_collapse(&self, from: usize, to: usize) -> Option<Vec<(u32, u32)>> {
+		if self.pinned[from] {
+			return None;
+		}
+		let triangles = self.triangles_of(from);
+
+		// Neighbors of `from` with the number of triangles on their edge
+		let mut edges: Vec<(usize, usize)> = Vec::new();
+		for &t in &triangles {
+			for &i in &self.triangles[t] {
+				let id = self.welded[i as usize];
+				match edges.iter_mut().find(|(other, _)| *other == id) {
+					Some((_, uses)) => *uses += 1,
+					None if id != from => edges.push((id, 1)),
+					None => {}
+				}
+			}
+		}
+		let shared = edges.iter().find(|&&(id, _)| id == to)?.1;
+		if edges.iter().any(|&(_, uses)| uses > 2) {
+			return None;
+		}
+
+		// A border vertex may only slide along its border
+		let border_edges = edges.iter().filter(|&&(_, uses)| uses == 1).count();
+		if border_edges > 0 && (border_edges != 2 || shared != 1) {
+			return None;
+		}
+
+		// Neighbors common to both ends must be the tips of the collapsed
+		// triangles, or the surface would fold onto itself
+		let common = self.neighbors(to).into_iter()
+			.filter(|&id| id != from && edges.iter().any(|&(other, _)| other == id))
+			.count();
+		if common != shared {
+			return None;
+		}
+
+		let mut mapping = Vec::new();
+		for &wedge in &self.wedges[from] {
+			let mut targets: Vec<u32> = self.vertex_triangles[wedge as usize].iter()
+				.filter(|&&t| self.alive[t])
+				.flat_map(|&t| self.triangles[t].iter().copied())
+				.filter(|&i| self.welded[i as usize] == to)
+				.collect();
+			targets.sort_unstable();
+			targets.dedup();
+			match targets[..] {
+				[target] => mapping.push((wedge, target)),
+				_ => return None,
+			}
+		}
+
+		for &(wedge, target) in &mapping {
+			let a = self.mesh.vertices[wedge as usize].normal;
+			let b = self.mesh.vertices[target as usize].normal;
+			if let (Some(a), Some(b)) = (normalize(a.map(|c| c as f64)), normalize(b.map(|c| c as f64)))
+				&& dot(a, b) < self.min_cos
+			{
+				return None;
+			}
+		}
+
+		let destination = self.position(to);
+		for &t in triangles.iter().filter(|&&t| !self.contains(t, to)) {
+			let (normal, area) = self.normal_and_area(t, Some((from, destination)));
+			if area <= 0.0 || dot(normal, self.original_normals[t]) < self.min_cos {
+				return None;
+			}
+		}
+
+		Some(mapping)
+	}
+
+	fn collapse(&mut self, from: usize, to: usize, mapping: &[(u32, u32)]) {
+		for t in self.triangles_of(from) {
+			if self.contains(t, to) {
+				self.alive[t] = false;
+				self.alive_count -= 1;
+				continue;
+			}
+			for corner in &mut self.triangles[t] {
+				if let Some(&(_, target)) = mapping.iter().find(|&&(wedge, _)| wedge == *corner) {
+					*corner = target;
+					self.vertex_triangles[target as usize].push(t);
+				}
+			}
+		}
+
+		let quadric = self.quadrics[from];
+		self.quadrics[to].add(&quadric);
+		self.wedges[from].clear();
+
+		// The cost of `to` changed, and so did the neighborhoods checked by
+		// the collapses of its neighbors
+		let mut changed = self.neighbors(to);
+		changed.push(to);
+		for id in changed {
+			for &wedge in &self.wedges[id] {
+				let alive = &self.alive;
+				self.vertex_triangles[wedge as usize].retain(|&t| alive[t]);
+			}
+			self.versions[id] += 1;
+			self.push(id);
+		}
+	}
+
+	fn neighbors(&self, id: usize) -> Vec<usize> {
+		let mut neighbors: Vec<usize> = self.triangles_of(id).iter()
+			.flat_map(|&t| self.triangles[t].map(|i| self.welded[i as usize]))
+			.filter(|&other| other != id)
+			.collect();
+		neighbors.sort_unstable();
+		neighbors.dedup();
+		neighbors
+	}
+}
+
+fn add(a: [f64; 3], b: [f64; 3]) -> [f64; 3] {
+	[a[0] + b[0], a[1] + b[1], a[2] + b[2]]
+}
+
+fn sub(a: [f64; 3], b: [f64; 3]) -> [f64; 3] {
+	[a[0] - b[0], a[1] - b[1], a[2] - b[2]]
+}
+
+fn dot(a: [f64; 3], b: [f64; 3]) -> f64 {
+	a[0] * b[0] + a[1] * b[1] + a[2] * b[2]
+}
+
+fn cross(a: [f64; 3], b: [f64; 3]) -> [f64; 3] {
+	[
+		a[1] * b[2] - a[2] * b[1],
+		a[2] * b[0] - a[0] * b[2],
+		a[0] * b[1] - a[1] * b[0],
+	]
+}
+
+fn normalize(v: [f64; 3]) -> Option<[f64; 3]> {
+	let len = dot(v, v).sqrt();
+	(len > 1e-20).then(|| v.map(|c| c / len))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mesh::{MeshPart, Vertex};
+
+    /// Unit grid of `n` by `n` quads lifted by `height`. With `seam`, the
+    /// right half uses its own vertices and UVs, shifted by 0.5 in u.
+    fn grid(n: usize, seam: bool, height: impl Fn(f32, f32) -> f32) -> Mesh {
+        let mut vertices = Vec::new();
+        let mut vertex = |x: usize, y: usize, offset: f32| {
+            let (u, v) = (x as f32 / n as f32, y as f32 / n as f32);
+            vertices.push(Vertex {
+                position: [u, v, height(u, v)],
+                tex_coords: [u + offset, v],
+                normal: [0.0, 0.0, 1.0],
+                ..Vertex::default()
+            });
+            (vertices.len() - 1) as u32
+        };
+        let left: Vec<Vec<u32>> = (0..=n).map(|y| (0..=n).map(|x| vertex(x, y, 0.0)).collect()).collect();
+        let right: Vec<Vec<u32>> = (0..=n).map(|y| (0..=n).map(|x| vertex(x, y, 0.5)).collect()).collect();
+
+        let mut indices = Vec::new();
+        for y in 0..n {
+            for x in 0..n {
+                let ids = if seam && x >= n / 2 { &right } else { &left };
+                let [a, b, c, d] = [ids[y][x], ids[y][x + 1], ids[y + 1][x + 1], ids[y + 1][x]];
+                indices.extend([a, b, c, a, c, d]);
+            }
+        }
+
+        let mut mesh = Mesh {
+            vertices,
+            indices,
+            materials: Vec::new(),
+            face_materials: Vec::new(),
+            parts: Vec::new(),
+            line_indices: Vec::new(),
+            point_indices: Vec::new(),
+            attributes: Vec::new(),
+            polygons: Vec::new(),
+            creases: Vec::new(),
+        };
+        // Drop the unused vertices
+        let triangles: Vec<[u32; 3]> = mesh.indices.chunks_exact(3).map(|t| [t[0], t[1], t[2]]).collect();
+        mesh.rebuild(&triangles, &vec![true; triangles.len()]);
+        mesh
+    }
+
+    fn flat_options() -> SimplifyOptions {
+        SimplifyOptions { target_ratio: 0.0, max_error: 1e-5, ..Default::default() }
+    }
+
+    #[test]
+    fn flat_grid_collapses_to_its_corners() {
+        let mut mesh = grid(8, false, |_, _| 0.0);
+        let bounds = mesh.compute_bounding_box();
+
+        let report = mesh.simplify_with(&flat_options());
+
+        assert_eq!((report.triangles_before, report.vertices_before), (128, 81));
+        assert_eq!((report.triangles_after, report.vertices_after), (2, 4));
+        assert_eq!(mesh.vertices.len(), 4);
+        assert!(report.error < 1e-5);
+        assert_eq!(mesh.compute_bounding_box(), bounds);
+    }
+
+    #[test]
+    fn uv_seam_is_kept() {
+        let mut mesh = grid(8, true, |_, _| 0.0);
+
+        let report = mesh.simplify_with(&flat_options());
+
+        // Each chart keeps its own rectangle, meeting along the seam
+        assert_eq!(report.triangles_after, 4);
+        for triangle in mesh.indices.chunks_exact(3) {
+            let charts: Vec<bool> = triangle.iter().map(|&i| mesh.vertices[i as usize].tex_coords[0] > 0.75).collect();
+            assert!(charts.iter().all(|&c| c == charts[0]));
+        }
+        let on_seam = |right: bool| {
+            let mut positions: Vec<[u32; 3]> = mesh.vertices.iter()
+                .filter(|v| v.position[0] == 0.5 && (v.tex_coords[0] > 0.75) == right)
+                .map(|v| v.position.map(f32::to_bits))
+                .collect();
+            positions.sort_unstable();
+            positions
+        };
+        assert_eq!(on_seam(false).len(), 2);
+        assert_eq!(on_seam(false), on_seam(true));
+    }
+
+    #[test]
+    fn curved_surface_reports_its_error() {
+        let bump = |x: f32, y: f32| 0.2 * (x * std::f32::consts::PI).sin() * (y * std::f32::consts::PI).sin();
+        let mut mesh = grid(16, false, bump);
+        mesh.face_materials = vec![Some(0); 512];
+        mesh.parts = vec![
+            MeshPart { name: "a".to_string(), first_index: 0, index_count: 768, visible: true },
+            MeshPart { name: "b".to_string(), first_index: 768, index_count: 768, visible: true },
+        ];
+
+        let report = mesh.simplify(0.25);
+
+        assert!(report.triangles_after <= 128);
+        assert!(report.error > 0.0 && report.error < 0.05, "error {}", report.error);
+        assert_eq!(mesh.face_materials.len(), report.triangles_after);
+        assert_eq!(mesh.parts[1].first_index, mesh.parts[0].index_count);
+        assert_eq!((mesh.parts[1].first_index + mesh.parts[1].index_count) as usize, mesh.indices.len());
+        assert!(mesh.vertices.iter().all(|v| (v.position[2] - bump(v.position[0], v.position[1])).abs() < 1e-6));
+    }
+
+    #[test]
+    fn flat_shading_is_simplified() {
+        // Every triangle has its own vertices with the face normal, as in STL
+        let bump = |x: f32, y: f32| 0.2 * (x * std::f32::consts::PI).sin() * (y * std::f32::consts::PI).sin();
+        let smooth = grid(16, false, bump);
+        let mut mesh = smooth.clone();
+        mesh.vertices = smooth.indices.chunks_exact(3)
+            .flat_map(|t| {
+                let [a, b, c] = [0, 1, 2].map(|k| smooth.vertices[t[k] as usize]);
+                let (u, v) = (sub(b.position.map(f64::from), a.position.map(f64::from)), sub(c.position.map(f64::from), a.position.map(f64::from)));
+                let normal = normalize(cross(u, v)).unwrap().map(|c| c as f32);
+                [a, b, c].map(|vertex| Vertex { normal, ..vertex })
+            })
+            .collect();
+        mesh.indices = (0..mesh.vertices.len() as u32).collect();
+
+        let report = mesh.simplify(0.25);
+
+        assert_eq!(report.target_triangles, 128);
+        assert!(report.triangles_after <= report.target_triangles, "{} triangles", report.triangles_after);
+        assert!(report.error > 0.0);
+        assert!(mesh.vertices.iter().all(|v| v.normal[2] > 0.7));
+    }
+
+    #[test]
+    fn sharp_normal_splits_are_kept() {
+        // Two flat halves folded along x = 0.5, split only by their normals
+        let mut mesh = grid(8, true, |x, _| 2.0 * (x - 0.5).max(0.0));
+        let slope = [-2.0 / 5.0_f32.sqrt(), 0.0, 1.0 / 5.0_f32.sqrt()];
+        for vertex in &mut mesh.vertices {
+            let right = vertex.tex_coords[0] > vertex.position[0] + 0.25;
+            vertex.normal = if right { slope } else { [0.0, 0.0, 1.0] };
+            vertex.tex_coords = [0.0, 0.0];
+        }
+
+        let report = mesh.simplify_with(&flat_options());
+
+        assert_eq!(report.triangles_after, 4);
+        for triangle in mesh.indices.chunks_exact(3) {
+            let normals: Vec<[f32; 3]> = triangle.iter().map(|&i| mesh.vertices[i as usize].normal).collect();
+            assert!(normals.iter().all(|&n| n == normals[0]));
+        }
+    }
+
+    #[test]
+    fn full_ratio_changes_nothing() {
+        let mut mesh = grid(4, true, |x, y| x * y);
+        let indices = mesh.indices.clone();
+
+        let report = mesh.simplify(1.0);
+
+        assert_eq!(report.triangles_after, report.triangles_before);
+        assert_eq!(report.error, 0.0);
+        assert_eq!(mesh.indices, indices);
+    }
+}
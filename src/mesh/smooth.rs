@@ -0,0 +1,117 @@
+use crate::mesh::half_edge::HalfEdgeMesh;
+use crate::mesh::normals::smooth_normals;
+use crate::mesh::Mesh;
+
+/// Taubin's shrinking and inflating factors, the second one pulling back what
+/// the first took in.
+const LAMBDA: f32 = 0.5;
+const MU: f32 = -0.53;
+
+impl Mesh {
+	/// Smooths the surface `iterations` times with Taubin's Laplacian filter,
+	/// which evens out noise while barely shrinking the mesh.
+	///
+	/// Topology follows positions, so UV and normal seams move together, and
+	/// border and non-manifold vertices stay in place. Polygons are kept,
+	/// normals recomputed within the seams they had, and tangents cleared.
+	pub fn smooth(&mut self, iterations: usize) {
+		if iterations == 0 || self.indices.len() < 3 {
+			return;
+		}
+
+		let mut topology = HalfEdgeMesh::from_mesh(self);
+		// Non-manifold edges have no twins, so their ends are border vertices
+		let neighbors: Vec<Vec<u32>> = topology.vertices()
+			.map(|v| if topology.is_border_vertex(v) { Vec::new() } else { topology.neighbors(v) })
+			.collect();
+
+		for _ in 0..iterations {
+			for factor in [LAMBDA, MU] {
+				let positions = topology.positions.clone();
+				for (position, neighbors) in topology.positions.iter_mut().zip(&neighbors) {
+					if neighbors.is_empty() {
+						continue;
+					}
+					let center = neighbors.iter().fold([0.0; 3], |sum, &n| {
+						let p = positions[n as usize];
+						[sum[0] + p[0], sum[1] + p[1], sum[2] + p[2]]
+					});
+					for axis in 0..3 {
+						position[axis] += (center[axis] / neighbors.len() as f32 - position[axis]) * factor;
+					}
+				}
+			}
+		}
+
+		*self = topology.to_mesh(self);
+		let positions: Vec<[f32; 3]> = self.vertices.iter().map(|v| v.position).collect();
+		let triangles: Vec<[u32; 3]> = self.indices.chunks_exact(3).map(|t| [t[0], t[1], t[2]]).collect();
+		// Vertices split at hard edges stay split, keeping the edges hard
+		for (vertex, normal) in self.vertices.iter_mut().zip(smooth_normals(&positions, &triangles)) {
+			if normal != [0.0; 3] {
+				vertex.normal = normal;
+			}
+			vertex.tangent = [0.0; 4];
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mesh::fixtures::{self, cube};
+
+    /// 5 by 5 grid of quads, its inner vertices lifted by alternating
+    /// heights.
+    fn bumpy_grid() -> Mesh {
+        fixtures::bumpy_grid(5, |u, v| {
+            let (x, y) = ((u * 5.0).round() as i32, (v * 5.0).round() as i32);
+            if !(1..5).contains(&x) || !(1..5).contains(&y) {
+                0.0
+            } else if (x + y) % 2 == 0 {
+                0.2
+            } else {
+                -0.2
+            }
+        })
+    }
+
+    fn max_height(mesh: &Mesh) -> f32 {
+        mesh.vertices.iter().map(|v| v.position[2].abs()).fold(0.0, f32::max)
+    }
+
+    #[test]
+    fn bumps_flatten_and_borders_stay() {
+        let mut mesh = bumpy_grid();
+        let border: Vec<[f32; 3]> = mesh.vertices.iter()
+            .map(|v| v.position)
+            .filter(|p| p[0] == 0.0 || p[0] == 1.0 || p[1] == 0.0 || p[1] == 1.0)
+            .collect();
+
+        mesh.smooth(10);
+
+        assert!(max_height(&mesh) < 0.02, "{}", max_height(&mesh));
+        assert_eq!(mesh.polygons, vec![2; 25]);
+        for p in &border {
+            assert!(mesh.vertices.iter().any(|v| v.position == *p));
+        }
+        assert!(mesh.indices.iter().all(|&i| mesh.vertices[i as usize].normal[2] > 0.99));
+    }
+
+    #[test]
+    fn seams_move_together_and_hard_edges_stay() {
+        // Every face has its own smoothing group, so its own corners
+        let mut mesh = cube();
+        let vertex_count = mesh.vertices.len();
+
+        mesh.smooth(1);
+
+        assert_eq!(mesh.vertices.len(), vertex_count);
+        let size = mesh.vertices[0].position[0].abs();
+        assert!(size > 0.5 && size < 1.0, "{}", size);
+        for v in &mesh.vertices {
+            assert!(v.position.iter().all(|c| c.abs() == size));
+            assert_eq!(v.normal.iter().filter(|c| c.abs() > 1e-6).count(), 1, "{:?}", v.normal);
+        }
+    }
+}
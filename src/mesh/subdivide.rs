@@ -0,0 +1,526 @@
+use std::collections::HashMap;
+
+use crate::mesh::half_edge::{follow_origins, keep_loose_vertices, triangulate_face, HalfEdge, HalfEdgeMesh};
+use crate::mesh::{Crease, Mesh, Vertex};
+
+/// Subdivision rule of [`Mesh::subdivide`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Scheme {
+	/// Loop, splitting each triangle in four.
+	Loop,
+	/// Catmull-Clark, turning polygons of any size into quads.
+	CatmullClark,
+}
+
+impl Mesh {
+	/// Catmull-Clark when the mesh kept polygons other than triangles from
+	/// its source, Loop otherwise.
+	pub fn subdivision_scheme(&self) -> Scheme {
+		if self.polygons.iter().any(|&count| count > 1) {
+			Scheme::CatmullClark
+		} else {
+			Scheme::Loop
+		}
+	}
+
+	/// Subdivides the triangles and polygons `levels` times.
+	///
+	/// Topology follows positions, so UV and normal seams do not tear the
+	/// surface. UVs, colors and vertex attributes are interpolated linearly
+	/// within each face, and normals are recomputed, kept split across
+	/// creases. Creases lose one level of sharpness per step and borders stay
+	/// sharp. Tangents are cleared.
+	pub fn subdivide(&mut self, scheme: Scheme, levels: usize) {
+		if levels == 0 || self.indices.len() < 3 {
+			return;
+		}
+
+		let mut cage = Cage::from_mesh(self, scheme);
+		for _ in 0..levels {
+			cage = cage.subdivide(scheme);
+		}
+		cage.write_to(self, scheme);
+	}
+}
+
+/// Polygon mesh over welded positions, with the values interpolated within
+/// faces kept per corner.
+struct Cage {
+	positions: Vec<[f32; 3]>,
+	/// Start of each face in `corners`, followed by the end of the last.
+	face_starts: Vec<usize>,
+	/// Welded vertex of each face corner.
+	corners: Vec<u32>,
+	/// `stride` values per corner: UV, color, then the vertex attributes.
+	values: Vec<f32>,
+	stride: usize,
+	/// First source triangle of the polygon each face comes from.
+	origins: Vec<u32>,
+	/// Sharpness of creased edges, by their sorted ends.
+	creases: HashMap<(u32, u32), f32>,
+}
+
+impl Cage {
+	fn from_mesh(mesh: &Mesh, scheme: Scheme) -> Self {
+		let half_edges = match scheme {
+			Scheme::CatmullClark => HalfEdgeMesh::from_mesh(mesh),
+			Scheme::Loop => HalfEdgeMesh::from_triangles(mesh),
+		};
+
+		let mut cage = Self {
+			positions: half_edges.positions.clone(),
+			face_starts: vec![0],
+			corners: Vec::new(),
+			values: Vec::new(),
+			stride: 5 + mesh.attributes.len(),
+			origins: Vec::new(),
+			creases: HashMap::new(),
+		};
+
+		for f in half_edges.faces() {
+			for h in half_edges.face_half_edges(f) {
+				let half_edge = half_edges.half_edge(h);
+				let vertex = &mesh.vertices[half_edge.corner as usize];
+				cage.corners.push(half_edge.vertex);
+				cage.values.extend(vertex.tex_coords);
+				cage.values.extend(vertex.color);
+				cage.values.extend(mesh.attributes.iter().map(|a| a.values.get(half_edge.corner as usize).copied().unwrap_or(0.0)));
+			}
+			cage.face_starts.push(cage.corners.len());
+			cage.origins.push(half_edges.face(f).source);
+		}
+
+		for crease in &mesh.creases {
+			let [a, b] = crease.edge.map(|i| half_edges.vertex_of(i));
+			let sharpness = cage.creases.entry((a.min(b), a.max(b))).or_insert(0.0);
+			*sharpness = sharpness.max(crease.sharpness);
+		}
+
+		cage
+	}
+
+	fn corner_values(&self, corner: usize) -> &[f32] {
+		&self.values[corner * self.stride..(corner + 1) * self.stride]
+	}
+
+	/// Adjacency of the faces. Half-edge corners are cage corners and face
+	/// sources cage faces.
+	fn topology(&self) -> HalfEdgeMesh {
+		HalfEdgeMesh::from_faces(self.positions.clone(), &self.face_starts, &self.corners)
+	}
+
+	/// Crease sharpness of the edge of half-edge `h`, infinite on borders and
+	/// non-manifold edges.
+	fn sharpness(&self, topology: &HalfEdgeMesh, h: u32) -> f32 {
+		if topology.is_border_edge(h) {
+			return f32::INFINITY;
+		}
+		let (a, b) = (topology.half_edge(h).vertex, topology.destination(h));
+		self.creases.get(&(a.min(b), a.max(b))).copied().unwrap_or(0.0)
+	}
+
+	fn subdivide(&self, scheme: Scheme) -> Self {
+		let topology = self.topology();
+		let edges: Vec<u32> = topology.edges().collect();
+		let mut edge_of = vec![0; topology.half_edge_count()];
+		for (e, &h) in edges.iter().enumerate() {
+			edge_of[h as usize] = e as u32;
+			if let Some(twin) = topology.half_edge(h).twin {
+				edge_of[twin as usize] = e as u32;
+			}
+		}
+		let sharpness: Vec<f32> = edges.iter().map(|&h| self.sharpness(&topology, h)).collect();
+		let position = |v: u32| self.positions[v as usize];
+
+		let face_points: Vec<[f32; 3]> = topology.faces()
+			.map(|f| average(topology.face_vertices(f).map(position)))
+			.collect();
+
+		let edge_points: Vec<[f32; 3]> = edges.iter().zip(&sharpness)
+			.map(|(&h, &sharpness)| {
+				let half_edge = topology.half_edge(h);
+				let [a, b] = [half_edge.vertex, topology.destination(h)].map(position);
+				let middle = lerp(a, b, 0.5);
+				// Edges below full sharpness have a face on each side
+				let Some(twin) = half_edge.twin.filter(|_| sharpness < 1.0) else {
+					return middle;
+				};
+
+				let smooth = match scheme {
+					Scheme::CatmullClark => {
+						let [f0, f1] = [h, twin].map(|h| face_points[topology.half_edge(h).face as usize]);
+						average([a, b, f0, f1].into_iter())
+					}
+					Scheme::Loop => {
+						// The corner of a triangle off an edge starts the half-edge before it
+						let [o0, o1] = [h, twin].map(|h| position(topology.half_edge(topology.half_edge(h).prev).vertex));
+						add(scale(add(a, b), 3.0 / 8.0), scale(add(o0, o1), 1.0 / 8.0))
+					}
+				};
+				lerp(smooth, middle, sharpness)
+			})
+			.collect();
+
+		let vertex_points: Vec<[f32; 3]> = topology.vertices()
+			.map(|v| {
+				let p = position(v);
+				let incident = topology.vertex_edges(v);
+				let other = |h: u32| position(topology.other_end(h, v));
+				let sharp: Vec<u32> = incident.iter().copied().filter(|&h| sharpness[edge_of[h as usize] as usize] > 0.0).collect();
+
+				let smooth = || match scheme {
+					Scheme::CatmullClark => {
+						let n = incident.len() as f32;
+						let f = average(topology.vertex_faces(v).map(|f| face_points[f as usize]));
+						let r = average(incident.iter().map(|&h| lerp(p, other(h), 0.5)));
+						scale(add(add(f, scale(r, 2.0)), scale(p, n - 3.0)), 1.0 / n)
+					}
+					Scheme::Loop => {
+						let n = incident.len() as f32;
+						let c = 3.0 / 8.0 + (2.0 * std::f32::consts::PI / n).cos() / 4.0;
+						let beta = (5.0 / 8.0 - c * c) / n;
+						let sum = incident.iter().fold([0.0; 3], |sum, &h| add(sum, other(h)));
+						add(scale(p, 1.0 - n * beta), scale(sum, beta))
+					}
+				};
+				let sharp_point = match sharp.len() {
+					0 | 1 => return smooth(),
+					2 => add(scale(p, 0.75), scale(add(other(sharp[0]), other(sharp[1])), 0.125)),
+					_ => p,
+				};
+
+				// Semi-sharp creases blend toward the smooth rule
+				let sharpness = sharp.iter().map(|&h| sharpness[edge_of[h as usize] as usize].min(1.0)).sum::<f32>() / sharp.len() as f32;
+				if sharpness >= 1.0 { sharp_point } else { lerp(smooth(), sharp_point, sharpness) }
+			})
+			.collect();
+
+		let edge_base = self.positions.len() as u32;
+		let face_base = edge_base + edges.len() as u32;
+		let mut positions = vertex_points;
+		positions.extend(edge_points);
+		if scheme == Scheme::CatmullClark {
+			positions.extend(face_points);
+		}
+
+		let mut creases = HashMap::new();
+		for (e, &h) in edges.iter().enumerate() {
+			let (a, b) = (topology.half_edge(h).vertex, topology.destination(h));
+			let sharpness = self.creases.get(&(a.min(b), a.max(b))).copied().unwrap_or(0.0) - 1.0;
+			if sharpness > 0.0 {
+				let middle = edge_base + e as u32;
+				for end in [a, b] {
+					creases.insert((end.min(middle), end.max(middle)), sharpness);
+				}
+			}
+		}
+
+		let mut child = Self {
+			positions,
+			face_starts: vec![0],
+			corners: Vec::new(),
+			values: Vec::new(),
+			stride: self.stride,
+			origins: Vec::new(),
+			creases,
+		};
+
+		for f in topology.faces() {
+			let half_edges: Vec<u32> = topology.face_half_edges(f).collect();
+			let n = half_edges.len();
+			let origin = self.origins[topology.face(f).source as usize];
+			let half_edge = |k: usize| topology.half_edge(half_edges[k % n]);
+			let vertex = |k: usize| (half_edge(k).vertex, vec![half_edge(k).corner as usize]);
+			let edge_point = |k: usize| (
+				edge_base + edge_of[half_edges[k % n] as usize],
+				vec![half_edge(k).corner as usize, half_edge(k + 1).corner as usize],
+			);
+
+			match scheme {
+				Scheme::CatmullClark => {
+					let center = (face_base + f, (0..n).map(|k| half_edge(k).corner as usize).collect());
+					for k in 0..n {
+						child.push_child(self, origin, &[vertex(k), edge_point(k), center.clone(), edge_point(k + n - 1)]);
+					}
+				}
+				Scheme::Loop => {
+					for k in 0..3 {
+						child.push_child(self, origin, &[vertex(k), edge_point(k), edge_point(k + 2)]);
+					}
+					child.push_child(self, origin, &[edge_point(0), edge_point(1), edge_point(2)]);
+				}
+			}
+		}
+
+		child
+	}
+
+	/// Adds a face from `(vertex, parent corners averaged for its values)`.
+	fn push_child(&mut self, parent: &Cage, origin: u32, corners: &[(u32, Vec<usize>)]) {
+		for (vertex, sources) in corners {
+			self.corners.push(*vertex);
+			let start = self.values.len();
+			self.values.resize(start + self.stride, 0.0);
+			for &source in sources {
+				for (value, &s) in self.values[start..].iter_mut().zip(parent.corner_values(source)) {
+					*value += s / sources.len() as f32;
+				}
+			}
+		}
+		self.face_starts.push(self.corners.len());
+		self.origins.push(origin);
+	}
+
+	/// Replaces the surface of `mesh` with the cage, keeping its lines and
+	/// points.
+	fn write_to(&self, mesh: &mut Mesh, scheme: Scheme) {
+		let topology = self.topology();
+		let (normals, sectors) = self.corner_normals(&topology);
+
+		let mut vertices: Vec<Vertex> = Vec::new();
+		let mut attributes: Vec<Vec<f32>> = vec![Vec::new(); mesh.attributes.len()];
+		let mut first_vertex: HashMap<u32, u32> = HashMap::new();
+		let mut cache: HashMap<(u32, u32, Vec<u32>), u32> = HashMap::new();
+		let corner_vertices: Vec<u32> = (0..self.corners.len())
+			.map(|corner| {
+				let values = self.corner_values(corner);
+				let key = (self.corners[corner], sectors[corner], values.iter().map(|v| v.to_bits()).collect());
+				*cache.entry(key).or_insert_with(|| {
+					vertices.push(Vertex {
+						position: self.positions[self.corners[corner] as usize],
+						tex_coords: [values[0], values[1]],
+						normal: normals[corner],
+						color: [values[2], values[3], values[4]],
+						tangent: [0.0; 4],
+					});
+					for (attribute, &value) in attributes.iter_mut().zip(&values[5..]) {
+						attribute.push(value);
+					}
+					let index = (vertices.len() - 1) as u32;
+					first_vertex.entry(self.corners[corner]).or_insert(index);
+					index
+				})
+			})
+			.collect();
+
+		let mut indices = Vec::new();
+		let mut origins = Vec::new();
+		let mut polygons = Vec::new();
+		for f in topology.faces() {
+			let half_edges: Vec<&HalfEdge> = topology.face_half_edges(f).map(|h| topology.half_edge(h)).collect();
+			let points: Vec<[f32; 3]> = half_edges.iter().map(|half_edge| self.positions[half_edge.vertex as usize]).collect();
+			let triangles = triangulate_face(&points);
+			for triangle in &triangles {
+				indices.extend(triangle.map(|k| corner_vertices[half_edges[k].corner as usize]));
+				origins.push(self.origins[topology.face(f).source as usize]);
+			}
+			polygons.push(triangles.len() as u32);
+		}
+
+		// Output triangles follow the order of the source triangles they come from
+		keep_loose_vertices(mesh, &mut vertices, &mut attributes);
+		follow_origins(mesh, &origins);
+
+		let mut creases: Vec<Crease> = self.creases.iter()
+			.filter_map(|(&(a, b), &sharpness)| Some(Crease {
+				edge: [*first_vertex.get(&a)?, *first_vertex.get(&b)?],
+				sharpness,
+			}))
+			.collect();
+		creases.sort_by_key(|crease| crease.edge);
+
+		for (attribute, values) in mesh.attributes.iter_mut().zip(attributes) {
+			attribute.values = values;
+		}
+		mesh.vertices = vertices;
+		mesh.indices = indices;
+		mesh.creases = creases;
+		mesh.polygons = match scheme {
+			Scheme::CatmullClark => polygons,
+			Scheme::Loop => Vec::new(),
+		};
+	}
+
+	/// Normal of each corner, averaged over the faces around its vertex that
+	/// are not cut off by a crease, and the sector of faces it was averaged
+	/// over, as one of its corners.
+	fn corner_normals(&self, topology: &HalfEdgeMesh) -> (Vec<[f32; 3]>, Vec<u32>) {
+		let mut sectors: Vec<u32> = (0..self.corners.len() as u32).collect();
+
+		for h in topology.edges() {
+			let Some(twin) = topology.half_edge(h).twin else {
+				continue;
+			};
+			if self.sharpness(topology, h) > 0.0 {
+				continue;
+			}
+			// Each end has a corner on both sides, leaving it
+			for (x, y) in [(h, topology.half_edge(twin).next), (topology.half_edge(h).next, twin)] {
+				let (a, b) = (find(&mut sectors, topology.half_edge(x).corner), find(&mut sectors, topology.half_edge(y).corner));
+				sectors[a as usize] = b;
+			}
+		}
+
+		let mut sums = vec![[0.0; 3]; self.corners.len()];
+		for f in topology.faces() {
+			// Newell's method, exact for planar polygons, its length twice the area
+			let normal = topology.face_half_edges(f).fold([0.0; 3], |sum, h| {
+				let [p, q] = [topology.half_edge(h).vertex, topology.destination(h)].map(|v| self.positions[v as usize]);
+				add(sum, [
+					(p[1] - q[1]) * (p[2] + q[2]),
+					(p[2] - q[2]) * (p[0] + q[0]),
+					(p[0] - q[0]) * (p[1] + q[1]),
+				])
+			});
+			for h in topology.face_half_edges(f) {
+				let root = find(&mut sectors, topology.half_edge(h).corner) as usize;
+				sums[root] = add(sums[root], normal);
+			}
+		}
+
+		let mut normals = Vec::with_capacity(self.corners.len());
+		for corner in 0..self.corners.len() {
+			let root = find(&mut sectors, corner as u32);
+			sectors[corner] = root;
+			normals.push(normalize(sums[root as usize]));
+		}
+
+		(normals, sectors)
+	}
+}
+
+/// Root of `i` in the union-find forest `parents`.
+fn find(parents: &mut [u32], mut i: u32) -> u32 {
+	while parents[i as usize] != i {
+		let parent = parents[i as usize];
+		parents[i as usize] = parents[parent as usize];
+		i = parent;
+	}
+	i
+}
+
+fn add(a: [f32; 3], b: [f32; 3]) -> [f32; 3] {
+	[a[0] + b[0], a[1] + b[1], a[2] + b[2]]
+}
+
+fn scale(a: [f32; 3], s: f32) -> [f32; 3] {
+	[a[0] * s, a[1] * s, a[2] * s]
+}
+
+fn lerp(a: [f32; 3], b: [f32; 3], t: f32) -> [f32; 3] {
+	add(scale(a, 1.0 - t), scale(b, t))
+}
+
+fn average(points: impl Iterator<Item = [f32; 3]>) -> [f32; 3] {
+	let (sum, count) = points.fold(([0.0; 3], 0), |(sum, count), p| (add(sum, p), count + 1));
+	if count > 0 { scale(sum, 1.0 / count as f32) } else { sum }
+}
+
+fn normalize(v: [f32; 3]) -> [f32; 3] {
+	let len = (v[0] * v[0] + v[1] * v[1] + v[2] * v[2]).sqrt();
+	if len > 1e-12 { scale(v, 1.0 / len) } else { [0.0; 3] }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mesh::fixtures::cube;
+    use crate::parser::obj::{obj_to_mesh, parse_obj_from_string};
+
+    fn unique_positions(mesh: &Mesh) -> usize {
+        let mut positions: Vec<[u32; 3]> = mesh.vertices.iter().map(|v| v.position.map(f32::to_bits)).collect();
+        positions.sort_unstable();
+        positions.dedup();
+        positions.len()
+    }
+
+    #[test]
+    fn catmull_clark_rounds_quads() {
+        let mut mesh = cube();
+        mesh.creases.clear();
+        assert_eq!(mesh.subdivision_scheme(), Scheme::CatmullClark);
+
+        mesh.subdivide(Scheme::CatmullClark, 1);
+
+        // 8 corners, 12 edge points and 6 face points, shared along seams
+        assert_eq!(mesh.indices.len() / 3, 48);
+        assert_eq!(mesh.polygons, vec![2; 24]);
+        assert_eq!(unique_positions(&mesh), 26);
+        // Corners are pulled in, face points stay on the faces
+        assert!(mesh.vertices.iter().all(|v| v.position.iter().filter(|c| c.abs() >= 1.0).count() <= 1));
+        assert!(mesh.vertices.iter().any(|v| v.tex_coords == [0.5, 0.5]));
+
+        let parts: Vec<(u32, u32)> = mesh.parts.iter().map(|p| (p.first_index, p.index_count)).collect();
+        assert_eq!(parts, vec![(0, 24), (24, 96), (120, 24)]);
+        assert_eq!(mesh.face_materials.len(), 48);
+
+        mesh.subdivide(Scheme::CatmullClark, 2);
+        assert_eq!(mesh.indices.len() / 3, 48 * 16);
+    }
+
+    #[test]
+    fn creases_keep_edges_sharp() {
+        // Every face has its own smoothing group, so every edge is creased
+        let mut mesh = cube();
+        assert_eq!(mesh.creases.len(), 12);
+
+        mesh.subdivide(Scheme::CatmullClark, 2);
+
+        for vertex in &mesh.vertices {
+            assert!(vertex.position.iter().any(|c| (c.abs() - 1.0).abs() < 1e-6));
+            assert!(vertex.normal.iter().filter(|c| c.abs() > 1e-6).count() == 1, "{:?}", vertex.normal);
+        }
+        assert_eq!(mesh.creases.len(), 12 * 4);
+    }
+
+    #[test]
+    fn semi_sharp_creases_wear_off() {
+        let mut mesh = cube();
+        for crease in &mut mesh.creases {
+            crease.sharpness = 1.5;
+        }
+
+        mesh.subdivide(Scheme::CatmullClark, 1);
+        assert_eq!(mesh.creases.len(), 24);
+        assert!(mesh.creases.iter().all(|c| c.sharpness == 0.5));
+
+        mesh.subdivide(Scheme::CatmullClark, 1);
+        assert!(mesh.creases.is_empty());
+    }
+
+    #[test]
+    fn loop_rounds_triangles() {
+        let mut mesh = obj_to_mesh(parse_obj_from_string("
+v 1 0 0
+v -1 0 0
+v 0 1 0
+v 0 -1 0
+v 0 0 1
+v 0 0 -1
+f 1 3 5
+f 3 2 5
+f 2 4 5
+f 4 1 5
+f 3 1 6
+f 2 3 6
+f 4 2 6
+f 1 4 6
+l 1 2
+").unwrap());
+        assert_eq!(mesh.subdivision_scheme(), Scheme::Loop);
+
+        mesh.subdivide(Scheme::Loop, 2);
+
+        assert_eq!(mesh.indices.len() / 3, 8 * 16);
+        assert!(mesh.polygons.is_empty());
+        for &i in &mesh.indices {
+            let v = &mesh.vertices[i as usize];
+            let radius = v.position.iter().map(|c| c * c).sum::<f32>().sqrt();
+            assert!(radius > 0.4 && radius < 1.0, "{}", radius);
+            let outward: f32 = v.normal.iter().zip(&v.position).map(|(n, p)| n * p).sum();
+            assert!(outward > 0.0);
+        }
+        let line: Vec<[f32; 3]> = mesh.line_indices.iter().map(|&i| mesh.vertices[i as usize].position).collect();
+        assert_eq!(line, vec![[1.0, 0.0, 0.0], [-1.0, 0.0, 0.0]]);
+    }
+}
@@ -0,0 +1,276 @@
+use crate::mesh::Mesh;
+
+/// Corners of a vertex share a tangent only when their frames are less than
+/// 90 degrees apart, the threshold MikkTSpace uses.
+const MIN_SHARED_COS: f32 = 0.0;
+
+/// Tangent frame of one triangle corner, projected on the vertex normal.
+struct Corner {
+	tangent: [f32; 3],
+	bitangent: [f32; 3],
+	/// Angle of the triangle at the corner, weighting its contribution.
+	angle: f32,
+	/// Whether the texture mapping keeps the triangle orientation.
+	orientation: bool,
+}
+
+/// Corners of one vertex that end up sharing a tangent.
+struct Group {
+	tangent: [f32; 3],
+	bitangent: [f32; 3],
+	orientation: bool,
+	sum: [f32; 3],
+	corners: Vec<usize>,
+}
+
+impl Mesh {
+	/// Whether every vertex of the triangles has a tangent, generated or read
+	/// from the file.
+	pub fn has_tangents(&self) -> bool {
+		self.indices.iter().all(|&i| self.vertices[i as usize].tangent[3] != 0.0)
+	}
+
+	/// Generates a tangent per vertex for normal mapping, with the sign of
+	/// the bitangent `cross(normal, tangent) * w` in `w`.
+	///
+	/// Follows the MikkTSpace conventions: tangents point along increasing
+	/// `u`, corners are weighted by angle, and a vertex is split where its
+	/// triangles have opposite UV orientation or frames more than 90 degrees
+	/// apart, as on UV mirrors and seams. Line and point vertices are left
+	/// untouched.
+	pub fn generate_tangents(&mut self) {
+		let corners: Vec<Corner> = self.indices.chunks_exact(3)
+			.flat_map(|triangle| self.triangle_corners([0, 1, 2].map(|k| triangle[k] as usize)))
+			.collect();
+
+		// Corners of each vertex, as offsets into `by_vertex`
+		let mut offsets = vec![0; self.vertices.len() + 1];
+		for &index in &self.indices[..corners.len()] {
+			offsets[index as usize + 1] += 1;
+		}
+		for i in 0..self.vertices.len() {
+			offsets[i + 1] += offsets[i];
+		}
+		let mut by_vertex = vec![0; corners.len()];
+		let mut next = offsets.clone();
+		for (corner, &index) in self.indices[..corners.len()].iter().enumerate() {
+			by_vertex[next[index as usize]] = corner;
+			next[index as usize] += 1;
+		}
+
+		for vertex in 0..self.vertices.len() {
+			let groups = group_corners(&corners, &by_vertex[offsets[vertex]..offsets[vertex + 1]]);
+			let normal = self.vertices[vertex].normal;
+
+			for (i, group) in groups.iter().enumerate() {
+				let index = if i == 0 {
+					vertex
+				} else {
+					self.vertices.push(self.vertices[vertex]);
+					for attribute in &mut self.attributes {
+						let value = attribute.values.get(vertex).copied().unwrap_or(0.0);
+						attribute.values.push(value);
+					}
+					for &corner in &group.corners {
+						self.indices[corner] = (self.vertices.len() - 1) as u32;
+					}
+					self.vertices.len() - 1
+				};
+
+				let [x, y, z] = normalize(reject(group.sum, normal)).unwrap_or_else(|| any_perpendicular(normal));
+				let w = if group.orientation { 1.0 } else { -1.0 };
+				self.vertices[index].tangent = [x, y, z, w];
+			}
+		}
+	}
+
+	fn triangle_corners(&self, triangle: [usize; 3]) -> [Corner; 3] {
+		let [v0, v1, v2] = triangle.map(|i| &self.vertices[i]);
+		let d1 = sub(v1.position, v0.position);
+		let d2 = sub(v2.position, v0.position);
+		let (s1, t1) = (v1.tex_coords[0] - v0.tex_coords[0], v1.tex_coords[1] - v0.tex_coords[1]);
+		let (s2, t2) = (v2.tex_coords[0] - v0.tex_coords[0], v2.tex_coords[1] - v0.tex_coords[1]);
+
+		// Directions of increasing u and v, up to the sign of the UV area
+		let signed_area = s1 * t2 - s2 * t1;
+		let sign = if signed_area < 0.0 { -1.0 } else { 1.0 };
+		let face_tangent = scale(sub(scale(d1, t2), scale(d2, t1)), sign);
+		let face_bitangent = scale(sub(scale(d2, s1), scale(d1, s2)), sign);
+
+		std::array::from_fn(|k| {
+			let vertex = &self.vertices[triangle[k]];
+			let normal = vertex.normal;
+			let position = |j: usize| self.vertices[triangle[(k + j) % 3]].position;
+
+			let edge1 = normalize(reject(sub(position(1), position(0)), normal));
+			let edge2 = normalize(reject(sub(position(2), position(0)), normal));
+			let angle = match (edge1, edge2) {
+				(Some(a), Some(b)) => dot(a, b).clamp(-1.0, 1.0).acos(),
+				_ => 0.0,
+			};
+
+			Corner {
+				tangent: normalize(reject(face_tangent, normal)).unwrap_or([0.0; 3]),
+				bitangent: normalize(reject(face_bitangent, normal)).unwrap_or([0.0; 3]),
+				angle,
+				orientation: signed_area >= 0.0,
+			}
+		})
+	}
+}
+
+/// Splits the corners of a vertex into groups of compatible frames. Corners
+/// of degenerate UV triangles join the first group without contributing.
+fn group_corners(corners: &[Corner], indices: &[usize]) -> Vec<Group> {
+	let mut groups: Vec<Group> = Vec::new();
+	let mut degenerate = Vec::new();
+
+	for &i in indices {
+		let corner = &corners[i];
+		if corner.tangent == [0.0; 3] {
+			degenerate.push(i);
+			continue;
+		}
+
+		let compatible = groups.iter_mut().find(|g| {
+			g.orientation == corner.orientation
+				&& dot(g.tangent, corner.tangent) > MIN_SHARED_COS
+				&& (g.bitangent == [0.0; 3] || corner.bitangent == [0.0; 3] || dot(g.bitangent, corner.bitangent) > MIN_SHARED_COS)
+		});
+		match compatible {
+			Some(group) => {
+				group.sum = add(group.sum, scale(corner.tangent, corner.angle));
+				group.corners.push(i);
+			}
+			None => groups.push(Group {
+				tangent: corner.tangent,
+				bitangent: corner.bitangent,
+				orientation: corner.orientation,
+				sum: scale(corner.tangent, corner.angle),
+				corners: vec![i],
+			}),
+		}
+	}
+
+	match groups.first_mut() {
+		Some(group) => group.corners.extend(degenerate),
+		None if !degenerate.is_empty() => groups.push(Group {
+			tangent: [0.0; 3],
+			bitangent: [0.0; 3],
+			orientation: true,
+			sum: [0.0; 3],
+			corners: degenerate,
+		}),
+		None => {}
+	}
+
+	groups
+}
+
+fn add(a: [f32; 3], b: [f32; 3]) -> [f32; 3] {
+	[a[0] + b[0], a[1] + b[1], a[2] + b[2]]
+}
+
+fn sub(a: [f32; 3], b: [f32; 3]) -> [f32; 3] {
+	[a[0] - b[0], a[1] - b[1], a[2] - b[2]]
+}
+
+fn scale(a: [f32; 3], s: f32) -> [f32; 3] {
+	[a[0] * s, a[1] * s, a[2] * s]
+}
+
+fn dot(a: [f32; 3], b: [f32; 3]) -> f32 {
+	a[0] * b[0] + a[1] * b[1] + a[2] * b[2]
+}
+
+/// `v` without its component along the unit vector `n`.
+fn reject(v: [f32; 3], n: [f32; 3]) -> [f32; 3] {
+	sub(v, scale(n, dot(v, n)))
+}
+
+fn normalize(v: [f32; 3]) -> Option<[f32; 3]> {
+	let len = dot(v, v).sqrt();
+	(len > 1e-12).then(|| scale(v, 1.0 / len))
+}
+
+/// A unit vector perpendicular to `n`, for vertices without usable UVs.
+fn any_perpendicular(n: [f32; 3]) -> [f32; 3] {
+	let axis = if n[0].abs() < 0.9 { [1.0, 0.0, 0.0] } else { [0.0, 1.0, 0.0] };
+	normalize(reject(axis, n)).unwrap_or(axis)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::mesh::VertexAttribute;
+    use crate::parser::obj::{obj_to_mesh, parse_obj_from_string};
+
+    fn assert_close(a: [f32; 4], b: [f32; 4]) {
+        assert!(a.iter().zip(&b).all(|(x, y)| (x - y).abs() < 1e-5), "{:?} != {:?}", a, b);
+    }
+
+    #[test]
+    fn planar_mapping_follows_u() {
+        let mut mesh = obj_to_mesh(parse_obj_from_string("
+v 0 0 0
+v 2 0 0
+v 2 1 0
+v 0 1 0
+v 5 5 5
+vt 0 0
+vt 1 0
+vt 1 1
+vt 0 1
+vn 0 0 1
+f 1/1/1 2/2/1 3/3/1 4/4/1
+p 5
+").unwrap());
+        assert!(!mesh.has_tangents());
+
+        let vertex_count = mesh.vertices.len();
+        mesh.generate_tangents();
+
+        assert!(mesh.has_tangents());
+        assert_eq!(mesh.vertices.len(), vertex_count);
+        for &index in &mesh.indices {
+            assert_close(mesh.vertices[index as usize].tangent, [1.0, 0.0, 0.0, 1.0]);
+        }
+        // The point keeps no tangent
+        let point = &mesh.vertices[mesh.point_indices[0] as usize];
+        assert_eq!(point.tangent, [0.0; 4]);
+    }
+
+    #[test]
+    fn mirrored_uvs_split_shared_vertices() {
+        // The right half reuses the left half of the texture, mirrored in u
+        let mut mesh = obj_to_mesh(parse_obj_from_string("
+v -1 0 0
+v 0 0 0
+v 1 0 0
+v -1 1 0
+v 0 1 0
+v 1 1 0
+vt 0 0
+vt 1 0
+vt 0 1
+vt 1 1
+vn 0 0 1
+f 1/1/1 2/2/1 5/4/1 4/3/1
+f 2/2/1 3/1/1 6/3/1 5/4/1
+").unwrap());
+        let values = (0..mesh.vertices.len()).map(|i| i as f32).collect();
+        mesh.attributes.push(VertexAttribute { name: "id".to_string(), values });
+
+        let vertex_count = mesh.vertices.len();
+        mesh.generate_tangents();
+
+        // The two vertices on the mirror line get a copy each
+        assert_eq!(mesh.vertices.len(), vertex_count + 2);
+        assert_eq!(mesh.attributes[0].values.len(), mesh.vertices.len());
+
+        for (k, &index) in mesh.indices.iter().enumerate() {
+            let vertex = &mesh.vertices[index as usize];
+            let expected = if k < 6 { [1.0, 0.0, 0.0, 1.0] } else { [-1.0, 0.0, 0.0, -1.0] };
+            assert_close(vertex.tangent, expected);
+        }
+    }
+}
@@ -0,0 +1,395 @@
+use std::fmt;
+
+use crate::parser::json::JsonError;
+
+/// Position of a problem in a text model file.
+///
+/// `line` and `column` are 1-based, `text` is the whole offending line.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SourceLocation {
+	pub line: usize,
+	pub column: usize,
+	pub text: String,
+}
+
+impl SourceLocation {
+	/// Locates `token` inside `line`, which must be a subslice of it.
+	pub(crate) fn of_token(line_num: usize, line: &str, token: &str) -> Self {
+		let offset = (token.as_ptr() as usize).wrapping_sub(line.as_ptr() as usize);
+		// Columns count characters, as the caret of `snippet` does
+		let column = line.get(..offset).map_or(1, |before| before.chars().count() + 1);
+
+		Self { line: line_num, column, text: line.to_string() }
+	}
+
+	/// Two-line snippet with a caret under the offending column.
+	pub fn snippet(&self) -> String {
+		let gutter = self.line.to_string();
+		let padding: String = self.text.chars()
+			.take(self.column.saturating_sub(1))
+			.map(|c| if c == '\t' { '\t' } else { ' ' })
+			.collect();
+
+		format!(
+			"{} | {}\n{} | {}^",
+			gutter, self.text,
+			" ".repeat(gutter.len()), padding
+		)
+	}
+}
+
+impl fmt::Display for SourceLocation {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		write!(f, "line {}, column {}", self.line, self.column)
+	}
+}
+
+#[derive(Debug)]
+pub enum ObjError {
+	Io {
+		path: String,
+		source: std::io::Error,
+	},
+	Syntax {
+		location: SourceLocation,
+		message: String,
+	},
+	IndexOutOfRange {
+		location: SourceLocation,
+		index: isize,
+		count: usize,
+	},
+	Unsupported {
+		location: SourceLocation,
+		feature: String,
+	},
+}
+
+impl ObjError {
+	pub fn location(&self) -> Option<&SourceLocation> {
+		match self {
+			ObjError::Io { .. } => None,
+			ObjError::Syntax { location, .. }
+			| ObjError::IndexOutOfRange { location, .. }
+			| ObjError::Unsupported { location, .. } => Some(location),
+		}
+	}
+
+	/// The error message without location.
+	pub fn message(&self) -> String {
+		match self {
+			ObjError::Io { path, source } => format!("Failed to read '{}': {}", path, source),
+			ObjError::Syntax { message, .. } => message.clone(),
+			ObjError::IndexOutOfRange { index, count, .. } => {
+				format!("Index {} out of range ({} elements declared)", index, count)
+			}
+			ObjError::Unsupported { feature, .. } => format!("Unsupported feature: {}", feature),
+		}
+	}
+}
+
+impl fmt::Display for ObjError {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self.location() {
+			Some(location) => write!(f, "{} at {}\n{}", self.message(), location, location.snippet()),
+			None => write!(f, "{}", self.message()),
+		}
+	}
+}
+
+impl std::error::Error for ObjError {
+	fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+		match self {
+			ObjError::Io { source, .. } => Some(source),
+			_ => None,
+		}
+	}
+}
+
+#[derive(Debug)]
+pub enum StlError {
+	Io {
+		path: String,
+		source: std::io::Error,
+	},
+	/// Binary file too short for its header or for the triangles it declares.
+	Corrupt(String),
+	/// ASCII file with a missing or unexpected keyword.
+	Syntax {
+		location: SourceLocation,
+		message: String,
+	},
+}
+
+impl fmt::Display for StlError {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			StlError::Io { path, source } => write!(f, "Failed to read '{}': {}", path, source),
+			StlError::Corrupt(message) => write!(f, "Corrupt binary STL: {}", message),
+			StlError::Syntax { location, message } => {
+				write!(f, "{} at {}\n{}", message, location, location.snippet())
+			}
+		}
+	}
+}
+
+impl std::error::Error for StlError {
+	fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+		match self {
+			StlError::Io { source, .. } => Some(source),
+			_ => None,
+		}
+	}
+}
+
+#[derive(Debug)]
+pub enum PlyError {
+	Io {
+		path: String,
+		source: std::io::Error,
+	},
+	/// Malformed header line, or ASCII element line.
+	Syntax {
+		location: SourceLocation,
+		message: String,
+	},
+	/// Element data that is truncated or references missing vertices.
+	Invalid(String),
+}
+
+impl fmt::Display for PlyError {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			PlyError::Io { path, source } => write!(f, "Failed to read '{}': {}", path, source),
+			PlyError::Syntax { location, message } => {
+				write!(f, "{} at {}\n{}", message, location, location.snippet())
+			}
+			PlyError::Invalid(message) => write!(f, "Invalid PLY data: {}", message),
+		}
+	}
+}
+
+impl std::error::Error for PlyError {
+	fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+		match self {
+			PlyError::Io { source, .. } => Some(source),
+			_ => None,
+		}
+	}
+}
+
+#[derive(Debug)]
+pub enum GltfError {
+	Io {
+		path: String,
+		source: std::io::Error,
+	},
+	/// Malformed JSON document or chunk.
+	Json(JsonError),
+	/// Well-formed file whose content breaks the glTF rules, such as an
+	/// accessor reading past its buffer.
+	Invalid(String),
+	/// Valid glTF relying on something this loader cannot read.
+	Unsupported(String),
+}
+
+impl fmt::Display for GltfError {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			GltfError::Io { path, source } => write!(f, "Failed to read '{}': {}", path, source),
+			GltfError::Json(error) => write!(f, "Invalid glTF JSON: {}", error),
+			GltfError::Invalid(message) => write!(f, "Invalid glTF data: {}", message),
+			GltfError::Unsupported(message) => write!(f, "Unsupported glTF feature: {}", message),
+		}
+	}
+}
+
+impl std::error::Error for GltfError {
+	fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+		match self {
+			GltfError::Io { source, .. } => Some(source),
+			_ => None,
+		}
+	}
+}
+
+#[derive(Debug)]
+pub enum TextureError {
+	Io {
+		path: String,
+		source: std::io::Error,
+	},
+	/// Truncated or inconsistent image data.
+	Corrupt(String),
+	/// Valid image using a variant the built-in decoders do not handle.
+	Unsupported(String),
+}
+
+impl fmt::Display for TextureError {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			TextureError::Io { path, source } => write!(f, "Failed to read '{}': {}", path, source),
+			TextureError::Corrupt(message) => write!(f, "Corrupt texture: {}", message),
+			TextureError::Unsupported(message) => write!(f, "Unsupported texture: {}", message),
+		}
+	}
+}
+
+impl std::error::Error for TextureError {
+	fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+		match self {
+			TextureError::Io { source, .. } => Some(source),
+			_ => None,
+		}
+	}
+}
+
+/// Error of [`crate::parser::format::load_model`], wrapping the error of
+/// the loader that handled the file.
+#[derive(Debug)]
+pub enum LoadError {
+	Io {
+		path: String,
+		source: std::io::Error,
+	},
+	/// Neither the content nor the extension matched a registered loader.
+	UnknownFormat(String),
+	Obj(ObjError),
+	Stl(StlError),
+	Ply(PlyError),
+	Gltf(GltfError),
+}
+
+impl fmt::Display for LoadError {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			LoadError::Io { path, source } => write!(f, "Failed to read '{}': {}", path, source),
+			LoadError::UnknownFormat(path) => write!(f, "Unrecognized model format: '{}'", path),
+			LoadError::Obj(e) => e.fmt(f),
+			LoadError::Stl(e) => e.fmt(f),
+			LoadError::Ply(e) => e.fmt(f),
+			LoadError::Gltf(e) => e.fmt(f),
+		}
+	}
+}
+
+impl std::error::Error for LoadError {
+	fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+		match self {
+			LoadError::Io { source, .. } => Some(source),
+			LoadError::UnknownFormat(_) => None,
+			LoadError::Obj(e) => Some(e),
+			LoadError::Stl(e) => Some(e),
+			LoadError::Ply(e) => Some(e),
+			LoadError::Gltf(e) => Some(e),
+		}
+	}
+}
+
+impl From<ObjError> for LoadError {
+	fn from(e: ObjError) -> Self {
+		LoadError::Obj(e)
+	}
+}
+
+impl From<StlError> for LoadError {
+	fn from(e: StlError) -> Self {
+		LoadError::Stl(e)
+	}
+}
+
+impl From<PlyError> for LoadError {
+	fn from(e: PlyError) -> Self {
+		LoadError::Ply(e)
+	}
+}
+
+impl From<GltfError> for LoadError {
+	fn from(e: GltfError) -> Self {
+		LoadError::Gltf(e)
+	}
+}
+
+/// A non-fatal problem found while parsing, collected instead of printed.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Diagnostic {
+	/// File the diagnostic comes from, when parsing from disk.
+	pub file: Option<String>,
+	pub location: Option<SourceLocation>,
+	pub message: String,
+}
+
+impl fmt::Display for Diagnostic {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		if let Some(file) = &self.file {
+			write!(f, "{}: ", file)?;
+		}
+		match &self.location {
+			Some(location) => write!(f, "{}: {}", location, self.message),
+			None => write!(f, "{}", self.message),
+		}
+	}
+}
+
+/// Error raised by a directive parser, tied to the token that caused it.
+///
+/// It is turned into an `ObjError` once the line it belongs to is known.
+pub(crate) struct TokenError<'a> {
+	token: &'a str,
+	kind: ErrorKind,
+}
+
+enum ErrorKind {
+	Syntax(String),
+	IndexOutOfRange { index: isize, count: usize },
+	Unsupported(String),
+}
+
+impl<'a> TokenError<'a> {
+	pub(crate) fn syntax(token: &'a str, message: String) -> Self {
+		Self { token, kind: ErrorKind::Syntax(message) }
+	}
+
+	pub(crate) fn out_of_range(token: &'a str, index: isize, count: usize) -> Self {
+		Self { token, kind: ErrorKind::IndexOutOfRange { index, count } }
+	}
+
+	pub(crate) fn unsupported(token: &'a str, feature: String) -> Self {
+		Self { token, kind: ErrorKind::Unsupported(feature) }
+	}
+
+	pub(crate) fn locate(self, line_num: usize, line: &str) -> ObjError {
+		let location = SourceLocation::of_token(line_num, line, self.token);
+
+		match self.kind {
+			ErrorKind::Syntax(message) => ObjError::Syntax { location, message },
+			ErrorKind::IndexOutOfRange { index, count } => ObjError::IndexOutOfRange { location, index, count },
+			ErrorKind::Unsupported(feature) => ObjError::Unsupported { location, feature },
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn token_column_is_located_in_line() {
+        let line = "f 1 2 x";
+        let token = &line[6..7];
+        let location = SourceLocation::of_token(3, line, token);
+
+        assert_eq!(location.column, 7);
+        assert_eq!(location.snippet(), "3 | f 1 2 x\n  |       ^");
+    }
+
+    #[test]
+    fn token_column_counts_characters() {
+        let line = "usemtl Métal x";
+        let token = &line[14..15];
+        let location = SourceLocation::of_token(1, line, token);
+
+        assert_eq!(location.column, 14);
+        assert_eq!(location.snippet(), "1 | usemtl Métal x\n  |              ^");
+    }
+}
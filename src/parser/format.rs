@@ -0,0 +1,306 @@
+use std::io::Read;
+use std::path::Path;
+
+use crate::mesh::Mesh;
+use crate::parser::error::LoadError;
+use crate::parser::gltf::{GLB_MAGIC, load_gltf};
+use crate::parser::obj::{ParseOptions, ParseProgress, obj_to_mesh, parse_obj_with_progress};
+use crate::parser::ply::{parse_ply, ply_to_mesh};
+use crate::parser::stl::load_stl;
+
+/// Bytes read from the start of a file to recognize its format.
+const SNIFF_SIZE: usize = 512;
+
+/// A loaded model and the non-fatal problems met while reading it.
+#[derive(Debug)]
+pub struct LoadedModel {
+	pub mesh: Mesh,
+	pub warnings: Vec<String>,
+}
+
+/// Options shared by every loader; each format uses those that apply to it.
+#[derive(Debug, Clone, Default)]
+pub struct LoadOptions {
+	/// Drop broken elements with a warning instead of failing the load.
+	pub lenient: bool,
+}
+
+/// A model file format that [`FormatRegistry`] can dispatch to.
+pub trait ModelLoader {
+	/// Short name shown to users, such as `"OBJ"`.
+	fn name(&self) -> &'static str;
+
+	/// Lowercase file extensions without the dot.
+	fn extensions(&self) -> &'static [&'static str];
+
+	/// Whether `header`, the first bytes of a file, is in this format.
+	/// Formats without a reliable signature return `false`.
+	fn sniff(&self, header: &[u8]) -> bool;
+
+	/// Loads the file at `path`. Formats read in one pass may ignore `progress`.
+	fn load(
+		&self,
+		path: &str,
+		options: &LoadOptions,
+		progress: &mut dyn FnMut(&ParseProgress),
+	) -> Result<LoadedModel, LoadError>;
+}
+
+pub struct ObjLoader;
+
+impl ModelLoader for ObjLoader {
+	fn name(&self) -> &'static str {
+		"OBJ"
+	}
+
+	fn extensions(&self) -> &'static [&'static str] {
+		&["obj"]
+	}
+
+	/// OBJ has no signature: accept text whose first statement is a
+	/// common OBJ directive.
+	fn sniff(&self, header: &[u8]) -> bool {
+		let text = String::from_utf8_lossy(header);
+		let first_statement = text.lines()
+			.map(str::trim)
+			.find(|line| !line.is_empty() && !line.starts_with('#'));
+
+		first_statement
+			.and_then(|line| line.split_whitespace().next())
+			.is_some_and(|directive| matches!(directive, "v" | "vt" | "vn" | "f" | "o" | "g" | "mtllib" | "usemtl"))
+	}
+
+	fn load(
+		&self,
+		path: &str,
+		options: &LoadOptions,
+		progress: &mut dyn FnMut(&ParseProgress),
+	) -> Result<LoadedModel, LoadError> {
+		let parse_options = ParseOptions { lenient: options.lenient };
+		let obj = parse_obj_with_progress(path, &parse_options, progress)?;
+
+		let mut warnings: Vec<String> = obj.diagnostics().iter().map(ToString::to_string).collect();
+		if obj.fallback_faces() > 0 {
+			warnings.push(format!(
+				"{} polygon(s) could not be ear-clipped and were fan-triangulated",
+				obj.fallback_faces(),
+			));
+		}
+
+		Ok(LoadedModel { mesh: obj_to_mesh(obj), warnings })
+	}
+}
+
+pub struct StlLoader;
+
+impl ModelLoader for StlLoader {
+	fn name(&self) -> &'static str {
+		"STL"
+	}
+
+	fn extensions(&self) -> &'static [&'static str] {
+		&["stl"]
+	}
+
+	/// Only ASCII files have a signature; binary ones go by extension.
+	fn sniff(&self, header: &[u8]) -> bool {
+		header.trim_ascii_start().starts_with(b"solid")
+	}
+
+	fn load(&self, path: &str, _: &LoadOptions, _: &mut dyn FnMut(&ParseProgress)) -> Result<LoadedModel, LoadError> {
+		Ok(LoadedModel { mesh: load_stl(path)?, warnings: Vec::new() })
+	}
+}
+
+pub struct PlyLoader;
+
+impl ModelLoader for PlyLoader {
+	fn name(&self) -> &'static str {
+		"PLY"
+	}
+
+	fn extensions(&self) -> &'static [&'static str] {
+		&["ply"]
+	}
+
+	fn sniff(&self, header: &[u8]) -> bool {
+		header.starts_with(b"ply\n") || header.starts_with(b"ply\r\n")
+	}
+
+	fn load(&self, path: &str, _: &LoadOptions, _: &mut dyn FnMut(&ParseProgress)) -> Result<LoadedModel, LoadError> {
+		let ply = parse_ply(path)?;
+		Ok(LoadedModel { mesh: ply_to_mesh(&ply), warnings: ply.diagnostics() })
+	}
+}
+
+pub struct GltfLoader;
+
+impl ModelLoader for GltfLoader {
+	fn name(&self) -> &'static str {
+		"glTF"
+	}
+
+	fn extensions(&self) -> &'static [&'static str] {
+		&["gltf", "glb"]
+	}
+
+	/// Binary containers start with their magic, JSON documents with `{`.
+	fn sniff(&self, header: &[u8]) -> bool {
+		let text = header.strip_prefix("\u{feff}".as_bytes()).unwrap_or(header);
+		header.starts_with(GLB_MAGIC) || text.trim_ascii_start().starts_with(b"{")
+	}
+
+	fn load(&self, path: &str, _: &LoadOptions, _: &mut dyn FnMut(&ParseProgress)) -> Result<LoadedModel, LoadError> {
+		Ok(LoadedModel { mesh: load_gltf(path)?, warnings: Vec::new() })
+	}
+}
+
+/// The known model formats, tried in registration order.
+pub struct FormatRegistry {
+	loaders: Vec<Box<dyn ModelLoader>>,
+}
+
+impl Default for FormatRegistry {
+	/// Registry of the built-in formats.
+	fn default() -> Self {
+		let mut registry = Self::empty();
+		registry.register(Box::new(PlyLoader));
+		registry.register(Box::new(GltfLoader));
+		registry.register(Box::new(StlLoader));
+		registry.register(Box::new(ObjLoader));
+		registry
+	}
+}
+
+impl FormatRegistry {
+	pub fn empty() -> Self {
+		Self { loaders: Vec::new() }
+	}
+
+	pub fn register(&mut self, loader: Box<dyn ModelLoader>) {
+		self.loaders.push(loader);
+	}
+
+	pub fn loaders(&self) -> impl Iterator<Item = &dyn ModelLoader> {
+		self.loaders.iter().map(|loader| loader.as_ref())
+	}
+
+	/// Picks the loader for a file from its first bytes, then its extension.
+	///
+	/// Content wins so that a mislabeled file still loads. Among loaders
+	/// recognizing the content, one that also claims the extension is
+	/// preferred.
+	pub fn detect(&self, path: &str, header: &[u8]) -> Option<&dyn ModelLoader> {
+		let extension = Path::new(path)
+			.extension()
+			.map(|ext| ext.to_string_lossy().to_lowercase());
+		let claims_extension = |loader: &&dyn ModelLoader| {
+			extension.as_deref().is_some_and(|ext| loader.extensions().contains(&ext))
+		};
+
+		let sniffed: Vec<&dyn ModelLoader> = self.loaders().filter(|loader| loader.sniff(header)).collect();
+
+		sniffed.iter().copied().find(claims_extension)
+			.or_else(|| sniffed.first().copied())
+			.or_else(|| self.loaders().find(claims_extension))
+	}
+
+	/// Detects the format of the file at `path` and loads it.
+	pub fn load(
+		&self,
+		path: &str,
+		options: &LoadOptions,
+		progress: &mut dyn FnMut(&ParseProgress),
+	) -> Result<LoadedModel, LoadError> {
+		let io_error = |source| LoadError::Io { path: path.to_string(), source };
+
+		let mut header = Vec::with_capacity(SNIFF_SIZE);
+		std::fs::File::open(path)
+			.and_then(|file| file.take(SNIFF_SIZE as u64).read_to_end(&mut header))
+			.map_err(io_error)?;
+
+		let loader = self.detect(path, &header)
+			.ok_or_else(|| LoadError::UnknownFormat(path.to_string()))?;
+		loader.load(path, options, progress)
+	}
+}
+
+/// Loads a model of any built-in format with default options.
+pub fn load_model(path: &str) -> Result<LoadedModel, LoadError> {
+	FormatRegistry::default().load(path, &LoadOptions::default(), &mut |_| {})
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn detected(path: &str, header: &[u8]) -> Option<&'static str> {
+        FormatRegistry::default().detect(path, header).map(|loader| loader.name())
+    }
+
+    #[test]
+    fn content_wins_over_extension() {
+        assert_eq!(detected("scan.obj", b"ply\nformat ascii 1.0\n"), Some("PLY"));
+        assert_eq!(detected("model.bin", b"glTF\x02\0\0\0"), Some("glTF"));
+        assert_eq!(detected("scene.txt", b"\n  {\"asset\": {}}"), Some("glTF"));
+        assert_eq!(detected("part", b"solid cube\n  facet normal 0 0 1"), Some("STL"));
+        assert_eq!(detected("model.dat", b"# exported\n\nv 0 0 0\n"), Some("OBJ"));
+    }
+
+    #[test]
+    fn extension_is_the_fallback() {
+        assert_eq!(detected("part.STL", &[0_u8; 84]), Some("STL"));
+        assert_eq!(detected("model.obj", b"# only a comment\n"), Some("OBJ"));
+        assert_eq!(detected("model.xyz", &[0_u8; 84]), None);
+    }
+
+    struct CountingLoader;
+
+    impl ModelLoader for CountingLoader {
+        fn name(&self) -> &'static str {
+            "Counting"
+        }
+
+        fn extensions(&self) -> &'static [&'static str] {
+            &["cnt"]
+        }
+
+        fn sniff(&self, _: &[u8]) -> bool {
+            false
+        }
+
+        fn load(&self, _: &str, _: &LoadOptions, _: &mut dyn FnMut(&ParseProgress)) -> Result<LoadedModel, LoadError> {
+            Err(LoadError::UnknownFormat("counting".to_string()))
+        }
+    }
+
+    #[test]
+    fn registered_loaders_are_dispatched_to() {
+        let mut registry = FormatRegistry::default();
+        registry.register(Box::new(CountingLoader));
+
+        assert_eq!(registry.detect("model.cnt", b"").map(|l| l.name()), Some("Counting"));
+        assert_eq!(registry.loaders().count(), 5);
+    }
+
+    #[test]
+    fn load_model_reads_a_mislabeled_file() {
+        let dir = std::env::temp_dir().join(format!("scop_format_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+
+        let path = dir.join("triangle.obj");
+        std::fs::write(&path, "ply\nformat ascii 1.0\nelement vertex 3\nproperty float x\nproperty float y\nproperty float z\nelement face 1\nproperty list uchar int vertex_indices\nend_header\n0 0 0\n1 0 0\n0 1 0\n3 0 1 2\n").unwrap();
+        let model = load_model(path.to_str().unwrap()).unwrap();
+        assert_eq!(model.mesh.indices, vec![0, 1, 2]);
+
+        let path = dir.join("empty.xyz");
+        std::fs::write(&path, [0_u8; 16]).unwrap();
+        let err = load_model(path.to_str().unwrap()).unwrap_err();
+        assert!(matches!(err, LoadError::UnknownFormat(_)));
+
+        let err = load_model(dir.join("missing.obj").to_str().unwrap()).unwrap_err();
+        assert!(matches!(err, LoadError::Io { .. }));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,337 @@
+//! Free-form curve and surface evaluation.
+//!
+//! Bezier and B-spline elements share one evaluator: a piecewise Bezier is
+//! turned into the equivalent clamped knot vector, then points are found with
+//! de Boor's algorithm, which only needs repeated `lerp`s. Rational elements
+//! are evaluated in homogeneous coordinates and projected back afterwards.
+
+use crate::math::{Vector, lerp};
+
+/// Basis of a free-form element, set by `cstype`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Basis {
+	Bezier,
+	BSpline,
+}
+
+/// A curve, or a surface patch defined by a grid of control points.
+///
+/// Surface control points are listed with `u` varying fastest. Curves only
+/// use the first entry of the per-direction arrays.
+#[derive(Debug, Clone)]
+pub struct FreeForm {
+	pub basis: Basis,
+	pub rational: bool,
+	pub surface: bool,
+	pub degree: [usize; 2],
+	/// Parameter interval to tessellate in each direction.
+	pub range: [[f32; 2]; 2],
+	/// `parm` values: breakpoints for Bezier, knots for B-spline.
+	pub parameters: [Vec<f32>; 2],
+	/// Position indices of the control points.
+	pub control: Vec<usize>,
+}
+
+/// Samples of a surface, `columns` along `u` by `rows` along `v`, stored
+/// row by row.
+#[derive(Debug)]
+pub struct SurfaceGrid {
+	pub columns: usize,
+	pub rows: usize,
+	pub positions: Vec<[f32; 3]>,
+	pub normals: Vec<[f32; 3]>,
+	/// Sample parameters mapped to `[0, 1]`.
+	pub tex_coords: Vec<[f32; 2]>,
+}
+
+const DIRECTION_NAMES: [&str; 2] = ["u", "v"];
+
+impl FreeForm {
+	fn directions(&self) -> usize {
+		if self.surface { 2 } else { 1 }
+	}
+
+	/// Knot vector along `direction`.
+	fn knots(&self, direction: usize) -> Vec<f32> {
+		let parameters = &self.parameters[direction];
+
+		match self.basis {
+			Basis::BSpline => parameters.clone(),
+			Basis::Bezier => {
+				// Inner breakpoints repeated `degree` times, ends clamped
+				let degree = self.degree[direction];
+				let last = parameters.len().saturating_sub(1);
+
+				parameters.iter()
+					.enumerate()
+					.flat_map(|(i, &p)| {
+						let repeat = if i == 0 || i == last { degree + 1 } else { degree };
+						std::iter::repeat_n(p, repeat)
+					})
+					.collect()
+			}
+		}
+	}
+
+	/// Number of control points along `direction` implied by the `parm` values.
+	fn control_count(&self, direction: usize) -> usize {
+		let degree = self.degree[direction];
+		let count = self.parameters[direction].len();
+
+		match self.basis {
+			Basis::BSpline => count.saturating_sub(degree + 1),
+			Basis::Bezier => count.saturating_sub(1) * degree + 1,
+		}
+	}
+
+	/// Checks that the degrees, `parm` values and control points agree.
+	pub fn validate(&self) -> Result<(), String> {
+		let mut expected = 1;
+
+		for (direction, name) in DIRECTION_NAMES.iter().enumerate().take(self.directions()) {
+			let degree = self.degree[direction];
+			let parameters = &self.parameters[direction];
+
+			if degree == 0 {
+				return Err(format!("no degree in {} set by 'deg'", name));
+			}
+
+			let min_parameters = match self.basis {
+				Basis::Bezier => 2,
+				Basis::BSpline => 2 * (degree + 1),
+			};
+			if parameters.len() < min_parameters {
+				return Err(format!("expected at least {} 'parm {}' values, found {}", min_parameters, name, parameters.len()));
+			}
+			if parameters.windows(2).any(|w| w[1] < w[0]) {
+				return Err(format!("'parm {}' values are not in increasing order", name));
+			}
+
+			let (start, end) = self.domain(direction);
+			if start >= end {
+				return Err(format!("empty parameter domain in {}", name));
+			}
+
+			expected *= self.control_count(direction);
+		}
+
+		if self.control.len() != expected {
+			return Err(format!("expected {} control points, found {}", expected, self.control.len()));
+		}
+
+		Ok(())
+	}
+
+	/// Interval over which the basis functions sum to one.
+	fn domain(&self, direction: usize) -> (f32, f32) {
+		let knots = self.knots(direction);
+		let degree = self.degree[direction];
+
+		(knots[degree], knots[knots.len() - degree - 1])
+	}
+
+	/// `segments + 1` parameters spanning the element range, clamped to its domain.
+	fn samples(&self, direction: usize, segments: usize) -> Vec<f32> {
+		let (start, end) = self.domain(direction);
+		let [from, to] = self.range[direction].map(|t| t.clamp(start, end));
+
+		(0..=segments)
+			.map(|i| lerp(&from, &to, i as f32 / segments as f32))
+			.collect()
+	}
+
+	/// Control points as `[x w, y w, z w, w]`.
+	fn homogeneous_points(&self, positions: &[[f32; 3]], weights: &[f32]) -> Vec<Vector> {
+		self.control.iter()
+			.map(|&i| {
+				let [x, y, z] = positions[i];
+				let w = if self.rational { weights.get(i).copied().unwrap_or(1.0) } else { 1.0 };
+				Vector::new(vec![x * w, y * w, z * w, w])
+			})
+			.collect()
+	}
+
+	/// Evaluates `segments + 1` evenly spaced points along a curve.
+	///
+	/// `weights` holds the `w` of each position, or is empty when all are 1.
+	pub fn tessellate_curve(&self, positions: &[[f32; 3]], weights: &[f32], segments: usize) -> Vec<[f32; 3]> {
+		let control = self.homogeneous_points(positions, weights);
+		let knots = self.knots(0);
+
+		self.samples(0, segments)
+			.into_iter()
+			.map(|t| project(&de_boor(self.degree[0], &knots, &control, t)))
+			.collect()
+	}
+
+	/// Evaluates a `(segments + 1)²` grid of surface points.
+	///
+	/// Normals are taken from the grid itself, pointing along `du × dv`.
+	pub fn tessellate_surface(&self, positions: &[[f32; 3]], weights: &[f32], segments: usize) -> SurfaceGrid {
+		let control = self.homogeneous_points(positions, weights);
+		let knots = [self.knots(0), self.knots(1)];
+		let u_samples = self.samples(0, segments);
+		let v_samples = self.samples(1, segments);
+
+		// Collapse each row of control points to a point at `u`, giving a curve along `v`
+		let v_curves: Vec<Vec<Vector>> = u_samples.iter()
+			.map(|&u| control.chunks(self.control_count(0))
+				.map(|row| de_boor(self.degree[0], &knots[0], row, u))
+				.collect())
+			.collect();
+
+		let (columns, rows) = (u_samples.len(), v_samples.len());
+		let mut positions = Vec::with_capacity(columns * rows);
+		let mut tex_coords = Vec::with_capacity(columns * rows);
+
+		for (row, &v) in v_samples.iter().enumerate() {
+			for (column, curve) in v_curves.iter().enumerate() {
+				positions.push(project(&de_boor(self.degree[1], &knots[1], curve, v)));
+				tex_coords.push([column as f32 / segments as f32, row as f32 / segments as f32]);
+			}
+		}
+
+		let normals = grid_normals(&positions, columns, rows);
+
+		SurfaceGrid { columns, rows, positions, normals, tex_coords }
+	}
+}
+
+/// Evaluates a B-spline at `t` with de Boor's algorithm.
+///
+/// `knots` must hold `control.len() + degree + 1` values.
+fn de_boor(degree: usize, knots: &[f32], control: &[Vector], t: f32) -> Vector {
+	let last = control.len() - 1;
+	// Last span starting at or before t, so the end of the domain is included
+	let span = (degree..=last).rev()
+		.find(|&k| knots[k] <= t)
+		.unwrap_or(degree);
+
+	let mut points: Vec<Vector> = control[span - degree..=span].to_vec();
+
+	for r in 1..=degree {
+		for j in (r..=degree).rev() {
+			let i = j + span - degree;
+			let width = knots[i + degree + 1 - r] - knots[i];
+			let alpha = if width > 0.0 { (t - knots[i]) / width } else { 0.0 };
+			points[j] = lerp(&points[j - 1], &points[j], alpha);
+		}
+	}
+
+	points.swap_remove(degree)
+}
+
+/// Projects a homogeneous point back to 3D.
+fn project(point: &Vector) -> [f32; 3] {
+	let [x, y, z, w] = [0, 1, 2, 3].map(|i| point.as_slice()[i]);
+	let w = if w.abs() > f32::EPSILON { w } else { 1.0 };
+
+	[x / w, y / w, z / w]
+}
+
+/// Normal at each grid point from central differences with its neighbours.
+fn grid_normals(positions: &[[f32; 3]], columns: usize, rows: usize) -> Vec<[f32; 3]> {
+	let at = |column: usize, row: usize| Vector::new(positions[row * columns + column].to_vec());
+	let mut normals = Vec::with_capacity(positions.len());
+
+	for row in 0..rows {
+		for column in 0..columns {
+			let du = at((column + 1).min(columns - 1), row).sub_vec(&at(column.saturating_sub(1), row));
+			let dv = at(column, (row + 1).min(rows - 1)).sub_vec(&at(column, row.saturating_sub(1)));
+			let normal = du.cross(&dv);
+
+			normals.push(if normal.norm() > 1e-12 {
+				let n = normal.normalize();
+				[n.as_slice()[0], n.as_slice()[1], n.as_slice()[2]]
+			} else {
+				[0.0, 1.0, 0.0]
+			});
+		}
+	}
+
+	normals
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(a: [f32; 3], b: [f32; 3]) {
+        for i in 0..3 {
+            assert!((a[i] - b[i]).abs() < 1e-5, "{:?} != {:?}", a, b);
+        }
+    }
+
+    fn curve(basis: Basis, degree: usize, parameters: Vec<f32>, control: usize) -> FreeForm {
+        FreeForm {
+            basis,
+            rational: false,
+            surface: false,
+            degree: [degree, 0],
+            range: [[0.0, 1.0], [0.0, 0.0]],
+            parameters: [parameters, Vec::new()],
+            control: (0..control).collect(),
+        }
+    }
+
+    #[test]
+    fn bezier_and_clamped_bspline_agree() {
+        let positions = [[0.0, 0.0, 0.0], [1.0, 2.0, 0.0], [3.0, 2.0, 0.0], [4.0, 0.0, 0.0]];
+        let bezier = curve(Basis::Bezier, 3, vec![0.0, 1.0], 4);
+        let bspline = curve(Basis::BSpline, 3, vec![0.0, 0.0, 0.0, 0.0, 1.0, 1.0, 1.0, 1.0], 4);
+        assert!(bezier.validate().is_ok());
+        assert!(bspline.validate().is_ok());
+
+        let a = bezier.tessellate_curve(&positions, &[], 4);
+        let b = bspline.tessellate_curve(&positions, &[], 4);
+        assert_eq!(a.len(), 5);
+        assert_close(a[0], positions[0]);
+        assert_close(a[4], positions[3]);
+        // B(0.5) = (P0 + 3 P1 + 3 P2 + P3) / 8
+        assert_close(a[2], [2.0, 1.5, 0.0]);
+        for (p, q) in a.iter().zip(&b) {
+            assert_close(*p, *q);
+        }
+    }
+
+    #[test]
+    fn rational_quarter_circle() {
+        let positions = [[1.0, 0.0, 0.0], [1.0, 1.0, 0.0], [0.0, 1.0, 0.0]];
+        let weights = [1.0, std::f32::consts::FRAC_1_SQRT_2, 1.0];
+        let arc = FreeForm { rational: true, ..curve(Basis::Bezier, 2, vec![0.0, 1.0], 3) };
+
+        for point in arc.tessellate_curve(&positions, &weights, 8) {
+            let radius = (point[0] * point[0] + point[1] * point[1]).sqrt();
+            assert!((radius - 1.0).abs() < 1e-5, "{:?}", point);
+        }
+    }
+
+    #[test]
+    fn validation_reports_mismatched_counts() {
+        assert!(curve(Basis::Bezier, 3, vec![0.0, 1.0], 5).validate().unwrap_err().contains("expected 4 control points"));
+        assert!(curve(Basis::BSpline, 2, vec![0.0, 0.0, 1.0], 2).validate().is_err());
+        assert!(curve(Basis::Bezier, 0, vec![0.0, 1.0], 1).validate().is_err());
+        assert!(curve(Basis::Bezier, 1, vec![1.0, 0.0], 2).validate().is_err());
+    }
+
+    #[test]
+    fn bilinear_patch_is_flat_and_facing_up() {
+        let positions = [[0.0, 0.0, 0.0], [2.0, 0.0, 0.0], [0.0, 2.0, 0.0], [2.0, 2.0, 0.0]];
+        let patch = FreeForm {
+            surface: true,
+            degree: [1, 1],
+            range: [[0.0, 1.0], [0.0, 1.0]],
+            parameters: [vec![0.0, 1.0], vec![0.0, 1.0]],
+            ..curve(Basis::Bezier, 1, Vec::new(), 4)
+        };
+        assert!(patch.validate().is_ok());
+
+        let grid = patch.tessellate_surface(&positions, &[], 2);
+        assert_eq!((grid.columns, grid.rows), (3, 3));
+        assert_close(grid.positions[1], [1.0, 0.0, 0.0]);
+        assert_close(grid.positions[3], [0.0, 1.0, 0.0]);
+        assert_close(grid.positions[4], [1.0, 1.0, 0.0]);
+        assert_eq!(grid.tex_coords[5], [1.0, 0.5]);
+        assert!(grid.normals.iter().all(|&n| n == [0.0, 0.0, 1.0]));
+    }
+}
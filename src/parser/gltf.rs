@@ -0,0 +1,1285 @@
+use std::path::Path;
+
+use crate::math::{Matrix, Transform, Vector};
+use crate::mesh::normals::smooth_normals;
+use crate::mesh::{Mesh, MeshPart, Vertex};
+use crate::parser::error::GltfError;
+use crate::parser::json::{JsonValue, parse_json};
+use crate::parser::mtl::{Material, resolve_relative};
+use crate::parser::obj::{BoudingBox, generate_planar_uv};
+
+/// First bytes of a binary glTF container.
+pub const GLB_MAGIC: &[u8; 4] = b"glTF";
+
+const GLB_HEADER_SIZE: usize = 12;
+const CHUNK_JSON: u32 = 0x4E4F_534A;
+const CHUNK_BIN: u32 = 0x004E_4942;
+
+/// Upper bound on the values of an accessor without a buffer view, which
+/// would otherwise let a single number in the JSON allocate any amount.
+const MAX_ZEROED_VALUES: usize = 1 << 28;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum AlphaMode {
+	Opaque,
+	Mask,
+	Blend,
+}
+
+/// Metallic-roughness material parameters.
+///
+/// Texture fields index into [`GltfDocument::images`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct PbrMaterial {
+	pub name: String,
+	pub base_color_factor: [f32; 4],
+	pub base_color_texture: Option<usize>,
+	pub metallic_factor: f32,
+	pub roughness_factor: f32,
+	pub metallic_roughness_texture: Option<usize>,
+	pub normal_texture: Option<usize>,
+	pub occlusion_texture: Option<usize>,
+	pub emissive_factor: [f32; 3],
+	pub emissive_texture: Option<usize>,
+	pub alpha_mode: AlphaMode,
+	pub alpha_cutoff: f32,
+	pub double_sided: bool,
+}
+
+impl PbrMaterial {
+	/// The closest OBJ-style material: base color as diffuse and opacity as
+	/// dissolve. The base color texture is kept when it is an external file.
+	pub fn to_material(&self, images: &[GltfImage]) -> Material {
+		let [r, g, b, a] = self.base_color_factor;
+		let mut material = Material::new(&self.name);
+
+		material.diffuse = [r, g, b];
+		material.dissolve = a;
+		material.diffuse_map = self.base_color_texture
+			.and_then(|i| match &images[i] {
+				GltfImage::File(path) => Some(path.clone()),
+				GltfImage::Embedded { .. } => None,
+			});
+
+		material
+	}
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum GltfImage {
+	/// External file, resolved against the glTF file.
+	File(String),
+	/// Encoded bytes from a data URI or a buffer view.
+	Embedded { mime_type: Option<String>, data: Vec<u8> },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PrimitiveMode {
+	Points,
+	Lines,
+	LineLoop,
+	LineStrip,
+	Triangles,
+	TriangleStrip,
+	TriangleFan,
+}
+
+impl PrimitiveMode {
+	fn from_code(code: usize) -> Option<Self> {
+		Some(match code {
+			0 => PrimitiveMode::Points,
+			1 => PrimitiveMode::Lines,
+			2 => PrimitiveMode::LineLoop,
+			3 => PrimitiveMode::LineStrip,
+			4 => PrimitiveMode::Triangles,
+			5 => PrimitiveMode::TriangleStrip,
+			6 => PrimitiveMode::TriangleFan,
+			_ => return None,
+		})
+	}
+}
+
+/// One draw of a glTF mesh, with attributes in the mesh's own space.
+#[derive(Debug, Clone)]
+pub struct GltfPrimitive {
+	pub mode: PrimitiveMode,
+	pub positions: Vec<[f32; 3]>,
+	pub normals: Option<Vec<[f32; 3]>>,
+	pub tex_coords: Option<Vec<[f32; 2]>>,
+	pub colors: Option<Vec<[f32; 3]>>,
+	/// Tangents with the bitangent sign in `w`.
+	pub tangents: Option<Vec<[f32; 4]>>,
+	/// Vertex order, `0..n` when the file has no index accessor.
+	pub indices: Vec<u32>,
+	pub material: Option<usize>,
+}
+
+/// Elements of a primitive once its mode is applied.
+#[derive(Debug, Default, PartialEq)]
+pub struct Topology {
+	pub triangles: Vec<[u32; 3]>,
+	/// Vertex pairs.
+	pub lines: Vec<u32>,
+	pub points: Vec<u32>,
+}
+
+impl GltfPrimitive {
+	/// Expands strips, fans and loops into plain lists.
+	pub fn topology(&self) -> Topology {
+		let indices = &self.indices;
+		let mut topology = Topology::default();
+
+		match self.mode {
+			PrimitiveMode::Points => topology.points = indices.clone(),
+			PrimitiveMode::Lines => topology.lines = indices[..indices.len() / 2 * 2].to_vec(),
+			PrimitiveMode::LineStrip | PrimitiveMode::LineLoop => {
+				topology.lines = indices.windows(2).flatten().copied().collect();
+				if self.mode == PrimitiveMode::LineLoop && indices.len() > 2 {
+					topology.lines.extend([indices[indices.len() - 1], indices[0]]);
+				}
+			}
+			PrimitiveMode::Triangles => {
+				topology.triangles = indices.chunks_exact(3).map(|t| [t[0], t[1], t[2]]).collect();
+			}
+			PrimitiveMode::TriangleStrip => {
+				// Every other triangle is reversed to keep a consistent winding
+				topology.triangles = indices.windows(3)
+					.enumerate()
+					.map(|(i, t)| if i % 2 == 0 { [t[0], t[1], t[2]] } else { [t[0], t[2], t[1]] })
+					.filter(|t| t[0] != t[1] && t[1] != t[2] && t[0] != t[2])
+					.collect();
+			}
+			PrimitiveMode::TriangleFan => {
+				topology.triangles = indices.windows(2)
+					.skip(1)
+					.map(|t| [t[0], t[1], indices[0]])
+					.collect();
+			}
+		}
+
+		topology
+	}
+}
+
+#[derive(Debug, Clone)]
+pub struct GltfMesh {
+	pub name: String,
+	pub primitives: Vec<GltfPrimitive>,
+}
+
+#[derive(Debug, Clone)]
+pub struct SceneNode {
+	pub name: String,
+	/// Transform relative to the parent node.
+	pub local: Matrix,
+	pub children: Vec<usize>,
+	pub mesh: Option<usize>,
+}
+
+#[derive(Debug, Clone)]
+pub struct GltfDocument {
+	pub meshes: Vec<GltfMesh>,
+	pub materials: Vec<PbrMaterial>,
+	pub images: Vec<GltfImage>,
+	/// Node forest, children always have a single parent.
+	pub nodes: Vec<SceneNode>,
+	/// Top-level nodes of the displayed scene.
+	pub roots: Vec<usize>,
+}
+
+impl GltfDocument {
+	/// Nodes reachable from the roots with their world transform, parents
+	/// before their children.
+	pub fn world_transforms(&self) -> Vec<(usize, Matrix)> {
+		let mut transforms = Vec::new();
+		let mut stack: Vec<(usize, Matrix)> = self.roots.iter()
+			.rev()
+			.map(|&root| (root, Matrix::identity(4)))
+			.collect();
+
+		while let Some((node, parent)) = stack.pop() {
+			let world = parent.mul_mat(&self.nodes[node].local);
+			stack.extend(self.nodes[node].children.iter().rev().map(|&child| (child, world.clone())));
+			transforms.push((node, world));
+		}
+
+		transforms
+	}
+}
+
+pub fn load_gltf(file_path: &str) -> Result<Mesh, GltfError> {
+	let document = parse_gltf(file_path)?;
+	Ok(gltf_to_mesh(&document))
+}
+
+pub fn parse_gltf(file_path: &str) -> Result<GltfDocument, GltfError> {
+	let bytes = std::fs::read(file_path)
+		.map_err(|source| GltfError::Io { path: file_path.to_string(), source })?;
+	let base_dir = Path::new(file_path).parent().unwrap_or(Path::new(""));
+
+	parse_gltf_from_bytes(&bytes, base_dir)
+}
+
+/// Parses a `.gltf` document or a `.glb` container.
+///
+/// External buffers and images are looked up relative to `base_dir`.
+pub fn parse_gltf_from_bytes(bytes: &[u8], base_dir: &Path) -> Result<GltfDocument, GltfError> {
+	let (json, bin) = if bytes.starts_with(GLB_MAGIC) {
+		split_glb(bytes)?
+	} else {
+		(bytes, None)
+	};
+
+	let text = std::str::from_utf8(json)
+		.map_err(|_| GltfError::Invalid("JSON is not UTF-8 text".to_string()))?;
+	let root = parse_json(text.trim_start_matches('\u{feff}')).map_err(GltfError::Json)?;
+
+	check_asset(&root)?;
+
+	let buffers = load_buffers(&root, base_dir, bin)?;
+	let loader = Loader { root: &root, buffers, views: read_buffer_views(&root)? };
+	loader.document(base_dir)
+}
+
+/// Splits a binary container into its JSON chunk and optional BIN chunk.
+fn split_glb(bytes: &[u8]) -> Result<(&[u8], Option<&[u8]>), GltfError> {
+	let word = |offset: usize| bytes.get(offset..offset + 4)
+		.map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]) as usize);
+
+	let (Some(version), Some(length)) = (word(4), word(8)) else {
+		return Err(GltfError::Invalid("GLB header is truncated".to_string()));
+	};
+	if version != 2 {
+		return Err(GltfError::Unsupported(format!("GLB container version {}", version)));
+	}
+	if length > bytes.len() {
+		return Err(GltfError::Invalid(format!("GLB declares {} bytes but the file has {}", length, bytes.len())));
+	}
+
+	let mut chunks = Vec::new();
+	let mut offset = GLB_HEADER_SIZE;
+	while offset < length {
+		let (Some(chunk_length), Some(chunk_type)) = (word(offset), word(offset + 4)) else {
+			return Err(GltfError::Invalid("GLB chunk header is truncated".to_string()));
+		};
+		let data = bytes.get(offset + 8..offset + 8 + chunk_length)
+			.filter(|_| offset + 8 + chunk_length <= length)
+			.ok_or_else(|| GltfError::Invalid("GLB chunk runs past the end of the file".to_string()))?;
+
+		chunks.push((chunk_type as u32, data));
+		offset += 8 + chunk_length;
+	}
+
+	match chunks[..] {
+		[(CHUNK_JSON, json), ..] => {
+			let bin = chunks.get(1).filter(|(ty, _)| *ty == CHUNK_BIN).map(|&(_, data)| data);
+			Ok((json, bin))
+		}
+		_ => Err(GltfError::Invalid("GLB does not start with a JSON chunk".to_string())),
+	}
+}
+
+fn check_asset(root: &JsonValue) -> Result<(), GltfError> {
+	let version = root.get("asset")
+		.and_then(|asset| asset.get("version"))
+		.and_then(JsonValue::as_str)
+		.ok_or_else(|| GltfError::Invalid("asset.version is missing".to_string()))?;
+	if !version.starts_with("2.") {
+		return Err(GltfError::Unsupported(format!("glTF version {}", version)));
+	}
+
+	let required: Vec<&str> = items(root, "extensionsRequired").iter()
+		.filter_map(JsonValue::as_str)
+		.collect();
+	if !required.is_empty() {
+		return Err(GltfError::Unsupported(format!("required extensions {}", required.join(", "))));
+	}
+
+	Ok(())
+}
+
+fn load_buffers(root: &JsonValue, base_dir: &Path, bin: Option<&[u8]>) -> Result<Vec<Vec<u8>>, GltfError> {
+	items(root, "buffers").iter().enumerate()
+		.map(|(i, buffer)| {
+			let context = format!("buffers[{}]", i);
+			let length = required_index(buffer, "byteLength", &context)?;
+
+			let data = match buffer.get("uri").and_then(JsonValue::as_str) {
+				Some(uri) => read_uri(uri, base_dir, &context)?.1,
+				None if i == 0 => bin
+					.ok_or_else(|| GltfError::Invalid(format!("{} has no uri and the file has no BIN chunk", context)))?
+					.to_vec(),
+				None => return Err(GltfError::Invalid(format!("{} has no uri", context))),
+			};
+
+			if data.len() < length {
+				return Err(GltfError::Invalid(format!(
+					"{} declares {} bytes but holds {}", context, length, data.len(),
+				)));
+			}
+			Ok(data)
+		})
+		.collect()
+}
+
+/// Contents of a data URI with its MIME type, or of an external file.
+fn read_uri(uri: &str, base_dir: &Path, context: &str) -> Result<(Option<String>, Vec<u8>), GltfError> {
+	if let Some(data_uri) = uri.strip_prefix("data:") {
+		let (header, payload) = data_uri.split_once(',')
+			.ok_or_else(|| GltfError::Invalid(format!("{} has a malformed data URI", context)))?;
+		let Some(mime_type) = header.strip_suffix(";base64") else {
+			return Err(GltfError::Unsupported(format!("{} uses a data URI without base64", context)));
+		};
+		let data = decode_base64(payload)
+			.ok_or_else(|| GltfError::Invalid(format!("{} has invalid base64 data", context)))?;
+
+		return Ok((Some(mime_type.to_string()).filter(|m| !m.is_empty()), data));
+	}
+
+	let path = resolve_relative(base_dir, &decode_percent(uri));
+	let data = std::fs::read(&path)
+		.map_err(|source| GltfError::Io { path, source })?;
+	Ok((None, data))
+}
+
+fn decode_base64(text: &str) -> Option<Vec<u8>> {
+	let text = text.trim_end_matches('=');
+	let mut out = Vec::with_capacity(text.len() * 3 / 4);
+	let mut bits = 0_u32;
+	let mut bit_count = 0;
+
+	for byte in text.bytes() {
+		let value = match byte {
+			b'A'..=b'Z' => byte - b'A',
+			b'a'..=b'z' => byte - b'a' + 26,
+			b'0'..=b'9' => byte - b'0' + 52,
+			b'+' | b'-' => 62,
+			b'/' | b'_' => 63,
+			_ => return None,
+		};
+		bits = (bits << 6) | value as u32;
+		bit_count += 6;
+
+		if bit_count >= 8 {
+			bit_count -= 8;
+			out.push((bits >> bit_count) as u8);
+		}
+	}
+
+	Some(out)
+}
+
+/// Undoes `%XX` escapes in a relative URI, leaving malformed ones as they are.
+fn decode_percent(uri: &str) -> String {
+	let bytes = uri.as_bytes();
+	let mut out = Vec::with_capacity(bytes.len());
+	let mut i = 0;
+
+	while i < bytes.len() {
+		let escaped = (bytes[i] == b'%')
+			.then(|| uri.get(i + 1..i + 3))
+			.flatten()
+			.and_then(|hex| u8::from_str_radix(hex, 16).ok());
+
+		match escaped {
+			Some(byte) => {
+				out.push(byte);
+				i += 3;
+			}
+			None => {
+				out.push(bytes[i]);
+				i += 1;
+			}
+		}
+	}
+
+	String::from_utf8_lossy(&out).into_owned()
+}
+
+struct BufferView {
+	buffer: usize,
+	offset: usize,
+	length: usize,
+	stride: Option<usize>,
+}
+
+fn read_buffer_views(root: &JsonValue) -> Result<Vec<BufferView>, GltfError> {
+	items(root, "bufferViews").iter().enumerate()
+		.map(|(i, view)| {
+			let context = format!("bufferViews[{}]", i);
+			Ok(BufferView {
+				buffer: required_index(view, "buffer", &context)?,
+				offset: index(view, "byteOffset", &context)?.unwrap_or(0),
+				length: required_index(view, "byteLength", &context)?,
+				stride: index(view, "byteStride", &context)?,
+			})
+		})
+		.collect()
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum ComponentType {
+	Int8,
+	UInt8,
+	Int16,
+	UInt16,
+	UInt32,
+	Float32,
+}
+
+impl ComponentType {
+	fn from_code(code: usize) -> Option<Self> {
+		Some(match code {
+			5120 => ComponentType::Int8,
+			5121 => ComponentType::UInt8,
+			5122 => ComponentType::Int16,
+			5123 => ComponentType::UInt16,
+			5125 => ComponentType::UInt32,
+			5126 => ComponentType::Float32,
+			_ => return None,
+		})
+	}
+
+	fn size(self) -> usize {
+		match self {
+			ComponentType::Int8 | ComponentType::UInt8 => 1,
+			ComponentType::Int16 | ComponentType::UInt16 => 2,
+			ComponentType::UInt32 | ComponentType::Float32 => 4,
+		}
+	}
+
+	/// Reads one little-endian component, mapping normalized integers to
+	/// [-1, 1] or [0, 1].
+	fn read(self, bytes: &[u8], normalized: bool) -> f64 {
+		let (value, max) = match self {
+			ComponentType::Int8 => (bytes[0] as i8 as f64, i8::MAX as f64),
+			ComponentType::UInt8 => (bytes[0] as f64, u8::MAX as f64),
+			ComponentType::Int16 => (i16::from_le_bytes([bytes[0], bytes[1]]) as f64, i16::MAX as f64),
+			ComponentType::UInt16 => (u16::from_le_bytes([bytes[0], bytes[1]]) as f64, u16::MAX as f64),
+			ComponentType::UInt32 => (u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]) as f64, u32::MAX as f64),
+			ComponentType::Float32 => return f32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]) as f64,
+		};
+
+		if normalized { (value / max).max(-1.0) } else { value }
+	}
+}
+
+fn element_components(ty: &str) -> Option<usize> {
+	Some(match ty {
+		"SCALAR" => 1,
+		"VEC2" => 2,
+		"VEC3" => 3,
+		"VEC4" | "MAT2" => 4,
+		"MAT3" => 9,
+		"MAT4" => 16,
+		_ => return None,
+	})
+}
+
+/// How one accessor element is stored.
+#[derive(Debug, Clone, Copy)]
+struct ElementLayout {
+	component: ComponentType,
+	components: usize,
+	normalized: bool,
+}
+
+impl ElementLayout {
+	fn size(self) -> usize {
+		self.component.size() * self.components
+	}
+}
+
+/// Reads `count` elements, `stride` bytes apart.
+fn read_elements(
+	bytes: &[u8],
+	offset: usize,
+	stride: usize,
+	count: usize,
+	layout: ElementLayout,
+	context: &str,
+) -> Result<Vec<f64>, GltfError> {
+	let ElementLayout { component, components, normalized } = layout;
+	let element_size = layout.size();
+	let end = count.checked_sub(1)
+		.map_or(Some(0), |last| last.checked_mul(stride)
+			.and_then(|last_start| last_start.checked_add(offset + element_size)));
+
+	if end.is_none_or(|end| end > bytes.len()) {
+		return Err(GltfError::Invalid(format!("{} reads past the end of its buffer view", context)));
+	}
+
+	let mut values = Vec::with_capacity(count * components);
+	for element in 0..count {
+		let start = offset + element * stride;
+		for k in 0..components {
+			values.push(component.read(&bytes[start + k * component.size()..], normalized));
+		}
+	}
+	Ok(values)
+}
+
+/// Reads accessors and the objects built on them out of the document.
+struct Loader<'a> {
+	root: &'a JsonValue,
+	buffers: Vec<Vec<u8>>,
+	views: Vec<BufferView>,
+}
+
+impl Loader<'_> {
+	fn document(&self, base_dir: &Path) -> Result<GltfDocument, GltfError> {
+		let images = items(self.root, "images").iter().enumerate()
+			.map(|(i, image)| self.image(image, &format!("images[{}]", i), base_dir))
+			.collect::<Result<Vec<_>, _>>()?;
+
+		let materials = items(self.root, "materials").iter().enumerate()
+			.map(|(i, material)| self.material(material, &format!("materials[{}]", i), images.len()))
+			.collect::<Result<Vec<_>, _>>()?;
+
+		let meshes = items(self.root, "meshes").iter().enumerate()
+			.map(|(i, mesh)| self.mesh(mesh, &format!("meshes[{}]", i), materials.len()))
+			.collect::<Result<Vec<_>, _>>()?;
+
+		let node_count = items(self.root, "nodes").len();
+		let nodes = items(self.root, "nodes").iter().enumerate()
+			.map(|(i, node)| read_node(node, &format!("nodes[{}]", i), node_count, meshes.len()))
+			.collect::<Result<Vec<_>, _>>()?;
+		let has_parent = check_hierarchy(&nodes)?;
+
+		let scenes = items(self.root, "scenes");
+		let scene = match index(self.root, "scene", "document")? {
+			Some(scene) => Some(lookup(scenes, scene, "scene", "document")?),
+			None => scenes.first(),
+		};
+		let roots = match scene {
+			Some(scene) => items(scene, "nodes").iter()
+				.map(|node| node.as_usize()
+					.filter(|&node| node < nodes.len())
+					.ok_or_else(|| GltfError::Invalid("scene lists a node that does not exist".to_string())))
+				.collect::<Result<Vec<_>, _>>()?,
+			None => (0..nodes.len()).filter(|&node| !has_parent[node]).collect(),
+		};
+
+		Ok(GltfDocument { meshes, materials, images, nodes, roots })
+	}
+
+	fn view(&self, view: usize, context: &str) -> Result<(&[u8], Option<usize>), GltfError> {
+		let view = lookup(&self.views, view, "buffer view", context)?;
+		let buffer = lookup(&self.buffers, view.buffer, "buffer", context)?;
+		let bytes = view.offset.checked_add(view.length)
+			.and_then(|end| buffer.get(view.offset..end))
+			.ok_or_else(|| GltfError::Invalid(format!("{} uses a buffer view larger than its buffer", context)))?;
+
+		Ok((bytes, view.stride))
+	}
+
+	/// Values of accessor `accessor` with sparse substitutions applied, and
+	/// the number of values per element.
+	fn accessor(&self, accessor: usize, context: &str) -> Result<(Vec<f64>, usize), GltfError> {
+		let json = lookup(items(self.root, "accessors"), accessor, "accessor", context)?;
+		let context = format!("accessors[{}]", accessor);
+
+		let layout = ElementLayout {
+			component: component_type(json, &context)?,
+			components: json.get("type")
+				.and_then(JsonValue::as_str)
+				.and_then(element_components)
+				.ok_or_else(|| GltfError::Invalid(format!("{}.type is missing or unknown", context)))?,
+			normalized: json.get("normalized").and_then(JsonValue::as_bool).unwrap_or(false),
+		};
+		let components = layout.components;
+		let count = required_index(json, "count", &context)?;
+		let offset = index(json, "byteOffset", &context)?.unwrap_or(0);
+
+		let mut values = match index(json, "bufferView", &context)? {
+			Some(view) => {
+				let (bytes, stride) = self.view(view, &context)?;
+				read_elements(bytes, offset, stride.unwrap_or(layout.size()), count, layout, &context)?
+			}
+			None => {
+				let size = count.checked_mul(components).filter(|&size| size <= MAX_ZEROED_VALUES)
+					.ok_or_else(|| GltfError::Invalid(format!("{} is too large", context)))?;
+				vec![0.0; size]
+			}
+		};
+
+		if let Some(sparse) = json.get("sparse") {
+			let context = format!("{}.sparse", context);
+			let sparse_count = required_index(sparse, "count", &context)?;
+			let section = |key: &str| sparse.get(key)
+				.ok_or_else(|| GltfError::Invalid(format!("{}.{} is missing", context, key)));
+
+			let indices = section("indices")?;
+			let index_type = component_type(indices, &context)?;
+			let (bytes, _) = self.view(required_index(indices, "bufferView", &context)?, &context)?;
+			let offset = index(indices, "byteOffset", &context)?.unwrap_or(0);
+			let index_layout = ElementLayout { component: index_type, components: 1, normalized: false };
+			let targets = read_elements(bytes, offset, index_type.size(), sparse_count, index_layout, &context)?;
+
+			let substitutes = section("values")?;
+			let (bytes, _) = self.view(required_index(substitutes, "bufferView", &context)?, &context)?;
+			let offset = index(substitutes, "byteOffset", &context)?.unwrap_or(0);
+			let substitutes = read_elements(bytes, offset, layout.size(), sparse_count, layout, &context)?;
+
+			for (k, &target) in targets.iter().enumerate() {
+				let target = target as usize;
+				if target >= count {
+					return Err(GltfError::Invalid(format!("{} replaces element {} of {}", context, target, count)));
+				}
+				values[target * components..(target + 1) * components]
+					.copy_from_slice(&substitutes[k * components..(k + 1) * components]);
+			}
+		}
+
+		Ok((values, components))
+	}
+
+	/// Reads an accessor of `N`-component elements, also accepting wider
+	/// elements whose extra components are dropped when `allow_wider`.
+	fn vectors<const N: usize>(&self, accessor: usize, allow_wider: bool, context: &str) -> Result<Vec<[f32; N]>, GltfError> {
+		let (values, components) = self.accessor(accessor, context)?;
+		if components != N && !(allow_wider && components > N) {
+			return Err(GltfError::Invalid(format!("{} expects {}-component elements, found {}", context, N, components)));
+		}
+
+		Ok(values.chunks_exact(components)
+			.map(|element| std::array::from_fn(|k| element[k] as f32))
+			.collect())
+	}
+
+	fn mesh(&self, json: &JsonValue, context: &str, material_count: usize) -> Result<GltfMesh, GltfError> {
+		let mut primitives = Vec::new();
+		for (i, primitive) in items(json, "primitives").iter().enumerate() {
+			let context = format!("{}.primitives[{}]", context, i);
+			primitives.extend(self.primitive(primitive, &context, material_count)?);
+		}
+
+		Ok(GltfMesh { name: name(json), primitives })
+	}
+
+	/// A primitive, or `None` for one without positions, which has nothing
+	/// to draw.
+	fn primitive(&self, json: &JsonValue, context: &str, material_count: usize) -> Result<Option<GltfPrimitive>, GltfError> {
+		let attributes = json.get("attributes")
+			.ok_or_else(|| GltfError::Invalid(format!("{}.attributes is missing", context)))?;
+		let attribute = |name: &str| index(attributes, name, context);
+
+		let Some(position) = attribute("POSITION")? else {
+			return Ok(None);
+		};
+		let positions: Vec<[f32; 3]> = self.vectors(position, false, context)?;
+		let vertex_count = positions.len();
+
+		let check_count = |name: &str, count: usize| if count == vertex_count {
+			Ok(())
+		} else {
+			Err(GltfError::Invalid(format!("{} has {} {} values for {} positions", context, count, name, vertex_count)))
+		};
+
+		let normals = attribute("NORMAL")?.map(|a| self.vectors::<3>(a, false, context)).transpose()?;
+		let tex_coords = attribute("TEXCOORD_0")?.map(|a| self.vectors::<2>(a, false, context)).transpose()?;
+		let colors = attribute("COLOR_0")?.map(|a| self.vectors::<3>(a, true, context)).transpose()?;
+		let tangents = attribute("TANGENT")?.map(|a| self.vectors::<4>(a, false, context)).transpose()?;
+		for (name, count) in [
+			("NORMAL", normals.as_ref().map(Vec::len)),
+			("TEXCOORD_0", tex_coords.as_ref().map(Vec::len)),
+			("COLOR_0", colors.as_ref().map(Vec::len)),
+			("TANGENT", tangents.as_ref().map(Vec::len)),
+		] {
+			if let Some(count) = count {
+				check_count(name, count)?;
+			}
+		}
+
+		let indices = match index(json, "indices", context)? {
+			Some(accessor) => {
+				let (values, _) = self.accessor(accessor, context)?;
+				if let Some(&bad) = values.iter().find(|&&i| i as usize >= vertex_count) {
+					return Err(GltfError::Invalid(format!("{} references vertex {} of {}", context, bad, vertex_count)));
+				}
+				values.into_iter().map(|i| i as u32).collect()
+			}
+			None => (0..vertex_count as u32).collect(),
+		};
+
+		let mode = index(json, "mode", context)?.unwrap_or(4);
+		let mode = PrimitiveMode::from_code(mode)
+			.ok_or_else(|| GltfError::Invalid(format!("{} has unknown mode {}", context, mode)))?;
+
+		let material = index(json, "material", context)?;
+		if let Some(material) = material {
+			check_index(material, material_count, "material", context)?;
+		}
+
+		Ok(Some(GltfPrimitive { mode, positions, normals, tex_coords, colors, tangents, indices, material }))
+	}
+
+	fn material(&self, json: &JsonValue, context: &str, image_count: usize) -> Result<PbrMaterial, GltfError> {
+		let no_pbr = JsonValue::Object(Vec::new());
+		let pbr = json.get("pbrMetallicRoughness").unwrap_or(&no_pbr);
+		let texture = |json: &JsonValue, key: &str| self.texture(json.get(key), context, image_count);
+
+		let alpha_mode = match json.get("alphaMode").and_then(JsonValue::as_str) {
+			None | Some("OPAQUE") => AlphaMode::Opaque,
+			Some("MASK") => AlphaMode::Mask,
+			Some("BLEND") => AlphaMode::Blend,
+			Some(other) => return Err(GltfError::Invalid(format!("{} has unknown alphaMode '{}'", context, other))),
+		};
+
+		Ok(PbrMaterial {
+			name: name(json),
+			base_color_factor: floats(pbr, "baseColorFactor", [1.0; 4], context)?,
+			base_color_texture: texture(pbr, "baseColorTexture")?,
+			metallic_factor: floats(pbr, "metallicFactor", [1.0], context)?[0],
+			roughness_factor: floats(pbr, "roughnessFactor", [1.0], context)?[0],
+			metallic_roughness_texture: texture(pbr, "metallicRoughnessTexture")?,
+			normal_texture: texture(json, "normalTexture")?,
+			occlusion_texture: texture(json, "occlusionTexture")?,
+			emissive_factor: floats(json, "emissiveFactor", [0.0; 3], context)?,
+			emissive_texture: texture(json, "emissiveTexture")?,
+			alpha_mode,
+			alpha_cutoff: floats(json, "alphaCutoff", [0.5], context)?[0],
+			double_sided: json.get("doubleSided").and_then(JsonValue::as_bool).unwrap_or(false),
+		})
+	}
+
+	/// Image behind a texture reference. Textures whose image only comes
+	/// from an extension have none.
+	fn texture(&self, info: Option<&JsonValue>, context: &str, image_count: usize) -> Result<Option<usize>, GltfError> {
+		let Some(info) = info else {
+			return Ok(None);
+		};
+
+		let texture = lookup(items(self.root, "textures"), required_index(info, "index", context)?, "texture", context)?;
+		let image = index(texture, "source", context)?;
+		if let Some(image) = image {
+			check_index(image, image_count, "image", context)?;
+		}
+		Ok(image)
+	}
+
+	fn image(&self, json: &JsonValue, context: &str, base_dir: &Path) -> Result<GltfImage, GltfError> {
+		let mime_type = json.get("mimeType").and_then(JsonValue::as_str).map(str::to_string);
+
+		if let Some(uri) = json.get("uri").and_then(JsonValue::as_str) {
+			if uri.starts_with("data:") {
+				let (uri_mime_type, data) = read_uri(uri, base_dir, context)?;
+				return Ok(GltfImage::Embedded { mime_type: uri_mime_type.or(mime_type), data });
+			}
+			return Ok(GltfImage::File(resolve_relative(base_dir, &decode_percent(uri))));
+		}
+
+		let view = index(json, "bufferView", context)?
+			.ok_or_else(|| GltfError::Invalid(format!("{} has neither uri nor bufferView", context)))?;
+		let (data, _) = self.view(view, context)?;
+		Ok(GltfImage::Embedded { mime_type, data: data.to_vec() })
+	}
+}
+
+fn read_node(json: &JsonValue, context: &str, node_count: usize, mesh_count: usize) -> Result<SceneNode, GltfError> {
+	let local = match json.get("matrix") {
+		Some(_) => Matrix::new(floats::<16>(json, "matrix", [0.0; 16], context)?.to_vec(), 4, 4),
+		None => {
+			let [tx, ty, tz] = floats(json, "translation", [0.0; 3], context)?;
+			let [sx, sy, sz] = floats(json, "scale", [1.0; 3], context)?;
+			let rotation = floats(json, "rotation", [0.0, 0.0, 0.0, 1.0], context)?;
+
+			Transform::translation(tx, ty, tz)
+				.mul_mat(&quaternion_matrix(rotation))
+				.mul_mat(&Transform::scale(sx, sy, sz))
+		}
+	};
+
+	let children = items(json, "children").iter()
+		.map(|child| child.as_usize()
+			.filter(|&child| child < node_count)
+			.ok_or_else(|| GltfError::Invalid(format!("{} has a child that does not exist", context))))
+		.collect::<Result<Vec<_>, _>>()?;
+
+	let mesh = index(json, "mesh", context)?;
+	if let Some(mesh) = mesh {
+		check_index(mesh, mesh_count, "mesh", context)?;
+	}
+
+	Ok(SceneNode { name: name(json), local, children, mesh })
+}
+
+/// Rotation matrix of a unit quaternion `[x, y, z, w]`.
+fn quaternion_matrix([x, y, z, w]: [f32; 4]) -> Matrix {
+	Matrix::new(
+		vec![
+			1.0 - 2.0 * (y * y + z * z), 2.0 * (x * y + z * w), 2.0 * (x * z - y * w), 0.0,
+			2.0 * (x * y - z * w), 1.0 - 2.0 * (x * x + z * z), 2.0 * (y * z + x * w), 0.0,
+			2.0 * (x * z + y * w), 2.0 * (y * z - x * w), 1.0 - 2.0 * (x * x + y * y), 0.0,
+			0.0, 0.0, 0.0, 1.0,
+		],
+		4,
+		4,
+	)
+}
+
+/// Checks that nodes form a forest, returning which nodes have a parent.
+fn check_hierarchy(nodes: &[SceneNode]) -> Result<Vec<bool>, GltfError> {
+	let mut parents: Vec<Option<usize>> = vec![None; nodes.len()];
+
+	for (parent, node) in nodes.iter().enumerate() {
+		for &child in &node.children {
+			if parents[child].replace(parent).is_some() {
+				return Err(GltfError::Invalid(format!("node {} has more than one parent", child)));
+			}
+		}
+	}
+
+	// With single parents, a chain longer than the node count must loop
+	for start in 0..nodes.len() {
+		let mut node = start;
+		for _ in 0..=nodes.len() {
+			match parents[node] {
+				Some(parent) if parent == start => {
+					return Err(GltfError::Invalid(format!("node {} is its own ancestor", start)));
+				}
+				Some(parent) => node = parent,
+				None => break,
+			}
+		}
+	}
+
+	Ok(parents.iter().map(Option::is_some).collect())
+}
+
+/// Builds a `Mesh` of every node instance in the scene, in world space.
+///
+/// Each node with a mesh becomes a `MeshPart` named after the node, or its
+/// mesh. Vertex colors are multiplied by the material base color, and
+/// missing normals and texture coordinates are generated.
+pub fn gltf_to_mesh(document: &GltfDocument) -> Mesh {
+	let mut mesh = Mesh {
+		vertices: Vec::new(),
+		indices: Vec::new(),
+		materials: document.materials.iter().map(|m| m.to_material(&document.images)).collect(),
+		face_materials: Vec::new(),
+		parts: Vec::new(),
+		line_indices: Vec::new(),
+		point_indices: Vec::new(),
+		attributes: Vec::new(),
+		polygons: Vec::new(),
+		creases: Vec::new(),
+	};
+
+	for (node_index, world) in document.world_transforms() {
+		let node = &document.nodes[node_index];
+		let Some(source) = node.mesh.map(|m| &document.meshes[m]) else {
+			continue;
+		};
+
+		let first_index = mesh.indices.len();
+		for primitive in &source.primitives {
+			append_primitive(&mut mesh, primitive, &world, &document.materials);
+		}
+
+		let name = [&node.name, &source.name].into_iter()
+			.find(|name| !name.is_empty())
+			.cloned()
+			.unwrap_or_else(|| format!("node {}", node_index));
+
+		if mesh.indices.len() > first_index {
+			mesh.parts.push(MeshPart {
+				name,
+				first_index: first_index as u32,
+				index_count: (mesh.indices.len() - first_index) as u32,
+				visible: true,
+			});
+		}
+	}
+
+	mesh
+}
+
+fn append_primitive(mesh: &mut Mesh, primitive: &GltfPrimitive, world: &Matrix, materials: &[PbrMaterial]) {
+	let base = mesh.vertices.len() as u32;
+	let linear = linear_part(world);
+
+	let positions: Vec<[f32; 3]> = primitive.positions.iter()
+		.map(|&[x, y, z]| {
+			let p = world.mul_vec(&Vector::new(vec![x, y, z, 1.0]));
+			[p.as_slice()[0], p.as_slice()[1], p.as_slice()[2]]
+		})
+		.collect();
+
+	// A mirroring transform turns front faces into back faces
+	let mut topology = primitive.topology();
+	let mirrored = linear.determinant() < 0.0;
+	if mirrored {
+		for triangle in &mut topology.triangles {
+			triangle.swap(1, 2);
+		}
+	}
+
+	let normals = match &primitive.normals {
+		Some(normals) => {
+			let normal_matrix = linear.inverse().map_or(linear.clone(), |inverse| inverse.transpose());
+			normals.iter()
+				.map(|&[x, y, z]| {
+					let n = normal_matrix.mul_vec(&Vector::new(vec![x, y, z]));
+					let n = n.as_slice();
+					let len = (n[0] * n[0] + n[1] * n[1] + n[2] * n[2]).sqrt();
+					if len > 1e-12 { [n[0] / len, n[1] / len, n[2] / len] } else { [0.0; 3] }
+				})
+				.collect()
+		}
+		None => smooth_normals(&positions, &topology.triangles),
+	};
+
+	// Tangents follow the surface like edges do, and mirroring flips the bitangent
+	let tangents: Option<Vec<[f32; 4]>> = primitive.tangents.as_ref().map(|tangents| tangents.iter()
+		.map(|&[x, y, z, w]| {
+			let t = linear.mul_vec(&Vector::new(vec![x, y, z]));
+			let t = t.as_slice();
+			let len = (t[0] * t[0] + t[1] * t[1] + t[2] * t[2]).sqrt();
+			// Flipping v below also turns the bitangent around
+			let w = if (w < 0.0) != mirrored { 1.0 } else { -1.0 };
+			if len > 1e-12 { [t[0] / len, t[1] / len, t[2] / len, w] } else { [0.0; 4] }
+		})
+		.collect());
+
+	let base_color = match primitive.material {
+		Some(material) => {
+			let [r, g, b, _] = materials[material].base_color_factor;
+			[r, g, b]
+		}
+		None => Material::new("").diffuse,
+	};
+
+	let bbox = BoudingBox::from_positions(&positions);
+	mesh.vertices.extend((0..positions.len()).map(|i| Vertex {
+		position: positions[i],
+		tex_coords: match &primitive.tex_coords {
+			// glTF puts the UV origin at the top left, OBJ at the bottom left
+			Some(tex_coords) => [tex_coords[i][0], 1.0 - tex_coords[i][1]],
+			None => generate_planar_uv(positions[i], normals[i], &bbox),
+		},
+		normal: normals[i],
+		color: match &primitive.colors {
+			Some(colors) => std::array::from_fn(|k| colors[i][k] * base_color[k]),
+			None => base_color,
+		},
+		tangent: tangents.as_ref().map_or([0.0; 4], |tangents| tangents[i]),
+	}));
+
+	mesh.indices.extend(topology.triangles.iter().flatten().map(|&i| base + i));
+	mesh.face_materials.extend(std::iter::repeat_n(primitive.material, topology.triangles.len()));
+	mesh.line_indices.extend(topology.lines.iter().map(|&i| base + i));
+	mesh.point_indices.extend(topology.points.iter().map(|&i| base + i));
+}
+
+/// Upper-left 3x3 block of a 4x4 transform.
+fn linear_part(m: &Matrix) -> Matrix {
+	let data = m.as_slice();
+	Matrix::new(
+		vec![
+			data[0], data[1], data[2],
+			data[4], data[5], data[6],
+			data[8], data[9], data[10],
+		],
+		3,
+		3,
+	)
+}
+
+/// Array `key` of `json`, empty when absent.
+fn items<'a>(json: &'a JsonValue, key: &str) -> &'a [JsonValue] {
+	json.get(key).and_then(JsonValue::as_array).unwrap_or(&[])
+}
+
+fn name(json: &JsonValue) -> String {
+	json.get("name").and_then(JsonValue::as_str).unwrap_or_default().to_string()
+}
+
+/// Optional index or size member, which must be a non-negative integer.
+fn index(json: &JsonValue, key: &str, context: &str) -> Result<Option<usize>, GltfError> {
+	json.get(key)
+		.map(|value| value.as_usize()
+			.ok_or_else(|| GltfError::Invalid(format!("{}.{} is not a valid index", context, key))))
+		.transpose()
+}
+
+fn required_index(json: &JsonValue, key: &str, context: &str) -> Result<usize, GltfError> {
+	index(json, key, context)?
+		.ok_or_else(|| GltfError::Invalid(format!("{}.{} is missing", context, key)))
+}
+
+fn component_type(json: &JsonValue, context: &str) -> Result<ComponentType, GltfError> {
+	let code = required_index(json, "componentType", context)?;
+	ComponentType::from_code(code)
+		.ok_or_else(|| GltfError::Invalid(format!("{} has unknown componentType {}", context, code)))
+}
+
+/// Number or number array member `key`; single numbers fill `[f32; 1]`.
+fn floats<const N: usize>(json: &JsonValue, key: &str, default: [f32; N], context: &str) -> Result<[f32; N], GltfError> {
+	let Some(value) = json.get(key) else {
+		return Ok(default);
+	};
+
+	let numbers: Option<Vec<f32>> = match value {
+		JsonValue::Number(n) => Some(vec![*n as f32]),
+		JsonValue::Array(items) => items.iter().map(|v| v.as_f64().map(|n| n as f32)).collect(),
+		_ => None,
+	};
+
+	numbers
+		.and_then(|numbers| <[f32; N]>::try_from(numbers).ok())
+		.ok_or_else(|| GltfError::Invalid(format!("{}.{} must hold {} numbers", context, key, N)))
+}
+
+fn lookup<'a, T>(list: &'a [T], index: usize, what: &str, context: &str) -> Result<&'a T, GltfError> {
+	check_index(index, list.len(), what, context)?;
+	Ok(&list[index])
+}
+
+fn check_index(index: usize, count: usize, what: &str, context: &str) -> Result<(), GltfError> {
+	if index < count {
+		return Ok(());
+	}
+	Err(GltfError::Invalid(format!("{} references {} {} but {} exist", context, what, index, count)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn encode_base64(bytes: &[u8]) -> String {
+        const ALPHABET: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+        let mut out = String::new();
+        for chunk in bytes.chunks(3) {
+            let n = chunk.iter().enumerate().fold(0_u32, |n, (i, &b)| n | (b as u32) << (16 - 8 * i));
+            for k in 0..=chunk.len() {
+                out.push(ALPHABET[(n >> (18 - 6 * k) & 63) as usize] as char);
+            }
+        }
+        while !out.len().is_multiple_of(4) {
+            out.push('=');
+        }
+        out
+    }
+
+    /// One quad: 4 float positions, then 6 u16 indices.
+    fn quad_buffer() -> Vec<u8> {
+        let mut bytes = Vec::new();
+        for p in [[0.0_f32, 0.0, 0.0], [1.0, 0.0, 0.0], [1.0, 1.0, 0.0], [0.0, 1.0, 0.0]] {
+            for v in p {
+                bytes.extend(v.to_le_bytes());
+            }
+        }
+        for i in [0_u16, 1, 2, 0, 2, 3] {
+            bytes.extend(i.to_le_bytes());
+        }
+        bytes
+    }
+
+    /// Document with a parent node moved along x holding a scaled child with
+    /// the quad. `buffer` is the JSON of the single buffer.
+    fn quad_json(buffer: &str) -> String {
+        format!(r#"{{
+            "asset": {{"version": "2.0"}},
+            "scene": 0,
+            "scenes": [{{"nodes": [0]}}],
+            "nodes": [
+                {{"name": "root", "translation": [10, 0, 0], "children": [1]}},
+                {{"name": "child", "scale": [2, 2, 2], "mesh": 0}}
+            ],
+            "meshes": [{{"name": "quad", "primitives": [{{"attributes": {{"POSITION": 0}}, "indices": 1, "material": 0}}]}}],
+            "materials": [{{
+                "name": "red",
+                "pbrMetallicRoughness": {{"baseColorFactor": [1, 0, 0, 0.5], "metallicFactor": 0.25, "baseColorTexture": {{"index": 0}}}},
+                "alphaMode": "BLEND",
+                "doubleSided": true
+            }}],
+            "textures": [{{"source": 0}}],
+            "images": [{{"uri": "tex%20ture.png"}}],
+            "buffers": [{buffer}],
+            "bufferViews": [
+                {{"buffer": 0, "byteOffset": 0, "byteLength": 48}},
+                {{"buffer": 0, "byteOffset": 48, "byteLength": 12}}
+            ],
+            "accessors": [
+                {{"bufferView": 0, "componentType": 5126, "count": 4, "type": "VEC3"}},
+                {{"bufferView": 1, "componentType": 5123, "count": 6, "type": "SCALAR"}}
+            ]
+        }}"#)
+    }
+
+    fn glb(json: &str, bin: &[u8]) -> Vec<u8> {
+        let mut json = json.as_bytes().to_vec();
+        while !json.len().is_multiple_of(4) {
+            json.push(b' ');
+        }
+        let mut bin = bin.to_vec();
+        while !bin.len().is_multiple_of(4) {
+            bin.push(0);
+        }
+
+        let mut bytes = GLB_MAGIC.to_vec();
+        bytes.extend(2_u32.to_le_bytes());
+        bytes.extend(((GLB_HEADER_SIZE + 16 + json.len() + bin.len()) as u32).to_le_bytes());
+        for (ty, data) in [(CHUNK_JSON, &json), (CHUNK_BIN, &bin)] {
+            bytes.extend((data.len() as u32).to_le_bytes());
+            bytes.extend(ty.to_le_bytes());
+            bytes.extend(data);
+        }
+        bytes
+    }
+
+    fn check_quad(document: &GltfDocument) {
+        assert_eq!(document.roots, vec![0]);
+        assert_eq!(document.nodes[0].children, vec![1]);
+
+        let material = &document.materials[0];
+        assert_eq!(material.base_color_factor, [1.0, 0.0, 0.0, 0.5]);
+        assert_eq!(material.metallic_factor, 0.25);
+        assert_eq!(material.roughness_factor, 1.0);
+        assert_eq!(material.alpha_mode, AlphaMode::Blend);
+        assert!(material.double_sided);
+        assert_eq!(material.base_color_texture, Some(0));
+        assert_eq!(document.images[0], GltfImage::File("assets/tex ture.png".to_string()));
+
+        let mesh = gltf_to_mesh(document);
+        assert_eq!(mesh.vertices.len(), 4);
+        assert_eq!(mesh.indices, vec![0, 1, 2, 0, 2, 3]);
+        assert_eq!(mesh.face_materials, vec![Some(0), Some(0)]);
+        assert_eq!(mesh.vertices[2].position, [12.0, 2.0, 0.0]);
+        assert_eq!(mesh.vertices[2].normal, [0.0, 0.0, 1.0]);
+        assert_eq!(mesh.vertices[2].color, [1.0, 0.0, 0.0]);
+        assert_eq!(mesh.parts[0].name, "child");
+        assert_eq!(mesh.diffuse_texture(), Some("assets/tex ture.png"));
+        assert_eq!(mesh.materials[0].dissolve, 0.5);
+    }
+
+    #[test]
+    fn embedded_buffer_and_node_hierarchy() {
+        let buffer = format!(
+            r#"{{"byteLength": 60, "uri": "data:application/octet-stream;base64,{}"}}"#,
+            encode_base64(&quad_buffer()),
+        );
+        let document = parse_gltf_from_bytes(quad_json(&buffer).as_bytes(), Path::new("assets")).unwrap();
+        check_quad(&document);
+    }
+
+    #[test]
+    fn tex_coords_move_to_a_bottom_left_origin() {
+        let mut bytes = Vec::new();
+        for v in [0.0_f32, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 1.0, 0.0, 0.25, 0.75] {
+            bytes.extend(v.to_le_bytes());
+        }
+        let json = format!(r#"{{
+            "asset": {{"version": "2.0"}},
+            "nodes": [{{"mesh": 0}}],
+            "meshes": [{{"primitives": [{{"attributes": {{"POSITION": 0, "TEXCOORD_0": 1}}}}]}}],
+            "buffers": [{{"byteLength": 60, "uri": "data:application/octet-stream;base64,{}"}}],
+            "bufferViews": [
+                {{"buffer": 0, "byteOffset": 0, "byteLength": 36}},
+                {{"buffer": 0, "byteOffset": 36, "byteLength": 24}}
+            ],
+            "accessors": [
+                {{"bufferView": 0, "componentType": 5126, "count": 3, "type": "VEC3"}},
+                {{"bufferView": 1, "componentType": 5126, "count": 3, "type": "VEC2"}}
+            ]
+        }}"#, encode_base64(&bytes));
+
+        let mesh = gltf_to_mesh(&parse_gltf_from_bytes(json.as_bytes(), Path::new("")).unwrap());
+
+        let tex_coords: Vec<[f32; 2]> = mesh.vertices.iter().map(|v| v.tex_coords).collect();
+        assert_eq!(tex_coords, vec![[0.0, 1.0], [1.0, 1.0], [0.25, 0.25]]);
+    }
+
+    #[test]
+    fn glb_reads_the_bin_chunk() {
+        let bytes = glb(&quad_json(r#"{"byteLength": 60}"#), &quad_buffer());
+        let document = parse_gltf_from_bytes(&bytes, Path::new("assets")).unwrap();
+        check_quad(&document);
+
+        let err = parse_gltf_from_bytes(&bytes[..bytes.len() - 8], Path::new("")).unwrap_err();
+        assert!(matches!(err, GltfError::Invalid(_)), "{}", err);
+    }
+
+    #[test]
+    fn external_buffer_is_read_next_to_the_file() {
+        let dir = std::env::temp_dir().join(format!("scop_gltf_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("quad.bin"), quad_buffer()).unwrap();
+        std::fs::write(dir.join("quad.gltf"), quad_json(r#"{"byteLength": 60, "uri": "quad.bin"}"#)).unwrap();
+
+        let mesh = load_gltf(dir.join("quad.gltf").to_str().unwrap()).unwrap();
+        assert_eq!(mesh.indices.len(), 6);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn strips_and_fans_become_triangle_lists() {
+        let primitive = |mode| GltfPrimitive {
+            mode,
+            positions: vec![[0.0; 3]; 5],
+            normals: None,
+            tex_coords: None,
+            colors: None,
+            tangents: None,
+            indices: vec![0, 1, 2, 3, 4],
+            material: None,
+        };
+
+        assert_eq!(primitive(PrimitiveMode::TriangleStrip).topology().triangles, vec![[0, 1, 2], [1, 3, 2], [2, 3, 4]]);
+        assert_eq!(primitive(PrimitiveMode::TriangleFan).topology().triangles, vec![[1, 2, 0], [2, 3, 0], [3, 4, 0]]);
+        assert_eq!(primitive(PrimitiveMode::LineLoop).topology().lines, vec![0, 1, 1, 2, 2, 3, 3, 4, 4, 0]);
+        assert_eq!(primitive(PrimitiveMode::Points).topology().points, vec![0, 1, 2, 3, 4]);
+    }
+
+    #[test]
+    fn sparse_accessor_replaces_elements() {
+        // Base positions are zero, then element 2 is replaced through sparse storage
+        let mut bytes = 2_u16.to_le_bytes().to_vec();
+        bytes.extend([0, 0]);
+        for v in [5.0_f32, 6.0, 7.0] {
+            bytes.extend(v.to_le_bytes());
+        }
+        let json = format!(r#"{{
+            "asset": {{"version": "2.0"}},
+            "meshes": [{{"primitives": [{{"attributes": {{"POSITION": 0}}, "mode": 0}}]}}],
+            "nodes": [{{"mesh": 0}}],
+            "buffers": [{{"byteLength": 16, "uri": "data:application/octet-stream;base64,{}"}}],
+            "bufferViews": [{{"buffer": 0, "byteLength": 2}}, {{"buffer": 0, "byteOffset": 4, "byteLength": 12}}],
+            "accessors": [{{
+                "componentType": 5126, "count": 3, "type": "VEC3",
+                "sparse": {{"count": 1, "indices": {{"bufferView": 0, "componentType": 5123}}, "values": {{"bufferView": 1}}}}
+            }}]
+        }}"#, encode_base64(&bytes));
+
+        let mesh = gltf_to_mesh(&parse_gltf_from_bytes(json.as_bytes(), Path::new("")).unwrap());
+        assert_eq!(mesh.point_indices, vec![0, 1, 2]);
+        assert_eq!(mesh.vertices[2].position, [5.0, 6.0, 7.0]);
+        assert_eq!(mesh.vertices[1].position, [0.0, 0.0, 0.0]);
+    }
+
+    #[test]
+    fn invalid_documents_are_rejected() {
+        let parse = |json: &str| parse_gltf_from_bytes(json.as_bytes(), Path::new("")).unwrap_err();
+
+        let err = parse("{\"asset\": {\"version\": \"2.0\"},\n \"nodes\": [}");
+        assert!(matches!(&err, GltfError::Json(e) if e.line == 2), "{}", err);
+
+        let err = parse(r#"{"asset": {"version": "1.0"}}"#);
+        assert!(matches!(err, GltfError::Unsupported(_)));
+
+        let err = parse(r#"{"asset": {"version": "2.0"}, "extensionsRequired": ["KHR_draco_mesh_compression"]}"#);
+        assert_eq!(err.to_string(), "Unsupported glTF feature: required extensions KHR_draco_mesh_compression");
+
+        let err = parse(r#"{"asset": {"version": "2.0"}, "nodes": [{"children": [1]}, {"children": [0]}]}"#);
+        assert!(matches!(err, GltfError::Invalid(_)), "{}", err);
+
+        let short_buffer = quad_json(&format!(
+            r#"{{"byteLength": 40, "uri": "data:application/octet-stream;base64,{}"}}"#,
+            encode_base64(&quad_buffer()[..40]),
+        ));
+        let err = parse(&short_buffer);
+        assert!(matches!(err, GltfError::Invalid(_)), "{}", err);
+    }
+}
@@ -0,0 +1,320 @@
+use std::fmt;
+
+/// A parsed JSON document. Objects keep their keys in file order.
+#[derive(Debug, Clone, PartialEq)]
+pub enum JsonValue {
+	Null,
+	Bool(bool),
+	Number(f64),
+	String(String),
+	Array(Vec<JsonValue>),
+	Object(Vec<(String, JsonValue)>),
+}
+
+impl JsonValue {
+	/// Member `key` of an object, `None` for other values or missing keys.
+	pub fn get(&self, key: &str) -> Option<&JsonValue> {
+		match self {
+			JsonValue::Object(members) => members.iter().find(|(k, _)| k == key).map(|(_, v)| v),
+			_ => None,
+		}
+	}
+
+	pub fn as_f64(&self) -> Option<f64> {
+		match self {
+			JsonValue::Number(n) => Some(*n),
+			_ => None,
+		}
+	}
+
+	/// Non-negative integral numbers only.
+	pub fn as_usize(&self) -> Option<usize> {
+		self.as_f64()
+			.filter(|n| *n >= 0.0 && n.fract() == 0.0 && *n <= usize::MAX as f64)
+			.map(|n| n as usize)
+	}
+
+	pub fn as_bool(&self) -> Option<bool> {
+		match self {
+			JsonValue::Bool(b) => Some(*b),
+			_ => None,
+		}
+	}
+
+	pub fn as_str(&self) -> Option<&str> {
+		match self {
+			JsonValue::String(s) => Some(s),
+			_ => None,
+		}
+	}
+
+	pub fn as_array(&self) -> Option<&[JsonValue]> {
+		match self {
+			JsonValue::Array(items) => Some(items),
+			_ => None,
+		}
+	}
+}
+
+/// Syntax error with its 1-based position in the document.
+#[derive(Debug, Clone, PartialEq)]
+pub struct JsonError {
+	pub line: usize,
+	pub column: usize,
+	pub message: String,
+}
+
+impl fmt::Display for JsonError {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		write!(f, "{} at line {}, column {}", self.message, self.line, self.column)
+	}
+}
+
+/// Nesting limit, so hostile input cannot overflow the stack.
+const MAX_DEPTH: usize = 128;
+
+pub fn parse_json(text: &str) -> Result<JsonValue, JsonError> {
+	let mut parser = JsonParser { text, pos: 0, depth: 0 };
+	let value = parser.value()?;
+
+	parser.skip_whitespace();
+	if parser.pos < text.len() {
+		return Err(parser.error("Unexpected data after the document"));
+	}
+	Ok(value)
+}
+
+struct JsonParser<'a> {
+	text: &'a str,
+	pos: usize,
+	depth: usize,
+}
+
+impl JsonParser<'_> {
+	fn error(&self, message: &str) -> JsonError {
+		let before = &self.text[..self.pos];
+		let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+
+		JsonError {
+			line: before.matches('\n').count() + 1,
+			column: before[line_start..].chars().count() + 1,
+			message: message.to_string(),
+		}
+	}
+
+	fn peek(&self) -> Option<u8> {
+		self.text.as_bytes().get(self.pos).copied()
+	}
+
+	fn skip_whitespace(&mut self) {
+		while matches!(self.peek(), Some(b' ' | b'\t' | b'\n' | b'\r')) {
+			self.pos += 1;
+		}
+	}
+
+	fn expect(&mut self, byte: u8) -> Result<(), JsonError> {
+		self.skip_whitespace();
+		if self.peek() != Some(byte) {
+			return Err(self.error(&format!("Expected '{}'", byte as char)));
+		}
+		self.pos += 1;
+		Ok(())
+	}
+
+	fn value(&mut self) -> Result<JsonValue, JsonError> {
+		self.skip_whitespace();
+
+		match self.peek() {
+			Some(b'{') => self.nested(Self::object),
+			Some(b'[') => self.nested(Self::array),
+			Some(b'"') => self.string().map(JsonValue::String),
+			Some(b'-' | b'0'..=b'9') => self.number(),
+			Some(_) => {
+				for (word, value) in [("true", JsonValue::Bool(true)), ("false", JsonValue::Bool(false)), ("null", JsonValue::Null)] {
+					if self.text[self.pos..].starts_with(word) {
+						self.pos += word.len();
+						return Ok(value);
+					}
+				}
+				Err(self.error("Expected a value"))
+			}
+			None => Err(self.error("Unexpected end of document")),
+		}
+	}
+
+	fn nested(&mut self, parse: fn(&mut Self) -> Result<JsonValue, JsonError>) -> Result<JsonValue, JsonError> {
+		if self.depth == MAX_DEPTH {
+			return Err(self.error("Document nested too deeply"));
+		}
+		self.depth += 1;
+		let value = parse(self);
+		self.depth -= 1;
+		value
+	}
+
+	fn object(&mut self) -> Result<JsonValue, JsonError> {
+		self.pos += 1;
+		let mut members = Vec::new();
+
+		self.skip_whitespace();
+		if self.peek() == Some(b'}') {
+			self.pos += 1;
+			return Ok(JsonValue::Object(members));
+		}
+
+		loop {
+			self.skip_whitespace();
+			if self.peek() != Some(b'"') {
+				return Err(self.error("Expected a member name"));
+			}
+			let key = self.string()?;
+			self.expect(b':')?;
+			members.push((key, self.value()?));
+
+			self.skip_whitespace();
+			match self.peek() {
+				Some(b',') => self.pos += 1,
+				Some(b'}') => {
+					self.pos += 1;
+					return Ok(JsonValue::Object(members));
+				}
+				_ => return Err(self.error("Expected ',' or '}'")),
+			}
+		}
+	}
+
+	fn array(&mut self) -> Result<JsonValue, JsonError> {
+		self.pos += 1;
+		let mut items = Vec::new();
+
+		self.skip_whitespace();
+		if self.peek() == Some(b']') {
+			self.pos += 1;
+			return Ok(JsonValue::Array(items));
+		}
+
+		loop {
+			items.push(self.value()?);
+
+			self.skip_whitespace();
+			match self.peek() {
+				Some(b',') => self.pos += 1,
+				Some(b']') => {
+					self.pos += 1;
+					return Ok(JsonValue::Array(items));
+				}
+				_ => return Err(self.error("Expected ',' or ']'")),
+			}
+		}
+	}
+
+	fn number(&mut self) -> Result<JsonValue, JsonError> {
+		let start = self.pos;
+		while matches!(self.peek(), Some(b'-' | b'+' | b'.' | b'e' | b'E' | b'0'..=b'9')) {
+			self.pos += 1;
+		}
+
+		self.text[start..self.pos].parse::<f64>()
+			.map(JsonValue::Number)
+			.map_err(|_| {
+				self.pos = start;
+				self.error("Invalid number")
+			})
+	}
+
+	fn string(&mut self) -> Result<String, JsonError> {
+		self.pos += 1;
+		let mut out = String::new();
+
+		loop {
+			let rest = &self.text[self.pos..];
+			let Some(end) = rest.find(['"', '\\']) else {
+				return Err(self.error("Unterminated string"));
+			};
+			if rest[..end].contains(|c: char| c < ' ') {
+				return Err(self.error("Control character in string"));
+			}
+			out.push_str(&rest[..end]);
+			self.pos += end + 1;
+
+			if rest.as_bytes()[end] == b'"' {
+				return Ok(out);
+			}
+
+			let escape = self.peek().ok_or_else(|| self.error("Unterminated string"))?;
+			self.pos += 1;
+			match escape {
+				b'"' => out.push('"'),
+				b'\\' => out.push('\\'),
+				b'/' => out.push('/'),
+				b'b' => out.push('\u{8}'),
+				b'f' => out.push('\u{c}'),
+				b'n' => out.push('\n'),
+				b'r' => out.push('\r'),
+				b't' => out.push('\t'),
+				b'u' => out.push(self.unicode_escape()?),
+				_ => {
+					self.pos -= 2;
+					return Err(self.error("Invalid escape sequence"));
+				}
+			}
+		}
+	}
+
+	/// Decodes the digits after `\u`, joining surrogate pairs.
+	fn unicode_escape(&mut self) -> Result<char, JsonError> {
+		let high = self.hex4()?;
+		if !(0xD800..0xDC00).contains(&high) {
+			return char::from_u32(high).ok_or_else(|| self.error("Invalid unicode escape"));
+		}
+
+		if !self.text[self.pos..].starts_with("\\u") {
+			return Err(self.error("Unpaired surrogate in unicode escape"));
+		}
+		self.pos += 2;
+		let low = self.hex4()?;
+		if !(0xDC00..0xE000).contains(&low) {
+			return Err(self.error("Unpaired surrogate in unicode escape"));
+		}
+
+		char::from_u32(0x10000 + ((high - 0xD800) << 10) + (low - 0xDC00))
+			.ok_or_else(|| self.error("Invalid unicode escape"))
+	}
+
+	fn hex4(&mut self) -> Result<u32, JsonError> {
+		let digits = self.text.get(self.pos..self.pos + 4)
+			.filter(|d| d.bytes().all(|b| b.is_ascii_hexdigit()))
+			.ok_or_else(|| self.error("Invalid unicode escape"))?;
+		self.pos += 4;
+		Ok(u32::from_str_radix(digits, 16).unwrap())
+	}
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_nested_documents() {
+        let value = parse_json(r#"{"a": [1, -2.5e1, true, null], "b": {"c": "dé\n"}}"#).unwrap();
+
+        let a = value.get("a").unwrap().as_array().unwrap();
+        assert_eq!(a[0].as_usize(), Some(1));
+        assert_eq!(a[1].as_f64(), Some(-25.0));
+        assert_eq!(a[1].as_usize(), None);
+        assert_eq!(a[2].as_bool(), Some(true));
+        assert_eq!(a[3], JsonValue::Null);
+        assert_eq!(value.get("b").and_then(|b| b.get("c")).and_then(JsonValue::as_str), Some("dé\n"));
+        assert_eq!(parse_json(r#""\ud83d\ude00""#).unwrap().as_str(), Some("😀"));
+    }
+
+    #[test]
+    fn errors_are_located() {
+        let err = parse_json("{\n  \"a\": [1, 2,]\n}").unwrap_err();
+        assert_eq!((err.line, err.column), (2, 14));
+
+        assert!(parse_json("[1] 2").is_err());
+        assert!(parse_json("\"abc").is_err());
+        assert!(parse_json(&"[".repeat(MAX_DEPTH + 1)).is_err());
+    }
+}
@@ -1 +1,11 @@
+pub mod error;
+pub mod format;
+pub mod freeform;
+pub mod gltf;
+pub(crate) mod json;
+pub mod mtl;
 pub mod obj;
+pub mod ply;
+pub mod stl;
+pub mod texture;
+pub mod triangulate;
\ No newline at end of file
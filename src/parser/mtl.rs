@@ -0,0 +1,251 @@
+use std::path::Path;
+
+use crate::parser::error::{Diagnostic, ObjError, SourceLocation, TokenError};
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Material {
+	pub name: String,
+
+	pub ambient: [f32; 3],
+	pub diffuse: [f32; 3],
+	pub specular: [f32; 3],
+	pub shininess: f32,
+	pub dissolve: f32,
+	pub illum: u32,
+
+	pub ambient_map: Option<String>,
+	pub diffuse_map: Option<String>,
+	pub specular_map: Option<String>,
+	pub shininess_map: Option<String>,
+	pub dissolve_map: Option<String>,
+	pub bump_map: Option<String>,
+}
+
+impl Material {
+	pub fn new(name: &str) -> Self {
+		Self {
+			name: name.to_string(),
+			ambient: [0.0, 0.0, 0.0],
+			diffuse: [0.8, 0.8, 0.8],
+			specular: [0.0, 0.0, 0.0],
+			shininess: 0.0,
+			dissolve: 1.0,
+			illum: 1,
+			ambient_map: None,
+			diffuse_map: None,
+			specular_map: None,
+			shininess_map: None,
+			dissolve_map: None,
+			bump_map: None,
+		}
+	}
+}
+
+/// Parses a material library, appending its warnings to `diagnostics`.
+pub fn parse_mtl(file_path: &str, diagnostics: &mut Vec<Diagnostic>) -> Result<Vec<Material>, ObjError> {
+	let content = std::fs::read_to_string(file_path)
+		.map_err(|source| ObjError::Io { path: file_path.to_string(), source })?;
+
+	let first_new = diagnostics.len();
+	let mut materials = parse_mtl_from_string(&content, diagnostics)?;
+	for diagnostic in &mut diagnostics[first_new..] {
+		diagnostic.file = Some(file_path.to_string());
+	}
+
+	// Texture maps are written relative to the library itself
+	let base_dir = Path::new(file_path).parent().unwrap_or(Path::new(""));
+	for material in &mut materials {
+		for map in [
+			&mut material.ambient_map,
+			&mut material.diffuse_map,
+			&mut material.specular_map,
+			&mut material.shininess_map,
+			&mut material.dissolve_map,
+			&mut material.bump_map,
+		].into_iter().flatten() {
+			*map = resolve_relative(base_dir, map);
+		}
+	}
+
+	Ok(materials)
+}
+
+pub fn parse_mtl_from_string(content: &str, diagnostics: &mut Vec<Diagnostic>) -> Result<Vec<Material>, ObjError> {
+	let mut materials: Vec<Material> = Vec::new();
+
+	for (line_num, raw_line) in content.lines().enumerate() {
+		let line = raw_line.trim();
+
+		if line.is_empty() || line.starts_with('#') {
+			continue;
+		}
+
+		let tokens: Vec<&str> = line.split_whitespace().collect();
+
+		if tokens[0] == "newmtl" {
+			if tokens.len() < 2 {
+				let err = TokenError::syntax(tokens[0], "Material name missing after 'newmtl'".to_string());
+				return Err(err.locate(line_num + 1, raw_line));
+			}
+			materials.push(Material::new(&tokens[1..].join(" ")));
+			continue;
+		}
+
+		let Some(material) = materials.last_mut() else {
+			let err = TokenError::syntax(tokens[0], format!("'{}' before any 'newmtl'", tokens[0]));
+			return Err(err.locate(line_num + 1, raw_line));
+		};
+
+		let result = match tokens[0] {
+			"Ka" => parse_color(&tokens, &mut material.ambient),
+			"Kd" => parse_color(&tokens, &mut material.diffuse),
+			"Ks" => parse_color(&tokens, &mut material.specular),
+			"Ns" => parse_scalar(&tokens, &mut material.shininess),
+			"d" => parse_scalar(&tokens, &mut material.dissolve),
+			"Tr" => {
+				let mut transparency = 0.0;
+				parse_scalar(&tokens, &mut transparency)
+					.map(|_| material.dissolve = 1.0 - transparency)
+			}
+			"illum" => tokens.get(1)
+				.ok_or(TokenError::syntax(tokens[0], "Invalid illum format: expected 'illum n'".to_string()))
+				.and_then(|t| t.parse::<u32>().map_err(|_| TokenError::syntax(t, format!("Invalid illum model: '{}'", t))))
+				.map(|n| material.illum = n),
+			"map_Ka" => parse_map(&tokens, &mut material.ambient_map),
+			"map_Kd" => parse_map(&tokens, &mut material.diffuse_map),
+			"map_Ks" => parse_map(&tokens, &mut material.specular_map),
+			"map_Ns" => parse_map(&tokens, &mut material.shininess_map),
+			"map_d" => parse_map(&tokens, &mut material.dissolve_map),
+			"map_Bump" | "map_bump" | "bump" => parse_map(&tokens, &mut material.bump_map),
+			"Ke" | "Ni" | "Tf" | "map_Ke" | "disp" | "decal" | "refl" => Ok(()),
+			_ => {
+				diagnostics.push(Diagnostic {
+					file: None,
+					location: Some(SourceLocation::of_token(line_num + 1, raw_line, tokens[0])),
+					message: format!("Unknown material statement '{}'", tokens[0]),
+				});
+				Ok(())
+			}
+		};
+
+		if let Err(e) = result {
+			return Err(e.locate(line_num + 1, raw_line));
+		}
+	}
+
+	Ok(materials)
+}
+
+pub(crate) fn resolve_relative(base_dir: &Path, path: &str) -> String {
+	let path = path.replace('\\', "/");
+
+	if Path::new(&path).is_absolute() {
+		path
+	} else {
+		base_dir.join(path).to_string_lossy().into_owned()
+	}
+}
+
+fn parse_component<'a>(token: &'a str, what: &str) -> Result<f32, TokenError<'a>> {
+	token.parse::<f32>()
+		.map_err(|_| TokenError::syntax(token, format!("Invalid {}: '{}'", what, token)))
+}
+
+fn parse_color<'a>(tokens: &[&'a str], color: &mut [f32; 3]) -> Result<(), TokenError<'a>> {
+	if tokens.len() < 2 {
+		return Err(TokenError::syntax(tokens[0], format!("Invalid color format: expected '{} r g b'", tokens[0])));
+	}
+
+	let r = parse_component(tokens[1], "red component")?;
+
+	// A single component means a grey level
+	match tokens.len() {
+		2 => {
+			*color = [r, r, r];
+			return Ok(());
+		}
+		3 => return Err(TokenError::syntax(tokens[2], format!("Invalid color format: expected '{} r g b' or '{} value'", tokens[0], tokens[0]))),
+		_ => {}
+	}
+
+	let g = parse_component(tokens[2], "green component")?;
+	let b = parse_component(tokens[3], "blue component")?;
+
+	*color = [r, g, b];
+	Ok(())
+}
+
+fn parse_scalar<'a>(tokens: &[&'a str], value: &mut f32) -> Result<(), TokenError<'a>> {
+	if tokens.len() < 2 {
+		return Err(TokenError::syntax(tokens[0], format!("Invalid format: expected '{} value'", tokens[0])));
+	}
+
+	*value = parse_component(tokens[1], &format!("value for '{}'", tokens[0]))?;
+	Ok(())
+}
+
+fn parse_map<'a>(tokens: &[&'a str], map: &mut Option<String>) -> Result<(), TokenError<'a>> {
+	// Options such as '-s 1 1 1' or '-bm 0.5' come first, the file name is last
+	match tokens.last() {
+		Some(file) if tokens.len() >= 2 && !file.starts_with('-') => {
+			*map = Some(file.to_string());
+			Ok(())
+		}
+		_ => Err(TokenError::syntax(tokens[0], format!("Texture file missing after '{}'", tokens[0]))),
+	}
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_basic_material() {
+        let content = "
+newmtl Red
+Ka 0.1 0.0 0.0
+Kd 0.9 0.1 0.1
+Ks 0.5
+Ns 96.0
+d 0.75
+illum 2
+map_Kd -s 1 1 1 textures/red.png
+";
+        let mut diagnostics = Vec::new();
+        let materials = parse_mtl_from_string(content, &mut diagnostics).unwrap();
+        assert!(diagnostics.is_empty());
+
+        assert_eq!(materials.len(), 1);
+        let red = &materials[0];
+        assert_eq!(red.name, "Red");
+        assert_eq!(red.ambient, [0.1, 0.0, 0.0]);
+        assert_eq!(red.diffuse, [0.9, 0.1, 0.1]);
+        assert_eq!(red.specular, [0.5, 0.5, 0.5]);
+        assert_eq!(red.shininess, 96.0);
+        assert_eq!(red.dissolve, 0.75);
+        assert_eq!(red.illum, 2);
+        assert_eq!(red.diffuse_map.as_deref(), Some("textures/red.png"));
+    }
+
+    #[test]
+    fn statement_before_newmtl_fails() {
+        let err = parse_mtl_from_string("Kd 1.0 1.0 1.0", &mut Vec::new()).unwrap_err();
+        assert_eq!(err.location().unwrap().line, 1);
+    }
+
+    #[test]
+    fn two_color_components_fail() {
+        let err = parse_mtl_from_string("newmtl A\nKd 0.2 0.5\n", &mut Vec::new()).unwrap_err();
+        let location = err.location().unwrap();
+        assert_eq!(location.line, 2);
+        assert_eq!(location.column, 8);
+    }
+
+    #[test]
+    fn unknown_statement_is_a_diagnostic() {
+        let mut diagnostics = Vec::new();
+        parse_mtl_from_string("newmtl A\nsheen 0.5\n", &mut diagnostics).unwrap();
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].location.as_ref().unwrap().line, 2);
+    }
+}
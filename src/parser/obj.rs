@@ -1,14 +1,22 @@
 use std::collections::HashMap;
-
-use crate::mesh::{Mesh, Vertex};
-
-struct BoudingBox {
+use std::fs::File;
+use std::io::{BufRead, BufReader, Read};
+use std::ops::Range;
+use std::path::Path;
+
+use crate::mesh::{Crease, Mesh, MeshPart, Vertex};
+use crate::parser::error::{Diagnostic, ObjError, SourceLocation, TokenError};
+use crate::parser::freeform::{Basis, FreeForm};
+use crate::parser::mtl::{self, Material};
+use crate::parser::triangulate::triangulate_polygon;
+
+pub(crate) struct BoudingBox {
 	min: [f32; 3],
 	max: [f32; 3],
 }
 
 impl BoudingBox {
-	fn from_positions(positions: &[[f32; 3]]) -> Self {
+	pub(crate) fn from_positions(positions: &[[f32; 3]]) -> Self {
 		let mut min = [f32::MAX; 3];
 		let mut max = [f32::NEG_INFINITY; 3];
 
@@ -35,15 +43,113 @@ impl BoudingBox {
 #[derive(Debug, Clone)]
 pub struct ObjData {
 	positions: Vec<[f32; 3]>,
+	/// Per-position colors from `v x y z r g b`, empty when the file has none.
+	colors: Vec<Option<[f32; 3]>>,
+	/// Per-position `w` from `v x y z w`, empty when the file has none.
+	weights: Vec<f32>,
 	tex_coords: Vec<[f32; 2]>,
 	normals: Vec<[f32; 3]>,
 
 	faces: Vec<Face>,
+	fallback_faces: usize,
+
+	polylines: Vec<VertexList>,
+	points: Vec<VertexList>,
+	free_forms: Vec<ObjFreeForm>,
+
+	material_libs: Vec<String>,
+	materials: Vec<Material>,
+
+	objects: Vec<ObjObject>,
+	has_smoothing_groups: bool,
+
+	diagnostics: Vec<Diagnostic>,
+}
+
+/// Options controlling how OBJ text is parsed.
+#[derive(Debug, Clone, Default)]
+pub struct ParseOptions {
+	/// Drop faces referencing undeclared elements and report them as
+	/// diagnostics, instead of failing the whole load.
+	pub lenient: bool,
+}
+
+/// Load progress reported while an OBJ is being read.
+#[derive(Debug, Clone, Copy)]
+pub struct ParseProgress {
+	pub bytes_read: u64,
+	/// Size of the input, when known up front.
+	pub total_bytes: Option<u64>,
+	pub lines_read: usize,
+}
+
+impl ParseProgress {
+	/// Fraction of the input read so far, in `[0, 1]`.
+	pub fn fraction(&self) -> Option<f32> {
+		self.total_bytes
+			.filter(|&total| total > 0)
+			.map(|total| (self.bytes_read as f64 / total as f64).min(1.0) as f32)
+	}
+}
+
+/// Number of lines between two progress reports.
+const PROGRESS_INTERVAL: usize = 1 << 16;
+
+/// Tokens per line kept on the stack; longer lines are split into a `Vec`.
+const INLINE_TOKENS: usize = 32;
+
+/// Smallest input slice worth handing to its own thread.
+const MIN_CHUNK_BYTES: usize = 1 << 20;
+
+/// Files at least this large are parsed on several threads.
+const PARALLEL_MIN_BYTES: u64 = 2 * MIN_CHUNK_BYTES as u64;
+
+/// Input held at once when parsing on several threads, split between them.
+const PARALLEL_BLOCK_BYTES: usize = 16 * MIN_CHUNK_BYTES;
+
+/// Options controlling how `ObjData` is turned into a `Mesh`.
+#[derive(Debug, Clone)]
+pub struct MeshOptions {
+	/// Maximum angle in radians between two faces for their shared vertices
+	/// to be smoothed. Only used when the file declares no smoothing groups.
+	pub crease_angle: f32,
+	/// Segments per direction when tessellating free-form curves and surfaces.
+	pub free_form_segments: usize,
+}
+
+impl Default for MeshOptions {
+	fn default() -> Self {
+		Self {
+			crease_angle: 30.0_f32.to_radians(),
+			free_form_segments: 16,
+		}
+	}
+}
+
+/// A named `o` block and the `g` groups declared inside it.
+///
+/// Face ranges index the triangulated faces of the owning `ObjData`.
+#[derive(Debug, Clone)]
+pub struct ObjObject {
+	pub name: String,
+	pub faces: Range<usize>,
+	pub groups: Vec<ObjGroup>,
+}
+
+#[derive(Debug, Clone)]
+pub struct ObjGroup {
+	pub name: String,
+	pub faces: Range<usize>,
 }
 
 #[derive(Debug, Clone)]
 pub struct Face {
 	vertices: Vec<FaceVertex>,
+	material_idx: Option<usize>,
+	/// Smoothing group set by `s`, 0 meaning smoothing is off.
+	smoothing_group: u32,
+	/// 1-based source line, shared by all triangles of a polygon.
+	line: usize,
 }
 
 #[derive(Debug, Clone)]
@@ -53,11 +159,41 @@ pub struct FaceVertex {
 	normal_idx: Option<usize>,
 }
 
+/// Position indices of an `l` polyline or of a `p` point list.
+#[derive(Debug, Clone)]
+pub struct VertexList {
+	vertices: Vec<usize>,
+	/// 1-based source line.
+	line: usize,
+}
+
+/// A `curv` or `surf` element and the state it was declared under.
+#[derive(Debug, Clone)]
+pub struct ObjFreeForm {
+	pub element: FreeForm,
+	material_idx: Option<usize>,
+	/// Name of the part it was declared in, as given by [`ObjData::parts`].
+	part: String,
+	/// The `curv` or `surf` line.
+	location: SourceLocation,
+}
+
+/// Free-form type set by `cstype`.
+#[derive(Debug, Clone, Default)]
+enum CurveType {
+	#[default]
+	Unset,
+	Supported { basis: Basis, rational: bool },
+	/// Reported on the `cstype` line, elements of this type are skipped.
+	Unsupported(Diagnostic),
+}
+
 #[derive(Hash, Eq, PartialEq)]
-struct VertexKey {
+pub(crate) struct VertexKey {
 	position: [i32; 3],
 	tex_coords: [i32; 2],
 	normal: [i32; 3],
+	material: Option<usize>,
 }
 
 impl VertexKey {
@@ -65,7 +201,7 @@ impl VertexKey {
 		(f * 10000.0).round() as i32
 	}
 
-	fn from_vertex(v: &Vertex) -> Self {
+	pub(crate) fn from_vertex(v: &Vertex, material: Option<usize>) -> Self {
 		Self {
 			position: [
 				Self::quantize(v.position[0]),
@@ -80,295 +216,1712 @@ impl VertexKey {
 				Self::quantize(v.normal[0]),
 				Self::quantize(v.normal[1]),
 				Self::quantize(v.normal[2]),
-			]
+			],
+			material,
+		}
+	}
+}
+
+impl ObjData {
+	/// Number of polygons that had to be fan-triangulated.
+	pub fn fallback_faces(&self) -> usize {
+		self.fallback_faces
+	}
+
+	/// Warnings collected while parsing, in file order.
+	pub fn diagnostics(&self) -> &[Diagnostic] {
+		&self.diagnostics
+	}
+
+	pub fn objects(&self) -> &[ObjObject] {
+		&self.objects
+	}
+
+	/// Flattens the object/group hierarchy into named, non-overlapping face ranges.
+	///
+	/// Groups are named `object/group`; faces of an object outside any group
+	/// are named after the object alone.
+	pub fn parts(&self) -> Vec<(String, Range<usize>)> {
+		let mut parts = Vec::new();
+
+		for object in &self.objects {
+			let mut cursor = object.faces.start;
+
+			for group in &object.groups {
+				if group.faces.start > cursor {
+					parts.push((object.name.clone(), cursor..group.faces.start));
+				}
+				parts.push((format!("{}/{}", object.name, group.name), group.faces.clone()));
+				cursor = group.faces.end;
+			}
+
+			if cursor < object.faces.end {
+				parts.push((object.name.clone(), cursor..object.faces.end));
+			}
 		}
+
+		parts
 	}
 }
 
-pub fn load_obj(file_path: &str) -> Result<Mesh, String> {
+pub fn load_obj(file_path: &str) -> Result<Mesh, ObjError> {
 	let obj_data = parse_obj(file_path)?;
 	Ok(obj_to_mesh(obj_data))
 }
 
-pub fn parse_obj(file_path: &str) -> Result<ObjData, String> {
-	let content = std::fs::read_to_string(file_path)
-		.map_err(|e| format!("Failed to read file: {}", e))?;
-
-	parse_obj_from_string(&content)
+pub fn parse_obj(file_path: &str) -> Result<ObjData, ObjError> {
+	parse_obj_with_options(file_path, &ParseOptions::default())
 }
 
-pub fn parse_obj_from_string(content: &str) -> Result<ObjData, String> {
-	let mut positions = Vec::new();
-	let mut tex_coords = Vec::new();
-	let mut normals = Vec::new();
-	let mut faces = Vec::new();
+pub fn parse_obj_with_options(file_path: &str, options: &ParseOptions) -> Result<ObjData, ObjError> {
+	parse_obj_with_progress(file_path, options, |_| {})
+}
 
-	for (line_num, line) in content.lines().enumerate() {
-		let line = line.trim();
+/// Reads an OBJ file from disk, calling `progress` as it is read.
+///
+/// Files of at least `PARALLEL_MIN_BYTES` are read in blocks of about
+/// `PARALLEL_BLOCK_BYTES`, each parsed on several threads, with progress
+/// reported after each block. Smaller ones are streamed line by line.
+pub fn parse_obj_with_progress(
+	file_path: &str,
+	options: &ParseOptions,
+	mut progress: impl FnMut(&ParseProgress),
+) -> Result<ObjData, ObjError> {
+	let io_error = |source| ObjError::Io { path: file_path.to_string(), source };
+
+	let file = File::open(file_path).map_err(io_error)?;
+	let total_bytes = file.metadata().ok().map(|m| m.len());
+
+	let reader = BufReader::new(file);
+	let obj = if total_bytes.is_some_and(|bytes| bytes >= PARALLEL_MIN_BYTES) {
+		read_obj_parallel(reader, total_bytes, options, PARALLEL_BLOCK_BYTES, &mut progress)
+	} else {
+		read_obj(reader, total_bytes, options, &mut progress)
+	};
+	let mut obj = obj.map_err(|e| match e {
+		ObjError::Io { source, .. } => io_error(source),
+		e => e,
+	})?;
+	for diagnostic in &mut obj.diagnostics {
+		diagnostic.file = Some(file_path.to_string());
+	}
 
-		if line.is_empty() || line.starts_with('#') {
-			continue;
-		}
+	let base_dir = Path::new(file_path).parent().unwrap_or(Path::new(""));
+	load_material_libs(&mut obj, base_dir);
 
-		let tokens: Vec<&str> = line.split_whitespace().collect();
-		if tokens.is_empty() {
-			continue;
-		}
+	Ok(obj)
+}
 
+/// Parses OBJ text from any buffered reader, one line at a time.
+///
+/// Only the current line is held in memory. `mtllib` statements are recorded
+/// but not loaded, see [`load_material_libs`].
+pub fn parse_obj_from_reader<R: BufRead>(
+	reader: R,
+	options: &ParseOptions,
+	mut progress: impl FnMut(&ParseProgress),
+) -> Result<ObjData, ObjError> {
+	read_obj(reader, None, options, &mut progress)
+}
 
-		let result = match tokens[0] {
-			"v" => parse_vertex(&tokens, &mut positions),
-			"vt" => parse_tex_coord(&tokens, &mut tex_coords),
-			"vn" => parse_normal(&tokens, &mut normals),
-			"f" => parse_face(&tokens, &mut faces),
-			"mtllib" | "usemtl" | "o" | "g" | "s" => Ok(()),
-			_ => {
-				eprintln!("Warning: Unknown directive '{}' at line {}", tokens[0], line_num + 1);
-				Ok(())
+/// Loads every `mtllib` referenced by `obj`, resolving paths against `base_dir`.
+///
+/// A missing or broken library only produces a diagnostic: faces keep their
+/// material ids and fall back to the default material values.
+pub fn load_material_libs(obj: &mut ObjData, base_dir: &Path) {
+	for lib in obj.material_libs.clone() {
+		let lib_path = mtl::resolve_relative(base_dir, &lib);
+
+		let materials = match mtl::parse_mtl(&lib_path, &mut obj.diagnostics) {
+			Ok(materials) => materials,
+			Err(e) => {
+				obj.diagnostics.push(Diagnostic {
+					file: Some(lib_path),
+					location: e.location().cloned(),
+					message: format!("Material library skipped: {}", e.message()),
+				});
+				continue;
 			}
 		};
 
-		if let Err(e) = result {
-			return Err(format!("Error at line {}: {}", line_num + 1, e));
+		for material in materials {
+			match obj.materials.iter_mut().find(|m| m.name == material.name) {
+				Some(existing) => *existing = material,
+				None => obj.materials.push(material),
+			}
 		}
 	}
+}
+
+/// Directives of the OBJ specification that are recognised but not handled.
+const IGNORED_DIRECTIVES: &[&str] = &[
+	"bmat", "step", "curv2",
+	"trim", "hole", "scrv", "sp", "con", "mg", "bevel", "c_interp",
+	"d_interp", "lod", "maplib", "usemap", "shadow_obj", "trace_obj", "ctech",
+	"stech", "csh",
+];
 
-	Ok(ObjData { positions, tex_coords, normals, faces })
+pub fn parse_obj_from_string(content: &str) -> Result<ObjData, ObjError> {
+	parse_obj_from_string_with_options(content, &ParseOptions::default())
 }
 
-fn parse_vertex(tokens: &[&str], positions: &mut Vec<[f32; 3]>) -> Result<(), String> {
-	if tokens.len() < 4 {
-		return Err("Invalid vertex format: expected 'v x y z'".to_string());
-	}
+pub fn parse_obj_from_string_with_options(content: &str, options: &ParseOptions) -> Result<ObjData, ObjError> {
+	read_obj(content.as_bytes(), Some(content.len() as u64), options, &mut |_| {})
+}
 
-	let x = tokens[1].parse::<f32>()
-		.map_err(|_| format!("Invalid x coordinate: '{}'", tokens[1]))?;
-	let y = tokens[2].parse::<f32>()
-		.map_err(|_| format!("Invalid y coordinate: '{}'", tokens[2]))?;
-	let z = tokens[3].parse::<f32>()
-		.map_err(|_| format!("Invalid z coordinate: '{}'", tokens[3]))?;
+fn read_obj<R: BufRead>(
+	mut reader: R,
+	total_bytes: Option<u64>,
+	options: &ParseOptions,
+	progress: &mut dyn FnMut(&ParseProgress),
+) -> Result<ObjData, ObjError> {
+	let mut parser = ObjParser::default();
+	let mut state = ParseProgress { bytes_read: 0, total_bytes, lines_read: 0 };
+	let mut line = String::new();
+
+	loop {
+		line.clear();
+		let read = reader.read_line(&mut line)
+			.map_err(|source| ObjError::Io { path: "<reader>".to_string(), source })?;
+		if read == 0 {
+			break;
+		}
 
-	positions.push([x, y, z]);
-	Ok(())
+		state.bytes_read += read as u64;
+		state.lines_read += 1;
+		parser.parse_line(state.lines_read, line.trim_end_matches(['\n', '\r']))?;
+
+		if state.lines_read.is_multiple_of(PROGRESS_INTERVAL) {
+			progress(&state);
+		}
+	}
+
+	progress(&state);
+	parser.finish(options)
 }
 
-fn parse_tex_coord(tokens: &[&str], tex_coords: &mut Vec<[f32; 2]>) -> Result<(), String> {
-	if tokens.len() < 3 {
-		return Err("Invalid texture coordinate format: expected 'vt u v'".to_string());
+/// Reads OBJ text in line-aligned blocks of about `block_bytes`, parsing each
+/// on all available cores and reporting progress after it.
+fn read_obj_parallel<R: BufRead>(
+	mut reader: R,
+	total_bytes: Option<u64>,
+	options: &ParseOptions,
+	block_bytes: usize,
+	progress: &mut dyn FnMut(&ParseProgress),
+) -> Result<ObjData, ObjError> {
+	let io_error = |source| ObjError::Io { path: "<reader>".to_string(), source };
+	let threads = std::thread::available_parallelism().map_or(1, |n| n.get());
+	let mut parser = ObjParser::default();
+	let mut state = ParseProgress { bytes_read: 0, total_bytes, lines_read: 0 };
+	let mut block = Vec::with_capacity(block_bytes);
+
+	loop {
+		block.clear();
+		reader.by_ref().take(block_bytes as u64).read_to_end(&mut block).map_err(io_error)?;
+		// Blocks end on a line end, so no line or character is split
+		reader.read_until(b'\n', &mut block).map_err(io_error)?;
+		if block.is_empty() {
+			break;
+		}
+
+		let text = std::str::from_utf8(&block)
+			.map_err(|e| io_error(std::io::Error::new(std::io::ErrorKind::InvalidData, e)))?;
+		let chunk_count = threads.min(text.len() / MIN_CHUNK_BYTES + 1);
+		state.lines_read += parse_chunks_into(&mut parser, &split_chunks(text, chunk_count), state.lines_read + 1)?;
+		state.bytes_read += block.len() as u64;
+		progress(&state);
 	}
 
-	let u = tokens[1].parse::<f32>()
-		.map_err(|_| format!("Invalid u coordinate: '{}'", tokens[1]))?;
-	let v = tokens[2].parse::<f32>()
-		.map_err(|_| format!("Invalid v coordinate: '{}'", tokens[2]))?;
+	parser.finish(options)
+}
+
+/// Parses OBJ text on `threads` threads, giving exactly the same result as
+/// [`parse_obj_from_string_with_options`].
+///
+/// The input is split into line-aligned chunks. Vertex lines are parsed first,
+/// so each chunk then knows how many elements precede it and can resolve and
+/// triangulate its faces independently. `usemtl`, `s`, `o` and `g` are
+/// replayed in file order afterwards. `threads == 0` uses all available cores.
+pub fn parse_obj_from_string_parallel(content: &str, options: &ParseOptions, threads: usize) -> Result<ObjData, ObjError> {
+	let threads = match threads {
+		0 => std::thread::available_parallelism().map_or(1, |n| n.get()),
+		n => n,
+	};
+	let chunk_count = threads.min(content.len() / MIN_CHUNK_BYTES + 1);
 
-	tex_coords.push([u, v]);
-	Ok(())
+	parse_chunks(&split_chunks(content, chunk_count), options)
 }
 
-fn parse_normal(tokens: &[&str], normals: &mut Vec<[f32; 3]>) -> Result<(), String> {
-	if tokens.len() < 4 {
-		return Err("Invalid normal format: expected 'vn x y z".to_string());
-	}
+/// Splits `content` into at most `count` chunks, each ending after a newline
+/// except the last one.
+fn split_chunks(content: &str, count: usize) -> Vec<&str> {
+	let bytes = content.as_bytes();
+	let mut chunks = Vec::with_capacity(count);
+	let mut start = 0;
 
-	let x = tokens[1].parse::<f32>()
-		.map_err(|_| format!("Invalid normal x: '{}'", tokens[1]))?;
-	let y = tokens[2].parse::<f32>()
-		.map_err(|_| format!("Invalid normal y: '{}'", tokens[2]))?;
-	let z = tokens[3].parse::<f32>()
-		.map_err(|_| format!("Invalid normal z: '{}'", tokens[3]))?;
+	for i in 1..=count {
+		if start >= bytes.len() {
+			break;
+		}
 
-	let length = (x*x + y*y + z*z).sqrt();
+		let target = (bytes.len() * i / count).max(start);
+		let end = bytes[target..].iter()
+			.position(|&b| b == b'\n')
+			.map_or(bytes.len(), |offset| target + offset + 1);
 
-	if length > 0.0001 {
-		normals.push([x / length, y / length, z / length]);
-	} else {
-		normals.push([0.0, 1.0, 0.0])
+		chunks.push(&content[start..end]);
+		start = end;
 	}
 
-	Ok(())
+	chunks
 }
 
-fn parse_face(tokens: &[&str], faces: &mut Vec<Face>) -> Result<(), String> {
-	if tokens.len() < 4 {
-		return Err("Face must have at least 3 vertices".to_string());
+/// Vertex data of one chunk, parsed before any face.
+struct VertexChunk<'a> {
+	positions: Vec<[f32; 3]>,
+	colors: Vec<Option<[f32; 3]>>,
+	weights: Vec<f32>,
+	tex_coords: Vec<[f32; 2]>,
+	normals: Vec<[f32; 3]>,
+	/// Lines read, up to and including the failing one.
+	lines: usize,
+	/// First error, with the line it was found on.
+	error: Option<(&'a str, TokenError<'a>)>,
+}
+
+/// Faces and statements of one chunk, parsed once the number of elements
+/// declared by the previous chunks is known.
+#[derive(Default)]
+struct FaceChunk {
+	faces: Vec<Face>,
+	fallback_faces: usize,
+	polylines: Vec<VertexList>,
+	points: Vec<VertexList>,
+	forward_refs: HashMap<usize, String>,
+	/// Statements, each with the number of chunk faces declared before it.
+	statements: Vec<(usize, Statement)>,
+	error: Option<ObjError>,
+}
+
+/// Where a chunk starts in the whole input, and how many of its lines to parse.
+#[derive(Clone, Copy)]
+struct ChunkStart {
+	first_line: usize,
+	counts: ElementCounts,
+	lines: usize,
+}
+
+fn parse_chunks(chunks: &[&str], options: &ParseOptions) -> Result<ObjData, ObjError> {
+	let mut parser = ObjParser::default();
+	parse_chunks_into(&mut parser, chunks, 1)?;
+	parser.finish(options)
+}
+
+/// Parses consecutive chunks into `parser`, one thread each, the first one
+/// starting at line `first_line` of the input. Returns the number of lines.
+fn parse_chunks_into(parser: &mut ObjParser, chunks: &[&str], mut first_line: usize) -> Result<usize, ObjError> {
+	let start_line = first_line;
+	let vertex_chunks = std::thread::scope(|scope| {
+		let handles: Vec<_> = chunks.iter()
+			.map(|&chunk| scope.spawn(move || parse_vertex_chunk(chunk)))
+			.collect();
+		join_all(handles)
+	});
+
+	let mut starts = Vec::with_capacity(chunks.len());
+	let mut vertex_error = None;
+
+	for data in vertex_chunks {
+		let mut start = ChunkStart { first_line, counts: parser.counts(), lines: data.lines };
+		first_line += data.lines;
+
+		let (before, added) = (parser.positions.len(), data.positions.len());
+		extend_sparse(&mut parser.colors, data.colors, before, added, None);
+		extend_sparse(&mut parser.weights, data.weights, before, added, 1.0);
+		parser.positions.extend(data.positions);
+		parser.tex_coords.extend(data.tex_coords);
+		parser.normals.extend(data.normals);
+
+		if let Some((raw_line, e)) = data.error {
+			// Faces after the failing line would see missing vertices
+			start.lines -= 1;
+			starts.push(start);
+			vertex_error = Some(e.locate(first_line - 1, raw_line));
+			break;
+		}
+		starts.push(start);
 	}
 
-	let mut face_vertices = Vec::new();
+	let positions = &parser.positions;
+	let face_chunks = std::thread::scope(|scope| {
+		let handles: Vec<_> = chunks.iter().zip(&starts)
+			.map(|(&chunk, &start)| scope.spawn(move || parse_face_chunk(chunk, start, positions)))
+			.collect();
+		join_all(handles)
+	});
+
+	for data in face_chunks {
+		parser.fallback_faces += data.fallback_faces;
+		parser.polylines.extend(data.polylines);
+		parser.points.extend(data.points);
+		parser.forward_refs.extend(data.forward_refs);
+
+		let mut faces = data.faces.into_iter();
+		let mut merged = 0;
+		for (faces_before, statement) in data.statements {
+			parser.push_faces(faces.by_ref().take(faces_before - merged));
+			merged = faces_before;
+			parser.apply(statement);
+		}
+		parser.push_faces(faces);
 
-	for i in 1..tokens.len() {
-		let vertex = parse_face_vertex(tokens[i])?;
-		face_vertices.push(vertex);
+		if let Some(e) = data.error {
+			return Err(e);
+		}
 	}
 
-	let triangulated = triangulate_face(&face_vertices);
-	faces.extend(triangulated);
+	if let Some(e) = vertex_error {
+		return Err(e);
+	}
 
-	Ok(())
+	Ok(first_line - start_line)
 }
 
-fn parse_face_vertex(token: &str) -> Result<FaceVertex, String> {
-	let parts: Vec<&str> = token.split('/').collect();
+/// Appends a chunk of per-position values that are only stored once one of
+/// them differs from `fill`, keeping `values` empty or as long as the positions.
+fn extend_sparse<T: Clone>(values: &mut Vec<T>, chunk: Vec<T>, before: usize, added: usize, fill: T) {
+	if values.is_empty() && chunk.is_empty() {
+		return;
+	}
 
-	let position_idx = parts[0].parse::<isize>()
-		.map_err(|_| format!("Invalid vertex index: '{}'", parts[0]))?;
+	values.resize(before, fill.clone());
+	values.extend(chunk);
+	values.resize(before + added, fill);
+}
 
-	let tex_coord_idx = if parts.len() > 1 && !parts[1].is_empty() {
-		Some(parts[1].parse::<isize>()
-			.map_err(|_| format!("Invalid texture index: '{}'", parts[1]))?)
-	} else {
-		None
-	};
+fn join_all<T>(handles: Vec<std::thread::ScopedJoinHandle<'_, T>>) -> Vec<T> {
+	handles.into_iter()
+		.map(|handle| handle.join().unwrap_or_else(|panic| std::panic::resume_unwind(panic)))
+		.collect()
+}
 
-	let normal_idx = if parts.len() > 2 {
-		Some(parts[2].parse::<isize>()
-			.map_err(|_| format!("Invalid normal index: '{}'", parts[2]))?)
-	} else {
-		None
+fn parse_vertex_chunk(chunk: &str) -> VertexChunk<'_> {
+	let mut data = VertexChunk {
+		positions: Vec::new(),
+		colors: Vec::new(),
+		weights: Vec::new(),
+		tex_coords: Vec::new(),
+		normals: Vec::new(),
+		lines: 0,
+		error: None,
 	};
 
-	Ok(FaceVertex {
-		position_idx: handle_obj_index(position_idx)?,
-		tex_coord_idx: tex_coord_idx.map(|i| handle_obj_index(i)).transpose()?,
-		normal_idx: normal_idx.map(|i| handle_obj_index(i)).transpose()?,
-	})
-}
+	for raw_line in chunk.split_inclusive('\n') {
+		let raw_line = raw_line.trim_end_matches(['\n', '\r']);
+		data.lines += 1;
 
+		let result = with_tokens(raw_line, |tokens| match tokens[0] {
+			"v" => parse_vertex(tokens, &mut data.positions, &mut data.colors, &mut data.weights),
+			"vt" => parse_tex_coord(tokens, &mut data.tex_coords),
+			"vn" => parse_normal(tokens, &mut data.normals),
+			_ => Ok(()),
+		});
 
-fn handle_obj_index(idx: isize) -> Result<usize, String> {
-	if idx > 0 {
-		Ok((idx - 1) as usize)
-	} else if idx < 0 {
-		Err("Negative indices not supported yet".to_string())
-	} else {
-		Err("Index cannot be 0".to_string())
+		if let Some(Err(e)) = result {
+			data.error = Some((raw_line, e));
+			break;
+		}
 	}
+
+	data
 }
 
-fn triangulate_face(vertices: &[FaceVertex]) -> Vec<Face> {
-	if vertices.len() == 3 {
-		return vec![Face { vertices: vertices.to_vec() }];
-	}
+fn parse_face_chunk(chunk: &str, start: ChunkStart, positions: &[[f32; 3]]) -> FaceChunk {
+	let mut data = FaceChunk::default();
+	let mut counts = start.counts;
 
-	let mut triangles = Vec::new();
+	for (i, raw_line) in chunk.split_inclusive('\n').take(start.lines).enumerate() {
+		let raw_line = raw_line.trim_end_matches(['\n', '\r']);
+		let line_num = start.first_line + i;
 
-	for i in 1..(vertices.len() - 1) {
-		triangles.push(Face {
-			vertices: vec![
-				vertices[0].clone(),
-				vertices[i].clone(),
-				vertices[i + 1].clone(),
-			],
+		let result = with_tokens(raw_line, |tokens| match tokens[0] {
+			"v" => {
+				counts.positions += 1;
+				Ok(())
+			}
+			"vt" => {
+				counts.tex_coords += 1;
+				Ok(())
+			}
+			"vn" => {
+				counts.normals += 1;
+				Ok(())
+			}
+			"f" => parse_face(tokens, counts, &positions[..counts.positions], line_num, raw_line, &mut data.faces, &mut data.forward_refs)
+				.map(|used_fallback| if used_fallback { data.fallback_faces += 1 }),
+			"l" => parse_vertex_list(tokens, counts.positions, line_num, raw_line, &mut data.polylines, &mut data.forward_refs),
+			"p" => parse_vertex_list(tokens, counts.positions, line_num, raw_line, &mut data.points, &mut data.forward_refs),
+			_ => parse_statement(tokens, counts.positions, line_num, raw_line)
+				.map(|statement| data.statements.push((data.faces.len(), statement))),
 		});
+
+		if let Some(Err(e)) = result {
+			data.error = Some(e.locate(line_num, raw_line));
+			break;
+		}
 	}
 
-	triangles
+	data
 }
 
-pub fn obj_to_mesh(obj: ObjData) -> Mesh {
-	let mut vertices = Vec::new();
-	let mut indices = Vec::new();
-	let mut vertex_cache: HashMap<VertexKey, u32> = HashMap::new();
+/// Splits `line` into `buf`, returning the token count, or `None` when the
+/// line has more tokens than `buf` can hold.
+fn tokenize<'a>(line: &'a str, buf: &mut [&'a str]) -> Option<usize> {
+	let mut count = 0;
 
-	let bbox = BoudingBox::from_positions(&obj.positions);
+	for token in line.split_whitespace() {
+		*buf.get_mut(count)? = token;
+		count += 1;
+	}
 
-	for (face_idx, face) in obj.faces.iter().enumerate() {
-		let face_normal = compute_face_normal(&obj, face);
+	Some(count)
+}
 
-		for face_vertex in &face.vertices {
-			let position = obj.positions[face_vertex.position_idx];
+/// Calls `f` with the tokens of `raw_line`, skipping blank and comment lines.
+fn with_tokens<'a, T>(raw_line: &'a str, f: impl FnOnce(&[&'a str]) -> T) -> Option<T> {
+	let line = raw_line.trim();
 
-			let tex_coords = match face_vertex.tex_coord_idx {
-				Some(i) => obj.tex_coords[i],
-				None => generate_planar_uv(position, face_normal, &bbox),
-			};
+	if line.is_empty() || line.starts_with('#') {
+		return None;
+	}
 
-			let normal = face_vertex.normal_idx
-				.map(|i| obj.normals[i])
-				.unwrap_or(face_normal);
+	let mut inline = [""; INLINE_TOKENS];
+	let overflow: Vec<&str>;
+	let tokens: &[&str] = match tokenize(line, &mut inline) {
+		Some(count) => &inline[..count],
+		None => {
+			overflow = line.split_whitespace().collect();
+			&overflow
+		}
+	};
 
-			let color = generate_face_color(face_idx);
+	Some(f(tokens))
+}
 
-			let vertex = Vertex {
-				position,
-				tex_coords,
-				normal,
-				color,
-			};
+/// A statement changing the parser state rather than adding geometry.
+///
+/// Free-form elements are statements too: their `parm` values follow on later
+/// lines, and they take the `cstype` and `deg` in effect.
+enum Statement {
+	MaterialLibs(Vec<String>),
+	UseMaterial(String),
+	Object(String),
+	Group(String),
+	Smoothing(u32),
+	CurveType(CurveType),
+	Degree([usize; 2]),
+	FreeForm {
+		surface: bool,
+		range: [[f32; 2]; 2],
+		control: Vec<usize>,
+		location: SourceLocation,
+	},
+	Parameters(usize, Vec<f32>),
+	End,
+	Ignored(Diagnostic),
+}
 
-			let key = VertexKey::from_vertex(&vertex);
+fn parse_statement<'a>(tokens: &[&'a str], position_count: usize, line_num: usize, raw_line: &str) -> Result<Statement, TokenError<'a>> {
+	match tokens[0] {
+		"mtllib" => parse_mtllib(tokens).map(Statement::MaterialLibs),
+		"usemtl" => parse_usemtl(tokens).map(Statement::UseMaterial),
+		"o" => Ok(Statement::Object(directive_name(tokens))),
+		"g" => Ok(Statement::Group(directive_name(tokens))),
+		"s" => parse_smoothing_group(tokens).map(Statement::Smoothing),
+		"cstype" => parse_curve_type(tokens, line_num, raw_line).map(Statement::CurveType),
+		"deg" => parse_degree(tokens).map(Statement::Degree),
+		"curv" | "surf" => parse_free_form(tokens, position_count, line_num, raw_line),
+		"parm" => parse_parameters(tokens),
+		"end" => Ok(Statement::End),
+		// Skipping an included file would silently drop geometry
+		"call" => Err(TokenError::unsupported(tokens[0], "'call' file inclusion".to_string())),
+		directive => {
+			let message = if IGNORED_DIRECTIVES.contains(&directive) {
+				format!("Unsupported directive '{}' ignored", directive)
+			} else {
+				format!("Unknown directive '{}'", directive)
+			};
+			Ok(Statement::Ignored(Diagnostic {
+				file: None,
+				location: Some(SourceLocation::of_token(line_num, raw_line, tokens[0])),
+				message,
+			}))
+		}
+	}
+}
 
-			let index = *vertex_cache.entry(key).or_insert_with(|| {
-				let idx = vertices.len() as u32;
-				vertices.push(vertex);
-				idx
-			});
+/// Parser state, fed one line at a time.
+#[derive(Default)]
+struct ObjParser {
+	positions: Vec<[f32; 3]>,
+	colors: Vec<Option<[f32; 3]>>,
+	weights: Vec<f32>,
+	tex_coords: Vec<[f32; 2]>,
+	normals: Vec<[f32; 3]>,
+	faces: Vec<Face>,
+	fallback_faces: usize,
+	polylines: Vec<VertexList>,
+	points: Vec<VertexList>,
+	free_forms: Vec<ObjFreeForm>,
+	curve_type: CurveType,
+	degree: [usize; 2],
+	/// Element waiting for its `end`.
+	open_free_form: Option<ObjFreeForm>,
+	material_libs: Vec<String>,
+	materials: Vec<Material>,
+	current_material: Option<usize>,
+	objects: Vec<ObjObject>,
+	smoothing_group: u32,
+	has_smoothing_groups: bool,
+	diagnostics: Vec<Diagnostic>,
+	/// Text of the face lines referencing elements declared further down.
+	/// Only those can fail validation, so the rest of the input is not kept.
+	forward_refs: HashMap<usize, String>,
+}
 
-			indices.push(index);
+impl ObjParser {
+	fn counts(&self) -> ElementCounts {
+		ElementCounts {
+			positions: self.positions.len(),
+			tex_coords: self.tex_coords.len(),
+			normals: self.normals.len(),
 		}
 	}
 
-	Mesh { vertices, indices }
-}
+	fn parse_line(&mut self, line_num: usize, raw_line: &str) -> Result<(), ObjError> {
+		let result = with_tokens(raw_line, |tokens| match tokens[0] {
+			"v" => parse_vertex(tokens, &mut self.positions, &mut self.colors, &mut self.weights),
+			"vt" => parse_tex_coord(tokens, &mut self.tex_coords),
+			"vn" => parse_normal(tokens, &mut self.normals),
+			"f" => {
+				let first_new = self.faces.len();
+				let counts = self.counts();
+
+				parse_face(tokens, counts, &self.positions, line_num, raw_line, &mut self.faces, &mut self.forward_refs)
+					.map(|used_fallback| {
+						if used_fallback {
+							self.fallback_faces += 1;
+						}
+						self.assign_face_state(first_new);
+					})
+			}
+			"l" => parse_vertex_list(tokens, self.positions.len(), line_num, raw_line, &mut self.polylines, &mut self.forward_refs),
+			"p" => parse_vertex_list(tokens, self.positions.len(), line_num, raw_line, &mut self.points, &mut self.forward_refs),
+			_ => parse_statement(tokens, self.positions.len(), line_num, raw_line)
+				.map(|statement| self.apply(statement)),
+		});
 
-fn generate_face_color(face_index: usize) -> [f32; 3] {
-    let shades = [
-        [0.3, 0.3, 0.3],
-        [0.4, 0.4, 0.4],
-        [0.5, 0.5, 0.5],
-        [0.6, 0.6, 0.6],
-        [0.7, 0.7, 0.7],
-        [0.8, 0.8, 0.8],
-    ];
+		match result {
+			Some(Err(e)) => Err(e.locate(line_num, raw_line)),
+			_ => Ok(()),
+		}
+	}
 
-	shades[face_index % shades.len()]
-}
+	fn apply(&mut self, statement: Statement) {
+		match statement {
+			Statement::MaterialLibs(libs) => self.material_libs.extend(libs),
+			Statement::UseMaterial(name) => {
+				// Placeholder until the library is loaded, so ids stay stable
+				let idx = match self.materials.iter().position(|m| m.name == name) {
+					Some(idx) => idx,
+					None => {
+						self.materials.push(Material::new(&name));
+						self.materials.len() - 1
+					}
+				};
+				self.current_material = Some(idx);
+			}
+			Statement::Object(name) => start_object(&mut self.objects, &name, self.faces.len()),
+			Statement::Group(name) => start_group(&mut self.objects, &name, self.faces.len()),
+			Statement::Smoothing(group) => {
+				self.smoothing_group = group;
+				self.has_smoothing_groups = true;
+			}
+			Statement::CurveType(curve_type) => {
+				if let CurveType::Unsupported(diagnostic) = &curve_type {
+					self.diagnostics.push(diagnostic.clone());
+				}
+				self.curve_type = curve_type;
+			}
+			Statement::Degree(degree) => self.degree = degree,
+			Statement::FreeForm { surface, range, control, location } => {
+				self.drop_open_free_form();
+
+				match self.curve_type {
+					CurveType::Supported { basis, rational } => {
+						self.open_free_form = Some(ObjFreeForm {
+							element: FreeForm {
+								basis,
+								rational,
+								surface,
+								degree: self.degree,
+								range,
+								parameters: Default::default(),
+								control,
+							},
+							material_idx: self.current_material,
+							part: self.current_part(),
+							location,
+						});
+					}
+					CurveType::Unset => self.diagnostics.push(Diagnostic {
+						file: None,
+						location: Some(location),
+						message: "Free-form element ignored: no 'cstype' declared".to_string(),
+					}),
+					CurveType::Unsupported(_) => {}
+				}
+			}
+			Statement::Parameters(direction, values) => {
+				if let Some(open) = &mut self.open_free_form {
+					open.element.parameters[direction] = values;
+				}
+			}
+			Statement::End => {
+				if let Some(free_form) = self.open_free_form.take() {
+					match free_form.element.validate() {
+						Ok(()) => self.free_forms.push(free_form),
+						Err(message) => self.diagnostics.push(Diagnostic {
+							file: None,
+							location: Some(free_form.location),
+							message: format!("Free-form element ignored: {}", message),
+						}),
+					}
+				}
+			}
+			Statement::Ignored(diagnostic) => self.diagnostics.push(diagnostic),
+		}
+	}
 
-fn compute_face_normal(obj: &ObjData, face: &Face) -> [f32; 3] {
-	if face.vertices.len() < 3 {
-		return [0.0, 1.0, 0.0];
+	/// Reports an element left without its `end`.
+	fn drop_open_free_form(&mut self) {
+		if let Some(free_form) = self.open_free_form.take() {
+			self.diagnostics.push(Diagnostic {
+				file: None,
+				location: Some(free_form.location),
+				message: "Free-form element ignored: missing 'end'".to_string(),
+			});
+		}
 	}
 
-	let v0 = obj.positions[face.vertices[0].position_idx];
-	let v1 = obj.positions[face.vertices[1].position_idx];
-	let v2 = obj.positions[face.vertices[2].position_idx];
+	/// Name of the part new elements go to, matching [`ObjData::parts`].
+	fn current_part(&self) -> String {
+		match self.objects.last() {
+			Some(object) => match object.groups.last() {
+				Some(group) => format!("{}/{}", object.name, group.name),
+				None => object.name.clone(),
+			},
+			None => "default".to_string(),
+		}
+	}
 
-	let edge1 = [v1[0] - v0[0], v1[1] - v0[1], v1[2] - v0[2]];
-	let edge2 = [v2[0] - v0[0], v2[1] - v0[1], v2[2] - v0[2]];
+	/// Appends faces parsed elsewhere, under the current material and smoothing group.
+	fn push_faces(&mut self, faces: impl Iterator<Item = Face>) {
+		let first_new = self.faces.len();
+		self.faces.extend(faces);
+		self.assign_face_state(first_new);
+	}
 
-	let normal = [
-		edge1[1] * edge2[2] - edge1[2] * edge2[1],
-		edge1[2] * edge2[0] - edge1[0] * edge2[2],
-		edge1[0] * edge2[1] - edge1[1] * edge2[0],
-	];
+	fn assign_face_state(&mut self, first_new: usize) {
+		for face in &mut self.faces[first_new..] {
+			face.material_idx = self.current_material;
+			face.smoothing_group = self.smoothing_group;
+		}
+	}
 
-	let len = (normal[0]*normal[0] + normal[1]*normal[1] + normal[2]*normal[2]).sqrt();
+	fn finish(mut self, options: &ParseOptions) -> Result<ObjData, ObjError> {
+		self.drop_open_free_form();
+
+		let mut objects = self.objects;
+		finish_objects(&mut objects, self.faces.len());
+
+		let mut obj = ObjData {
+			positions: self.positions,
+			colors: self.colors,
+			weights: self.weights,
+			tex_coords: self.tex_coords,
+			normals: self.normals,
+			faces: self.faces,
+			fallback_faces: self.fallback_faces,
+			polylines: self.polylines,
+			points: self.points,
+			free_forms: self.free_forms,
+			material_libs: self.material_libs,
+			materials: self.materials,
+			objects,
+			has_smoothing_groups: self.has_smoothing_groups,
+			diagnostics: self.diagnostics,
+		};
 
-	if len > 0.0001 {
-		[normal[0]/len, normal[1]/len, normal[2]/len]
-	} else {
-		[0.0, 1.0, 0.0]
+		validate_faces(&mut obj, &self.forward_refs, options)?;
+		validate_vertex_lists(&mut obj, &self.forward_refs, options)?;
+		validate_free_forms(&mut obj, options)?;
+
+		Ok(obj)
 	}
 }
 
+/// Checks that every face only references declared elements.
+///
+/// Fails on the first bad face, or in lenient mode drops the bad faces,
+/// reports one diagnostic per source line and shifts object ranges.
+fn validate_faces(obj: &mut ObjData, forward_refs: &HashMap<usize, String>, options: &ParseOptions) -> Result<(), ObjError> {
+	let counts = ElementCounts {
+		positions: obj.positions.len(),
+		tex_coords: obj.tex_coords.len(),
+		normals: obj.normals.len(),
+	};
 
-fn generate_planar_uv(position: [f32; 3], face_normal: [f32; 3], bbox: &BoudingBox) -> [f32; 2] {
-	let ax = face_normal[0].abs();
-	let ay = face_normal[1].abs();
-	let az = face_normal[2].abs();
+	if obj.faces.iter().all(|face| find_bad_reference(face, counts).is_none()) {
+		return Ok(());
+	}
 
-	let (u_axis, v_axis) = if ax >= ay && ax >= az {
-		(1, 2)
-	} else if ay >= ax && ay >= az {
-		(0, 2)
-	} else {
-		(0, 1)
-	};
+	let mut kept_before = Vec::with_capacity(obj.faces.len() + 1);
+	let mut kept = 0;
+	let mut last_reported_line = 0;
 
-	let u = bbox.normalize(position[u_axis], u_axis);
-	let v = bbox.normalize(position[v_axis], v_axis);
+	let faces = std::mem::take(&mut obj.faces);
+	for face in faces {
+		kept_before.push(kept);
 
-	[u, v]
+		let Some((slot, index, count)) = find_bad_reference(&face, counts) else {
+			obj.faces.push(face);
+			kept += 1;
+			continue;
+		};
+
+		let location = locate_reference(forward_refs, face.line, slot, index);
+
+		if !options.lenient {
+			return Err(ObjError::IndexOutOfRange { location, index: index as isize + 1, count });
+		}
+
+		if face.line != last_reported_line {
+			last_reported_line = face.line;
+			obj.diagnostics.push(Diagnostic {
+				file: None,
+				location: Some(location),
+				message: format!("Face dropped: index {} out of range ({} elements declared)", index + 1, count),
+			});
+		}
+	}
+	kept_before.push(kept);
+
+	for object in &mut obj.objects {
+		object.faces = kept_before[object.faces.start]..kept_before[object.faces.end];
+		for group in &mut object.groups {
+			group.faces = kept_before[group.faces.start]..kept_before[group.faces.end];
+		}
+	}
+	drop_empty_objects(&mut obj.objects);
+
+	Ok(())
+}
+
+/// Checks the positions referenced by `l` and `p` elements, dropping bad ones
+/// in lenient mode like [`validate_faces`].
+fn validate_vertex_lists(obj: &mut ObjData, forward_refs: &HashMap<usize, String>, options: &ParseOptions) -> Result<(), ObjError> {
+	let count = obj.positions.len();
+
+	for (lists, kind) in [(&mut obj.polylines, "Line"), (&mut obj.points, "Point element")] {
+		let mut error = None;
+
+		lists.retain(|list| {
+			let Some(&index) = list.vertices.iter().find(|&&v| v >= count) else {
+				return true;
+			};
+			if error.is_some() {
+				return false;
+			}
+
+			let location = locate_reference(forward_refs, list.line, 0, index);
+			if options.lenient {
+				obj.diagnostics.push(Diagnostic {
+					file: None,
+					location: Some(location),
+					message: format!("{} dropped: index {} out of range ({} elements declared)", kind, index + 1, count),
+				});
+			} else {
+				error = Some(ObjError::IndexOutOfRange { location, index: index as isize + 1, count });
+			}
+			false
+		});
+
+		if let Some(error) = error {
+			return Err(error);
+		}
+	}
+
+	Ok(())
+}
+
+/// Checks the control points of free-form elements, dropping bad elements in
+/// lenient mode like [`validate_faces`].
+fn validate_free_forms(obj: &mut ObjData, options: &ParseOptions) -> Result<(), ObjError> {
+	let count = obj.positions.len();
+	let mut error = None;
+
+	obj.free_forms.retain(|free_form| {
+		let Some(&index) = free_form.element.control.iter().find(|&&v| v >= count) else {
+			return true;
+		};
+		if error.is_some() {
+			return false;
+		}
+
+		let text = &free_form.location.text;
+		let location = SourceLocation::of_token(free_form.location.line, text, find_index_token(text, 0, index).unwrap_or(text));
+		if options.lenient {
+			obj.diagnostics.push(Diagnostic {
+				file: None,
+				location: Some(location),
+				message: format!("Free-form element dropped: index {} out of range ({} elements declared)", index + 1, count),
+			});
+		} else {
+			error = Some(ObjError::IndexOutOfRange { location, index: index as isize + 1, count });
+		}
+		false
+	});
+
+	match error {
+		Some(error) => Err(error),
+		None => Ok(()),
+	}
+}
+
+/// Returns the first out-of-range reference as `(slot, index, count)`, where
+/// slot 0, 1 and 2 are position, texture and normal.
+fn find_bad_reference(face: &Face, counts: ElementCounts) -> Option<(usize, usize, usize)> {
+	face.vertices.iter().find_map(|v| {
+		let slots = [
+			(Some(v.position_idx), counts.positions),
+			(v.tex_coord_idx, counts.tex_coords),
+			(v.normal_idx, counts.normals),
+		];
+
+		slots.iter().enumerate().find_map(|(slot, &(idx, count))| match idx {
+			Some(idx) if idx >= count => Some((slot, idx, count)),
+			_ => None,
+		})
+	})
+}
+
+/// Finds the face token holding the 0-based `index` in `slot` on `line`.
+fn locate_reference(forward_refs: &HashMap<usize, String>, line: usize, slot: usize, index: usize) -> SourceLocation {
+	let text = forward_refs.get(&line).map_or("", String::as_str);
+	let token = find_index_token(text, slot, index).unwrap_or(text);
+
+	SourceLocation::of_token(line, text, token)
+}
+
+/// Finds the token of `text` holding the 0-based `index` in `slot`.
+fn find_index_token(text: &str, slot: usize, index: usize) -> Option<&str> {
+	text.split_whitespace()
+		.skip(1)
+		.filter_map(|t| t.split('/').nth(slot))
+		.find(|part| part.parse::<usize>() == Ok(index + 1))
+}
+
+fn parse_smoothing_group<'a>(tokens: &[&'a str]) -> Result<u32, TokenError<'a>> {
+	match tokens.get(1) {
+		None => Err(TokenError::syntax(tokens[0], "Invalid smoothing group format: expected 's n' or 's off'".to_string())),
+		Some(&"off") => Ok(0),
+		Some(group) => group.parse::<u32>()
+			.map_err(|_| TokenError::syntax(group, format!("Invalid smoothing group: '{}'", group))),
+	}
+}
+
+/// Parses `cstype [rat] type`.
+fn parse_curve_type<'a>(tokens: &[&'a str], line_num: usize, raw_line: &str) -> Result<CurveType, TokenError<'a>> {
+	let (rational, name) = match tokens {
+		[_, "rat", name] => (true, *name),
+		[_, name] => (false, *name),
+		_ => return Err(TokenError::syntax(tokens[0], "Invalid cstype format: expected 'cstype [rat] type'".to_string())),
+	};
+
+	match name {
+		"bezier" => Ok(CurveType::Supported { basis: Basis::Bezier, rational }),
+		"bspline" => Ok(CurveType::Supported { basis: Basis::BSpline, rational }),
+		"bmat" | "cardinal" | "taylor" => Ok(CurveType::Unsupported(Diagnostic {
+			file: None,
+			location: Some(SourceLocation::of_token(line_num, raw_line, name)),
+			message: format!("Unsupported free-form type '{}': its elements are ignored", name),
+		})),
+		_ => Err(TokenError::syntax(name, format!("Unknown free-form type: '{}'", name))),
+	}
+}
+
+/// Parses `deg du [dv]`.
+fn parse_degree<'a>(tokens: &[&'a str]) -> Result<[usize; 2], TokenError<'a>> {
+	if !(2..=3).contains(&tokens.len()) {
+		return Err(TokenError::syntax(tokens[0], "Invalid deg format: expected 'deg du [dv]'".to_string()));
+	}
+
+	let mut degree = [0; 2];
+	for (value, token) in degree.iter_mut().zip(&tokens[1..]) {
+		*value = token.parse::<usize>()
+			.map_err(|_| TokenError::syntax(token, format!("Invalid degree: '{}'", token)))?;
+	}
+
+	Ok(degree)
+}
+
+/// Parses a `curv u0 u1 v1 v2 ...` or `surf s0 s1 t0 t1 v1 v2 ...` header.
+///
+/// Surface control points may carry `/vt/vn` references, which are ignored:
+/// texture coordinates and normals are derived from the surface instead.
+fn parse_free_form<'a>(
+	tokens: &[&'a str],
+	position_count: usize,
+	line_num: usize,
+	raw_line: &str,
+) -> Result<Statement, TokenError<'a>> {
+	let surface = tokens[0] == "surf";
+	let (range_count, min_control, format) = if surface {
+		(4, 4, "'surf s0 s1 t0 t1 v1 v2 v3 v4 ...'")
+	} else {
+		(2, 2, "'curv u0 u1 v1 v2 ...'")
+	};
+	if tokens.len() < 1 + range_count + min_control {
+		return Err(TokenError::syntax(tokens[0], format!("Invalid {} format: expected {}", tokens[0], format)));
+	}
+
+	let mut range = [[0.0; 2]; 2];
+	for (i, token) in tokens[1..=range_count].iter().enumerate() {
+		range[i / 2][i % 2] = parse_coordinate(token, "parameter")?;
+	}
+
+	let control = tokens[1 + range_count..].iter()
+		.map(|token| handle_obj_index(token.split('/').next().unwrap_or(token), "vertex", position_count))
+		.collect::<Result<Vec<_>, _>>()?;
+
+	Ok(Statement::FreeForm {
+		surface,
+		range,
+		control,
+		location: SourceLocation::of_token(line_num, raw_line, tokens[0]),
+	})
+}
+
+/// Parses `parm u|v p1 p2 ...`.
+fn parse_parameters<'a>(tokens: &[&'a str]) -> Result<Statement, TokenError<'a>> {
+	let direction = match tokens.get(1) {
+		Some(&"u") => 0,
+		Some(&"v") => 1,
+		Some(token) => return Err(TokenError::syntax(token, format!("Invalid parm direction: '{}'", token))),
+		None => return Err(TokenError::syntax(tokens[0], "Invalid parm format: expected 'parm u|v p1 p2 ...'".to_string())),
+	};
+
+	let values = tokens[2..].iter()
+		.map(|token| parse_coordinate(token, "parameter"))
+		.collect::<Result<Vec<_>, _>>()?;
+
+	Ok(Statement::Parameters(direction, values))
+}
+
+fn directive_name(tokens: &[&str]) -> String {
+	if tokens.len() < 2 {
+		"default".to_string()
+	} else {
+		tokens[1..].join(" ")
+	}
+}
+
+fn close_current_group(objects: &mut [ObjObject], face_count: usize) {
+	if let Some(group) = objects.last_mut().and_then(|o| o.groups.last_mut()) {
+		group.faces.end = face_count;
+	}
+}
+
+fn start_object(objects: &mut Vec<ObjObject>, name: &str, face_count: usize) {
+	close_current_group(objects, face_count);
+	if let Some(object) = objects.last_mut() {
+		object.faces.end = face_count;
+	}
+
+	objects.push(ObjObject {
+		name: name.to_string(),
+		faces: face_count..face_count,
+		groups: Vec::new(),
+	});
+}
+
+fn start_group(objects: &mut Vec<ObjObject>, name: &str, face_count: usize) {
+	if objects.is_empty() {
+		start_object(objects, "default", 0);
+	}
+
+	close_current_group(objects, face_count);
+
+	if let Some(object) = objects.last_mut() {
+		object.groups.push(ObjGroup {
+			name: name.to_string(),
+			faces: face_count..face_count,
+		});
+	}
+}
+
+/// Closes the open ranges and drops empty objects and groups.
+///
+/// Faces declared before the first `o`/`g` go to an implicit `default` object.
+fn finish_objects(objects: &mut Vec<ObjObject>, face_count: usize) {
+	close_current_group(objects, face_count);
+	if let Some(object) = objects.last_mut() {
+		object.faces.end = face_count;
+	}
+
+	let first_start = objects.first().map_or(face_count, |o| o.faces.start);
+	if first_start > 0 {
+		objects.insert(0, ObjObject {
+			name: "default".to_string(),
+			faces: 0..first_start,
+			groups: Vec::new(),
+		});
+	}
+
+	drop_empty_objects(objects);
+}
+
+fn drop_empty_objects(objects: &mut Vec<ObjObject>) {
+	for object in objects.iter_mut() {
+		object.groups.retain(|g| !g.faces.is_empty());
+	}
+	objects.retain(|o| !o.faces.is_empty());
+}
+
+fn parse_coordinate<'a>(token: &'a str, what: &str) -> Result<f32, TokenError<'a>> {
+	token.parse::<f32>()
+		.map_err(|_| TokenError::syntax(token, format!("Invalid {}: '{}'", what, token)))
+}
+
+/// Parses `v x y z [w]` or the `v x y z r g b` vertex color extension.
+///
+/// `colors` stays empty until a colored vertex shows up, then holds one entry
+/// per position. Colors above 1 are taken as 0-255 values. `weights` works
+/// the same way for `w` values other than 1.
+fn parse_vertex<'a>(
+	tokens: &[&'a str],
+	positions: &mut Vec<[f32; 3]>,
+	colors: &mut Vec<Option<[f32; 3]>>,
+	weights: &mut Vec<f32>,
+) -> Result<(), TokenError<'a>> {
+	if tokens.len() < 4 {
+		return Err(TokenError::syntax(tokens[0], "Invalid vertex format: expected 'v x y z'".to_string()));
+	}
+
+	let x = parse_coordinate(tokens[1], "x coordinate")?;
+	let y = parse_coordinate(tokens[2], "y coordinate")?;
+	let z = parse_coordinate(tokens[3], "z coordinate")?;
+
+	let color = if tokens.len() >= 7 {
+		let r = parse_coordinate(tokens[4], "red component")?;
+		let g = parse_coordinate(tokens[5], "green component")?;
+		let b = parse_coordinate(tokens[6], "blue component")?;
+
+		let scale = if r > 1.0 || g > 1.0 || b > 1.0 { 1.0 / 255.0 } else { 1.0 };
+		Some([r * scale, g * scale, b * scale])
+	} else {
+		None
+	};
+
+	if color.is_some() || !colors.is_empty() {
+		colors.resize(positions.len(), None);
+		colors.push(color);
+	}
+
+	let weight = match tokens.len() {
+		5 => parse_coordinate(tokens[4], "weight")?,
+		_ => 1.0,
+	};
+
+	if weight != 1.0 || !weights.is_empty() {
+		weights.resize(positions.len(), 1.0);
+		weights.push(weight);
+	}
+
+	positions.push([x, y, z]);
+	Ok(())
+}
+
+fn parse_tex_coord<'a>(tokens: &[&'a str], tex_coords: &mut Vec<[f32; 2]>) -> Result<(), TokenError<'a>> {
+	if tokens.len() < 3 {
+		return Err(TokenError::syntax(tokens[0], "Invalid texture coordinate format: expected 'vt u v'".to_string()));
+	}
+
+	let u = parse_coordinate(tokens[1], "u coordinate")?;
+	let v = parse_coordinate(tokens[2], "v coordinate")?;
+
+	tex_coords.push([u, v]);
+	Ok(())
+}
+
+fn parse_normal<'a>(tokens: &[&'a str], normals: &mut Vec<[f32; 3]>) -> Result<(), TokenError<'a>> {
+	if tokens.len() < 4 {
+		return Err(TokenError::syntax(tokens[0], "Invalid normal format: expected 'vn x y z'".to_string()));
+	}
+
+	let x = parse_coordinate(tokens[1], "normal x")?;
+	let y = parse_coordinate(tokens[2], "normal y")?;
+	let z = parse_coordinate(tokens[3], "normal z")?;
+
+	let length = (x*x + y*y + z*z).sqrt();
+
+	if length > 0.0001 {
+		normals.push([x / length, y / length, z / length]);
+	} else {
+		normals.push([0.0, 1.0, 0.0])
+	}
+
+	Ok(())
+}
+
+fn parse_mtllib<'a>(tokens: &[&'a str]) -> Result<Vec<String>, TokenError<'a>> {
+	if tokens.len() < 2 {
+		return Err(TokenError::syntax(tokens[0], "Invalid mtllib format: expected 'mtllib file.mtl'".to_string()));
+	}
+
+	Ok(tokens[1..].iter().map(|t| t.to_string()).collect())
+}
+
+fn parse_usemtl<'a>(tokens: &[&'a str]) -> Result<String, TokenError<'a>> {
+	if tokens.len() < 2 {
+		return Err(TokenError::syntax(tokens[0], "Invalid usemtl format: expected 'usemtl name'".to_string()));
+	}
+
+	Ok(tokens[1..].join(" "))
+}
+
+/// Number of elements declared so far, used to resolve relative face indices.
+#[derive(Clone, Copy)]
+struct ElementCounts {
+	positions: usize,
+	tex_coords: usize,
+	normals: usize,
+}
+
+/// Parses and triangulates a face, returning whether the fan fallback was needed.
+///
+/// Lines referencing elements not declared yet are kept in `forward_refs`.
+fn parse_face<'a>(
+	tokens: &[&'a str],
+	counts: ElementCounts,
+	positions: &[[f32; 3]],
+	line: usize,
+	raw_line: &str,
+	faces: &mut Vec<Face>,
+	forward_refs: &mut HashMap<usize, String>,
+) -> Result<bool, TokenError<'a>> {
+	if tokens.len() < 4 {
+		return Err(TokenError::syntax(tokens[0], "Face must have at least 3 vertices".to_string()));
+	}
+
+	let mut face_vertices = Vec::new();
+
+	for token in &tokens[1..] {
+		let vertex = parse_face_vertex(token, counts)?;
+		face_vertices.push(vertex);
+	}
+
+	let (triangulated, used_fallback) = triangulate_face(&face_vertices, positions);
+	let first_new = faces.len();
+	faces.extend(triangulated.into_iter().map(|face| Face { line, ..face }));
+
+	if faces[first_new..].iter().any(|f| find_bad_reference(f, counts).is_some()) {
+		forward_refs.insert(line, raw_line.to_string());
+	}
+
+	Ok(used_fallback)
+}
+
+/// Parses an `l` or `p` element, keeping only the position of each vertex.
+fn parse_vertex_list<'a>(
+	tokens: &[&'a str],
+	position_count: usize,
+	line: usize,
+	raw_line: &str,
+	lists: &mut Vec<VertexList>,
+	forward_refs: &mut HashMap<usize, String>,
+) -> Result<(), TokenError<'a>> {
+	let (min_tokens, message) = match tokens[0] {
+		"l" => (3, "Line must have at least 2 vertices"),
+		_ => (2, "Point element must have at least 1 vertex"),
+	};
+	if tokens.len() < min_tokens {
+		return Err(TokenError::syntax(tokens[0], message.to_string()));
+	}
+
+	let vertices = tokens[1..].iter()
+		.map(|token| handle_obj_index(token.split('/').next().unwrap_or(token), "vertex", position_count))
+		.collect::<Result<Vec<_>, _>>()?;
+
+	if vertices.iter().any(|&v| v >= position_count) {
+		forward_refs.insert(line, raw_line.to_string());
+	}
+
+	lists.push(VertexList { vertices, line });
+	Ok(())
+}
+
+fn parse_face_vertex(token: &str, counts: ElementCounts) -> Result<FaceVertex, TokenError<'_>> {
+	let mut parts = token.split('/');
+
+	let position_idx = handle_obj_index(parts.next().unwrap_or(token), "vertex", counts.positions)?;
+
+	let tex_coord_idx = match parts.next() {
+		Some(part) if !part.is_empty() => Some(handle_obj_index(part, "texture", counts.tex_coords)?),
+		_ => None,
+	};
+
+	let normal_idx = parts.next()
+		.map(|part| handle_obj_index(part, "normal", counts.normals))
+		.transpose()?;
+
+	Ok(FaceVertex { position_idx, tex_coord_idx, normal_idx })
+}
+
+/// Converts a 1-based OBJ index to a 0-based one.
+///
+/// Negative indices are relative to `count`, the number of elements of that
+/// kind declared before the face: `-1` is the last one.
+fn handle_obj_index<'a>(token: &'a str, what: &str, count: usize) -> Result<usize, TokenError<'a>> {
+	let idx = token.parse::<isize>()
+		.map_err(|_| TokenError::syntax(token, format!("Invalid {} index: '{}'", what, token)))?;
+
+	if idx > 0 {
+		Ok((idx - 1) as usize)
+	} else if idx < 0 {
+		let back = idx.unsigned_abs();
+		if back > count {
+			return Err(TokenError::out_of_range(token, idx, count));
+		}
+		Ok(count - back)
+	} else {
+		Err(TokenError::syntax(token, "Index cannot be 0".to_string()))
+	}
+}
+
+/// Splits a polygon into triangles by ear clipping.
+///
+/// Falls back to a fan from vertex 0 when ear clipping fails or when the face
+/// references positions not declared yet; the flag reports that case.
+fn triangulate_face(vertices: &[FaceVertex], positions: &[[f32; 3]]) -> (Vec<Face>, bool) {
+	if vertices.len() == 3 {
+		return (vec![Face { vertices: vertices.to_vec(), material_idx: None, smoothing_group: 0, line: 0 }], false);
+	}
+
+	let points: Option<Vec<[f32; 3]>> = vertices.iter()
+		.map(|v| positions.get(v.position_idx).copied())
+		.collect();
+
+	if let Some(triangles) = points.as_deref().and_then(triangulate_polygon) {
+		let faces = triangles.iter()
+			.map(|t| Face {
+				vertices: t.iter().map(|&i| vertices[i].clone()).collect(),
+				material_idx: None,
+				smoothing_group: 0,
+				line: 0,
+			})
+			.collect();
+		return (faces, false);
+	}
+
+	let mut triangles = Vec::new();
+
+	for i in 1..(vertices.len() - 1) {
+		triangles.push(Face {
+			vertices: vec![
+				vertices[0].clone(),
+				vertices[i].clone(),
+				vertices[i + 1].clone(),
+			],
+			material_idx: None,
+			smoothing_group: 0,
+			line: 0,
+		});
+	}
+
+	(triangles, true)
+}
+
+pub fn obj_to_mesh(obj: ObjData) -> Mesh {
+	obj_to_mesh_with_options(obj, &MeshOptions::default())
+}
+
+pub fn obj_to_mesh_with_options(obj: ObjData, options: &MeshOptions) -> Mesh {
+	let mut mesh = build_mesh(&obj, 0..obj.faces.len(), options);
+
+	mesh.parts = obj.parts()
+		.into_iter()
+		.map(|(name, faces)| MeshPart {
+			name,
+			first_index: (faces.start * 3) as u32,
+			index_count: (faces.len() * 3) as u32,
+			visible: true,
+		})
+		.collect();
+
+	append_vertex_lists(&obj, &mut mesh);
+	append_free_forms(&obj, &mut mesh, options);
+
+	mesh
+}
+
+/// Builds one standalone `Mesh` per part returned by [`ObjData::parts`].
+pub fn obj_to_sub_meshes(obj: &ObjData, options: &MeshOptions) -> Vec<(String, Mesh)> {
+	obj.parts()
+		.into_iter()
+		.map(|(name, faces)| (name, build_mesh(obj, faces, options)))
+		.collect()
+}
+
+fn build_mesh(obj: &ObjData, faces: Range<usize>, options: &MeshOptions) -> Mesh {
+	let mut vertices = Vec::new();
+	let mut indices = Vec::new();
+	let mut face_materials = Vec::with_capacity(faces.len());
+	let mut vertex_cache: HashMap<VertexKey, u32> = HashMap::new();
+	let mut polygons: Vec<u32> = Vec::new();
+	// Smoothing group and mesh vertices of the first face on each edge, by
+	// position, and whether a face of another group shares it
+	let mut edges: HashMap<(usize, usize), (u32, [u32; 2], bool)> = HashMap::new();
+
+	let bbox = BoudingBox::from_positions(&obj.positions);
+	let smooth_normals = generate_corner_normals(obj, faces.clone(), options);
+
+	for (face_idx, face) in obj.faces.iter().enumerate().skip(faces.start).take(faces.len()) {
+		let face_normal = compute_face_normal(obj, face);
+		let corner_normals = &smooth_normals[(face_idx - faces.start) * 3..];
+
+		for (corner, face_vertex) in face.vertices.iter().enumerate() {
+			let position = obj.positions[face_vertex.position_idx];
+
+			let tex_coords = match face_vertex.tex_coord_idx {
+				Some(i) => obj.tex_coords[i],
+				None => generate_planar_uv(position, face_normal, &bbox),
+			};
+
+			let normal = face_vertex.normal_idx
+				.map(|i| obj.normals[i])
+				.unwrap_or(corner_normals[corner]);
+
+			let vertex_color = obj.colors.get(face_vertex.position_idx).copied().flatten();
+			let color = match (vertex_color, face.material_idx) {
+				(Some(color), _) => color,
+				(None, Some(i)) => obj.materials[i].diffuse,
+				(None, None) => generate_face_color(face_idx),
+			};
+
+			let vertex = Vertex {
+				position,
+				tex_coords,
+				normal,
+				color,
+				tangent: [0.0; 4],
+			};
+
+			let key = VertexKey::from_vertex(&vertex, face.material_idx);
+
+			let index = *vertex_cache.entry(key).or_insert_with(|| {
+				let idx = vertices.len() as u32;
+				vertices.push(vertex);
+				idx
+			});
+
+			indices.push(index);
+		}
+
+		// Triangles of one polygon come from the same line
+		match polygons.last_mut() {
+			Some(count) if face_idx > faces.start && obj.faces[face_idx - 1].line == face.line => *count += 1,
+			_ => polygons.push(1),
+		}
+
+		if obj.has_smoothing_groups {
+			let corners = &indices[indices.len() - 3..];
+			for k in 0..3 {
+				let (a, b) = (face.vertices[k].position_idx, face.vertices[(k + 1) % 3].position_idx);
+				let edge = edges.entry((a.min(b), a.max(b)))
+					.or_insert((face.smoothing_group, [corners[k], corners[(k + 1) % 3]], false));
+				if edge.0 != face.smoothing_group && edge.0 != 0 && face.smoothing_group != 0 {
+					edge.2 = true;
+				}
+			}
+		}
+
+		face_materials.push(face.material_idx);
+	}
+
+	// Edges between two smoothing groups are meant to stay hard
+	let mut creases: Vec<Crease> = edges.into_values()
+		.filter(|&(_, _, crease)| crease)
+		.map(|(_, edge, _)| Crease { edge, sharpness: f32::INFINITY })
+		.collect();
+	creases.sort_by_key(|crease| crease.edge);
+
+	Mesh {
+		vertices,
+		indices,
+		materials: obj.materials.clone(),
+		face_materials,
+		parts: Vec::new(),
+		line_indices: Vec::new(),
+		point_indices: Vec::new(),
+		attributes: Vec::new(),
+		polygons,
+		creases,
+	}
+}
+
+/// Color of line and point vertices without a vertex color.
+const WIRE_COLOR: [f32; 3] = [0.9, 0.9, 0.9];
+
+/// Adds the `l` and `p` elements of `obj` to `mesh`.
+///
+/// Their vertices get their own copies with a zero normal, which the shaders
+/// draw unlit.
+fn append_vertex_lists(obj: &ObjData, mesh: &mut Mesh) {
+	let mut vertex_cache: HashMap<usize, u32> = HashMap::new();
+	let mut vertex_index = |position_idx: usize| *vertex_cache.entry(position_idx).or_insert_with(|| {
+		mesh.vertices.push(Vertex {
+			position: obj.positions[position_idx],
+			tex_coords: [0.0, 0.0],
+			normal: [0.0, 0.0, 0.0],
+			color: obj.colors.get(position_idx).copied().flatten().unwrap_or(WIRE_COLOR),
+			tangent: [0.0; 4],
+		});
+		(mesh.vertices.len() - 1) as u32
+	});
+
+	let mut line_indices = Vec::new();
+	for polyline in &obj.polylines {
+		for segment in polyline.vertices.windows(2) {
+			line_indices.push(vertex_index(segment[0]));
+			line_indices.push(vertex_index(segment[1]));
+		}
+	}
+
+	let point_indices: Vec<u32> = obj.points.iter()
+		.flat_map(|points| points.vertices.iter())
+		.map(|&p| vertex_index(p))
+		.collect();
+
+	mesh.line_indices = line_indices;
+	mesh.point_indices = point_indices;
+}
+
+/// Tessellates the `curv` and `surf` elements of `obj` into `mesh`.
+///
+/// Curves become line strips. Each surface is added as its own part, named
+/// after the object or group it was declared in.
+fn append_free_forms(obj: &ObjData, mesh: &mut Mesh, options: &MeshOptions) {
+	let segments = options.free_form_segments.max(1);
+
+	for (i, free_form) in obj.free_forms.iter().enumerate() {
+		let element = &free_form.element;
+		let material_color = free_form.material_idx.map(|m| obj.materials[m].diffuse);
+		let first_vertex = mesh.vertices.len() as u32;
+
+		if !element.surface {
+			for position in element.tessellate_curve(&obj.positions, &obj.weights, segments) {
+				mesh.vertices.push(Vertex {
+					position,
+					tex_coords: [0.0, 0.0],
+					normal: [0.0, 0.0, 0.0],
+					color: material_color.unwrap_or(WIRE_COLOR),
+					tangent: [0.0; 4],
+				});
+			}
+			for k in first_vertex..mesh.vertices.len() as u32 - 1 {
+				mesh.line_indices.extend([k, k + 1]);
+			}
+			continue;
+		}
+
+		let grid = element.tessellate_surface(&obj.positions, &obj.weights, segments);
+		let color = material_color.unwrap_or_else(|| generate_face_color(i));
+
+		for ((&position, &normal), &tex_coords) in grid.positions.iter().zip(&grid.normals).zip(&grid.tex_coords) {
+			mesh.vertices.push(Vertex { position, tex_coords, normal, color, tangent: [0.0; 4] });
+		}
+
+		let first_index = mesh.indices.len() as u32;
+		let columns = grid.columns as u32;
+		for row in 0..grid.rows as u32 - 1 {
+			for column in 0..columns - 1 {
+				let a = first_vertex + row * columns + column;
+				let c = a + columns;
+				mesh.indices.extend([a, a + 1, c + 1, a, c + 1, c]);
+				mesh.face_materials.extend([free_form.material_idx; 2]);
+				mesh.polygons.push(2);
+			}
+		}
+
+		mesh.parts.push(MeshPart {
+			name: free_form.part.clone(),
+			first_index,
+			index_count: mesh.indices.len() as u32 - first_index,
+			visible: true,
+		});
+	}
+}
+
+/// Grey shade for faces with neither vertex colors nor a material.
+pub(crate) fn generate_face_color(face_index: usize) -> [f32; 3] {
+    let shades = [
+        [0.3, 0.3, 0.3],
+        [0.4, 0.4, 0.4],
+        [0.5, 0.5, 0.5],
+        [0.6, 0.6, 0.6],
+        [0.7, 0.7, 0.7],
+        [0.8, 0.8, 0.8],
+    ];
+
+	shades[face_index % shades.len()]
+}
+
+/// Generates one normal per face corner for faces in `faces`.
+///
+/// Corners are averaged with the neighbouring faces around the same position,
+/// weighted by face area and corner angle. Neighbours must share the smoothing
+/// group, or, when the file declares none, lie within `options.crease_angle`.
+/// Faces with smoothing off keep their flat normal.
+fn generate_corner_normals(obj: &ObjData, faces: Range<usize>, options: &MeshOptions) -> Vec<[f32; 3]> {
+	let face_list = &obj.faces[faces];
+
+	let mut face_normals = Vec::with_capacity(face_list.len());
+	let mut weights = Vec::with_capacity(face_list.len() * 3);
+	let mut adjacency: HashMap<usize, Vec<(usize, usize)>> = HashMap::new();
+
+	for (f, face) in face_list.iter().enumerate() {
+		let cross = face_cross(obj, face);
+		let double_area = length(cross);
+		face_normals.push(compute_face_normal(obj, face));
+
+		let n = face.vertices.len();
+		for k in 0..n {
+			let p = obj.positions[face.vertices[k].position_idx];
+			let next = obj.positions[face.vertices[(k + 1) % n].position_idx];
+			let prev = obj.positions[face.vertices[(k + n - 1) % n].position_idx];
+
+			weights.push(double_area * 0.5 * corner_angle(p, next, prev));
+			adjacency.entry(face.vertices[k].position_idx).or_default().push((f, k));
+		}
+	}
+
+	let cos_crease = options.crease_angle.cos();
+	let mut normals = Vec::with_capacity(face_list.len() * 3);
+
+	for (f, face) in face_list.iter().enumerate() {
+		let flat = face_normals[f];
+
+		for face_vertex in &face.vertices {
+			if obj.has_smoothing_groups && face.smoothing_group == 0 {
+				normals.push(flat);
+				continue;
+			}
+
+			let mut sum = [0.0_f32; 3];
+			for &(g, j) in &adjacency[&face_vertex.position_idx] {
+				let other = face_normals[g];
+				let smooth = if obj.has_smoothing_groups {
+					face_list[g].smoothing_group == face.smoothing_group
+				} else {
+					dot(flat, other) >= cos_crease
+				};
+
+				if smooth {
+					let w = weights[g * 3 + j];
+					sum = [sum[0] + other[0] * w, sum[1] + other[1] * w, sum[2] + other[2] * w];
+				}
+			}
+
+			let len = length(sum);
+			normals.push(if len > 1e-12 { [sum[0] / len, sum[1] / len, sum[2] / len] } else { flat });
+		}
+	}
+
+	normals
+}
+
+fn corner_angle(p: [f32; 3], a: [f32; 3], b: [f32; 3]) -> f32 {
+	let e1 = [a[0] - p[0], a[1] - p[1], a[2] - p[2]];
+	let e2 = [b[0] - p[0], b[1] - p[1], b[2] - p[2]];
+	let denom = length(e1) * length(e2);
+
+	if denom > 0.0 {
+		(dot(e1, e2) / denom).clamp(-1.0, 1.0).acos()
+	} else {
+		0.0
+	}
+}
+
+fn dot(a: [f32; 3], b: [f32; 3]) -> f32 {
+	a[0] * b[0] + a[1] * b[1] + a[2] * b[2]
+}
+
+fn length(a: [f32; 3]) -> f32 {
+	dot(a, a).sqrt()
+}
+
+/// Unnormalized face normal, its length is twice the triangle area.
+fn face_cross(obj: &ObjData, face: &Face) -> [f32; 3] {
+	let v0 = obj.positions[face.vertices[0].position_idx];
+	let v1 = obj.positions[face.vertices[1].position_idx];
+	let v2 = obj.positions[face.vertices[2].position_idx];
+
+	let edge1 = [v1[0] - v0[0], v1[1] - v0[1], v1[2] - v0[2]];
+	let edge2 = [v2[0] - v0[0], v2[1] - v0[1], v2[2] - v0[2]];
+
+	[
+		edge1[1] * edge2[2] - edge1[2] * edge2[1],
+		edge1[2] * edge2[0] - edge1[0] * edge2[2],
+		edge1[0] * edge2[1] - edge1[1] * edge2[0],
+	]
+}
+
+fn compute_face_normal(obj: &ObjData, face: &Face) -> [f32; 3] {
+	if face.vertices.len() < 3 {
+		return [0.0, 1.0, 0.0];
+	}
+
+	let normal = face_cross(obj, face);
+
+	let len = (normal[0]*normal[0] + normal[1]*normal[1] + normal[2]*normal[2]).sqrt();
+
+	if len > 0.0001 {
+		[normal[0]/len, normal[1]/len, normal[2]/len]
+	} else {
+		[0.0, 1.0, 0.0]
+	}
+}
+
+
+pub(crate) fn generate_planar_uv(position: [f32; 3], face_normal: [f32; 3], bbox: &BoudingBox) -> [f32; 2] {
+	let ax = face_normal[0].abs();
+	let ay = face_normal[1].abs();
+	let az = face_normal[2].abs();
+
+	let (u_axis, v_axis) = if ax >= ay && ax >= az {
+		(1, 2)
+	} else if ay >= ax && ay >= az {
+		(0, 2)
+	} else {
+		(0, 1)
+	};
+
+	let u = bbox.normalize(position[u_axis], u_axis);
+	let v = bbox.normalize(position[v_axis], v_axis);
+
+	[u, v]
 }
 
 #[cfg(test)]
@@ -403,4 +1956,634 @@ f 1 2 3 4
         let obj = parse_obj_from_string(content).unwrap();
         assert_eq!(obj.faces.len(), 2);  // Triangulé
     }
+
+    #[test]
+    fn objects_and_groups_record_face_ranges() {
+        let content = "
+v 0.0 0.0 0.0
+v 1.0 0.0 0.0
+v 0.0 1.0 0.0
+v 1.0 1.0 0.0
+f 1 2 3
+o Body
+f 1 2 3
+g Left
+f 1 2 4 3
+g Right
+f 2 4 3
+o Wheel
+g
+f 1 3 4
+";
+        let obj = parse_obj_from_string(content).unwrap();
+
+        let names: Vec<_> = obj.objects().iter().map(|o| o.name.as_str()).collect();
+        assert_eq!(names, vec!["default", "Body", "Wheel"]);
+        assert_eq!(obj.objects()[1].faces, 1..5);
+        assert_eq!(obj.objects()[1].groups.len(), 2);
+        assert_eq!(obj.objects()[1].groups[0].faces, 2..4);
+
+        let parts = obj.parts();
+        let part_names: Vec<_> = parts.iter().map(|(n, _)| n.as_str()).collect();
+        assert_eq!(part_names, vec!["default", "Body", "Body/Left", "Body/Right", "Wheel/default"]);
+
+        let sub_meshes = obj_to_sub_meshes(&obj, &MeshOptions::default());
+        assert_eq!(sub_meshes.len(), 5);
+        assert_eq!(sub_meshes[2].1.indices.len(), 6);
+
+        let mesh = obj_to_mesh(obj);
+        assert_eq!(mesh.parts[2].first_index, 6);
+        assert_eq!(mesh.parts[2].index_count, 6);
+    }
+
+    // Two triangles folded 90 degrees along the edge (1, 2)
+    const FOLD: &str = "
+v 0.0 0.0 0.0
+v 1.0 0.0 0.0
+v 0.0 1.0 0.0
+v 0.0 0.0 -1.0
+";
+
+    fn normal_at_origin(mesh: &Mesh) -> Vec<[f32; 3]> {
+        mesh.vertices.iter()
+            .filter(|v| v.position == [0.0, 0.0, 0.0])
+            .map(|v| v.normal)
+            .collect()
+    }
+
+    fn distinct_normals_at_origin(mesh: &Mesh) -> usize {
+        let mut normals = normal_at_origin(mesh);
+        normals.dedup();
+        normals.len()
+    }
+
+    #[test]
+    fn shared_smoothing_group_averages_normals() {
+        let content = format!("{}s 1\nf 1 2 3\nf 1 4 2\n", FOLD);
+        let mesh = obj_to_mesh(parse_obj_from_string(&content).unwrap());
+
+        // Generated UVs still split the corner, the normals must agree
+        let expected = std::f32::consts::FRAC_1_SQRT_2;
+        for n in normal_at_origin(&mesh) {
+            assert!(n[0].abs() < 1e-5 && (n[1] + expected).abs() < 1e-5 && (n[2] - expected).abs() < 1e-5);
+        }
+    }
+
+    #[test]
+    fn different_smoothing_groups_keep_hard_edge() {
+        let content = format!("{}s 1\nf 1 2 3\ns 2\nf 1 4 2\n", FOLD);
+        let mesh = obj_to_mesh(parse_obj_from_string(&content).unwrap());
+        assert_eq!(distinct_normals_at_origin(&mesh), 2);
+
+        let content = format!("{}s off\nf 1 2 3\nf 1 4 2\n", FOLD);
+        let mesh = obj_to_mesh(parse_obj_from_string(&content).unwrap());
+        assert_eq!(distinct_normals_at_origin(&mesh), 2);
+    }
+
+    #[test]
+    fn crease_angle_applies_without_smoothing_groups() {
+        let content = format!("{}f 1 2 3\nf 1 4 2\n", FOLD);
+
+        let mesh = obj_to_mesh(parse_obj_from_string(&content).unwrap());
+        assert_eq!(distinct_normals_at_origin(&mesh), 2);
+
+        let options = MeshOptions { crease_angle: 100.0_f32.to_radians(), ..Default::default() };
+        let mesh = obj_to_mesh_with_options(parse_obj_from_string(&content).unwrap(), &options);
+        assert_eq!(distinct_normals_at_origin(&mesh), 1);
+    }
+
+    #[test]
+    fn concave_face_is_ear_clipped() {
+        let content = "
+v 2.0 0.0 0.0
+v 1.0 0.5 0.0
+v 2.0 1.0 0.0
+v 0.0 0.5 0.0
+f 1 2 3 4
+";
+        let obj = parse_obj_from_string(content).unwrap();
+        assert_eq!(obj.faces.len(), 2);
+        assert_eq!(obj.fallback_faces(), 0);
+
+        // The fan diagonal 1-3 lies outside the polygon, it must not be used
+        for face in &obj.faces {
+            let ids: Vec<_> = face.vertices.iter().map(|v| v.position_idx).collect();
+            assert!(!(ids.contains(&0) && ids.contains(&2)));
+        }
+    }
+
+    #[test]
+    fn degenerate_face_uses_fan_fallback() {
+        let content = "
+v 0.0 0.0 0.0
+v 1.0 0.0 0.0
+v 2.0 0.0 0.0
+v 3.0 0.0 0.0
+f 1 2 3 4
+";
+        let obj = parse_obj_from_string(content).unwrap();
+        assert_eq!(obj.faces.len(), 2);
+        assert_eq!(obj.fallback_faces(), 1);
+    }
+
+    #[test]
+    fn negative_indices_are_relative() {
+        let content = "
+v 0.0 0.0 0.0
+v 1.0 0.0 0.0
+v 0.0 1.0 0.0
+vt 0.0 0.0
+vt 1.0 0.0
+vt 0.0 1.0
+vn 0.0 0.0 1.0
+f -3/-3/-1 -2/-2/-1 -1/-1/-1
+v 1.0 1.0 0.0
+vn 0.0 0.0 -1.0
+f 2/2/1 -1/3/-1 -2//-2
+";
+        let obj = parse_obj_from_string(content).unwrap();
+        assert_eq!(obj.faces.len(), 2);
+
+        let first: Vec<_> = obj.faces[0].vertices.iter()
+            .map(|v| (v.position_idx, v.tex_coord_idx, v.normal_idx))
+            .collect();
+        assert_eq!(first, vec![(0, Some(0), Some(0)), (1, Some(1), Some(0)), (2, Some(2), Some(0))]);
+
+        let second: Vec<_> = obj.faces[1].vertices.iter()
+            .map(|v| (v.position_idx, v.tex_coord_idx, v.normal_idx))
+            .collect();
+        assert_eq!(second, vec![(1, Some(1), Some(0)), (3, Some(2), Some(1)), (2, None, Some(0))]);
+    }
+
+    #[test]
+    fn negative_index_before_declaration_fails() {
+        let content = "
+v 0.0 0.0 0.0
+v 1.0 0.0 0.0
+f 1 2 -3
+";
+        assert!(parse_obj_from_string(content).is_err());
+    }
+
+    #[test]
+    fn syntax_error_reports_line_and_column() {
+        let content = "v 0.0 0.0 0.0\nv 1.0 oops 0.0\n";
+        let err = parse_obj_from_string(content).unwrap_err();
+
+        match &err {
+            ObjError::Syntax { location, .. } => {
+                assert_eq!(location.line, 2);
+                assert_eq!(location.column, 7);
+                assert_eq!(location.text, "v 1.0 oops 0.0");
+            }
+            other => panic!("unexpected error: {:?}", other),
+        }
+        assert!(err.to_string().contains("2 | v 1.0 oops 0.0"));
+    }
+
+    #[test]
+    fn relative_index_out_of_range_is_typed() {
+        let err = parse_obj_from_string("v 0 0 0\nf 1 1/-4 1\n").unwrap_err();
+        assert!(matches!(err, ObjError::IndexOutOfRange { index: -4, count: 0, .. }));
+        assert_eq!(err.location().unwrap().column, 7);
+    }
+
+    #[test]
+    fn call_is_unsupported() {
+        let err = parse_obj_from_string("call other.obj\n").unwrap_err();
+        assert!(matches!(err, ObjError::Unsupported { .. }));
+    }
+
+    #[test]
+    fn unknown_directives_become_diagnostics() {
+        let obj = parse_obj_from_string("v 0 0 0\n  foo bar\nlod 1\n").unwrap();
+        let diagnostics = obj.diagnostics();
+
+        assert_eq!(diagnostics.len(), 2);
+        assert_eq!(diagnostics[0].location.as_ref().unwrap().line, 2);
+        assert_eq!(diagnostics[0].location.as_ref().unwrap().column, 3);
+        assert!(diagnostics[1].message.contains("Unsupported"));
+    }
+
+    #[test]
+    fn missing_material_library_is_a_diagnostic() {
+        let dir = std::env::temp_dir().join(format!("scop_missing_mtl_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("model.obj"), "mtllib nowhere.mtl\nv 0 0 0\nv 1 0 0\nv 0 1 0\nf 1 2 3\n").unwrap();
+
+        let obj = parse_obj(dir.join("model.obj").to_str().unwrap()).unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(obj.diagnostics().len(), 1);
+        assert!(obj.diagnostics()[0].file.as_ref().unwrap().ends_with("nowhere.mtl"));
+    }
+
+    #[test]
+    fn missing_vertex_is_an_error() {
+        let content = "
+v 0.0 0.0 0.0
+v 1.0 0.0 0.0
+v 0.0 1.0 0.0
+f 1 2 3
+f 1 2 7
+";
+        let err = parse_obj_from_string(content).unwrap_err();
+        match err {
+            ObjError::IndexOutOfRange { location, index, count } => {
+                assert_eq!((location.line, location.column), (6, 7));
+                assert_eq!((index, count), (7, 3));
+            }
+            other => panic!("unexpected error: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn missing_tex_coord_and_normal_are_errors() {
+        let content = "v 0 0 0\nv 1 0 0\nv 0 1 0\nvt 0 0\nf 1/1 2/2 3/1\n";
+        let err = parse_obj_from_string(content).unwrap_err();
+        assert!(matches!(err, ObjError::IndexOutOfRange { index: 2, count: 1, .. }));
+        assert_eq!(err.location().unwrap().column, 9);
+
+        let content = "v 0 0 0\nv 1 0 0\nv 0 1 0\nf 1//1 2//1 3//1\n";
+        let err = parse_obj_from_string(content).unwrap_err();
+        assert!(matches!(err, ObjError::IndexOutOfRange { index: 1, count: 0, .. }));
+    }
+
+    #[test]
+    fn forward_reference_is_accepted() {
+        let content = "f 1 2 3\nv 0 0 0\nv 1 0 0\nv 0 1 0\n";
+        let obj = parse_obj_from_string(content).unwrap();
+        assert_eq!(obj.faces.len(), 1);
+    }
+
+    #[test]
+    fn lenient_mode_drops_bad_faces() {
+        let content = "
+v 0.0 0.0 0.0
+v 1.0 0.0 0.0
+v 0.0 1.0 0.0
+v 1.0 1.0 0.0
+o Good
+f 1 2 3
+o Broken
+f 1 2 9 4
+f 2 4 3
+";
+        let options = ParseOptions { lenient: true };
+        let obj = parse_obj_from_string_with_options(content, &options).unwrap();
+
+        assert_eq!(obj.faces.len(), 2);
+        assert_eq!(obj.diagnostics().len(), 1);
+        assert_eq!(obj.diagnostics()[0].location.as_ref().unwrap().line, 9);
+        assert_eq!(obj.objects()[1].faces, 1..2);
+
+        let mesh = obj_to_mesh(obj);
+        assert_eq!(mesh.indices.len(), 6);
+        assert_eq!(mesh.parts[1].first_index, 3);
+    }
+
+    #[test]
+    fn lines_and_points_become_separate_index_lists() {
+        let content = "
+v 0 0 0
+v 1 0 0 0 0 1
+v 1 1 0
+v 0 1 0
+f 1 2 3
+l 1 2 3 -1
+p 4 -3
+";
+        let obj = parse_obj_from_string(content).unwrap();
+        assert_eq!(obj.polylines.len(), 1);
+        assert_eq!(obj.polylines[0].vertices, vec![0, 1, 2, 3]);
+        assert_eq!(obj.points[0].vertices, vec![3, 1]);
+
+        let mesh = obj_to_mesh(obj);
+        assert_eq!(mesh.indices.len(), 3);
+        assert_eq!(mesh.line_indices.len(), 6);
+        assert_eq!(mesh.point_indices.len(), 2);
+        assert_eq!(mesh.vertices.len(), 3 + 4);
+
+        let line_vertex = mesh.vertices[mesh.line_indices[1] as usize];
+        assert_eq!(line_vertex.position, [1.0, 0.0, 0.0]);
+        assert_eq!(line_vertex.color, [0.0, 0.0, 1.0]);
+        assert_eq!(line_vertex.normal, [0.0, 0.0, 0.0]);
+        assert_eq!(mesh.line_indices[1], mesh.line_indices[2]);
+        assert_eq!(mesh.point_indices[1], mesh.line_indices[1]);
+    }
+
+    #[test]
+    fn out_of_range_line_is_an_error_or_dropped() {
+        let content = "v 0 0 0\nv 1 0 0\nl 1 2\nl 1 7\np 3\n";
+        let err = parse_obj_from_string(content).unwrap_err();
+        assert!(matches!(err, ObjError::IndexOutOfRange { index: 7, count: 2, .. }), "{}", err);
+        assert!(matches!(parse_obj_from_string("v 0 0 0\nl 1\n"), Err(ObjError::Syntax { .. })));
+
+        let obj = parse_obj_from_string_with_options(content, &ParseOptions { lenient: true }).unwrap();
+        assert_eq!(obj.polylines.len(), 1);
+        assert!(obj.points.is_empty());
+        assert_eq!(obj.diagnostics().len(), 2);
+    }
+
+    const FREE_FORM_MODEL: &str = "
+v 0 0 0
+v 1 0 1
+v 2 0 0
+v 0 1 1
+v 1 1 2
+v 2 1 1
+v 0 2 0
+v 1 2 1
+v 2 2 0
+o Patch
+cstype bezier
+deg 2 2
+surf 0 1 0 1 1 2 3 4 5 6 7 8 9
+parm u 0 1
+parm v 0 1
+end
+cstype bspline
+deg 1
+curv 0 2 1 2 3
+parm u 0 0 1 2 2
+end
+";
+
+    #[test]
+    fn free_forms_are_tessellated() {
+        let obj = parse_obj_from_string(FREE_FORM_MODEL).unwrap();
+        assert!(obj.diagnostics().is_empty(), "{:?}", obj.diagnostics());
+        assert_eq!(obj.free_forms.len(), 2);
+        assert_eq!(obj.free_forms[0].part, "Patch");
+
+        let options = MeshOptions { free_form_segments: 4, ..Default::default() };
+        let mesh = obj_to_mesh_with_options(obj, &options);
+
+        assert_eq!(mesh.vertices.len(), 25 + 5);
+        assert_eq!(mesh.indices.len(), 4 * 4 * 6);
+        assert_eq!(mesh.face_materials.len(), 4 * 4 * 2);
+        assert_eq!(mesh.line_indices.len(), 4 * 2);
+        assert_eq!(mesh.parts.len(), 1);
+        assert_eq!(mesh.parts[0].index_count, mesh.indices.len() as u32);
+
+        // Corners of a Bezier patch interpolate the corner control points
+        assert_eq!(mesh.vertices[0].position, [0.0, 0.0, 0.0]);
+        assert_eq!(mesh.vertices[24].position, [2.0, 2.0, 0.0]);
+        assert_eq!(mesh.vertices[24].tex_coords, [1.0, 1.0]);
+        assert!(mesh.vertices[12].normal[2] > 0.99);
+        assert_eq!(mesh.vertices[27].position, [1.0, 0.0, 1.0]);
+        assert_eq!(mesh.vertices[29].position, [2.0, 0.0, 0.0]);
+    }
+
+    #[test]
+    fn bad_free_forms_become_diagnostics() {
+        let content = "
+v 0 0 0
+v 1 0 0
+curv 0 1 1 2
+cstype taylor
+curv 0 1 1 2
+end
+cstype bezier
+deg 3
+curv 0 1 1 2
+parm u 0 1
+end
+deg 1
+curv 0 1 1 2
+";
+        let obj = parse_obj_from_string(content).unwrap();
+        let messages: Vec<_> = obj.diagnostics().iter()
+            .map(|d| (d.location.as_ref().unwrap().line, d.message.as_str()))
+            .collect();
+
+        assert_eq!(messages.len(), 4, "{:?}", messages);
+        assert_eq!(messages[0], (4, "Free-form element ignored: no 'cstype' declared"));
+        assert_eq!(messages[1].0, 5);
+        assert_eq!(messages[2], (10, "Free-form element ignored: expected 4 control points, found 2"));
+        assert_eq!(messages[3], (14, "Free-form element ignored: missing 'end'"));
+        assert!(obj.free_forms.is_empty());
+
+        assert!(parse_obj_from_string("cstype nurbs\n").is_err());
+        assert!(parse_obj_from_string("parm w 0 1\n").is_err());
+    }
+
+    #[test]
+    fn free_form_control_points_are_validated() {
+        let content = "v 0 0 0\nv 1 0 0\ncstype bezier\ndeg 1\ncurv 0 1 1 5\nparm u 0 1\nend\n";
+        let err = parse_obj_from_string(content).unwrap_err();
+        assert!(matches!(err, ObjError::IndexOutOfRange { index: 5, count: 2, .. }), "{}", err);
+        assert_eq!(err.location().unwrap().column, 12);
+
+        let obj = parse_obj_from_string_with_options(content, &ParseOptions { lenient: true }).unwrap();
+        assert!(obj.free_forms.is_empty());
+        assert_eq!(obj.diagnostics().len(), 1);
+    }
+
+    #[test]
+    fn reader_reports_progress() {
+        let mut content = "v 0 0 0\n".repeat(PROGRESS_INTERVAL);
+        content.push_str("v 1 0 0\r\nv 0 1 0\r\nf -3 -2 -1\r\n");
+
+        let mut reports = Vec::new();
+        let obj = parse_obj_from_reader(content.as_bytes(), &ParseOptions::default(), |p| reports.push(*p)).unwrap();
+
+        assert_eq!(obj.positions.len(), PROGRESS_INTERVAL + 2);
+        assert_eq!(obj.faces.len(), 1);
+
+        assert_eq!(reports.len(), 2);
+        assert_eq!(reports[0].lines_read, PROGRESS_INTERVAL);
+        let last = reports[1];
+        assert_eq!(last.lines_read, PROGRESS_INTERVAL + 3);
+        assert_eq!(last.bytes_read, content.len() as u64);
+        assert_eq!(last.fraction(), None);
+    }
+
+    /// A model exercising every piece of state carried across chunk boundaries.
+    fn stateful_model() -> String {
+        let mut content = String::from("# generated\nmtllib a.mtl\n");
+        for i in 0..40 {
+            let (x, y) = ((i % 8) as f32 * 0.25, (i / 8) as f32 * 0.5);
+            if (12..30).contains(&i) {
+                content.push_str(&format!("v {} {} {} {} 0.5 1\n", x, y, x * y, i as f32 / 40.0));
+            } else {
+                content.push_str(&format!("v {} {} {}\n", x, y, x * y));
+            }
+            content.push_str(&format!("vt {} {}\nvn 0 0 1\n", x, y));
+            if i % 10 == 0 {
+                content.push_str(&format!("o part{}\ng half{}\nusemtl mat{}\ns {}\n", i, i % 3, i % 4, i % 2));
+            }
+            if i >= 3 {
+                content.push_str("f -3/-3/-3 -2/-2/-2 -1/-1/-1\n");
+                content.push_str(&format!("f {} {} {} {}\n", i - 2, i - 1, i, i + 1));
+            }
+            if i == 20 {
+                content.push_str("bevel on\nf 1 2 50\nf 2 3 4 5 6 7\n");
+            }
+            if i == 30 {
+                content.push_str("l 1 2/2 3 -1\np 5 -2\n");
+            }
+            if i == 35 {
+                content.push_str("v 0 0 0 2\ncstype rat bspline\ndeg 2\ncurv 0 1 1 2 3 -1\nparm u 0 0 0 0.5 1 1 1\nend\n");
+            }
+        }
+        content
+    }
+
+    #[test]
+    fn parallel_parse_matches_sequential() {
+        let content = stateful_model();
+        let options = ParseOptions { lenient: true };
+        let expected = format!("{:?}", parse_obj_from_string_with_options(&content, &options).unwrap());
+
+        for chunk_count in 1..=7 {
+            let chunks = split_chunks(&content, chunk_count);
+            assert_eq!(chunks.concat(), content);
+
+            let obj = parse_chunks(&chunks, &options).unwrap();
+            assert_eq!(format!("{:?}", obj), expected, "{} chunks", chunk_count);
+        }
+
+        let obj = parse_obj_from_string_parallel(&content, &options, 0).unwrap();
+        assert_eq!(format!("{:?}", obj), expected);
+    }
+
+    #[test]
+    fn large_files_are_parsed_in_parallel_blocks() {
+        let mut content = String::new();
+        for i in 0.. {
+            content.push_str(&format!("v {} {} 0\nv {} {} 1\nv {} {} 2\nf -3 -2 -1\n", i, i, i, i, i, i));
+            if content.len() as u64 > PARALLEL_MIN_BYTES {
+                break;
+            }
+        }
+        let expected = format!("{:?}", parse_obj_from_string(&content).unwrap());
+
+        let dir = std::env::temp_dir().join(format!("scop_large_obj_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("large.obj"), &content).unwrap();
+        let obj = parse_obj_with_progress(dir.join("large.obj").to_str().unwrap(), &ParseOptions::default(), |_| {});
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(format!("{:?}", obj.unwrap()), expected);
+
+        // Small blocks split the input, each reported once parsed
+        let mut reports = Vec::new();
+        let total_bytes = Some(content.len() as u64);
+        let obj = read_obj_parallel(content.as_bytes(), total_bytes, &ParseOptions::default(), 1 << 18, &mut |progress| {
+            reports.push(*progress);
+        });
+        assert_eq!(format!("{:?}", obj.unwrap()), expected);
+        // Blocks run on to the end of their last line
+        assert!(reports.len() > 1 && reports.len() <= content.len().div_ceil(1 << 18), "{}", reports.len());
+        assert!(reports.windows(2).all(|w| w[0].bytes_read < w[1].bytes_read && w[0].lines_read < w[1].lines_read));
+        let last = reports.last().unwrap();
+        assert_eq!(last.fraction(), Some(1.0));
+        assert_eq!(last.lines_read, content.lines().count());
+    }
+
+    #[test]
+    fn parallel_parse_reports_first_error() {
+        let base = stateful_model();
+        let bad_face = base.replacen("f -3/-3/-3", "f 1/x/1", 1);
+        let bad_vertex = base.replacen("v 0.75 1", "v 0.75 oops", 1);
+        let both = bad_vertex.replacen("mtllib a.mtl", "f 1", 1);
+        let out_of_range = base.replacen("f 1 2 50", "f 1 2 -90", 1);
+
+        for content in [&bad_face, &bad_vertex, &both, &out_of_range] {
+            let expected = parse_obj_from_string(content).unwrap_err().to_string();
+
+            for chunk_count in 1..=7 {
+                let err = parse_chunks(&split_chunks(content, chunk_count), &ParseOptions::default()).unwrap_err();
+                assert_eq!(err.to_string(), expected, "{} chunks", chunk_count);
+            }
+        }
+    }
+
+    #[test]
+    fn face_with_many_vertices_is_tokenized() {
+        let count = INLINE_TOKENS * 2;
+        let mut content = String::new();
+        for i in 0..count {
+            let angle = i as f32 / count as f32 * std::f32::consts::TAU;
+            content.push_str(&format!("v {} {} 0\n", angle.cos(), angle.sin()));
+        }
+        content.push('f');
+        for i in 1..=count {
+            content.push_str(&format!(" {}", i));
+        }
+
+        let obj = parse_obj_from_string(&content).unwrap();
+        assert_eq!(obj.faces.len(), count - 2);
+    }
+
+    #[test]
+    fn vertex_colors_override_palette_and_materials() {
+        let content = "
+v 0 0 0
+v 1 0 0 1.0 0.0 0.0
+v 0 1 0 0 255 0
+v 1 1 0
+usemtl Paint
+f 1 2 3
+f 2 4 3
+";
+        let obj = parse_obj_from_string(content).unwrap();
+        assert_eq!(obj.colors, vec![None, Some([1.0, 0.0, 0.0]), Some([0.0, 1.0, 0.0]), None]);
+
+        let mesh = obj_to_mesh(obj);
+        let color_at = |position: [f32; 3]| mesh.vertices.iter().find(|v| v.position == position).unwrap().color;
+        assert_eq!(color_at([1.0, 0.0, 0.0]), [1.0, 0.0, 0.0]);
+        assert_eq!(color_at([0.0, 1.0, 0.0]), [0.0, 1.0, 0.0]);
+        assert_eq!(color_at([0.0, 0.0, 0.0]), mesh.materials[0].diffuse);
+    }
+
+    #[test]
+    fn uncolored_file_keeps_no_colors() {
+        let obj = parse_obj_from_string("v 0 0 0\nv 1 0 0\nv 0 1 0\nf 1 2 3\n").unwrap();
+        assert!(obj.colors.is_empty());
+        assert_eq!(obj_to_mesh(obj).vertices[0].color, generate_face_color(0));
+    }
+
+    #[test]
+    fn usemtl_assigns_material_ids() {
+        let content = "
+mtllib scene.mtl
+v 0.0 0.0 0.0
+v 1.0 0.0 0.0
+v 0.0 1.0 0.0
+v 1.0 1.0 0.0
+f 1 2 3
+usemtl Red
+f 2 4 3
+usemtl Blue
+f 1 2 4
+usemtl Red
+f 1 3 4
+";
+        let obj = parse_obj_from_string(content).unwrap();
+
+        assert_eq!(obj.material_libs, vec!["scene.mtl".to_string()]);
+        assert_eq!(obj.materials.len(), 2);
+        let ids: Vec<_> = obj.faces.iter().map(|f| f.material_idx).collect();
+        assert_eq!(ids, vec![None, Some(0), Some(1), Some(0)]);
+
+        let mesh = obj_to_mesh(obj);
+        assert_eq!(mesh.face_materials, vec![None, Some(0), Some(1), Some(0)]);
+    }
+
+    #[test]
+    fn mtllib_is_resolved_next_to_obj() {
+        let dir = std::env::temp_dir().join(format!("scop_mtl_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("model.mtl"), "newmtl Green\nKd 0.0 1.0 0.0\nmap_Kd green.png\n").unwrap();
+        std::fs::write(dir.join("model.obj"), "mtllib model.mtl\nv 0 0 0\nv 1 0 0\nv 0 1 0\nusemtl Green\nf 1 2 3\n").unwrap();
+
+        let mesh = load_obj(dir.join("model.obj").to_str().unwrap()).unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(mesh.materials.len(), 1);
+        assert_eq!(mesh.materials[0].diffuse, [0.0, 1.0, 0.0]);
+        assert_eq!(mesh.vertices[0].color, [0.0, 1.0, 0.0]);
+        assert_eq!(mesh.materials[0].diffuse_map, Some(dir.join("green.png").to_string_lossy().into_owned()));
+    }
 }
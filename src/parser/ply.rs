@@ -0,0 +1,747 @@
+use crate::mesh::normals::smooth_normals;
+use crate::mesh::{Mesh, Vertex, VertexAttribute};
+use crate::parser::error::{PlyError, SourceLocation};
+use crate::parser::obj::{BoudingBox, generate_planar_uv};
+use crate::parser::triangulate::triangulate_polygon;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PlyFormat {
+	Ascii,
+	BinaryLittleEndian,
+	BinaryBigEndian,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ScalarType {
+	Int8,
+	UInt8,
+	Int16,
+	UInt16,
+	Int32,
+	UInt32,
+	Float32,
+	Float64,
+}
+
+impl ScalarType {
+	fn from_name(name: &str) -> Option<Self> {
+		match name {
+			"char" | "int8" => Some(ScalarType::Int8),
+			"uchar" | "uint8" => Some(ScalarType::UInt8),
+			"short" | "int16" => Some(ScalarType::Int16),
+			"ushort" | "uint16" => Some(ScalarType::UInt16),
+			"int" | "int32" => Some(ScalarType::Int32),
+			"uint" | "uint32" => Some(ScalarType::UInt32),
+			"float" | "float32" => Some(ScalarType::Float32),
+			"double" | "float64" => Some(ScalarType::Float64),
+			_ => None,
+		}
+	}
+
+	pub fn size(self) -> usize {
+		match self {
+			ScalarType::Int8 | ScalarType::UInt8 => 1,
+			ScalarType::Int16 | ScalarType::UInt16 => 2,
+			ScalarType::Int32 | ScalarType::UInt32 | ScalarType::Float32 => 4,
+			ScalarType::Float64 => 8,
+		}
+	}
+
+	fn is_integer(self) -> bool {
+		!matches!(self, ScalarType::Float32 | ScalarType::Float64)
+	}
+
+	/// Value standing for full intensity in a color channel of this type.
+	fn color_scale(self) -> f32 {
+		match self {
+			ScalarType::Int8 | ScalarType::UInt8 => 255.0,
+			ScalarType::Int16 | ScalarType::UInt16 => 65535.0,
+			ScalarType::Int32 | ScalarType::UInt32 => u32::MAX as f32,
+			ScalarType::Float32 | ScalarType::Float64 => 1.0,
+		}
+	}
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PropertyType {
+	Scalar(ScalarType),
+	List { count: ScalarType, item: ScalarType },
+}
+
+/// Values of one property for every instance of its element.
+#[derive(Debug, Clone, PartialEq)]
+pub enum PropertyValues {
+	Scalar(Vec<f64>),
+	/// Items of all lists end to end: list `i` is `items[offsets[i]..offsets[i + 1]]`.
+	List { offsets: Vec<usize>, items: Vec<f64> },
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct PlyProperty {
+	pub name: String,
+	pub kind: PropertyType,
+	pub values: PropertyValues,
+}
+
+impl PlyProperty {
+	fn new(name: &str, kind: PropertyType) -> Self {
+		let values = match kind {
+			PropertyType::Scalar(_) => PropertyValues::Scalar(Vec::new()),
+			PropertyType::List { .. } => PropertyValues::List { offsets: vec![0], items: Vec::new() },
+		};
+		Self { name: name.to_string(), kind, values }
+	}
+
+	fn scalars(&self) -> Option<(ScalarType, &[f64])> {
+		match (&self.kind, &self.values) {
+			(PropertyType::Scalar(ty), PropertyValues::Scalar(values)) => Some((*ty, values)),
+			_ => None,
+		}
+	}
+
+	fn lists(&self) -> Option<impl Iterator<Item = &[f64]>> {
+		match &self.values {
+			PropertyValues::List { offsets, items } => Some(offsets.windows(2).map(|w| &items[w[0]..w[1]])),
+			PropertyValues::Scalar(_) => None,
+		}
+	}
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct PlyElement {
+	pub name: String,
+	pub count: usize,
+	pub properties: Vec<PlyProperty>,
+}
+
+impl PlyElement {
+	pub fn property(&self, name: &str) -> Option<&PlyProperty> {
+		self.properties.iter().find(|p| p.name == name)
+	}
+}
+
+#[derive(Debug, Clone)]
+pub struct PlyData {
+	pub format: PlyFormat,
+	pub comments: Vec<String>,
+	pub elements: Vec<PlyElement>,
+}
+
+impl PlyData {
+	pub fn element(&self, name: &str) -> Option<&PlyElement> {
+		self.elements.iter().find(|e| e.name == name)
+	}
+
+	/// Warnings about what [`ply_to_mesh`] cannot keep: list vertex
+	/// properties, which have no place in a mesh vertex.
+	pub fn diagnostics(&self) -> Vec<String> {
+		self.element("vertex")
+			.map(|vertex| vertex.properties.iter()
+				.filter(|p| p.scalars().is_none())
+				.map(|p| format!("vertex property '{}' is a list and was dropped", p.name))
+				.collect())
+			.unwrap_or_default()
+	}
+
+	/// Vertex index lists of the `face` element.
+	fn face_lists(&self) -> Option<&PlyProperty> {
+		let face = self.element("face")?;
+		face.property("vertex_indices").or_else(|| face.property("vertex_index"))
+	}
+}
+
+pub fn load_ply(file_path: &str) -> Result<Mesh, PlyError> {
+	let ply_data = parse_ply(file_path)?;
+	Ok(ply_to_mesh(&ply_data))
+}
+
+pub fn parse_ply(file_path: &str) -> Result<PlyData, PlyError> {
+	let bytes = std::fs::read(file_path)
+		.map_err(|source| PlyError::Io { path: file_path.to_string(), source })?;
+
+	parse_ply_from_bytes(&bytes)
+}
+
+/// Parses an ASCII or binary PLY, checking that faces and edges only
+/// reference declared vertices.
+pub fn parse_ply_from_bytes(bytes: &[u8]) -> Result<PlyData, PlyError> {
+	let header = parse_header(bytes)?;
+	let mut data = header.data;
+	let body = &bytes[header.body_start..];
+
+	match data.format {
+		PlyFormat::Ascii => read_ascii_body(body, header.lines, &mut data.elements)?,
+		format => read_binary_body(body, format == PlyFormat::BinaryBigEndian, &mut data.elements)?,
+	}
+
+	validate_references(&data)?;
+	Ok(data)
+}
+
+struct Header {
+	/// Declared elements, without values yet.
+	data: PlyData,
+	/// Offset of the first byte after `end_header`.
+	body_start: usize,
+	lines: usize,
+}
+
+fn parse_header(bytes: &[u8]) -> Result<Header, PlyError> {
+	let mut format = None;
+	let mut comments = Vec::new();
+	let mut elements: Vec<PlyElement> = Vec::new();
+	let mut start = 0;
+	let mut line_num = 0;
+
+	loop {
+		let end = bytes[start..].iter()
+			.position(|&b| b == b'\n')
+			.map(|offset| start + offset)
+			.ok_or_else(|| PlyError::Invalid("header is not terminated by 'end_header'".to_string()))?;
+		line_num += 1;
+
+		let raw_line = std::str::from_utf8(&bytes[start..end])
+			.map_err(|_| PlyError::Invalid(format!("header line {} is not text", line_num)))?
+			.trim_end_matches('\r');
+		start = end + 1;
+
+		let tokens: Vec<&str> = raw_line.split_whitespace().collect();
+		let error = |token: &str, message: String| PlyError::Syntax {
+			location: SourceLocation::of_token(line_num, raw_line, token),
+			message,
+		};
+
+		if line_num == 1 {
+			if tokens != ["ply"] {
+				return Err(error(raw_line, "Not a PLY file: expected 'ply'".to_string()));
+			}
+			continue;
+		}
+
+		let Some(&keyword) = tokens.first() else {
+			continue;
+		};
+
+		match keyword {
+			"format" => {
+				let [_, name, version] = tokens[..] else {
+					return Err(error(keyword, "Invalid format line: expected 'format type version'".to_string()));
+				};
+				format = Some(match name {
+					"ascii" => PlyFormat::Ascii,
+					"binary_little_endian" => PlyFormat::BinaryLittleEndian,
+					"binary_big_endian" => PlyFormat::BinaryBigEndian,
+					_ => return Err(error(name, format!("Unknown PLY format '{}'", name))),
+				});
+				if version != "1.0" {
+					return Err(error(version, format!("Unsupported PLY version '{}'", version)));
+				}
+			}
+			"comment" => comments.push(raw_line.trim_start()[keyword.len()..].trim().to_string()),
+			"obj_info" => {}
+			"element" => {
+				let [_, name, count] = tokens[..] else {
+					return Err(error(keyword, "Invalid element line: expected 'element name count'".to_string()));
+				};
+				let count = count.parse::<usize>()
+					.map_err(|_| error(count, format!("Invalid element count: '{}'", count)))?;
+				elements.push(PlyElement { name: name.to_string(), count, properties: Vec::new() });
+			}
+			"property" => {
+				let element = elements.last_mut()
+					.ok_or_else(|| error(keyword, "Property declared before any element".to_string()))?;
+				let scalar_type = |token: &str| ScalarType::from_name(token)
+					.ok_or_else(|| error(token, format!("Unknown property type '{}'", token)));
+
+				let property = match tokens[..] {
+					[_, "list", count, item, name] => {
+						let count_type = scalar_type(count)?;
+						if !count_type.is_integer() {
+							return Err(error(count, "List counts must have an integer type".to_string()));
+						}
+						PlyProperty::new(name, PropertyType::List { count: count_type, item: scalar_type(item)? })
+					}
+					[_, ty, name] if ty != "list" => PlyProperty::new(name, PropertyType::Scalar(scalar_type(ty)?)),
+					_ => return Err(error(keyword, "Invalid property line: expected 'property type name' or 'property list count_type item_type name'".to_string())),
+				};
+				element.properties.push(property);
+			}
+			"end_header" => {
+				let format = format.ok_or_else(|| error(keyword, "Missing 'format' line".to_string()))?;
+				return Ok(Header {
+					data: PlyData { format, comments, elements },
+					body_start: start,
+					lines: line_num,
+				});
+			}
+			_ => return Err(error(keyword, format!("Unknown header keyword '{}'", keyword))),
+		}
+	}
+}
+
+/// Reads one line per element instance, with values in property order.
+fn read_ascii_body(body: &[u8], header_lines: usize, elements: &mut [PlyElement]) -> Result<(), PlyError> {
+	let text = std::str::from_utf8(body)
+		.map_err(|_| PlyError::Invalid("ASCII data is not text".to_string()))?;
+	let mut lines = text.lines()
+		.enumerate()
+		.map(|(i, line)| (header_lines + i + 1, line))
+		.filter(|(_, line)| !line.trim().is_empty());
+
+	for element in elements {
+		for instance in 0..element.count {
+			let (line_num, raw_line) = lines.next()
+				.ok_or_else(|| truncated(&element.name, instance, element.count))?;
+			let mut tokens = raw_line.split_whitespace();
+			let mut next = |what: &str| next_ascii_value(&mut tokens, line_num, raw_line, what);
+
+			for property in &mut element.properties {
+				match &mut property.values {
+					PropertyValues::Scalar(values) => values.push(next(&property.name)?),
+					PropertyValues::List { offsets, items } => {
+						let count = next(&property.name)?;
+						for _ in 0..list_count(count, &property.name)? {
+							items.push(next(&property.name)?);
+						}
+						offsets.push(items.len());
+					}
+				}
+			}
+
+			if let Some(extra) = tokens.next() {
+				return Err(PlyError::Syntax {
+					location: SourceLocation::of_token(line_num, raw_line, extra),
+					message: format!("Unexpected value '{}' after the last '{}' property", extra, element.name),
+				});
+			}
+		}
+	}
+
+	Ok(())
+}
+
+fn next_ascii_value<'a>(
+	tokens: &mut impl Iterator<Item = &'a str>,
+	line_num: usize,
+	raw_line: &'a str,
+	what: &str,
+) -> Result<f64, PlyError> {
+	let error = |token: &str, message: String| PlyError::Syntax {
+		location: SourceLocation::of_token(line_num, raw_line, token),
+		message,
+	};
+
+	let token = tokens.next()
+		.ok_or_else(|| error(&raw_line[raw_line.len()..], format!("Missing value for '{}'", what)))?;
+	token.parse::<f64>()
+		.map_err(|_| error(token, format!("Invalid number: '{}'", token)))
+}
+
+fn list_count(count: f64, what: &str) -> Result<usize, PlyError> {
+	if count < 0.0 || count.fract() != 0.0 {
+		return Err(PlyError::Invalid(format!("invalid list length {} for '{}'", count, what)));
+	}
+	Ok(count as usize)
+}
+
+fn truncated(element: &str, instance: usize, count: usize) -> PlyError {
+	PlyError::Invalid(format!("data ends at '{}' element {} of {}", element, instance + 1, count))
+}
+
+/// Reads scalars of either byte order.
+struct BinaryReader<'a> {
+	bytes: &'a [u8],
+	pos: usize,
+	big_endian: bool,
+}
+
+impl BinaryReader<'_> {
+	fn read(&mut self, ty: ScalarType) -> Option<f64> {
+		let size = ty.size();
+		let bytes = self.bytes.get(self.pos..self.pos + size)?;
+		self.pos += size;
+
+		let mut buf = [0u8; 8];
+		buf[..size].copy_from_slice(bytes);
+		if self.big_endian {
+			buf[..size].reverse();
+		}
+
+		Some(match ty {
+			ScalarType::Int8 => i8::from_le_bytes([buf[0]]) as f64,
+			ScalarType::UInt8 => buf[0] as f64,
+			ScalarType::Int16 => i16::from_le_bytes([buf[0], buf[1]]) as f64,
+			ScalarType::UInt16 => u16::from_le_bytes([buf[0], buf[1]]) as f64,
+			ScalarType::Int32 => i32::from_le_bytes([buf[0], buf[1], buf[2], buf[3]]) as f64,
+			ScalarType::UInt32 => u32::from_le_bytes([buf[0], buf[1], buf[2], buf[3]]) as f64,
+			ScalarType::Float32 => f32::from_le_bytes([buf[0], buf[1], buf[2], buf[3]]) as f64,
+			ScalarType::Float64 => f64::from_le_bytes(buf),
+		})
+	}
+}
+
+fn read_binary_body(body: &[u8], big_endian: bool, elements: &mut [PlyElement]) -> Result<(), PlyError> {
+	let mut reader = BinaryReader { bytes: body, pos: 0, big_endian };
+
+	for element in elements {
+		for instance in 0..element.count {
+			let truncated = || truncated(&element.name, instance, element.count);
+
+			for property in &mut element.properties {
+				match (property.kind, &mut property.values) {
+					(PropertyType::Scalar(ty), PropertyValues::Scalar(values)) => {
+						values.push(reader.read(ty).ok_or_else(truncated)?);
+					}
+					(PropertyType::List { count, item }, PropertyValues::List { offsets, items }) => {
+						let count = reader.read(count).ok_or_else(truncated)?;
+						for _ in 0..list_count(count, &property.name)? {
+							items.push(reader.read(item).ok_or_else(truncated)?);
+						}
+						offsets.push(items.len());
+					}
+					_ => unreachable!("property values are created from their type"),
+				}
+			}
+		}
+	}
+
+	Ok(())
+}
+
+fn validate_references(data: &PlyData) -> Result<(), PlyError> {
+	let vertex_count = data.element("vertex").map_or(0, |e| e.count);
+	let is_valid = |index: f64| index >= 0.0 && index < vertex_count as f64 && index.fract() == 0.0;
+
+	if let Some(lists) = data.face_lists().and_then(PlyProperty::lists) {
+		for (face, list) in lists.enumerate() {
+			if let Some(&index) = list.iter().find(|&&index| !is_valid(index)) {
+				return Err(PlyError::Invalid(format!(
+					"face {} references vertex {} but {} vertices are declared",
+					face, index, vertex_count,
+				)));
+			}
+		}
+	}
+
+	for (edge, [a, b]) in edge_ends(data).into_iter().flatten().enumerate() {
+		if let Some(index) = [a, b].into_iter().find(|&index| !is_valid(index)) {
+			return Err(PlyError::Invalid(format!(
+				"edge {} references vertex {} but {} vertices are declared",
+				edge, index, vertex_count,
+			)));
+		}
+	}
+
+	Ok(())
+}
+
+/// End points of the `edge` element, when it has `vertex1` and `vertex2`.
+fn edge_ends(data: &PlyData) -> Option<impl Iterator<Item = [f64; 2]> + '_> {
+	let edge = data.element("edge")?;
+	let (_, first) = edge.property("vertex1")?.scalars()?;
+	let (_, second) = edge.property("vertex2")?.scalars()?;
+
+	Some(first.iter().zip(second).map(|(&a, &b)| [a, b]))
+}
+
+/// Vertex property names mapped onto `Vertex`, first match wins.
+const POSITION_NAMES: &[&[&str]] = &[&["x", "y", "z"]];
+const NORMAL_NAMES: &[&[&str]] = &[&["nx", "ny", "nz"]];
+const COLOR_NAMES: &[&[&str]] = &[&["red", "green", "blue"], &["diffuse_red", "diffuse_green", "diffuse_blue"]];
+const TEX_COORD_NAMES: &[&[&str]] = &[&["u", "v"], &["s", "t"], &["texture_u", "texture_v"], &["texture_s", "texture_t"]];
+
+/// Color of vertices without a color property.
+const DEFAULT_COLOR: [f32; 3] = [0.7, 0.7, 0.7];
+
+/// Builds a `Mesh` keeping the PLY vertex order.
+///
+/// Known vertex properties fill `Vertex`, other scalar ones become
+/// [`VertexAttribute`]s and list ones are dropped, as reported by
+/// [`PlyData::diagnostics`]. Missing normals are smoothed from the faces and
+/// missing texture coordinates are projected. Polygons are triangulated,
+/// `edge` elements become lines, and a file without either is shown as a
+/// point cloud.
+pub fn ply_to_mesh(ply: &PlyData) -> Mesh {
+	let vertex_element = ply.element("vertex");
+	let count = vertex_element.map_or(0, |e| e.count);
+	let mut known: Vec<&str> = Vec::new();
+
+	// Columns of the first name group fully declared as scalar properties
+	let mut columns = |groups: &[&[&'static str]]| -> Option<Vec<(ScalarType, &[f64])>> {
+		groups.iter().find_map(|names| {
+			let columns: Option<Vec<_>> = names.iter()
+				.map(|name| vertex_element?.property(name)?.scalars())
+				.collect();
+			columns.inspect(|_| known.extend(names.iter()))
+		})
+	};
+	let positions_columns = columns(POSITION_NAMES);
+	let normal_columns = columns(NORMAL_NAMES);
+	let color_columns = columns(COLOR_NAMES);
+	let tex_coord_columns = columns(TEX_COORD_NAMES);
+
+	let gather = |columns: &[(ScalarType, &[f64])], i: usize, scale: bool| -> Vec<f32> {
+		columns.iter()
+			.map(|&(ty, values)| values[i] as f32 / if scale { ty.color_scale() } else { 1.0 })
+			.collect()
+	};
+
+	let positions: Vec<[f32; 3]> = (0..count)
+		.map(|i| match &positions_columns {
+			Some(columns) => {
+				let p = gather(columns, i, false);
+				[p[0], p[1], p[2]]
+			}
+			None => [0.0; 3],
+		})
+		.collect();
+
+	let (triangles, polygons) = ply.face_lists()
+		.and_then(PlyProperty::lists)
+		.map(|lists| triangulate_faces(lists, &positions))
+		.unwrap_or_default();
+
+	let normals: Vec<[f32; 3]> = match &normal_columns {
+		Some(columns) => (0..count).map(|i| {
+			let n = gather(columns, i, false);
+			[n[0], n[1], n[2]]
+		}).collect(),
+		None => smooth_normals(&positions, &triangles),
+	};
+
+	let bbox = BoudingBox::from_positions(&positions);
+	let vertices = (0..count)
+		.map(|i| Vertex {
+			position: positions[i],
+			tex_coords: match &tex_coord_columns {
+				Some(columns) => {
+					let t = gather(columns, i, false);
+					[t[0], t[1]]
+				}
+				None => generate_planar_uv(positions[i], normals[i], &bbox),
+			},
+			normal: normals[i],
+			color: match &color_columns {
+				Some(columns) => {
+					let c = gather(columns, i, true);
+					[c[0], c[1], c[2]]
+				}
+				None => DEFAULT_COLOR,
+			},
+			tangent: [0.0; 4],
+		})
+		.collect();
+
+	let attributes = vertex_element
+		.map(|e| e.properties.iter()
+			.filter(|p| !known.contains(&p.name.as_str()))
+			.filter_map(|p| p.scalars().map(|(_, values)| VertexAttribute {
+				name: p.name.clone(),
+				values: values.iter().map(|&v| v as f32).collect(),
+			}))
+			.collect())
+		.unwrap_or_default();
+
+	let line_indices: Vec<u32> = edge_ends(ply)
+		.map(|edges| edges.flat_map(|[a, b]| [a as u32, b as u32]).collect())
+		.unwrap_or_default();
+
+	let point_indices = if triangles.is_empty() && line_indices.is_empty() {
+		(0..count as u32).collect()
+	} else {
+		Vec::new()
+	};
+
+	Mesh {
+		vertices,
+		indices: triangles.as_flattened().to_vec(),
+		materials: Vec::new(),
+		face_materials: vec![None; triangles.len()],
+		parts: Vec::new(),
+		line_indices,
+		point_indices,
+		attributes,
+		polygons,
+		creases: Vec::new(),
+	}
+}
+
+/// Splits faces into triangles by ear clipping, or as a fan when that fails.
+/// Also returns the triangle count of each face.
+fn triangulate_faces<'a>(faces: impl Iterator<Item = &'a [f64]>, positions: &[[f32; 3]]) -> (Vec<[u32; 3]>, Vec<u32>) {
+	let mut triangles = Vec::new();
+	let mut polygons = Vec::new();
+
+	for face in faces {
+		let indices: Vec<u32> = face.iter().map(|&i| i as u32).collect();
+		if indices.len() < 3 {
+			continue;
+		}
+
+		let first = triangles.len();
+		let points: Vec<[f32; 3]> = indices.iter().map(|&i| positions[i as usize]).collect();
+		match triangulate_polygon(&points) {
+			Some(ears) => triangles.extend(ears.iter().map(|t| t.map(|k| indices[k]))),
+			None => triangles.extend((1..indices.len() - 1).map(|k| [indices[0], indices[k], indices[k + 1]])),
+		}
+		polygons.push((triangles.len() - first) as u32);
+	}
+
+	(triangles, polygons)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HEADER: &str = "\
+element vertex 4
+property float x
+property float y
+property float z
+property uchar red
+property uchar green
+property uchar blue
+property float confidence
+element face 1
+property list uchar int vertex_indices
+end_header
+";
+
+    const VERTICES: [([f32; 3], [u8; 3], f32); 4] = [
+        ([0.0, 0.0, 0.0], [255, 0, 0], 0.5),
+        ([1.0, 0.0, 0.0], [0, 255, 0], 0.25),
+        ([1.0, 1.0, 0.0], [0, 0, 255], 1.0),
+        ([0.0, 1.0, 0.0], [255, 255, 255], 0.0),
+    ];
+
+    fn ascii() -> String {
+        let mut text = format!("ply\nformat ascii 1.0\ncomment scanned\n{}", HEADER);
+        for (p, c, confidence) in VERTICES {
+            text.push_str(&format!("{} {} {} {} {} {} {}\n", p[0], p[1], p[2], c[0], c[1], c[2], confidence));
+        }
+        text.push_str("4 0 1 2 3\n");
+        text
+    }
+
+    fn binary(big_endian: bool) -> Vec<u8> {
+        let format = if big_endian { "binary_big_endian" } else { "binary_little_endian" };
+        let mut bytes = format!("ply\nformat {} 1.0\n{}", format, HEADER).into_bytes();
+        let mut push = |data: &[u8]| {
+            let mut data = data.to_vec();
+            if big_endian {
+                data.reverse();
+            }
+            bytes.extend(data);
+        };
+
+        for (p, c, confidence) in VERTICES {
+            for value in p {
+                push(&value.to_le_bytes());
+            }
+            for value in c {
+                push(&[value]);
+            }
+            push(&confidence.to_le_bytes());
+        }
+        push(&[4]);
+        for index in 0..4_i32 {
+            push(&index.to_le_bytes());
+        }
+        bytes
+    }
+
+    #[test]
+    fn ascii_quad_keeps_colors_and_custom_properties() {
+        let ply = parse_ply_from_bytes(ascii().as_bytes()).unwrap();
+        assert_eq!(ply.format, PlyFormat::Ascii);
+        assert_eq!(ply.comments, vec!["scanned".to_string()]);
+
+        let mesh = ply_to_mesh(&ply);
+        assert_eq!(mesh.vertices.len(), 4);
+        assert_eq!(mesh.indices.len(), 6);
+        assert!(mesh.point_indices.is_empty());
+        assert_eq!(mesh.vertices[2].position, [1.0, 1.0, 0.0]);
+        assert_eq!(mesh.vertices[2].color, [0.0, 0.0, 1.0]);
+        assert_eq!(mesh.vertices[0].normal, [0.0, 0.0, 1.0]);
+
+        assert_eq!(mesh.attributes.len(), 1);
+        assert_eq!(mesh.attribute("confidence").unwrap().values, vec![0.5, 0.25, 1.0, 0.0]);
+    }
+
+    #[test]
+    fn binary_orders_match_ascii() {
+        let expected = parse_ply_from_bytes(ascii().as_bytes()).unwrap();
+
+        for big_endian in [false, true] {
+            let ply = parse_ply_from_bytes(&binary(big_endian)).unwrap();
+            assert_eq!(ply.elements, expected.elements, "big endian: {}", big_endian);
+        }
+    }
+
+    #[test]
+    fn point_cloud_without_faces() {
+        let text = "ply\nformat ascii 1.0\nelement vertex 2\nproperty double x\nproperty double y\nproperty double z\nproperty float nx\nproperty float ny\nproperty float nz\nproperty float intensity\nend_header\n0 0 0 0 0 1 7\n1 2 3 0 1 0 9\n";
+        let mesh = ply_to_mesh(&parse_ply_from_bytes(text.as_bytes()).unwrap());
+
+        assert!(mesh.indices.is_empty());
+        assert_eq!(mesh.point_indices, vec![0, 1]);
+        assert_eq!(mesh.vertices[1].position, [1.0, 2.0, 3.0]);
+        assert_eq!(mesh.vertices[1].normal, [0.0, 1.0, 0.0]);
+        assert_eq!(mesh.attribute("intensity").unwrap().values, vec![7.0, 9.0]);
+    }
+
+    #[test]
+    fn list_vertex_properties_are_reported() {
+        let text = "ply\nformat ascii 1.0\nelement vertex 2\nproperty float x\nproperty float y\nproperty float z\nproperty list uchar float weights\nproperty float intensity\nend_header\n0 0 0 2 0.5 0.5 7\n1 2 3 0 9\n";
+        let ply = parse_ply_from_bytes(text.as_bytes()).unwrap();
+
+        assert_eq!(ply.diagnostics(), vec!["vertex property 'weights' is a list and was dropped".to_string()]);
+        let mesh = ply_to_mesh(&ply);
+        assert_eq!(mesh.attributes.len(), 1);
+        assert_eq!(mesh.attribute("intensity").unwrap().values, vec![7.0, 9.0]);
+        assert!(parse_ply_from_bytes(ascii().as_bytes()).unwrap().diagnostics().is_empty());
+    }
+
+    #[test]
+    fn truncated_binary_is_an_error() {
+        let mut bytes = binary(false);
+        bytes.truncate(bytes.len() - 3);
+
+        let err = parse_ply_from_bytes(&bytes).unwrap_err();
+        assert_eq!(err.to_string(), "Invalid PLY data: data ends at 'face' element 1 of 1");
+    }
+
+    #[test]
+    fn header_errors_are_located() {
+        let err = parse_ply_from_bytes(b"ply\nformat ascii 1.0\nproperty float x\nend_header\n").unwrap_err();
+        assert!(matches!(&err, PlyError::Syntax { location, .. } if location.line == 3), "{}", err);
+
+        let err = parse_ply_from_bytes(b"ply\nformat ascii 1.0\nelement vertex 1\nproperty half x\nend_header\n").unwrap_err();
+        assert!(matches!(&err, PlyError::Syntax { location, .. } if location.line == 4 && location.column == 10), "{}", err);
+
+        let err = parse_ply_from_bytes(b"PLY\n").unwrap_err();
+        assert!(matches!(err, PlyError::Syntax { .. }));
+
+        let err = parse_ply_from_bytes(b"ply\nformat ascii 1.0\n").unwrap_err();
+        assert!(matches!(err, PlyError::Invalid(_)));
+    }
+
+    #[test]
+    fn ascii_body_errors_are_located() {
+        let text = ascii().replace("0.25", "oops");
+        let err = parse_ply_from_bytes(text.as_bytes()).unwrap_err();
+        assert!(matches!(&err, PlyError::Syntax { location, .. } if location.line == 16 && location.column == 15), "{}", err);
+
+        let text = ascii().replace("4 0 1 2 3", "4 0 1 2");
+        let err = parse_ply_from_bytes(text.as_bytes()).unwrap_err();
+        assert!(matches!(&err, PlyError::Syntax { message, .. } if message == "Missing value for 'vertex_indices'"), "{}", err);
+    }
+
+    #[test]
+    fn out_of_range_face_is_an_error() {
+        let text = ascii().replace("4 0 1 2 3", "3 0 1 4");
+        let err = parse_ply_from_bytes(text.as_bytes()).unwrap_err();
+        assert_eq!(err.to_string(), "Invalid PLY data: face 0 references vertex 4 but 4 vertices are declared");
+    }
+}
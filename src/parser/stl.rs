@@ -0,0 +1,425 @@
+use std::collections::HashMap;
+use std::ops::Range;
+
+use crate::mesh::{Mesh, MeshPart, Vertex};
+use crate::parser::error::{SourceLocation, StlError};
+use crate::parser::obj::{BoudingBox, VertexKey, generate_face_color, generate_planar_uv};
+
+/// Size of the free-form header of a binary STL.
+const HEADER_SIZE: usize = 80;
+
+/// Size of one binary triangle: normal, 3 vertices and a 16-bit attribute.
+const TRIANGLE_SIZE: usize = 50;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct StlFacet {
+	/// Normal as written in the file, possibly zero.
+	pub normal: [f32; 3],
+	pub vertices: [[f32; 3]; 3],
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct StlData {
+	pub facets: Vec<StlFacet>,
+	/// Named `solid` blocks of an ASCII file and their facets. Empty for
+	/// binary files, which hold a single unnamed solid.
+	pub solids: Vec<(String, Range<usize>)>,
+}
+
+pub fn load_stl(file_path: &str) -> Result<Mesh, StlError> {
+	let stl_data = parse_stl(file_path)?;
+	Ok(stl_to_mesh(stl_data))
+}
+
+pub fn parse_stl(file_path: &str) -> Result<StlData, StlError> {
+	let bytes = std::fs::read(file_path)
+		.map_err(|source| StlError::Io { path: file_path.to_string(), source })?;
+
+	parse_stl_from_bytes(&bytes)
+}
+
+/// Parses a binary or ASCII STL.
+///
+/// Files whose size matches the triangle count of a binary header are read
+/// as binary even when the header starts with `solid`, as many exporters do.
+/// Such files that are not text either are read as binary too, so a truncated
+/// one reports its missing triangles.
+pub fn parse_stl_from_bytes(bytes: &[u8]) -> Result<StlData, StlError> {
+	if is_binary(bytes) {
+		return parse_binary(bytes);
+	}
+
+	match std::str::from_utf8(bytes) {
+		Ok(text) => parse_ascii(text),
+		Err(_) if declared_triangles(bytes).is_some() => parse_binary(bytes),
+		Err(e) => Err(StlError::Corrupt(format!("not a binary STL, and not ASCII text either ({})", e))),
+	}
+}
+
+fn is_binary(bytes: &[u8]) -> bool {
+	let declared_size = declared_triangles(bytes)
+		.and_then(|count| count.checked_mul(TRIANGLE_SIZE))
+		.and_then(|size| size.checked_add(HEADER_SIZE + 4));
+
+	declared_size == Some(bytes.len()) || !bytes.trim_ascii_start().starts_with(b"solid")
+}
+
+fn declared_triangles(bytes: &[u8]) -> Option<usize> {
+	let count = bytes.get(HEADER_SIZE..HEADER_SIZE + 4)?;
+	Some(u32::from_le_bytes([count[0], count[1], count[2], count[3]]) as usize)
+}
+
+fn parse_binary(bytes: &[u8]) -> Result<StlData, StlError> {
+	let count = declared_triangles(bytes).ok_or_else(|| StlError::Corrupt(format!(
+		"{} bytes is too short for the {}-byte header",
+		bytes.len(), HEADER_SIZE + 4,
+	)))?;
+
+	let available = (bytes.len() - HEADER_SIZE - 4) / TRIANGLE_SIZE;
+	if count > available {
+		return Err(StlError::Corrupt(format!(
+			"header declares {} triangles but the file only holds {}",
+			count, available,
+		)));
+	}
+
+	let facets = bytes[HEADER_SIZE + 4..]
+		.chunks_exact(TRIANGLE_SIZE)
+		.take(count)
+		.map(|triangle| {
+			let value = |i: usize| f32::from_le_bytes([
+				triangle[i * 4], triangle[i * 4 + 1], triangle[i * 4 + 2], triangle[i * 4 + 3],
+			]);
+			let vector = |first: usize| [value(first), value(first + 1), value(first + 2)];
+
+			StlFacet {
+				normal: vector(0),
+				vertices: [vector(3), vector(6), vector(9)],
+			}
+		})
+		.collect();
+
+	Ok(StlData { facets, solids: Vec::new() })
+}
+
+/// A facet being read, with the line it started on.
+struct OpenFacet {
+	normal: [f32; 3],
+	vertices: Vec<[f32; 3]>,
+	line: usize,
+}
+
+fn parse_ascii(text: &str) -> Result<StlData, StlError> {
+	let mut data = StlData::default();
+	let mut solid: Option<(String, usize)> = None;
+	let mut facet: Option<OpenFacet> = None;
+
+	for (i, raw_line) in text.lines().enumerate() {
+		let line_num = i + 1;
+		let tokens: Vec<&str> = raw_line.split_whitespace().collect();
+		let Some(&keyword) = tokens.first() else {
+			continue;
+		};
+
+		let error = |token: &str, message: &str| StlError::Syntax {
+			location: SourceLocation::of_token(line_num, raw_line, token),
+			message: message.to_string(),
+		};
+		let inside_facet = |facet: &mut Option<OpenFacet>| {
+			facet.take().ok_or_else(|| error(keyword, &format!("'{}' outside of a facet", keyword)))
+		};
+
+		match keyword {
+			"solid" => {
+				if solid.is_some() {
+					return Err(error(keyword, "'solid' inside another solid"));
+				}
+				solid = Some((tokens[1..].join(" "), data.facets.len()));
+			}
+			"endsolid" => {
+				if facet.is_some() {
+					return Err(error(keyword, "'endsolid' inside a facet"));
+				}
+				let (name, first) = solid.take().ok_or_else(|| error(keyword, "'endsolid' without 'solid'"))?;
+				data.solids.push((solid_name(name), first..data.facets.len()));
+			}
+			"facet" => {
+				if solid.is_none() || facet.is_some() {
+					return Err(error(keyword, "'facet' outside of a solid"));
+				}
+				if tokens.get(1) != Some(&"normal") {
+					return Err(error(keyword, "Invalid facet format: expected 'facet normal nx ny nz'"));
+				}
+				let normal = parse_vector(&tokens[1..], &error)?;
+				facet = Some(OpenFacet { normal, vertices: Vec::with_capacity(3), line: line_num });
+			}
+			"vertex" => {
+				let mut open = inside_facet(&mut facet)?;
+				if open.vertices.len() == 3 {
+					return Err(error(keyword, "Facet has more than 3 vertices"));
+				}
+				open.vertices.push(parse_vector(&tokens, &error)?);
+				facet = Some(open);
+			}
+			"outer" | "endloop" => facet = Some(inside_facet(&mut facet)?),
+			"endfacet" => {
+				let open = inside_facet(&mut facet)?;
+				let vertices: [[f32; 3]; 3] = open.vertices.try_into()
+					.map_err(|v: Vec<_>| error(keyword, &format!("Facet must have 3 vertices, found {}", v.len())))?;
+				data.facets.push(StlFacet { normal: open.normal, vertices });
+			}
+			_ => return Err(error(keyword, &format!("Unknown keyword '{}'", keyword))),
+		}
+	}
+
+	if let Some(open) = facet {
+		let raw_line = text.lines().nth(open.line - 1).unwrap_or("");
+		return Err(StlError::Syntax {
+			location: SourceLocation::of_token(open.line, raw_line, raw_line.trim_start()),
+			message: "Unexpected end of file: facet is not closed by 'endfacet'".to_string(),
+		});
+	}
+
+	// A missing 'endsolid' at the end of the file is common enough to accept
+	if let Some((name, first)) = solid {
+		data.solids.push((solid_name(name), first..data.facets.len()));
+	}
+
+	Ok(data)
+}
+
+fn solid_name(name: String) -> String {
+	if name.is_empty() { "default".to_string() } else { name }
+}
+
+/// Parses the 3 values following `tokens[0]`.
+fn parse_vector(tokens: &[&str], error: &impl Fn(&str, &str) -> StlError) -> Result<[f32; 3], StlError> {
+	if tokens.len() != 4 {
+		return Err(error(tokens[0], &format!("Expected 3 values after '{}'", tokens[0])));
+	}
+
+	let mut vector = [0.0; 3];
+	for (value, token) in vector.iter_mut().zip(&tokens[1..]) {
+		*value = token.parse::<f32>()
+			.map_err(|_| error(token, &format!("Invalid number: '{}'", token)))?;
+	}
+
+	Ok(vector)
+}
+
+/// Builds a `Mesh`, welding corners that share position and facet normal.
+///
+/// Zero normals, which some exporters write, are recomputed from the winding.
+pub fn stl_to_mesh(stl: StlData) -> Mesh {
+	let positions: Vec<[f32; 3]> = stl.facets.iter().flat_map(|f| f.vertices).collect();
+	let bbox = BoudingBox::from_positions(&positions);
+
+	let mut vertices = Vec::new();
+	let mut indices = Vec::with_capacity(positions.len());
+	let mut vertex_cache: HashMap<VertexKey, u32> = HashMap::new();
+
+	for (facet_idx, facet) in stl.facets.iter().enumerate() {
+		let normal = facet_normal(facet);
+
+		for &position in &facet.vertices {
+			let vertex = Vertex {
+				position,
+				tex_coords: generate_planar_uv(position, normal, &bbox),
+				normal,
+				color: generate_face_color(facet_idx),
+				tangent: [0.0; 4],
+			};
+
+			let index = *vertex_cache.entry(VertexKey::from_vertex(&vertex, None)).or_insert_with(|| {
+				vertices.push(vertex);
+				(vertices.len() - 1) as u32
+			});
+			indices.push(index);
+		}
+	}
+
+	let parts = stl.solids.into_iter()
+		.map(|(name, facets)| MeshPart {
+			name,
+			first_index: (facets.start * 3) as u32,
+			index_count: (facets.len() * 3) as u32,
+			visible: true,
+		})
+		.collect();
+
+	Mesh {
+		vertices,
+		indices,
+		materials: Vec::new(),
+		face_materials: vec![None; stl.facets.len()],
+		parts,
+		line_indices: Vec::new(),
+		point_indices: Vec::new(),
+		attributes: Vec::new(),
+		polygons: Vec::new(),
+		creases: Vec::new(),
+	}
+}
+
+fn facet_normal(facet: &StlFacet) -> [f32; 3] {
+	let normalized = |n: [f32; 3]| {
+		let len = (n[0] * n[0] + n[1] * n[1] + n[2] * n[2]).sqrt();
+		(len > 0.0001).then(|| [n[0] / len, n[1] / len, n[2] / len])
+	};
+
+	let [v0, v1, v2] = facet.vertices;
+	let edge1 = [v1[0] - v0[0], v1[1] - v0[1], v1[2] - v0[2]];
+	let edge2 = [v2[0] - v0[0], v2[1] - v0[1], v2[2] - v0[2]];
+	let cross = [
+		edge1[1] * edge2[2] - edge1[2] * edge2[1],
+		edge1[2] * edge2[0] - edge1[0] * edge2[2],
+		edge1[0] * edge2[1] - edge1[1] * edge2[0],
+	];
+
+	normalized(facet.normal)
+		.or_else(|| normalized(cross))
+		.unwrap_or([0.0, 1.0, 0.0])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The 12 facets of a unit cube, outward normals and counter-clockwise.
+    fn cube() -> Vec<StlFacet> {
+        let quads = [
+            ([0.0, 0.0, -1.0], [[0.0, 0.0, 0.0], [0.0, 1.0, 0.0], [1.0, 1.0, 0.0], [1.0, 0.0, 0.0]]),
+            ([0.0, 0.0, 1.0], [[0.0, 0.0, 1.0], [1.0, 0.0, 1.0], [1.0, 1.0, 1.0], [0.0, 1.0, 1.0]]),
+            ([0.0, -1.0, 0.0], [[0.0, 0.0, 0.0], [1.0, 0.0, 0.0], [1.0, 0.0, 1.0], [0.0, 0.0, 1.0]]),
+            ([0.0, 1.0, 0.0], [[0.0, 1.0, 0.0], [0.0, 1.0, 1.0], [1.0, 1.0, 1.0], [1.0, 1.0, 0.0]]),
+            ([-1.0, 0.0, 0.0], [[0.0, 0.0, 0.0], [0.0, 0.0, 1.0], [0.0, 1.0, 1.0], [0.0, 1.0, 0.0]]),
+            ([1.0, 0.0, 0.0], [[1.0, 0.0, 0.0], [1.0, 1.0, 0.0], [1.0, 1.0, 1.0], [1.0, 0.0, 1.0]]),
+        ];
+
+        quads.iter()
+            .flat_map(|&(normal, q)| [
+                StlFacet { normal, vertices: [q[0], q[1], q[2]] },
+                StlFacet { normal, vertices: [q[0], q[2], q[3]] },
+            ])
+            .collect()
+    }
+
+    fn binary(header: &[u8], facets: &[StlFacet]) -> Vec<u8> {
+        let mut bytes = header.to_vec();
+        bytes.resize(HEADER_SIZE, 0);
+        bytes.extend((facets.len() as u32).to_le_bytes());
+        for facet in facets {
+            for value in facet.normal.iter().chain(facet.vertices.as_flattened()) {
+                bytes.extend(value.to_le_bytes());
+            }
+            bytes.extend([0, 0]);
+        }
+        bytes
+    }
+
+    fn ascii(facets: &[StlFacet]) -> String {
+        let mut text = String::from("solid cube\n");
+        for facet in facets {
+            let [x, y, z] = facet.normal;
+            text.push_str(&format!("  facet normal {} {} {}\n    outer loop\n", x, y, z));
+            for [x, y, z] in facet.vertices {
+                text.push_str(&format!("      vertex {} {} {}\n", x, y, z));
+            }
+            text.push_str("    endloop\n  endfacet\n");
+        }
+        text.push_str("endsolid cube\n");
+        text
+    }
+
+    fn load_from_text(text: &str) -> Mesh {
+        stl_to_mesh(parse_stl_from_bytes(text.as_bytes()).unwrap())
+    }
+
+    #[test]
+    fn binary_cube_is_welded_per_face() {
+        let stl = parse_stl_from_bytes(&binary(b"binary cube", &cube())).unwrap();
+        assert_eq!(stl.facets, cube());
+        assert!(stl.solids.is_empty());
+
+        let mesh = stl_to_mesh(stl);
+        assert_eq!(mesh.indices.len(), 36);
+        // Corners are only shared within a side, which keeps its normal
+        assert_eq!(mesh.vertices.len(), 24);
+        assert_eq!(mesh.vertices[mesh.indices[0] as usize].normal, [0.0, 0.0, -1.0]);
+        assert!(mesh.parts.is_empty());
+    }
+
+    #[test]
+    fn binary_header_may_start_with_solid() {
+        let stl = parse_stl_from_bytes(&binary(b"solid exported by some tool", &cube())).unwrap();
+        assert_eq!(stl.facets.len(), 12);
+    }
+
+    #[test]
+    fn truncated_binary_starting_with_solid_reports_missing_triangles() {
+        let mut bytes = binary(b"solid exported by some tool", &cube());
+        bytes.truncate(bytes.len() - 20);
+
+        let error = parse_stl_from_bytes(&bytes).unwrap_err();
+        assert_eq!(error.to_string(), "Corrupt binary STL: header declares 12 triangles but the file only holds 11");
+
+        // Padding after the declared triangles is ignored
+        let mut bytes = binary(b"solid exported by some tool", &cube());
+        bytes.extend([0; 7]);
+        assert_eq!(parse_stl_from_bytes(&bytes).unwrap().facets, cube());
+    }
+
+    #[test]
+    fn ascii_matches_binary() {
+        let stl = parse_stl_from_bytes(ascii(&cube()).as_bytes()).unwrap();
+        assert_eq!(stl.facets, cube());
+        assert_eq!(stl.solids, vec![("cube".to_string(), 0..12)]);
+
+        let mesh = stl_to_mesh(stl);
+        assert_eq!(mesh.vertices.len(), 24);
+        assert_eq!(mesh.parts[0].index_count, 36);
+    }
+
+    #[test]
+    fn ascii_solids_become_parts() {
+        let text = "solid a\nfacet normal 0 0 0\nouter loop\nvertex 0 0 0\nvertex 1 0 0\nvertex 0 1 0\nendloop\nendfacet\nendsolid a\n\
+                    solid\nfacet normal 0 0 1\nouter loop\nvertex 0 0 1\nvertex 1 0 1\nvertex 0 1 1\nendloop\nendfacet\n";
+        let mesh = load_from_text(text);
+
+        let parts: Vec<_> = mesh.parts.iter().map(|p| (p.name.as_str(), p.first_index, p.index_count)).collect();
+        assert_eq!(parts, vec![("a", 0, 3), ("default", 3, 3)]);
+        // Zero normals are rebuilt from the winding
+        assert_eq!(mesh.vertices[0].normal, [0.0, 0.0, 1.0]);
+    }
+
+    #[test]
+    fn truncated_binary_is_an_error() {
+        let mut bytes = binary(b"", &cube());
+        bytes.truncate(bytes.len() - 10);
+
+        let err = parse_stl_from_bytes(&bytes).unwrap_err();
+        assert!(matches!(&err, StlError::Corrupt(message) if message.contains("declares 12 triangles but the file only holds 11")), "{}", err);
+
+        let err = parse_stl_from_bytes(&[0; 40]).unwrap_err();
+        assert!(matches!(&err, StlError::Corrupt(message) if message.contains("too short")), "{}", err);
+    }
+
+    #[test]
+    fn ascii_errors_are_located() {
+        let text = "solid a\nfacet normal 0 0 1\nouter loop\nvertex 0 0 0\nvertex 1 0 0\nendloop\nendfacet\nendsolid a\n";
+        let err = parse_stl_from_bytes(text.as_bytes()).unwrap_err();
+        match err {
+            StlError::Syntax { location, message } => {
+                assert_eq!(location.line, 7);
+                assert_eq!(message, "Facet must have 3 vertices, found 2");
+            }
+            other => panic!("unexpected error: {}", other),
+        }
+
+        let err = parse_stl_from_bytes(b"solid a\nfacet normal 0 0 1\nouter loop\nvertex 0 x 0\n").unwrap_err();
+        assert!(matches!(&err, StlError::Syntax { location, .. } if location.line == 4 && location.column == 10), "{}", err);
+
+        let err = parse_stl_from_bytes(b"solid a\nfacet normal 0 0 1\nouter loop\n").unwrap_err();
+        assert!(matches!(&err, StlError::Syntax { location, .. } if location.line == 2), "{}", err);
+    }
+}
@@ -0,0 +1,658 @@
+use crate::parser::error::TextureError;
+use crate::parser::texture::TextureData;
+
+/// Block-compressed encodings, which all store 4x4 pixel blocks.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BlockFormat {
+	/// RGB with 1-bit alpha.
+	Bc1,
+	/// RGB with explicit 4-bit alpha.
+	Bc2,
+	/// RGB with interpolated alpha.
+	Bc3,
+	/// Single red channel.
+	Bc4,
+	/// Red and green channels, typically a normal map.
+	Bc5,
+	/// HDR RGB as half floats.
+	Bc6h { signed: bool },
+	/// High quality RGBA.
+	Bc7,
+}
+
+impl BlockFormat {
+	/// Bytes per 4x4 block.
+	pub fn block_size(self) -> usize {
+		match self {
+			BlockFormat::Bc1 | BlockFormat::Bc4 => 8,
+			_ => 16,
+		}
+	}
+
+	/// Bytes of a `width` x `height` image, partial blocks included.
+	pub fn image_size(self, width: u32, height: u32) -> usize {
+		width.div_ceil(4) as usize * height.div_ceil(4) as usize * self.block_size()
+	}
+}
+
+/// Decodes a block-compressed image to RGBA8.
+///
+/// BC4 and BC5 fill red and green like a GPU sampling them would, and BC6H
+/// values are clamped to `[0, 1]`.
+pub fn decode_blocks(format: BlockFormat, width: u32, height: u32, data: &[u8]) -> Result<TextureData, TextureError> {
+	let size = format.image_size(width, height);
+	if data.len() < size {
+		return Err(TextureError::Corrupt(format!("{}x{} {:?} image is truncated", width, height, format)));
+	}
+
+	let mut image = TextureData::with_size(width, height, size, 16)?;
+	image.pixels.resize(width as usize * height as usize * 4, 0);
+
+	let blocks_x = width.div_ceil(4) as usize;
+	for (i, block) in data[..size].chunks_exact(format.block_size()).enumerate() {
+		let texels = decode_block(format, block);
+		let (bx, by) = (i % blocks_x * 4, i / blocks_x * 4);
+
+		for (j, texel) in texels.iter().enumerate() {
+			let (x, y) = (bx + j % 4, by + j / 4);
+			if x < width as usize && y < height as usize {
+				let offset = (y * width as usize + x) * 4;
+				image.pixels[offset..offset + 4].copy_from_slice(texel);
+			}
+		}
+	}
+
+	Ok(image)
+}
+
+fn decode_block(format: BlockFormat, block: &[u8]) -> [[u8; 4]; 16] {
+	match format {
+		BlockFormat::Bc1 => decode_color(block, false),
+		BlockFormat::Bc2 => {
+			let mut texels = decode_color(&block[8..], true);
+			let alpha = u64::from_le_bytes(block[..8].try_into().unwrap());
+			for (i, texel) in texels.iter_mut().enumerate() {
+				texel[3] = ((alpha >> (4 * i)) & 0xF) as u8 * 17;
+			}
+			texels
+		}
+		BlockFormat::Bc3 => {
+			let mut texels = decode_color(&block[8..], true);
+			for (texel, alpha) in texels.iter_mut().zip(decode_channel(&block[..8])) {
+				texel[3] = alpha;
+			}
+			texels
+		}
+		BlockFormat::Bc4 => decode_channel(block).map(|red| [red, 0, 0, 255]),
+		BlockFormat::Bc5 => {
+			let green = decode_channel(&block[8..]);
+			let mut texels = [[0, 0, 0, 255]; 16];
+			for (i, red) in decode_channel(&block[..8]).into_iter().enumerate() {
+				texels[i][0] = red;
+				texels[i][1] = green[i];
+			}
+			texels
+		}
+		BlockFormat::Bc6h { signed } => decode_bc6h(block, signed),
+		BlockFormat::Bc7 => decode_bc7(block),
+	}
+}
+
+/// BC1 color block. BC2 and BC3 always interpolate four colors.
+fn decode_color(block: &[u8], four_colors: bool) -> [[u8; 4]; 16] {
+	let c0 = u16::from_le_bytes([block[0], block[1]]);
+	let c1 = u16::from_le_bytes([block[2], block[3]]);
+	let indices = u32::from_le_bytes([block[4], block[5], block[6], block[7]]);
+
+	let expand = |c: u16| {
+		let (r, g, b) = ((c >> 11) as u32, ((c >> 5) & 0x3F) as u32, (c & 0x1F) as u32);
+		[(r << 3) | (r >> 2), (g << 2) | (g >> 4), (b << 3) | (b >> 2)]
+	};
+	let (e0, e1) = (expand(c0), expand(c1));
+	let mix = |w0: u32, w1: u32| {
+		let [r, g, b] = [0, 1, 2].map(|k| ((e0[k] * w0 + e1[k] * w1) / (w0 + w1)) as u8);
+		[r, g, b, 255]
+	};
+
+	let palette = if four_colors || c0 > c1 {
+		[mix(1, 0), mix(0, 1), mix(2, 1), mix(1, 2)]
+	} else {
+		[mix(1, 0), mix(0, 1), mix(1, 1), [0, 0, 0, 0]]
+	};
+
+	std::array::from_fn(|i| palette[((indices >> (2 * i)) & 3) as usize])
+}
+
+/// BC4 channel block, also the alpha of BC3 and each half of BC5.
+fn decode_channel(block: &[u8]) -> [u8; 16] {
+	let (a0, a1) = (block[0] as usize, block[1] as usize);
+	let indices = block[2..8].iter().rev().fold(0u64, |bits, &b| (bits << 8) | b as u64);
+
+	let mut palette = [0u8; 8];
+	palette[0] = a0 as u8;
+	palette[1] = a1 as u8;
+	if a0 > a1 {
+		for i in 1..7 {
+			palette[i + 1] = (((7 - i) * a0 + i * a1) / 7) as u8;
+		}
+	} else {
+		for i in 1..5 {
+			palette[i + 1] = (((5 - i) * a0 + i * a1) / 5) as u8;
+		}
+		palette[7] = 255;
+	}
+
+	std::array::from_fn(|i| palette[((indices >> (3 * i)) & 7) as usize])
+}
+
+/// Reads a 128-bit block from the least significant bit up.
+struct Bits {
+	value: u128,
+	position: u32,
+}
+
+impl Bits {
+	fn new(block: &[u8]) -> Self {
+		Self { value: u128::from_le_bytes(block.try_into().unwrap()), position: 0 }
+	}
+
+	fn read(&mut self, count: u32) -> u32 {
+		let bits = self.value.checked_shr(self.position).unwrap_or(0) as u32 & ((1u64 << count) - 1) as u32;
+		self.position += count;
+		bits
+	}
+}
+
+/// Subset of each pixel for the 64 two-subset partitions, one bit per pixel.
+const PARTITIONS_2: [u16; 64] = [
+	0xCCCC, 0x8888, 0xEEEE, 0xECC8, 0xC880, 0xFEEC, 0xFEC8, 0xEC80,
+	0xC800, 0xFFEC, 0xFE80, 0xE800, 0xFFE8, 0xFF00, 0xFFF0, 0xF000,
+	0xF710, 0x008E, 0x7100, 0x08CE, 0x008C, 0x7310, 0x3100, 0x8CCE,
+	0x088C, 0x3110, 0x6666, 0x366C, 0x17E8, 0x0FF0, 0x718E, 0x399C,
+	0xAAAA, 0xF0F0, 0x5A5A, 0x33CC, 0x3C3C, 0x55AA, 0x9696, 0xA55A,
+	0x73CE, 0x13C8, 0x324C, 0x3BDC, 0x6996, 0xC33C, 0x9966, 0x0660,
+	0x0272, 0x04E4, 0x4E40, 0x2720, 0xC936, 0x936C, 0x39C6, 0x639C,
+	0x9336, 0x9CC6, 0x817E, 0xE718, 0xCCF0, 0x0FCC, 0x7744, 0xEE22,
+];
+
+/// Subset of each pixel for the 64 three-subset partitions, two bits per pixel.
+const PARTITIONS_3: [u32; 64] = [
+	0xAA685050, 0x6A5A5040, 0x5A5A4200, 0x5450A0A8, 0xA5A50000, 0xA0A05050, 0x5555A0A0, 0x5A5A5050,
+	0xAA550000, 0xAA555500, 0xAAAA5500, 0x90909090, 0x94949494, 0xA4A4A4A4, 0xA9A59450, 0x2A0A4250,
+	0xA5945040, 0x0A425054, 0xA5A5A500, 0x55A0A0A0, 0xA8A85454, 0x6A6A4040, 0xA4A45000, 0x1A1A0500,
+	0x0050A4A4, 0xAAA59090, 0x14696914, 0x69691400, 0xA08585A0, 0xAA821414, 0x50A4A450, 0x6A5A0200,
+	0xA9A58000, 0x5090A0A8, 0xA8A09050, 0x24242424, 0x00AA5500, 0x24924924, 0x24499224, 0x50A50A50,
+	0x500AA550, 0xAAAA4444, 0x66660000, 0xA5A0A5A0, 0x50A050A0, 0x69286928, 0x44AAAA44, 0x66666600,
+	0xAA444444, 0x54A854A8, 0x95809580, 0x96969600, 0xA85454A8, 0x80959580, 0xAA141414, 0x96960000,
+	0xAAAA1414, 0xA05050A0, 0xA0A5A5A0, 0x96000000, 0x40804080, 0xA9A8A9A8, 0xAAAAAA44, 0x2A4A5254,
+];
+
+/// Anchor pixel of the second subset of two-subset partitions.
+const ANCHORS_2: [u8; 64] = [
+	15, 15, 15, 15, 15, 15, 15, 15, 15, 15, 15, 15, 15, 15, 15, 15,
+	15, 2, 8, 2, 2, 8, 8, 15, 2, 8, 2, 2, 8, 8, 2, 2,
+	15, 15, 6, 8, 2, 8, 15, 15, 2, 8, 2, 2, 2, 15, 15, 6,
+	6, 2, 6, 8, 15, 15, 2, 2, 15, 15, 15, 15, 15, 2, 2, 15,
+];
+
+/// Anchor pixels of the second and third subsets of three-subset partitions.
+const ANCHORS_3: [[u8; 64]; 2] = [
+	[
+		3, 3, 15, 15, 8, 3, 15, 15, 8, 8, 6, 6, 6, 5, 3, 3,
+		3, 3, 8, 15, 3, 3, 6, 10, 5, 8, 8, 6, 8, 5, 15, 15,
+		8, 15, 3, 5, 6, 10, 8, 15, 15, 3, 15, 5, 15, 15, 15, 15,
+		3, 15, 5, 5, 5, 8, 5, 10, 5, 10, 8, 13, 15, 12, 3, 3,
+	],
+	[
+		15, 8, 8, 3, 15, 15, 3, 8, 15, 15, 15, 15, 15, 15, 15, 8,
+		15, 8, 15, 3, 15, 8, 15, 8, 3, 15, 6, 10, 15, 15, 10, 8,
+		15, 3, 15, 10, 10, 8, 9, 10, 6, 15, 8, 15, 3, 6, 6, 8,
+		15, 3, 15, 15, 15, 15, 15, 15, 15, 15, 15, 15, 3, 15, 15, 8,
+	],
+];
+
+fn weights(index_bits: u32) -> &'static [u32] {
+	match index_bits {
+		2 => &[0, 21, 43, 64],
+		3 => &[0, 9, 18, 27, 37, 46, 55, 64],
+		_ => &[0, 4, 9, 13, 17, 21, 26, 30, 34, 38, 43, 47, 51, 55, 60, 64],
+	}
+}
+
+fn interpolate(e0: i32, e1: i32, weight: u32) -> i32 {
+	((64 - weight as i32) * e0 + weight as i32 * e1 + 32) >> 6
+}
+
+fn subset_of(subsets: usize, partition: usize, pixel: usize) -> usize {
+	match subsets {
+		2 => ((PARTITIONS_2[partition] >> pixel) & 1) as usize,
+		3 => ((PARTITIONS_3[partition] >> (2 * pixel)) & 3) as usize,
+		_ => 0,
+	}
+}
+
+/// Anchor pixels store their index with one bit less.
+fn is_anchor(subsets: usize, partition: usize, pixel: usize) -> bool {
+	pixel == 0 || match subsets {
+		2 => pixel == ANCHORS_2[partition] as usize,
+		3 => ANCHORS_3.iter().any(|anchors| pixel == anchors[partition] as usize),
+		_ => false,
+	}
+}
+
+struct Bc7Mode {
+	subsets: usize,
+	partition_bits: u32,
+	rotation_bits: u32,
+	selection_bits: u32,
+	color_bits: u32,
+	alpha_bits: u32,
+	endpoint_pbits: bool,
+	shared_pbits: bool,
+	index_bits: u32,
+	index2_bits: u32,
+}
+
+/// One line per mode, 0 to 7.
+const BC7_MODES: [Bc7Mode; 8] = [
+	Bc7Mode { subsets: 3, partition_bits: 4, rotation_bits: 0, selection_bits: 0, color_bits: 4, alpha_bits: 0, endpoint_pbits: true, shared_pbits: false, index_bits: 3, index2_bits: 0 },
+	Bc7Mode { subsets: 2, partition_bits: 6, rotation_bits: 0, selection_bits: 0, color_bits: 6, alpha_bits: 0, endpoint_pbits: false, shared_pbits: true, index_bits: 3, index2_bits: 0 },
+	Bc7Mode { subsets: 3, partition_bits: 6, rotation_bits: 0, selection_bits: 0, color_bits: 5, alpha_bits: 0, endpoint_pbits: false, shared_pbits: false, index_bits: 2, index2_bits: 0 },
+	Bc7Mode { subsets: 2, partition_bits: 6, rotation_bits: 0, selection_bits: 0, color_bits: 7, alpha_bits: 0, endpoint_pbits: true, shared_pbits: false, index_bits: 2, index2_bits: 0 },
+	Bc7Mode { subsets: 1, partition_bits: 0, rotation_bits: 2, selection_bits: 1, color_bits: 5, alpha_bits: 6, endpoint_pbits: false, shared_pbits: false, index_bits: 2, index2_bits: 3 },
+	Bc7Mode { subsets: 1, partition_bits: 0, rotation_bits: 2, selection_bits: 0, color_bits: 7, alpha_bits: 8, endpoint_pbits: false, shared_pbits: false, index_bits: 2, index2_bits: 2 },
+	Bc7Mode { subsets: 1, partition_bits: 0, rotation_bits: 0, selection_bits: 0, color_bits: 7, alpha_bits: 7, endpoint_pbits: true, shared_pbits: false, index_bits: 4, index2_bits: 0 },
+	Bc7Mode { subsets: 2, partition_bits: 6, rotation_bits: 0, selection_bits: 0, color_bits: 5, alpha_bits: 5, endpoint_pbits: true, shared_pbits: false, index_bits: 2, index2_bits: 0 },
+];
+
+fn decode_bc7(block: &[u8]) -> [[u8; 4]; 16] {
+	let mode_number = block[0].trailing_zeros();
+	// Reserved mode, decoded as transparent black
+	let Some(mode) = BC7_MODES.get(mode_number as usize) else {
+		return [[0; 4]; 16];
+	};
+
+	let mut bits = Bits::new(block);
+	bits.read(mode_number + 1);
+	let partition = bits.read(mode.partition_bits) as usize;
+	let rotation = bits.read(mode.rotation_bits);
+	let selection = bits.read(mode.selection_bits);
+
+	// endpoints[subset][end][channel]
+	let mut endpoints = [[[0u32; 4]; 2]; 3];
+	let channels = if mode.alpha_bits > 0 { 4 } else { 3 };
+	for channel in 0..channels {
+		let count = if channel == 3 { mode.alpha_bits } else { mode.color_bits };
+		for subset in endpoints.iter_mut().take(mode.subsets) {
+			for end in subset.iter_mut() {
+				end[channel] = bits.read(count);
+			}
+		}
+	}
+
+	let mut pbits = [[0u32; 2]; 3];
+	if mode.endpoint_pbits {
+		for subset in pbits.iter_mut().take(mode.subsets) {
+			*subset = [bits.read(1), bits.read(1)];
+		}
+	} else if mode.shared_pbits {
+		for subset in pbits.iter_mut().take(mode.subsets) {
+			let pbit = bits.read(1);
+			*subset = [pbit, pbit];
+		}
+	}
+
+	let has_pbits = (mode.endpoint_pbits || mode.shared_pbits) as u32;
+	for (subset, pbits) in endpoints.iter_mut().zip(pbits) {
+		for (end, pbit) in subset.iter_mut().zip(pbits) {
+			for (channel, value) in end.iter_mut().enumerate() {
+				if channel == 3 && mode.alpha_bits == 0 {
+					*value = 255;
+					continue;
+				}
+				let precision = if channel == 3 { mode.alpha_bits } else { mode.color_bits } + has_pbits;
+				let v = (*value << has_pbits) | (pbit & has_pbits);
+				*value = (v << (8 - precision)) | (v >> (2 * precision - 8));
+			}
+		}
+	}
+
+	let read_indices = |bits: &mut Bits, count: u32, anchored: &dyn Fn(usize) -> bool| -> [u32; 16] {
+		std::array::from_fn(|pixel| bits.read(count - anchored(pixel) as u32))
+	};
+	let primary = read_indices(&mut bits, mode.index_bits, &|pixel| is_anchor(mode.subsets, partition, pixel));
+	let secondary = if mode.index2_bits > 0 {
+		read_indices(&mut bits, mode.index2_bits, &|pixel| pixel == 0)
+	} else {
+		[0; 16]
+	};
+
+	// Modes 4 and 5 weight alpha with the second index set, or color when selected
+	let (color_bits, color_index, alpha_bits, alpha_index) = match (mode.index2_bits, selection) {
+		(0, _) => (mode.index_bits, primary, mode.index_bits, primary),
+		(_, 0) => (mode.index_bits, primary, mode.index2_bits, secondary),
+		_ => (mode.index2_bits, secondary, mode.index_bits, primary),
+	};
+
+	std::array::from_fn(|pixel| {
+		let [e0, e1] = endpoints[subset_of(mode.subsets, partition, pixel)];
+		let color_weight = weights(color_bits)[color_index[pixel] as usize];
+		let alpha_weight = weights(alpha_bits)[alpha_index[pixel] as usize];
+
+		let mut texel = [0u8; 4];
+		for channel in 0..4 {
+			let weight = if channel == 3 { alpha_weight } else { color_weight };
+			texel[channel] = interpolate(e0[channel] as i32, e1[channel] as i32, weight) as u8;
+		}
+		if rotation > 0 {
+			texel.swap(3, rotation as usize - 1);
+		}
+		texel
+	})
+}
+
+/// Destination of a BC6H endpoint field: channel * 4 + endpoint.
+const R0: u8 = 0;
+const R1: u8 = 1;
+const R2: u8 = 2;
+const R3: u8 = 3;
+const G0: u8 = 4;
+const G1: u8 = 5;
+const G2: u8 = 6;
+const G3: u8 = 7;
+const B0: u8 = 8;
+const B1: u8 = 9;
+const B2: u8 = 10;
+const B3: u8 = 11;
+
+/// Bits `[first, first + count)` of an endpoint, stored from the most
+/// significant bit down when `reversed`.
+#[derive(Clone, Copy)]
+struct Field {
+	target: u8,
+	first: u8,
+	count: u8,
+	reversed: bool,
+}
+
+const fn f(target: u8, first: u8, count: u8) -> Field {
+	Field { target, first, count, reversed: false }
+}
+
+const fn rev(target: u8, first: u8, count: u8) -> Field {
+	Field { target, first, count, reversed: true }
+}
+
+struct Bc6hMode {
+	mode: u32,
+	two_regions: bool,
+	transformed: bool,
+	endpoint_bits: u32,
+	delta_bits: [u32; 3],
+	layout: &'static [Field],
+}
+
+/// The 14 BC6H modes with the bit layout of their endpoints after the mode
+/// bits, as given by the format specification.
+const BC6H_MODES: [Bc6hMode; 14] = [
+	Bc6hMode { mode: 0, two_regions: true, transformed: true, endpoint_bits: 10, delta_bits: [5, 5, 5], layout: &[
+		f(G2, 4, 1), f(B2, 4, 1), f(B3, 4, 1), f(R0, 0, 10), f(G0, 0, 10), f(B0, 0, 10), f(R1, 0, 5), f(G3, 4, 1),
+		f(G2, 0, 4), f(G1, 0, 5), f(B3, 0, 1), f(G3, 0, 4), f(B1, 0, 5), f(B3, 1, 1), f(B2, 0, 4), f(R2, 0, 5),
+		f(B3, 2, 1), f(R3, 0, 5), f(B3, 3, 1),
+	] },
+	Bc6hMode { mode: 1, two_regions: true, transformed: true, endpoint_bits: 7, delta_bits: [6, 6, 6], layout: &[
+		f(G2, 5, 1), f(G3, 4, 1), f(G3, 5, 1), f(R0, 0, 7), f(B3, 0, 1), f(B3, 1, 1), f(B2, 4, 1), f(G0, 0, 7),
+		f(B2, 5, 1), f(B3, 2, 1), f(G2, 4, 1), f(B0, 0, 7), f(B3, 3, 1), f(B3, 5, 1), f(B3, 4, 1), f(R1, 0, 6),
+		f(G2, 0, 4), f(G1, 0, 6), f(G3, 0, 4), f(B1, 0, 6), f(B2, 0, 4), f(R2, 0, 6), f(R3, 0, 6),
+	] },
+	Bc6hMode { mode: 2, two_regions: true, transformed: true, endpoint_bits: 11, delta_bits: [5, 4, 4], layout: &[
+		f(R0, 0, 10), f(G0, 0, 10), f(B0, 0, 10), f(R1, 0, 5), f(R0, 10, 1), f(G2, 0, 4), f(G1, 0, 4), f(G0, 10, 1),
+		f(B3, 0, 1), f(G3, 0, 4), f(B1, 0, 4), f(B0, 10, 1), f(B3, 1, 1), f(B2, 0, 4), f(R2, 0, 5), f(B3, 2, 1),
+		f(R3, 0, 5), f(B3, 3, 1),
+	] },
+	Bc6hMode { mode: 6, two_regions: true, transformed: true, endpoint_bits: 11, delta_bits: [4, 5, 4], layout: &[
+		f(R0, 0, 10), f(G0, 0, 10), f(B0, 0, 10), f(R1, 0, 4), f(R0, 10, 1), f(G3, 4, 1), f(G2, 0, 4), f(G1, 0, 5),
+		f(G0, 10, 1), f(G3, 0, 4), f(B1, 0, 4), f(B0, 10, 1), f(B3, 1, 1), f(B2, 0, 4), f(R2, 0, 4), f(B3, 0, 1),
+		f(B3, 2, 1), f(R3, 0, 4), f(G2, 4, 1), f(B3, 3, 1),
+	] },
+	Bc6hMode { mode: 10, two_regions: true, transformed: true, endpoint_bits: 11, delta_bits: [4, 4, 5], layout: &[
+		f(R0, 0, 10), f(G0, 0, 10), f(B0, 0, 10), f(R1, 0, 4), f(R0, 10, 1), f(B2, 4, 1), f(G2, 0, 4), f(G1, 0, 4),
+		f(G0, 10, 1), f(B3, 0, 1), f(G3, 0, 4), f(B1, 0, 5), f(B0, 10, 1), f(B2, 0, 4), f(R2, 0, 4), f(B3, 1, 1),
+		f(B3, 2, 1), f(R3, 0, 4), f(B3, 4, 1), f(B3, 3, 1),
+	] },
+	Bc6hMode { mode: 14, two_regions: true, transformed: true, endpoint_bits: 9, delta_bits: [5, 5, 5], layout: &[
+		f(R0, 0, 9), f(B2, 4, 1), f(G0, 0, 9), f(G2, 4, 1), f(B0, 0, 9), f(B3, 4, 1), f(R1, 0, 5), f(G3, 4, 1),
+		f(G2, 0, 4), f(G1, 0, 5), f(B3, 0, 1), f(G3, 0, 4), f(B1, 0, 5), f(B3, 1, 1), f(B2, 0, 4), f(R2, 0, 5),
+		f(B3, 2, 1), f(R3, 0, 5), f(B3, 3, 1),
+	] },
+	Bc6hMode { mode: 18, two_regions: true, transformed: true, endpoint_bits: 8, delta_bits: [6, 5, 5], layout: &[
+		f(R0, 0, 8), f(G3, 4, 1), f(B2, 4, 1), f(G0, 0, 8), f(B3, 2, 1), f(G2, 4, 1), f(B0, 0, 8), f(B3, 3, 1),
+		f(B3, 4, 1), f(R1, 0, 6), f(G2, 0, 4), f(G1, 0, 5), f(B3, 0, 1), f(G3, 0, 4), f(B1, 0, 5), f(B3, 1, 1),
+		f(B2, 0, 4), f(R2, 0, 6), f(R3, 0, 6),
+	] },
+	Bc6hMode { mode: 22, two_regions: true, transformed: true, endpoint_bits: 8, delta_bits: [5, 6, 5], layout: &[
+		f(R0, 0, 8), f(B3, 0, 1), f(B2, 4, 1), f(G0, 0, 8), f(G2, 5, 1), f(G2, 4, 1), f(B0, 0, 8), f(G3, 5, 1),
+		f(B3, 4, 1), f(R1, 0, 5), f(G3, 4, 1), f(G2, 0, 4), f(G1, 0, 6), f(G3, 0, 4), f(B1, 0, 5), f(B3, 1, 1),
+		f(B2, 0, 4), f(R2, 0, 5), f(B3, 2, 1), f(R3, 0, 5), f(B3, 3, 1),
+	] },
+	Bc6hMode { mode: 26, two_regions: true, transformed: true, endpoint_bits: 8, delta_bits: [5, 5, 6], layout: &[
+		f(R0, 0, 8), f(B3, 1, 1), f(B2, 4, 1), f(G0, 0, 8), f(B2, 5, 1), f(G2, 4, 1), f(B0, 0, 8), f(B3, 5, 1),
+		f(B3, 4, 1), f(R1, 0, 5), f(G3, 4, 1), f(G2, 0, 4), f(G1, 0, 5), f(B3, 0, 1), f(G3, 0, 4), f(B1, 0, 6),
+		f(B2, 0, 4), f(R2, 0, 5), f(B3, 2, 1), f(R3, 0, 5), f(B3, 3, 1),
+	] },
+	Bc6hMode { mode: 30, two_regions: true, transformed: false, endpoint_bits: 6, delta_bits: [6, 6, 6], layout: &[
+		f(R0, 0, 6), f(G3, 4, 1), f(B3, 0, 1), f(B3, 1, 1), f(B2, 4, 1), f(G0, 0, 6), f(G2, 5, 1), f(B2, 5, 1),
+		f(B3, 2, 1), f(G2, 4, 1), f(B0, 0, 6), f(G3, 5, 1), f(B3, 3, 1), f(B3, 5, 1), f(B3, 4, 1), f(R1, 0, 6),
+		f(G2, 0, 4), f(G1, 0, 6), f(G3, 0, 4), f(B1, 0, 6), f(B2, 0, 4), f(R2, 0, 6), f(R3, 0, 6),
+	] },
+	Bc6hMode { mode: 3, two_regions: false, transformed: false, endpoint_bits: 10, delta_bits: [10, 10, 10], layout: &[
+		f(R0, 0, 10), f(G0, 0, 10), f(B0, 0, 10), f(R1, 0, 10), f(G1, 0, 10), f(B1, 0, 10),
+	] },
+	Bc6hMode { mode: 7, two_regions: false, transformed: true, endpoint_bits: 11, delta_bits: [9, 9, 9], layout: &[
+		f(R0, 0, 10), f(G0, 0, 10), f(B0, 0, 10), f(R1, 0, 9), f(R0, 10, 1), f(G1, 0, 9), f(G0, 10, 1), f(B1, 0, 9),
+		f(B0, 10, 1),
+	] },
+	Bc6hMode { mode: 11, two_regions: false, transformed: true, endpoint_bits: 12, delta_bits: [8, 8, 8], layout: &[
+		f(R0, 0, 10), f(G0, 0, 10), f(B0, 0, 10), f(R1, 0, 8), rev(R0, 10, 2), f(G1, 0, 8), rev(G0, 10, 2), f(B1, 0, 8),
+		rev(B0, 10, 2),
+	] },
+	Bc6hMode { mode: 15, two_regions: false, transformed: true, endpoint_bits: 16, delta_bits: [4, 4, 4], layout: &[
+		f(R0, 0, 10), f(G0, 0, 10), f(B0, 0, 10), f(R1, 0, 4), rev(R0, 10, 6), f(G1, 0, 4), rev(G0, 10, 6), f(B1, 0, 4),
+		rev(B0, 10, 6),
+	] },
+];
+
+fn sign_extend(value: i32, bits: u32) -> i32 {
+	let shift = 32 - bits;
+	(value << shift) >> shift
+}
+
+fn decode_bc6h(block: &[u8], signed: bool) -> [[u8; 4]; 16] {
+	let mut bits = Bits::new(block);
+	let mut mode_number = bits.read(2);
+	if mode_number > 1 {
+		mode_number |= bits.read(3) << 2;
+	}
+	// Reserved mode, decoded as black
+	let Some(mode) = BC6H_MODES.iter().find(|m| m.mode == mode_number) else {
+		return [[0, 0, 0, 255]; 16];
+	};
+
+	let mut endpoints = [0i32; 12];
+	for field in mode.layout {
+		let mut value = bits.read(field.count as u32);
+		if field.reversed {
+			value = value.reverse_bits() >> (32 - field.count as u32);
+		}
+		endpoints[field.target as usize] |= (value << field.first) as i32;
+	}
+	let partition = if mode.two_regions { bits.read(5) as usize } else { 0 };
+
+	let ends = if mode.two_regions { 4 } else { 2 };
+	let mask = (1i32 << mode.endpoint_bits) - 1;
+	for channel in 0..3 {
+		let base = channel * 4;
+		if signed {
+			endpoints[base] = sign_extend(endpoints[base], mode.endpoint_bits);
+		}
+		let first = endpoints[base];
+		for value in &mut endpoints[base + 1..base + ends] {
+			if mode.transformed || signed {
+				*value = sign_extend(*value, mode.delta_bits[channel]);
+			}
+			if mode.transformed {
+				*value = (first + *value) & mask;
+				if signed {
+					*value = sign_extend(*value, mode.endpoint_bits);
+				}
+			}
+		}
+	}
+
+	let unquantized = endpoints.map(|v| unquantize(v, mode.endpoint_bits, signed));
+
+	let subsets = if mode.two_regions { 2 } else { 1 };
+	let index_bits = if mode.two_regions { 3 } else { 4 };
+	let indices: [u32; 16] = std::array::from_fn(|pixel| {
+		let anchor = pixel == 0 || (mode.two_regions && pixel == ANCHORS_2[partition] as usize);
+		bits.read(index_bits - anchor as u32)
+	});
+
+	std::array::from_fn(|pixel| {
+		let subset = subset_of(subsets, partition, pixel);
+		let weight = weights(index_bits)[indices[pixel] as usize];
+		let mut texel = [0, 0, 0, 255];
+		for (channel, value) in texel.iter_mut().take(3).enumerate() {
+			let e0 = unquantized[channel * 4 + subset * 2];
+			let e1 = unquantized[channel * 4 + subset * 2 + 1];
+			let half = finish_unquantize(interpolate(e0, e1, weight), signed);
+			*value = (half_to_f32(half).clamp(0.0, 1.0) * 255.0).round() as u8;
+		}
+		texel
+	})
+}
+
+fn unquantize(value: i32, bits: u32, signed: bool) -> i32 {
+	if !signed {
+		return match value {
+			_ if bits >= 15 => value,
+			0 => 0,
+			_ if value == (1 << bits) - 1 => 0xFFFF,
+			_ => ((value << 16) + 0x8000) >> bits,
+		};
+	}
+
+	if bits >= 16 {
+		return value;
+	}
+	let magnitude = value.abs();
+	let unsigned = if magnitude == 0 {
+		0
+	} else if magnitude >= (1 << (bits - 1)) - 1 {
+		0x7FFF
+	} else {
+		((magnitude << 15) + 0x4000) >> (bits - 1)
+	};
+	if value < 0 { -unsigned } else { unsigned }
+}
+
+/// Scales an interpolated value to the bits of a half float.
+fn finish_unquantize(value: i32, signed: bool) -> u16 {
+	if !signed {
+		return ((value * 31) >> 6) as u16;
+	}
+	if value < 0 {
+		0x8000 | (((-value) * 31) >> 5) as u16
+	} else {
+		((value * 31) >> 5) as u16
+	}
+}
+
+fn half_to_f32(half: u16) -> f32 {
+	let sign = if half & 0x8000 != 0 { -1.0 } else { 1.0 };
+	let exponent = ((half >> 10) & 0x1F) as i32;
+	let mantissa = (half & 0x3FF) as f32;
+
+	sign * match exponent {
+		0 => mantissa * 2f32.powi(-24),
+		31 if mantissa == 0.0 => f32::INFINITY,
+		31 => f32::NAN,
+		_ => (1.0 + mantissa / 1024.0) * 2f32.powi(exponent - 15),
+	}
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bc1_palettes_and_clipped_blocks() {
+        // Red and blue endpoints; pixel 1 picks the 2:1 mix, pixel 2 the second endpoint
+        let block = [0x00, 0xF8, 0x1F, 0x00, 0b0001_1000, 0, 0, 0];
+        let image = decode_blocks(BlockFormat::Bc1, 3, 1, &block).unwrap();
+        assert_eq!(image.pixels, vec![255, 0, 0, 255, 170, 0, 85, 255, 0, 0, 255, 255]);
+
+        // Swapped endpoints select the three-color mode with transparent black
+        let block = [0x1F, 0x00, 0x00, 0xF8, 0b11, 0, 0, 0];
+        let image = decode_blocks(BlockFormat::Bc1, 1, 1, &block).unwrap();
+        assert_eq!(image.pixels, vec![0, 0, 0, 0]);
+
+        assert!(matches!(decode_blocks(BlockFormat::Bc1, 5, 1, &block), Err(TextureError::Corrupt(_))));
+    }
+
+    #[test]
+    fn bc3_and_bc5_channels() {
+        // Alpha 255 to 0 with index 7 (the 6:1 step), color white
+        let mut block = [255, 0, 7, 0, 0, 0, 0, 0, 0xFF, 0xFF, 0xFF, 0xFF, 0, 0, 0, 0];
+        let image = decode_blocks(BlockFormat::Bc3, 1, 1, &block).unwrap();
+        assert_eq!(image.pixels, vec![255, 255, 255, 36]);
+
+        // Six-value mode: indices 6 and 7 are 0 and 255
+        block[..8].copy_from_slice(&[10, 20, 6 | (7 << 3), 0, 0, 0, 0, 0]);
+        let image = decode_blocks(BlockFormat::Bc5, 2, 1, &block).unwrap();
+        assert_eq!(image.pixels, vec![0, 255, 0, 255, 255, 255, 0, 255]);
+    }
+
+    #[test]
+    fn bc7_mode_6() {
+        // Mode 6: RGBA 7.7.7.7 endpoints plus a p-bit each, 4-bit indices
+        let mut bits: u128 = 1 << 6;
+        let mut position = 7;
+        let mut push = |value: u128, count: u32| {
+            bits |= value << position;
+            position += count;
+        };
+        for e in [[0, 127], [0, 127], [0, 127], [127, 64]] {
+            push(e[0], 7);
+            push(e[1], 7);
+        }
+        push(0, 1);
+        push(1, 1);
+        push(0, 3);
+        push(15, 4);
+        let image = decode_blocks(BlockFormat::Bc7, 2, 1, &bits.to_le_bytes()).unwrap();
+
+        assert_eq!(image.pixels, vec![0, 0, 0, 254, 255, 255, 255, 129]);
+    }
+
+    #[test]
+    fn bc6h_one_region_unsigned() {
+        // Mode 11: 10-bit endpoints, black to full range, 4-bit indices
+        let mut bits: u128 = 0b00011;
+        bits |= 0x3FF << 35 | 0x3FF << 45 | 0x3FF << 55;
+        bits |= 15 << 68;
+        let image = decode_blocks(BlockFormat::Bc6h { signed: false }, 2, 1, &bits.to_le_bytes()).unwrap();
+
+        // 0xFFFF maps to the largest finite half, clamped to white
+        assert_eq!(image.pixels, vec![0, 0, 0, 255, 255, 255, 255, 255]);
+    }
+
+    #[test]
+    fn partition_anchors_belong_to_their_subset() {
+        for partition in 0..64 {
+            assert_eq!(subset_of(2, partition, ANCHORS_2[partition] as usize), 1);
+            assert_eq!(subset_of(3, partition, ANCHORS_3[0][partition] as usize), 1);
+            assert_eq!(subset_of(3, partition, ANCHORS_3[1][partition] as usize), 2);
+        }
+    }
+}
@@ -0,0 +1,157 @@
+use crate::parser::error::TextureError;
+use crate::parser::texture::{TextureData, read_le};
+
+const FILE_HEADER_SIZE: usize = 14;
+const INFO_HEADER_SIZE: usize = 40;
+
+const BI_RGB: u32 = 0;
+const BI_BITFIELDS: u32 = 3;
+const BI_ALPHABITFIELDS: u32 = 6;
+
+/// Channel masks of a 32-bit pixel, in red, green, blue, alpha order.
+type Masks = [u32; 4];
+
+/// Decodes an uncompressed or bit-field 24 or 32-bit BMP.
+pub fn decode_bmp(bytes: &[u8]) -> Result<TextureData, TextureError> {
+	let corrupt = |message: &str| TextureError::Corrupt(format!("BMP {}", message));
+	let field = |offset: usize| read_le::<4>(bytes, offset).ok_or_else(|| corrupt("header is truncated"));
+
+	if !bytes.starts_with(b"BM") {
+		return Err(corrupt("signature is missing"));
+	}
+	let data_offset = field(10)? as usize;
+	let header_size = field(FILE_HEADER_SIZE)? as usize;
+	if header_size < INFO_HEADER_SIZE {
+		return Err(TextureError::Unsupported(format!("BMP header of {} bytes", header_size)));
+	}
+
+	let width = field(18)? as i32;
+	let height = field(22)? as i32;
+	let bits = read_le::<2>(bytes, 28).ok_or_else(|| corrupt("header is truncated"))?;
+	let compression = field(30)?;
+	if width <= 0 || height == 0 {
+		return Err(corrupt("has an empty size"));
+	}
+	let top_down = height < 0;
+	let (width, height) = (width as u32, height.unsigned_abs());
+
+	let masks: Masks = match (bits, compression) {
+		(24, BI_RGB) => [0; 4],
+		(32, BI_RGB) => [0x00FF_0000, 0x0000_FF00, 0x0000_00FF, 0],
+		(32, BI_BITFIELDS | BI_ALPHABITFIELDS) => {
+			// Masks follow a plain info header and are part of any longer one
+			let base = FILE_HEADER_SIZE + INFO_HEADER_SIZE;
+			let has_alpha = compression == BI_ALPHABITFIELDS || header_size >= 56;
+			[
+				field(base)?,
+				field(base + 4)?,
+				field(base + 8)?,
+				if has_alpha { field(base + 12)? } else { 0 },
+			]
+		}
+		_ => return Err(TextureError::Unsupported(format!("BMP with {} bits per pixel and compression {}", bits, compression))),
+	};
+
+	let stride = (bits as usize * width as usize).div_ceil(32) * 4;
+	let data = bytes.get(data_offset..).ok_or_else(|| corrupt("pixel data is truncated"))?;
+	let mut image = TextureData::with_size(width, height, data.len(), 1)?;
+	let size = stride.checked_mul(height as usize)
+		.filter(|&size| size <= data.len())
+		.ok_or_else(|| corrupt("pixel data is truncated"))?;
+
+	for row in data[..size].chunks_exact(stride) {
+		if bits == 24 {
+			for pixel in row.chunks_exact(3).take(width as usize) {
+				image.pixels.extend([pixel[2], pixel[1], pixel[0], 255]);
+			}
+		} else {
+			for pixel in row.chunks_exact(4) {
+				let value = u32::from_le_bytes([pixel[0], pixel[1], pixel[2], pixel[3]]);
+				image.pixels.extend(masks.map(|mask| extract(value, mask)));
+			}
+		}
+	}
+
+	if !top_down {
+		image.flip_vertically();
+	}
+
+	Ok(image)
+}
+
+/// Scales the bits of `value` under `mask` to 0–255; an empty mask is opaque.
+fn extract(value: u32, mask: u32) -> u8 {
+	if mask == 0 {
+		return 255;
+	}
+	let max = mask >> mask.trailing_zeros();
+	let bits = (value & mask) >> mask.trailing_zeros();
+
+	((bits as u64 * 255 + max as u64 / 2) / max as u64) as u8
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bmp(width: i32, height: i32, bits: u16, compression: u32, masks: &[u32], pixels: &[u8]) -> Vec<u8> {
+        let data_offset = 54 + masks.len() as u32 * 4;
+        let mut bytes = b"BM".to_vec();
+        bytes.extend((data_offset + pixels.len() as u32).to_le_bytes());
+        bytes.extend([0; 4]);
+        bytes.extend(data_offset.to_le_bytes());
+        bytes.extend(40_u32.to_le_bytes());
+        bytes.extend(width.to_le_bytes());
+        bytes.extend(height.to_le_bytes());
+        bytes.extend(1_u16.to_le_bytes());
+        bytes.extend(bits.to_le_bytes());
+        bytes.extend(compression.to_le_bytes());
+        bytes.extend([0; 20]);
+        for mask in masks {
+            bytes.extend(mask.to_le_bytes());
+        }
+        bytes.extend(pixels);
+        bytes
+    }
+
+    #[test]
+    fn padded_24_bit_bottom_up() {
+        // Rows of two BGR pixels padded to 8 bytes, bottom row first
+        let pixels = [
+            255, 0, 0, 0, 255, 0, 0, 0,
+            0, 0, 255, 10, 20, 30, 0, 0,
+        ];
+        let image = decode_bmp(&bmp(2, 2, 24, BI_RGB, &[], &pixels)).unwrap();
+
+        assert_eq!((image.width, image.height), (2, 2));
+        assert_eq!(image.pixels, vec![
+            255, 0, 0, 255, 30, 20, 10, 255,
+            0, 0, 255, 255, 0, 255, 0, 255,
+        ]);
+    }
+
+    #[test]
+    fn bitfields_with_alpha_top_down() {
+        let masks = [0x0000_00FF, 0x0000_FF00, 0x00FF_0000, 0xFF00_0000];
+        let pixels = [1, 2, 3, 4, 250, 251, 252, 128];
+        let image = decode_bmp(&bmp(2, -1, 32, BI_ALPHABITFIELDS, &masks, &pixels)).unwrap();
+        assert_eq!(image.pixels, vec![1, 2, 3, 4, 250, 251, 252, 128]);
+
+        // 5-6-5 style narrow masks are scaled to the full range
+        let masks = [0xF800, 0x07E0, 0x001F];
+        let pixels = 0xFFE0_u32.to_le_bytes();
+        let image = decode_bmp(&bmp(1, 1, 32, BI_BITFIELDS, &masks, &pixels)).unwrap();
+        assert_eq!(image.pixels, vec![255, 255, 0, 255]);
+    }
+
+    #[test]
+    fn invalid_files_are_rejected() {
+        let truncated = bmp(4, 4, 24, BI_RGB, &[], &[0; 20]);
+        assert!(matches!(decode_bmp(&truncated), Err(TextureError::Corrupt(_))));
+
+        let paletted = bmp(1, 1, 8, BI_RGB, &[], &[0; 4]);
+        assert!(matches!(decode_bmp(&paletted), Err(TextureError::Unsupported(_))));
+
+        assert!(matches!(decode_bmp(b"BM"), Err(TextureError::Corrupt(_))));
+    }
+}
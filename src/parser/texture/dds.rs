@@ -0,0 +1,133 @@
+use crate::parser::error::TextureError;
+use crate::parser::texture::bc::BlockFormat;
+use crate::parser::texture::{CompressedTexture, read_le};
+
+pub const MAGIC: &[u8; 4] = b"DDS ";
+
+const HEADER_SIZE: u32 = 124;
+const DX10_HEADER_SIZE: usize = 20;
+
+const DDPF_FOURCC: u32 = 0x4;
+const DDSCAPS2_VOLUME: u32 = 0x20_0000;
+
+/// Parses a DDS holding BC1 to BC7 blocks, keeping its mip chain.
+///
+/// Only the first image of arrays and cube maps is read. Legacy `DXTn`
+/// color formats carry no color space and are taken as sRGB, like every
+/// other color texture.
+pub fn parse_dds(bytes: &[u8]) -> Result<CompressedTexture, TextureError> {
+	let field = |offset: usize| read_le::<4>(bytes, offset)
+		.ok_or_else(|| TextureError::Corrupt("DDS header is truncated".to_string()));
+
+	if !bytes.starts_with(MAGIC) || field(4)? != HEADER_SIZE {
+		return Err(TextureError::Corrupt("DDS header is invalid".to_string()));
+	}
+	let height = field(12)?;
+	let width = field(16)?;
+	let mip_count = field(28)?;
+	let pixel_flags = field(80)?;
+	let caps2 = field(112)?;
+	let four_cc: [u8; 4] = bytes[84..88].try_into().unwrap();
+	if caps2 & DDSCAPS2_VOLUME != 0 {
+		return Err(TextureError::Unsupported("DDS volume texture".to_string()));
+	}
+
+	let mut data_offset = 4 + HEADER_SIZE as usize;
+	let (format, srgb) = match &four_cc {
+		_ if pixel_flags & DDPF_FOURCC == 0 => {
+			return Err(TextureError::Unsupported("DDS without block compression".to_string()));
+		}
+		b"DX10" => {
+			data_offset += DX10_HEADER_SIZE;
+			let dxgi_format = field(128)?;
+			dxgi_block_format(dxgi_format)
+				.ok_or_else(|| TextureError::Unsupported(format!("DDS DXGI format {}", dxgi_format)))?
+		}
+		b"DXT1" => (BlockFormat::Bc1, true),
+		b"DXT2" | b"DXT3" => (BlockFormat::Bc2, true),
+		b"DXT4" | b"DXT5" => (BlockFormat::Bc3, true),
+		b"ATI1" | b"BC4U" => (BlockFormat::Bc4, false),
+		b"ATI2" | b"BC5U" => (BlockFormat::Bc5, false),
+		_ => return Err(TextureError::Unsupported(format!("DDS format {}", String::from_utf8_lossy(&four_cc)))),
+	};
+
+	// Levels follow each other from the full-size image down
+	let mut offset = data_offset;
+	CompressedTexture::from_levels(format, srgb, (width, height), mip_count, |_, size| {
+		let data = bytes.get(offset..offset + size);
+		offset += size;
+		data
+	})
+}
+
+/// Block format and color space of the BC entries of `DXGI_FORMAT`.
+fn dxgi_block_format(format: u32) -> Option<(BlockFormat, bool)> {
+	Some(match format {
+		70 | 71 => (BlockFormat::Bc1, false),
+		72 => (BlockFormat::Bc1, true),
+		73 | 74 => (BlockFormat::Bc2, false),
+		75 => (BlockFormat::Bc2, true),
+		76 | 77 => (BlockFormat::Bc3, false),
+		78 => (BlockFormat::Bc3, true),
+		79 | 80 => (BlockFormat::Bc4, false),
+		82 | 83 => (BlockFormat::Bc5, false),
+		94 | 95 => (BlockFormat::Bc6h { signed: false }, false),
+		96 => (BlockFormat::Bc6h { signed: true }, false),
+		97 | 98 => (BlockFormat::Bc7, false),
+		99 => (BlockFormat::Bc7, true),
+		_ => return None,
+	})
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn dds(width: u32, height: u32, mip_count: u32, four_cc: &[u8; 4], dxgi_format: Option<u32>, data_len: usize) -> Vec<u8> {
+        let mut bytes = MAGIC.to_vec();
+        bytes.resize(128, 0);
+        bytes[4..8].copy_from_slice(&HEADER_SIZE.to_le_bytes());
+        bytes[12..16].copy_from_slice(&height.to_le_bytes());
+        bytes[16..20].copy_from_slice(&width.to_le_bytes());
+        bytes[28..32].copy_from_slice(&mip_count.to_le_bytes());
+        bytes[80..84].copy_from_slice(&DDPF_FOURCC.to_le_bytes());
+        bytes[84..88].copy_from_slice(four_cc);
+        if let Some(format) = dxgi_format {
+            bytes.extend(format.to_le_bytes());
+            bytes.extend([0; 16]);
+        }
+        bytes.extend((0..data_len).map(|i| i as u8));
+        bytes
+    }
+
+    #[test]
+    fn legacy_mip_chain() {
+        // 8x4 BC3: two blocks, then one block for 4x2 and 2x1 each, and 1x1
+        let bytes = dds(8, 4, 4, b"DXT5", None, 32 + 16 * 3);
+        let texture = parse_dds(&bytes).unwrap();
+
+        assert_eq!((texture.format, texture.srgb), (BlockFormat::Bc3, true));
+        assert_eq!(texture.levels.iter().map(Vec::len).collect::<Vec<_>>(), vec![32, 16, 16, 16]);
+        assert_eq!(texture.levels[1][0], 32);
+        assert_eq!(texture.level_extent(3), (1, 1));
+    }
+
+    #[test]
+    fn dx10_header() {
+        let texture = parse_dds(&dds(4, 4, 0, b"DX10", Some(98), 16)).unwrap();
+        assert_eq!((texture.format, texture.srgb), (BlockFormat::Bc7, false));
+        assert_eq!(texture.levels.len(), 1);
+
+        let bytes = dds(4, 4, 1, b"DX10", Some(28), 64);
+        assert!(matches!(parse_dds(&bytes), Err(TextureError::Unsupported(_))));
+    }
+
+    #[test]
+    fn truncated_levels_are_rejected() {
+        let bytes = dds(8, 8, 2, b"DXT1", None, 32);
+        assert!(matches!(parse_dds(&bytes), Err(TextureError::Corrupt(_))));
+
+        let bytes = dds(4, 4, 5, b"DXT1", None, 64);
+        assert!(matches!(parse_dds(&bytes), Err(TextureError::Corrupt(_))));
+    }
+}
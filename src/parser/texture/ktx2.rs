@@ -0,0 +1,117 @@
+use crate::parser::error::TextureError;
+use crate::parser::texture::bc::BlockFormat;
+use crate::parser::texture::CompressedTexture;
+
+pub const IDENTIFIER: [u8; 12] = [0xAB, b'K', b'T', b'X', b' ', b'2', b'0', 0xBB, b'\r', b'\n', 0x1A, b'\n'];
+
+/// Size of the fixed header and index before the level index.
+const HEADER_SIZE: usize = 80;
+const LEVEL_ENTRY_SIZE: usize = 24;
+
+/// Parses a KTX2 holding BC1 to BC7 blocks, keeping its mip chain.
+///
+/// Only the first layer and face of arrays and cube maps is read, and
+/// supercompressed files are not supported.
+pub fn parse_ktx2(bytes: &[u8]) -> Result<CompressedTexture, TextureError> {
+	let corrupt = |message: &str| TextureError::Corrupt(format!("KTX2 {}", message));
+	let u32_at = |offset: usize| bytes.get(offset..offset + 4)
+		.map(|b| u32::from_le_bytes(b.try_into().unwrap()))
+		.ok_or_else(|| corrupt("header is truncated"));
+	let u64_at = |offset: usize| bytes.get(offset..offset + 8)
+		.map(|b| u64::from_le_bytes(b.try_into().unwrap()))
+		.ok_or_else(|| corrupt("level index is truncated"));
+
+	if !bytes.starts_with(&IDENTIFIER) {
+		return Err(corrupt("identifier is missing"));
+	}
+	let vk_format = u32_at(12)?;
+	let width = u32_at(20)?;
+	let height = u32_at(24)?;
+	let depth = u32_at(28)?;
+	let level_count = u32_at(40)?;
+	let supercompression = u32_at(44)?;
+
+	let (format, srgb) = vk_block_format(vk_format)
+		.ok_or_else(|| TextureError::Unsupported(format!("KTX2 Vulkan format {}", vk_format)))?;
+	if depth > 1 {
+		return Err(TextureError::Unsupported("KTX2 volume texture".to_string()));
+	}
+	if supercompression != 0 {
+		return Err(TextureError::Unsupported(format!("KTX2 supercompression scheme {}", supercompression)));
+	}
+
+	CompressedTexture::from_levels(format, srgb, (width, height), level_count, |level, size| {
+		let entry = HEADER_SIZE + level as usize * LEVEL_ENTRY_SIZE;
+		let offset = usize::try_from(u64_at(entry).ok()?).ok()?;
+		let length = usize::try_from(u64_at(entry + 8).ok()?).ok()?;
+		// A level may hold several layers or faces, the first comes first
+		bytes.get(offset..offset.checked_add(length.min(size))?)
+	})
+}
+
+/// Block format and color space of the BC entries of `VkFormat`.
+fn vk_block_format(format: u32) -> Option<(BlockFormat, bool)> {
+	Some(match format {
+		131 | 133 => (BlockFormat::Bc1, false),
+		132 | 134 => (BlockFormat::Bc1, true),
+		135 => (BlockFormat::Bc2, false),
+		136 => (BlockFormat::Bc2, true),
+		137 => (BlockFormat::Bc3, false),
+		138 => (BlockFormat::Bc3, true),
+		139 => (BlockFormat::Bc4, false),
+		141 => (BlockFormat::Bc5, false),
+		143 => (BlockFormat::Bc6h { signed: false }, false),
+		144 => (BlockFormat::Bc6h { signed: true }, false),
+		145 => (BlockFormat::Bc7, false),
+		146 => (BlockFormat::Bc7, true),
+		_ => return None,
+	})
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// KTX2 file with `levels` given as (offset from the data start, bytes).
+    fn ktx2(vk_format: u32, width: u32, height: u32, levels: &[(usize, usize)], supercompression: u32) -> Vec<u8> {
+        let data_start = HEADER_SIZE + levels.len() * LEVEL_ENTRY_SIZE;
+        let mut bytes = IDENTIFIER.to_vec();
+        for value in [vk_format, 1, width, height, 0, 0, 1, levels.len() as u32, supercompression] {
+            bytes.extend(value.to_le_bytes());
+        }
+        bytes.resize(HEADER_SIZE, 0);
+        for &(offset, length) in levels {
+            bytes.extend(((data_start + offset) as u64).to_le_bytes());
+            bytes.extend((length as u64).to_le_bytes());
+            bytes.extend((length as u64).to_le_bytes());
+        }
+        let end = levels.iter().map(|&(offset, length)| offset + length).max().unwrap_or(0);
+        bytes.extend((0..end).map(|i| i as u8));
+        bytes
+    }
+
+    #[test]
+    fn levels_are_read_from_the_index() {
+        // Levels stored smallest first, as the format recommends
+        let bytes = ktx2(146, 8, 8, &[(32, 64), (16, 16), (0, 16)], 0);
+        let texture = parse_ktx2(&bytes).unwrap();
+
+        assert_eq!((texture.format, texture.srgb), (BlockFormat::Bc7, true));
+        assert_eq!((texture.width, texture.height), (8, 8));
+        assert_eq!(texture.levels.len(), 3);
+        assert_eq!(texture.levels[0][0], 32);
+        assert_eq!(texture.levels[2][0], 0);
+    }
+
+    #[test]
+    fn unsupported_and_truncated_files() {
+        let bytes = ktx2(37, 4, 4, &[(0, 64)], 0);
+        assert!(matches!(parse_ktx2(&bytes), Err(TextureError::Unsupported(_))));
+
+        let bytes = ktx2(131, 4, 4, &[(0, 8)], 2);
+        assert!(matches!(parse_ktx2(&bytes), Err(TextureError::Unsupported(_))));
+
+        let bytes = ktx2(131, 8, 8, &[(0, 16)], 0);
+        assert!(matches!(parse_ktx2(&bytes), Err(TextureError::Corrupt(_))));
+    }
+}
@@ -0,0 +1,195 @@
+//! Built-in decoders for texture formats the `image` crate is not built with.
+
+pub mod bc;
+pub mod bmp;
+pub mod dds;
+pub mod ktx2;
+pub mod ppm;
+pub mod tga;
+
+use std::path::Path;
+
+use crate::parser::error::TextureError;
+use crate::parser::texture::bc::{BlockFormat, decode_blocks};
+
+/// Decoded pixels, 8-bit RGBA rows from top to bottom.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TextureData {
+	pub width: u32,
+	pub height: u32,
+	pub pixels: Vec<u8>,
+}
+
+impl TextureData {
+	/// Allocates a `width` x `height` image, refusing sizes that overflow or
+	/// that `data_len` bytes of input at `max_pixels_per_byte` could not fill.
+	pub(crate) fn with_size(width: u32, height: u32, data_len: usize, max_pixels_per_byte: usize) -> Result<Self, TextureError> {
+		let pixel_count = (width as usize).checked_mul(height as usize)
+			.filter(|&count| count <= data_len.saturating_mul(max_pixels_per_byte))
+			.ok_or_else(|| TextureError::Corrupt(format!("{}x{} image does not fit its data", width, height)))?;
+
+		Ok(Self { width, height, pixels: Vec::with_capacity(pixel_count * 4) })
+	}
+
+	/// Reverses the row order, for formats stored bottom to top.
+	pub(crate) fn flip_vertically(&mut self) {
+		let row = self.width as usize * 4;
+		let height = self.height as usize;
+
+		for y in 0..height / 2 {
+			let (top, bottom) = self.pixels.split_at_mut((height - 1 - y) * row);
+			top[y * row..(y + 1) * row].swap_with_slice(&mut bottom[..row]);
+		}
+	}
+}
+
+/// Block-compressed image with its mip chain, kept encoded so it can be
+/// uploaded as is.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CompressedTexture {
+	pub format: BlockFormat,
+	pub srgb: bool,
+	pub width: u32,
+	pub height: u32,
+	/// Mip levels from full size down, each holding its blocks.
+	pub levels: Vec<Vec<u8>>,
+}
+
+impl CompressedTexture {
+	/// Gathers `level_count` mip levels, `level_data(level, size)` returning
+	/// the bytes of each.
+	pub(crate) fn from_levels<'a>(
+		format: BlockFormat,
+		srgb: bool,
+		(width, height): (u32, u32),
+		level_count: u32,
+		mut level_data: impl FnMut(u32, usize) -> Option<&'a [u8]>,
+	) -> Result<Self, TextureError> {
+		if width == 0 || height == 0 {
+			return Err(TextureError::Corrupt("texture has an empty size".to_string()));
+		}
+		let full_chain = 32 - width.max(height).leading_zeros();
+		if level_count > full_chain {
+			return Err(TextureError::Corrupt(format!("{} mip levels for a {}x{} texture", level_count, width, height)));
+		}
+
+		let mut texture = Self { format, srgb, width, height, levels: Vec::new() };
+		for level in 0..level_count.max(1) {
+			let (w, h) = texture.level_extent(level);
+			let size = format.image_size(w, h);
+			let data = level_data(level, size)
+				.filter(|data| data.len() >= size)
+				.ok_or_else(|| TextureError::Corrupt(format!("mip level {} is truncated", level)))?;
+			texture.levels.push(data[..size].to_vec());
+		}
+
+		Ok(texture)
+	}
+
+	pub fn level_extent(&self, level: u32) -> (u32, u32) {
+		((self.width >> level).max(1), (self.height >> level).max(1))
+	}
+
+	/// Decodes one mip level to RGBA8, for devices that cannot sample the
+	/// block format.
+	pub fn decode_level(&self, level: u32) -> Result<TextureData, TextureError> {
+		let (width, height) = self.level_extent(level);
+		decode_blocks(self.format, width, height, &self.levels[level as usize])
+	}
+}
+
+/// Result of [`load_builtin_texture`].
+#[derive(Debug, Clone, PartialEq)]
+pub enum LoadedTexture {
+	Decoded(TextureData),
+	Compressed(CompressedTexture),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TextureFormat {
+	Tga,
+	Bmp,
+	Ppm,
+	Ktx2,
+	Dds,
+}
+
+impl TextureFormat {
+	/// Recognizes BMP, PPM, KTX2 and DDS by their signature and TGA, which
+	/// has none, by its extension.
+	pub fn detect(path: &str, header: &[u8]) -> Option<Self> {
+		if header.starts_with(&ktx2::IDENTIFIER) {
+			return Some(TextureFormat::Ktx2);
+		}
+		if header.starts_with(dds::MAGIC) {
+			return Some(TextureFormat::Dds);
+		}
+		if header.starts_with(b"BM") {
+			return Some(TextureFormat::Bmp);
+		}
+		if header.starts_with(b"P3") || header.starts_with(b"P6") {
+			return Some(TextureFormat::Ppm);
+		}
+
+		let extension = Path::new(path).extension()?.to_string_lossy().to_lowercase();
+		match extension.as_str() {
+			"tga" | "tpic" => Some(TextureFormat::Tga),
+			_ => None,
+		}
+	}
+}
+
+/// Loads `path` when it is in a built-in format, `None` otherwise so the
+/// caller can fall back to the `image` crate. KTX2 and DDS stay compressed.
+pub fn load_builtin_texture(path: &str) -> Result<Option<LoadedTexture>, TextureError> {
+	let bytes = std::fs::read(path)
+		.map_err(|source| TextureError::Io { path: path.to_string(), source })?;
+
+	let Some(format) = TextureFormat::detect(path, &bytes) else {
+		return Ok(None);
+	};
+	let texture = match format {
+		TextureFormat::Ktx2 => LoadedTexture::Compressed(ktx2::parse_ktx2(&bytes)?),
+		TextureFormat::Dds => LoadedTexture::Compressed(dds::parse_dds(&bytes)?),
+		_ => LoadedTexture::Decoded(decode_texture(format, &bytes)?),
+	};
+
+	Ok(Some(texture))
+}
+
+/// Decodes `bytes` to RGBA8, keeping only the full-size level of KTX2 and DDS.
+pub fn decode_texture(format: TextureFormat, bytes: &[u8]) -> Result<TextureData, TextureError> {
+	match format {
+		TextureFormat::Tga => tga::decode_tga(bytes),
+		TextureFormat::Bmp => bmp::decode_bmp(bytes),
+		TextureFormat::Ppm => ppm::decode_ppm(bytes),
+		TextureFormat::Ktx2 => ktx2::parse_ktx2(bytes)?.decode_level(0),
+		TextureFormat::Dds => dds::parse_dds(bytes)?.decode_level(0),
+	}
+}
+
+/// Little-endian integer of `N` bytes at `offset`, if the data reaches it.
+pub(crate) fn read_le<const N: usize>(bytes: &[u8], offset: usize) -> Option<u32> {
+	let field = bytes.get(offset..offset + N)?;
+	Some(field.iter().rev().fold(0, |value, &b| (value << 8) | b as u32))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn formats_are_detected() {
+        assert_eq!(TextureFormat::detect("a.png", b"BM\0\0"), Some(TextureFormat::Bmp));
+        assert_eq!(TextureFormat::detect("a", b"P6\n1 1\n255\n"), Some(TextureFormat::Ppm));
+        assert_eq!(TextureFormat::detect("skin.TGA", b"\0\0\x02"), Some(TextureFormat::Tga));
+        assert_eq!(TextureFormat::detect("a.png", b"\x89PNG"), None);
+    }
+
+    #[test]
+    fn rows_are_flipped() {
+        let mut image = TextureData { width: 1, height: 3, pixels: (0..12).collect() };
+        image.flip_vertically();
+        assert_eq!(image.pixels, vec![8, 9, 10, 11, 4, 5, 6, 7, 0, 1, 2, 3]);
+    }
+}
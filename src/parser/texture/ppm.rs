@@ -0,0 +1,115 @@
+use crate::parser::error::TextureError;
+use crate::parser::texture::TextureData;
+
+/// Decodes a binary (`P6`) or ASCII (`P3`) PPM with up to 16-bit samples.
+pub fn decode_ppm(bytes: &[u8]) -> Result<TextureData, TextureError> {
+	let corrupt = |message: &str| TextureError::Corrupt(format!("PPM {}", message));
+
+	let binary = match bytes.get(..2) {
+		Some(b"P6") => true,
+		Some(b"P3") => false,
+		_ => return Err(TextureError::Unsupported("PPM other than P3 or P6".to_string())),
+	};
+
+	let mut pos = 2;
+	let mut header = [0u32; 3];
+	for value in &mut header {
+		*value = next_number(bytes, &mut pos).ok_or_else(|| corrupt("header is incomplete"))?;
+	}
+	let [width, height, max_value] = header;
+	if max_value == 0 || max_value > 65535 {
+		return Err(corrupt("maximum value is out of range"));
+	}
+
+	let scale = |sample: u32| -> Result<u8, TextureError> {
+		if sample > max_value {
+			return Err(corrupt("sample exceeds the maximum value"));
+		}
+		Ok(((sample * 255 + max_value / 2) / max_value) as u8)
+	};
+
+	let pixel_count = width as usize * height as usize;
+	if binary {
+		// A single whitespace byte separates the header from the samples
+		let data = bytes.get(pos + 1..).unwrap_or_default();
+		let sample_size = if max_value > 255 { 2 } else { 1 };
+		let mut image = TextureData::with_size(width, height, data.len() / (3 * sample_size), 1)?;
+		let samples = &data[..pixel_count * 3 * sample_size];
+
+		for pixel in samples.chunks_exact(3 * sample_size) {
+			for sample in pixel.chunks_exact(sample_size) {
+				let value = sample.iter().fold(0, |value, &b| (value << 8) | b as u32);
+				image.pixels.push(scale(value)?);
+			}
+			image.pixels.push(255);
+		}
+		Ok(image)
+	} else {
+		let mut image = TextureData::with_size(width, height, bytes.len() - pos, 1)?;
+
+		for _ in 0..pixel_count {
+			for _ in 0..3 {
+				let sample = next_number(bytes, &mut pos).ok_or_else(|| corrupt("pixel data is truncated"))?;
+				image.pixels.push(scale(sample)?);
+			}
+			image.pixels.push(255);
+		}
+		Ok(image)
+	}
+}
+
+/// Reads the next decimal number, skipping whitespace and `#` comments.
+fn next_number(bytes: &[u8], pos: &mut usize) -> Option<u32> {
+	loop {
+		match bytes.get(*pos)? {
+			b'#' => {
+				while bytes.get(*pos).is_some_and(|&b| b != b'\n') {
+					*pos += 1;
+				}
+			}
+			b if b.is_ascii_whitespace() => *pos += 1,
+			_ => break,
+		}
+	}
+
+	let start = *pos;
+	while bytes.get(*pos).is_some_and(u8::is_ascii_digit) {
+		*pos += 1;
+	}
+	std::str::from_utf8(&bytes[start..*pos]).ok()?.parse().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ascii_with_comments() {
+        let text = "P3\n# made by hand\n2 1 # size\n15\n15 0 0   0 15 7\n";
+        let image = decode_ppm(text.as_bytes()).unwrap();
+
+        assert_eq!((image.width, image.height), (2, 1));
+        assert_eq!(image.pixels, vec![255, 0, 0, 255, 0, 255, 119, 255]);
+    }
+
+    #[test]
+    fn binary_with_16_bit_samples() {
+        let mut bytes = b"P6 1 2 65535\n".to_vec();
+        bytes.extend([0xFF, 0xFF, 0x80, 0x00, 0x00, 0x00]);
+        bytes.extend([0x00, 0x00, 0x00, 0x00, 0xFF, 0xFF]);
+        let image = decode_ppm(&bytes).unwrap();
+        assert_eq!(image.pixels, vec![255, 128, 0, 255, 0, 0, 255, 255]);
+
+        let mut bytes = b"P6\n1 1\n255\n".to_vec();
+        bytes.extend([1, 2, 3]);
+        assert_eq!(decode_ppm(&bytes).unwrap().pixels, vec![1, 2, 3, 255]);
+    }
+
+    #[test]
+    fn invalid_files_are_rejected() {
+        assert!(matches!(decode_ppm(b"P6\n2 2\n255\n\x01\x02\x03"), Err(TextureError::Corrupt(_))));
+        assert!(matches!(decode_ppm(b"P3\n1 1\n255\n1 2"), Err(TextureError::Corrupt(_))));
+        assert!(matches!(decode_ppm(b"P3\n1 1\n15\n16 0 0"), Err(TextureError::Corrupt(_))));
+        assert!(matches!(decode_ppm(b"P5\n1 1\n255\n\x00"), Err(TextureError::Unsupported(_))));
+    }
+}
@@ -0,0 +1,239 @@
+use crate::parser::error::TextureError;
+use crate::parser::texture::{TextureData, read_le};
+
+const HEADER_SIZE: usize = 18;
+
+/// Descriptor bit set when rows are stored top to bottom.
+const TOP_TO_BOTTOM: u8 = 0x20;
+/// Descriptor bit set when pixels are stored right to left.
+const RIGHT_TO_LEFT: u8 = 0x10;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Kind {
+	ColorMapped,
+	TrueColor,
+	Grayscale,
+}
+
+/// Decodes an uncompressed or RLE TGA with color-mapped, true-color or
+/// grayscale pixels.
+pub fn decode_tga(bytes: &[u8]) -> Result<TextureData, TextureError> {
+	let corrupt = |message: &str| TextureError::Corrupt(format!("TGA {}", message));
+	if bytes.len() < HEADER_SIZE {
+		return Err(corrupt("header is truncated"));
+	}
+
+	let id_length = bytes[0] as usize;
+	let has_color_map = bytes[1] == 1;
+	let image_type = bytes[2];
+	let (kind, rle) = match image_type {
+		1 => (Kind::ColorMapped, false),
+		2 => (Kind::TrueColor, false),
+		3 => (Kind::Grayscale, false),
+		9 => (Kind::ColorMapped, true),
+		10 => (Kind::TrueColor, true),
+		11 => (Kind::Grayscale, true),
+		_ => return Err(TextureError::Unsupported(format!("TGA image type {}", image_type))),
+	};
+
+	let map_first = read_le::<2>(bytes, 3).unwrap() as usize;
+	let map_length = read_le::<2>(bytes, 5).unwrap() as usize;
+	let map_depth = bytes[7];
+	let width = read_le::<2>(bytes, 12).unwrap();
+	let height = read_le::<2>(bytes, 14).unwrap();
+	let depth = bytes[16];
+	let descriptor = bytes[17];
+	let has_alpha = descriptor & 0x0F != 0;
+
+	let mut offset = HEADER_SIZE + id_length;
+
+	// Entries are stored even for true-color images that carry a palette
+	let map_entry_size = (map_depth as usize).div_ceil(8);
+	let map_bytes = if has_color_map { map_length * map_entry_size } else { 0 };
+	let map_data = bytes.get(offset..offset + map_bytes)
+		.ok_or_else(|| corrupt("color map is truncated"))?;
+	offset += map_bytes;
+
+	let palette: Vec<[u8; 4]> = match kind {
+		Kind::ColorMapped => {
+			if !has_color_map {
+				return Err(corrupt("color-mapped image has no color map"));
+			}
+			if !matches!(map_depth, 15 | 16 | 24 | 32) {
+				return Err(TextureError::Unsupported(format!("TGA color map depth {}", map_depth)));
+			}
+			map_data.chunks_exact(map_entry_size)
+				.map(|entry| decode_color(entry, map_depth, has_alpha).unwrap())
+				.collect()
+		}
+		_ => Vec::new(),
+	};
+
+	let valid_depth = match kind {
+		Kind::ColorMapped => matches!(depth, 8 | 16),
+		Kind::TrueColor => matches!(depth, 15 | 16 | 24 | 32),
+		Kind::Grayscale => matches!(depth, 8 | 16),
+	};
+	if !valid_depth {
+		return Err(TextureError::Unsupported(format!("TGA {:?} image with {} bits per pixel", kind, depth)));
+	}
+	let pixel_size = (depth as usize).div_ceil(8);
+
+	let to_rgba = |pixel: &[u8]| -> Result<[u8; 4], TextureError> {
+		match kind {
+			Kind::ColorMapped => {
+				let index = pixel.iter().rev().fold(0, |value, &b| (value << 8) | b as usize);
+				index.checked_sub(map_first)
+					.and_then(|i| palette.get(i))
+					.copied()
+					.ok_or_else(|| corrupt("pixel references a missing color map entry"))
+			}
+			Kind::TrueColor => Ok(decode_color(pixel, depth, has_alpha).unwrap()),
+			Kind::Grayscale => {
+				let alpha = if pixel_size == 2 { pixel[1] } else { 255 };
+				Ok([pixel[0], pixel[0], pixel[0], alpha])
+			}
+		}
+	};
+
+	let data = &bytes[offset.min(bytes.len())..];
+	let max_pixels_per_byte = if rle { 128 } else { 1 };
+	let mut image = TextureData::with_size(width, height, data.len(), max_pixels_per_byte)?;
+	let pixel_count = width as usize * height as usize;
+
+	if rle {
+		let mut pos = 0;
+		while image.pixels.len() < pixel_count * 4 {
+			let header = *data.get(pos).ok_or_else(|| corrupt("RLE data is truncated"))?;
+			let count = (header & 0x7F) as usize + 1;
+			pos += 1;
+
+			if header & 0x80 != 0 {
+				let pixel = data.get(pos..pos + pixel_size).ok_or_else(|| corrupt("RLE data is truncated"))?;
+				let rgba = to_rgba(pixel)?;
+				for _ in 0..count {
+					image.pixels.extend(rgba);
+				}
+				pos += pixel_size;
+			} else {
+				let raw = data.get(pos..pos + count * pixel_size).ok_or_else(|| corrupt("RLE data is truncated"))?;
+				for pixel in raw.chunks_exact(pixel_size) {
+					image.pixels.extend(to_rgba(pixel)?);
+				}
+				pos += count * pixel_size;
+			}
+		}
+		// A last packet may run past the image
+		image.pixels.truncate(pixel_count * 4);
+	} else {
+		let raw = data.get(..pixel_count * pixel_size).ok_or_else(|| corrupt("pixel data is truncated"))?;
+		for pixel in raw.chunks_exact(pixel_size) {
+			image.pixels.extend(to_rgba(pixel)?);
+		}
+	}
+
+	if descriptor & RIGHT_TO_LEFT != 0 {
+		for row in image.pixels.chunks_exact_mut(width as usize * 4) {
+			row.reverse();
+			for pixel in row.chunks_exact_mut(4) {
+				pixel.reverse();
+			}
+		}
+	}
+	if descriptor & TOP_TO_BOTTOM == 0 {
+		image.flip_vertically();
+	}
+
+	Ok(image)
+}
+
+/// Converts a little-endian BGR(A) pixel of `depth` bits to RGBA.
+fn decode_color(pixel: &[u8], depth: u8, has_alpha: bool) -> Option<[u8; 4]> {
+	match depth {
+		15 | 16 => {
+			let value = u16::from_le_bytes([pixel[0], pixel[1]]);
+			let expand = |bits: u16| ((bits & 0x1F) * 255 / 31) as u8;
+			let alpha = if depth == 16 && has_alpha && value & 0x8000 == 0 { 0 } else { 255 };
+			Some([expand(value >> 10), expand(value >> 5), expand(value), alpha])
+		}
+		24 => Some([pixel[2], pixel[1], pixel[0], 255]),
+		32 => Some([pixel[2], pixel[1], pixel[0], if has_alpha { pixel[3] } else { 255 }]),
+		_ => None,
+	}
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn header(image_type: u8, width: u16, height: u16, depth: u8, descriptor: u8) -> Vec<u8> {
+        let mut bytes = vec![0, 0, image_type, 0, 0, 0, 0, 0, 0, 0, 0, 0];
+        bytes.extend(width.to_le_bytes());
+        bytes.extend(height.to_le_bytes());
+        bytes.extend([depth, descriptor]);
+        bytes
+    }
+
+    #[test]
+    fn uncompressed_bottom_up() {
+        let mut bytes = header(2, 2, 2, 24, 0);
+        // Bottom row first: blue, green, then red, white on top
+        bytes.extend([255, 0, 0, 0, 255, 0, 0, 0, 255, 255, 255, 255]);
+
+        let image = decode_tga(&bytes).unwrap();
+        assert_eq!((image.width, image.height), (2, 2));
+        assert_eq!(image.pixels, vec![
+            255, 0, 0, 255, 255, 255, 255, 255,
+            0, 0, 255, 255, 0, 255, 0, 255,
+        ]);
+    }
+
+    #[test]
+    fn rle_with_alpha_top_down() {
+        let mut bytes = header(10, 3, 1, 32, TOP_TO_BOTTOM | 8);
+        // Run of two half-transparent reds, then one raw opaque blue
+        bytes.extend([0x81, 0, 0, 255, 128, 0x00, 255, 0, 0, 255]);
+
+        let image = decode_tga(&bytes).unwrap();
+        assert_eq!(image.pixels, vec![255, 0, 0, 128, 255, 0, 0, 128, 0, 0, 255, 255]);
+    }
+
+    #[test]
+    fn color_mapped_and_grayscale() {
+        let mut bytes = header(1, 2, 1, 8, TOP_TO_BOTTOM);
+        bytes[1] = 1;
+        bytes[5..8].copy_from_slice(&[2, 0, 24]);
+        bytes.extend([0, 0, 255, 0, 255, 0]);
+        bytes.extend([1, 0]);
+        let image = decode_tga(&bytes).unwrap();
+        assert_eq!(image.pixels, vec![0, 255, 0, 255, 255, 0, 0, 255]);
+
+        let mut bytes = header(11, 2, 1, 8, TOP_TO_BOTTOM);
+        bytes.extend([0x81, 7]);
+        assert_eq!(decode_tga(&bytes).unwrap().pixels, vec![7, 7, 7, 255, 7, 7, 7, 255]);
+    }
+
+    #[test]
+    fn truncated_data_is_an_error() {
+        let mut bytes = header(2, 2, 2, 24, 0);
+        bytes.extend([0; 11]);
+        assert!(matches!(decode_tga(&bytes), Err(TextureError::Corrupt(_))));
+
+        let mut bytes = header(10, 64, 64, 24, 0);
+        bytes.extend([0xFF, 1, 2, 3]);
+        assert!(matches!(decode_tga(&bytes), Err(TextureError::Corrupt(_))));
+
+        assert!(matches!(decode_tga(&header(32, 1, 1, 8, 0)), Err(TextureError::Unsupported(_))));
+    }
+
+    #[test]
+    fn unusual_color_map_depth_is_unsupported() {
+        for map_depth in [0, 8] {
+            let mut bytes = header(1, 1, 1, 8, 0);
+            bytes[1] = 1;
+            bytes[5..8].copy_from_slice(&[1, 0, map_depth]);
+            bytes.extend([0, 0]);
+            assert!(matches!(decode_tga(&bytes), Err(TextureError::Unsupported(_))), "depth {}", map_depth);
+        }
+    }
+}
@@ -0,0 +1,205 @@
+//! Polygon triangulation by ear clipping.
+//!
+//! Polygons are projected onto their best-fit plane (Newell normal) before
+//! clipping, so concave and slightly non-planar faces are handled. When no ear
+//! can be found the caller is expected to fall back to a simple fan.
+
+/// Triangulates a polygon given its vertex positions in winding order.
+///
+/// Returns triangles as indices into `points`, keeping the original winding.
+/// Vertices collinear with their neighbours are never clipped as ears, so
+/// points in the middle of an edge, shared with the next face, stay corners.
+/// Returns `None` when the polygon is degenerate or ear clipping gets stuck
+/// (e.g. self-intersecting outlines).
+pub fn triangulate_polygon(points: &[[f32; 3]]) -> Option<Vec<[usize; 3]>> {
+	if points.len() < 3 {
+		return None;
+	}
+
+	if points.len() == 3 {
+		return Some(vec![[0, 1, 2]]);
+	}
+
+	let projected = project_to_plane(points)?;
+
+	let area = signed_area(&projected);
+	if area.abs() < f32::EPSILON {
+		return None;
+	}
+	let eps = area.abs() * 1e-6;
+
+	let mut remaining: Vec<usize> = (0..points.len()).collect();
+	let mut triangles = Vec::with_capacity(points.len() - 2);
+
+	while remaining.len() > 3 {
+		let n = remaining.len();
+		let mut clipped = false;
+
+		for i in 0..n {
+			let prev = remaining[(i + n - 1) % n];
+			let curr = remaining[i];
+			let next = remaining[(i + 1) % n];
+
+			let turn = cross_2d(projected[prev], projected[curr], projected[next]);
+
+			// Reflex vertices are no ears, and collinear ones would enclose no area
+			if turn <= eps {
+				continue;
+			}
+
+			let blocked = remaining.iter()
+				.filter(|&&j| j != prev && j != curr && j != next)
+				.any(|&j| point_in_triangle(projected[j], projected[prev], projected[curr], projected[next], eps));
+
+			if !blocked {
+				triangles.push([prev, curr, next]);
+				remaining.remove(i);
+				clipped = true;
+				break;
+			}
+		}
+
+		if !clipped {
+			return None;
+		}
+	}
+
+	triangles.push([remaining[0], remaining[1], remaining[2]]);
+
+	Some(triangles)
+}
+
+/// Projects the polygon onto the plane orthogonal to its Newell normal.
+///
+/// The basis is built so that the polygon winding is counter-clockwise in 2D.
+fn project_to_plane(points: &[[f32; 3]]) -> Option<Vec<[f32; 2]>> {
+	let mut normal = [0.0_f32; 3];
+
+	for i in 0..points.len() {
+		let a = points[i];
+		let b = points[(i + 1) % points.len()];
+		normal[0] += (a[1] - b[1]) * (a[2] + b[2]);
+		normal[1] += (a[2] - b[2]) * (a[0] + b[0]);
+		normal[2] += (a[0] - b[0]) * (a[1] + b[1]);
+	}
+
+	let len = length(normal);
+	if len < 1e-12 {
+		return None;
+	}
+	let normal = [normal[0] / len, normal[1] / len, normal[2] / len];
+
+	let helper = if normal[0].abs() < 0.9 { [1.0, 0.0, 0.0] } else { [0.0, 1.0, 0.0] };
+	let u = cross(helper, normal);
+	let u_len = length(u);
+	let u = [u[0] / u_len, u[1] / u_len, u[2] / u_len];
+	let v = cross(normal, u);
+
+	Some(points.iter()
+		.map(|p| [dot(*p, u), dot(*p, v)])
+		.collect())
+}
+
+fn signed_area(polygon: &[[f32; 2]]) -> f32 {
+	let mut area = 0.0;
+
+	for i in 0..polygon.len() {
+		let a = polygon[i];
+		let b = polygon[(i + 1) % polygon.len()];
+		area += a[0] * b[1] - b[0] * a[1];
+	}
+
+	area * 0.5
+}
+
+fn cross_2d(a: [f32; 2], b: [f32; 2], c: [f32; 2]) -> f32 {
+	(b[0] - a[0]) * (c[1] - a[1]) - (b[1] - a[1]) * (c[0] - a[0])
+}
+
+/// Inclusive test: points on the triangle edges count as inside.
+fn point_in_triangle(p: [f32; 2], a: [f32; 2], b: [f32; 2], c: [f32; 2], eps: f32) -> bool {
+	cross_2d(a, b, p) >= -eps && cross_2d(b, c, p) >= -eps && cross_2d(c, a, p) >= -eps
+}
+
+fn cross(a: [f32; 3], b: [f32; 3]) -> [f32; 3] {
+	[
+		a[1] * b[2] - a[2] * b[1],
+		a[2] * b[0] - a[0] * b[2],
+		a[0] * b[1] - a[1] * b[0],
+	]
+}
+
+fn dot(a: [f32; 3], b: [f32; 3]) -> f32 {
+	a[0] * b[0] + a[1] * b[1] + a[2] * b[2]
+}
+
+fn length(a: [f32; 3]) -> f32 {
+	dot(a, a).sqrt()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn triangle_area(points: &[[f32; 3]], t: [usize; 3]) -> f32 {
+        let (a, b, c) = (points[t[0]], points[t[1]], points[t[2]]);
+        let n = cross(
+            [b[0] - a[0], b[1] - a[1], b[2] - a[2]],
+            [c[0] - a[0], c[1] - a[1], c[2] - a[2]],
+        );
+        // Signed along +Z for planar test polygons
+        n[2] * 0.5
+    }
+
+    #[test]
+    fn square_gives_two_triangles() {
+        let square = [[0.0, 0.0, 0.0], [1.0, 0.0, 0.0], [1.0, 1.0, 0.0], [0.0, 1.0, 0.0]];
+        let triangles = triangulate_polygon(&square).unwrap();
+        assert_eq!(triangles.len(), 2);
+    }
+
+    #[test]
+    fn concave_polygon_has_no_inverted_triangles() {
+        // Arrow head: a fan from vertex 0 would produce an inverted triangle
+        let arrow = [
+            [2.0, 0.0, 0.0],
+            [1.0, 0.5, 0.0],
+            [2.0, 1.0, 0.0],
+            [0.0, 0.5, 0.0],
+        ];
+        let triangles = triangulate_polygon(&arrow).unwrap();
+        assert_eq!(triangles.len(), 2);
+
+        let total: f32 = triangles.iter().map(|&t| triangle_area(&arrow, t)).sum();
+        for &t in &triangles {
+            assert!(triangle_area(&arrow, t) > 0.0);
+        }
+        assert!((total - 0.5).abs() < 1e-5);
+    }
+
+    #[test]
+    fn edge_midpoint_stays_a_corner() {
+        // The midpoint of the bottom edge is a corner of the next face too
+        let square = [
+            [0.0, 0.0, 0.0], [0.5, 0.0, 0.0], [1.0, 0.0, 0.0],
+            [1.0, 1.0, 0.0], [0.0, 1.0, 0.0],
+        ];
+        let triangles = triangulate_polygon(&square).unwrap();
+
+        assert_eq!(triangles.len(), 3);
+        for i in 0..square.len() {
+            assert!(triangles.iter().any(|t| t.contains(&i)), "{} is missing", i);
+        }
+        for &t in &triangles {
+            assert!(triangle_area(&square, t) > 0.0);
+        }
+        let total: f32 = triangles.iter().map(|&t| triangle_area(&square, t)).sum();
+        assert!((total - 1.0).abs() < 1e-5);
+    }
+
+    #[test]
+    fn degenerate_polygon_fails() {
+        let line = [[0.0, 0.0, 0.0], [1.0, 0.0, 0.0], [2.0, 0.0, 0.0], [3.0, 0.0, 0.0]];
+        assert!(triangulate_polygon(&line).is_none());
+    }
+}
@@ -1,4 +1,5 @@
 use ash::vk;
+use crate::renderer::{MeshBuffers, PrimitivePushConstants, VulkanPipeline};
 
 pub struct VulkanCommands {
 	pub command_pool: vk::CommandPool,
@@ -146,12 +147,11 @@ impl VulkanCommands {
 		framebuffer: vk::Framebuffer,
 		render_pass: vk::RenderPass,
 		extent: vk::Extent2D,
-		pipeline: vk::Pipeline,
-		pipeline_layout: vk::PipelineLayout,
-		vertex_buffer: vk::Buffer,
-		index_buffer: vk::Buffer,
-		index_count: u32,
+		pipeline: &VulkanPipeline,
+		mesh_buffers: &MeshBuffers,
 		descriptor_set: vk::DescriptorSet,
+		line_width: f32,
+		point_size: f32,
 	) -> Result<(), String> {
 		let begin_info = vk::CommandBufferBeginInfo::default();
 
@@ -189,14 +189,14 @@ impl VulkanCommands {
 			device.cmd_bind_pipeline(
 				command_buffer,
 				vk::PipelineBindPoint::GRAPHICS,
-				pipeline
+				pipeline.pipeline
 			);
 
-			let vertex_buffers = [vertex_buffer];
+			let vertex_buffers = [mesh_buffers.vertex_buffer.buffer];
 			let offsets = [0_u64];
 			device.cmd_bind_vertex_buffers(command_buffer, 0, &vertex_buffers, &offsets);
 
-			device.cmd_bind_index_buffer(command_buffer, index_buffer, 0, vk::IndexType::UINT32);
+			device.cmd_bind_index_buffer(command_buffer, mesh_buffers.index_buffer.buffer, 0, vk::IndexType::UINT32);
 
 			let viewport = vk::Viewport::default()
 				.x(0.0)
@@ -217,13 +217,36 @@ impl VulkanCommands {
 			device.cmd_bind_descriptor_sets(
 				command_buffer,
 				vk::PipelineBindPoint::GRAPHICS,
-				pipeline_layout,
+				pipeline.pipeline_layout,
 				0,
 				&[descriptor_set],
 				&[],
 			);
 
-			device.cmd_draw_indexed(command_buffer, index_count, 1, 0, 0, 0);
+			let push_constants = PrimitivePushConstants { point_size };
+			let push_bytes = std::slice::from_raw_parts(
+				(&push_constants as *const PrimitivePushConstants).cast::<u8>(),
+				std::mem::size_of::<PrimitivePushConstants>(),
+			);
+			device.cmd_push_constants(command_buffer, pipeline.pipeline_layout, vk::ShaderStageFlags::VERTEX, 0, push_bytes);
+
+			for (first_index, index_count, vertex_offset) in mesh_buffers.draw_ranges() {
+				device.cmd_draw_indexed(command_buffer, index_count, 1, first_index, vertex_offset, 0);
+			}
+
+			// Viewport, scissor and push constants carry over: all pipelines share them
+			let (first_index, index_count) = mesh_buffers.line_range;
+			if index_count > 0 {
+				device.cmd_bind_pipeline(command_buffer, vk::PipelineBindPoint::GRAPHICS, pipeline.line_pipeline);
+				device.cmd_set_line_width(command_buffer, line_width);
+				device.cmd_draw_indexed(command_buffer, index_count, 1, first_index, 0, 0);
+			}
+
+			let (first_index, index_count) = mesh_buffers.point_range;
+			if index_count > 0 {
+				device.cmd_bind_pipeline(command_buffer, vk::PipelineBindPoint::GRAPHICS, pipeline.point_pipeline);
+				device.cmd_draw_indexed(command_buffer, index_count, 1, first_index, 0, 0);
+			}
 
 			device.cmd_end_render_pass(command_buffer);
 
@@ -18,6 +18,12 @@ pub struct VulkanDevice {
 	pub graphics_queue: vk::Queue,
 	pub present_queue: vk::Queue,
 	pub queue_family_indices: QueueFamilyIndices,
+	/// Supported `[min, max]` line width, `[1, 1]` without `wideLines`.
+	pub line_width_range: [f32; 2],
+	/// Supported `[min, max]` point size, `[1, 1]` without `largePoints`.
+	pub point_size_range: [f32; 2],
+	/// Whether BC1 to BC7 textures can be sampled.
+	pub texture_compression_bc: bool,
 }
 
 impl VulkanDevice {
@@ -37,9 +43,21 @@ impl VulkanDevice {
 			surface_loader,
 		)?;
 
-		// 3. Create logical device
+		// 3. Enable thick lines, big points and BC textures when available
+		let supported = unsafe { instance.get_physical_device_features(physical_device) };
+		let limits = unsafe { instance.get_physical_device_properties(physical_device) }.limits;
+
+		let wide_lines = supported.wide_lines == vk::TRUE;
+		let large_points = supported.large_points == vk::TRUE;
+		let texture_compression_bc = supported.texture_compression_bc == vk::TRUE;
+		let device_features = vk::PhysicalDeviceFeatures::default()
+			.wide_lines(wide_lines)
+			.large_points(large_points)
+			.texture_compression_bc(texture_compression_bc);
+
+		// 4. Create logical device
 		let (device, graphics_queue, present_queue) =
-			Self::create_logical_device(instance, physical_device, &queue_family_indices)?;
+			Self::create_logical_device(instance, physical_device, &queue_family_indices, &device_features)?;
 
 		Ok(Self {
 			physical_device,
@@ -47,6 +65,9 @@ impl VulkanDevice {
 			graphics_queue,
 			present_queue,
 			queue_family_indices,
+			line_width_range: if wide_lines { limits.line_width_range } else { [1.0, 1.0] },
+			point_size_range: if large_points { limits.point_size_range } else { [1.0, 1.0] },
+			texture_compression_bc,
 		})
 	}
 
@@ -207,6 +228,7 @@ impl VulkanDevice {
 		instance: &ash::Instance,
 		physical_device: vk::PhysicalDevice,
 		indices: &QueueFamilyIndices,
+		device_features: &vk::PhysicalDeviceFeatures,
 	) -> Result<(ash::Device, vk::Queue, vk::Queue), String> {
 		let mut unique_queue_families = std::collections::HashSet::new();
 		unique_queue_families.insert(indices.graphics_family.unwrap());
@@ -222,8 +244,6 @@ impl VulkanDevice {
 			})
 			.collect();
 
-		let device_features = vk::PhysicalDeviceFeatures::default();
-
 		let device_extensions = [ash::khr::swapchain::NAME.as_ptr()];
 
 		//let layer_names = if cfg!(debug_assertions) {
@@ -234,7 +254,7 @@ impl VulkanDevice {
 
 		let create_info = vk::DeviceCreateInfo::default()
 			.queue_create_infos(&queue_create_infos)
-			.enabled_features(&device_features)
+			.enabled_features(device_features)
 			.enabled_extension_names(&device_extensions);
 			//.enabled_layer_names(&layer_names);
 
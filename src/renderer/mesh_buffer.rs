@@ -1,43 +1,115 @@
 use ash::vk;
 use crate::mesh::{Mesh, Vertex};
+use crate::mesh::lod::LodLevel;
 use crate::renderer::{VulkanDevice, buffer::Buffer};
 
+/// Where one level of detail lives in the shared buffers.
+pub struct MeshLod {
+	/// Added to the indices of the level, which count from its first vertex.
+	pub vertex_offset: i32,
+	/// `(first_index, index_count)` of each part, or of the whole level for a
+	/// mesh without parts.
+	pub part_ranges: Vec<(u32, u32)>,
+	/// Estimated distance to the full mesh, in model units.
+	pub error: f32,
+}
+
 pub struct MeshBuffers {
 	pub vertex_buffer: Buffer,
 	pub index_buffer: Buffer,
-	pub index_count: u32,
+	/// Levels of detail from the full mesh down, all in the same buffers.
+	pub lods: Vec<MeshLod>,
+	/// Level drawn by the next frames.
+	pub current_lod: usize,
+	/// Visibility of each part, matching `MeshLod::part_ranges`.
+	pub visible_parts: Vec<bool>,
+	/// Distance from the origin to the farthest vertex.
+	pub radius: f32,
+	/// `(first_index, index_count)` of the line list, stored after the triangles.
+	pub line_range: (u32, u32),
+	/// `(first_index, index_count)` of the point list, stored after the lines.
+	pub point_range: (u32, u32),
 }
 
 impl MeshBuffers {
+	/// Uploads `mesh` followed by its coarser `lods`.
+	///
+	/// Lines and points are drawn from the full mesh only.
 	pub fn from_mesh(
 		instance: &ash::Instance,
 		device: &VulkanDevice,
 		command_pool: vk::CommandPool,
 		mesh: &Mesh,
+		lods: &[LodLevel],
 	) -> Result<Self, String> {
 		println!("Loading mesh: {} vertices, {} indices", mesh.vertices.len(), mesh.indices.len());
 
+		let levels = std::iter::once((mesh, 0.0))
+			.chain(lods.iter().map(|lod| (&lod.mesh, lod.error)));
+
+		let mut vertices: Vec<Vertex> = Vec::new();
+		let mut indices: Vec<u32> = Vec::new();
+		let mut mesh_lods = Vec::new();
+		for (level, error) in levels {
+			let first_index = indices.len() as u32;
+			let part_ranges = if level.parts.is_empty() {
+				vec![(first_index, level.indices.len() as u32)]
+			} else {
+				level.parts.iter()
+					.map(|part| (first_index + part.first_index, part.index_count))
+					.collect()
+			};
+
+			mesh_lods.push(MeshLod { vertex_offset: vertices.len() as i32, part_ranges, error });
+			vertices.extend_from_slice(&level.vertices);
+			indices.extend_from_slice(&level.indices);
+		}
+
+		// Lines and points index the vertices of the full mesh, first in the buffer
+		let line_start = indices.len() as u32;
+		let point_start = line_start + mesh.line_indices.len() as u32;
+		indices.extend(mesh.line_indices.iter().chain(&mesh.point_indices));
+
+		let radius = mesh.vertices.iter()
+			.map(|v| v.position.iter().map(|c| c * c).sum::<f32>().sqrt())
+			.fold(0.0, f32::max);
+
 		let vertex_buffer = Self::create_vertex_buffer(
 			instance,
 			device,
 			command_pool,
-			&mesh.vertices
+			&vertices
 		)?;
 
 		let index_buffer = Self::create_index_buffer(
 			instance,
 			device,
 			command_pool,
-			&mesh.indices
+			&indices
 		)?;
 
 		Ok(Self {
 			vertex_buffer,
 			index_buffer,
-			index_count: mesh.indices.len() as u32,
+			lods: mesh_lods,
+			current_lod: 0,
+			visible_parts: mesh.parts.iter().map(|part| part.visible).collect(),
+			radius,
+			line_range: (line_start, mesh.line_indices.len() as u32),
+			point_range: (point_start, mesh.point_indices.len() as u32),
 		})
 	}
 
+	/// `(first_index, index_count, vertex_offset)` of the visible parts of the
+	/// current level, issued as separate draws.
+	pub fn draw_ranges(&self) -> impl Iterator<Item = (u32, u32, i32)> + '_ {
+		let lod = &self.lods[self.current_lod];
+		lod.part_ranges.iter()
+			.enumerate()
+			.filter(|&(part, _)| self.visible_parts.get(part).copied().unwrap_or(true))
+			.map(|(_, &(first_index, index_count))| (first_index, index_count, lod.vertex_offset))
+	}
+
 	fn create_vertex_buffer(
 		instance: &ash::Instance,
 		device: &VulkanDevice,
@@ -21,7 +21,7 @@ pub use descriptors::Descriptors;
 pub use device::VulkanDevice;
 pub use instance::VulkanInstance;
 pub use mesh_buffer::MeshBuffers;
-pub use pipeline::VulkanPipeline;
+pub use pipeline::{PrimitivePushConstants, VulkanPipeline};
 pub use render_pass::VulkanRenderPass;
 pub use renderer::Renderer;
 pub use swapchain::VulkanSwapchain;
@@ -2,8 +2,18 @@ use ash::{qcom, vk};
 use crate::mesh::Vertex;
 use crate::renderer::shader::ShaderModule;
 
+/// Per-draw settings pushed to the vertex shader.
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct PrimitivePushConstants {
+	pub point_size: f32,
+}
+
 pub struct VulkanPipeline {
 	pub pipeline: vk::Pipeline,
+	/// Same state as `pipeline`, for line lists with a dynamic line width.
+	pub line_pipeline: vk::Pipeline,
+	pub point_pipeline: vk::Pipeline,
 	pub pipeline_layout: vk::PipelineLayout,
 	pub descriptor_set_layout: vk::DescriptorSetLayout,
 }
@@ -24,15 +34,23 @@ impl VulkanPipeline {
 		// 3. Create pipeline layout
 		let pipeline_layout = Self::create_pipeline_layout(device, descriptor_set_layout)?;
 
-		// 4. Create graphics pipeline
-		let pipeline = Self::create_graphics_pipeline(
+		// 4. Create graphics pipelines, one per topology
+		let mut pipelines = [
+			vk::PrimitiveTopology::TRIANGLE_LIST,
+			vk::PrimitiveTopology::LINE_LIST,
+			vk::PrimitiveTopology::POINT_LIST,
+		].into_iter().map(|topology| Self::create_graphics_pipeline(
 			device,
 			render_pass,
 			pipeline_layout,
 			&vert_shader,
 			&frag_shader,
-			extent
-		)?;
+			extent,
+			topology,
+		));
+		let pipeline = pipelines.next().unwrap()?;
+		let line_pipeline = pipelines.next().unwrap()?;
+		let point_pipeline = pipelines.next().unwrap()?;
 
 		// 5. Cleanup shader modules
 		vert_shader.cleanup(device);
@@ -40,6 +58,8 @@ impl VulkanPipeline {
 
 		Ok(Self {
 			pipeline,
+			line_pipeline,
+			point_pipeline,
 			pipeline_layout,
 			descriptor_set_layout,
 		})
@@ -91,8 +111,14 @@ impl VulkanPipeline {
 	) -> Result<vk::PipelineLayout, String> {
 		let set_layouts = [descriptor_set_layout];
 
+		let push_constant_range = vk::PushConstantRange::default()
+			.stage_flags(vk::ShaderStageFlags::VERTEX)
+			.offset(0)
+			.size(std::mem::size_of::<PrimitivePushConstants>() as u32);
+
 		let pipeline_layout_info = vk::PipelineLayoutCreateInfo::default()
-			.set_layouts(&set_layouts);
+			.set_layouts(&set_layouts)
+			.push_constant_ranges(std::slice::from_ref(&push_constant_range));
 
 		let pipeline_layout = unsafe {
 			device
@@ -112,6 +138,7 @@ impl VulkanPipeline {
 		vert_shader: &ShaderModule,
 		frag_shader: &ShaderModule,
 		extent: vk::Extent2D,
+		topology: vk::PrimitiveTopology,
 	) -> Result<vk::Pipeline, String> {
 		// ===== SHADER STAGES =====
 		let entry_point = c"main";
@@ -161,6 +188,12 @@ impl VulkanPipeline {
 				.location(3)
 				.format(vk::Format::R32G32B32_SFLOAT)
 				.offset(32), // 3 + 2 + 3 floats * 4 bytes
+			// Tangent (location = 4)
+			vk::VertexInputAttributeDescription::default()
+				.binding(0)
+				.location(4)
+				.format(vk::Format::R32G32B32A32_SFLOAT)
+				.offset(44), // 3 + 2 + 3 + 3 floats * 4 bytes
 		];
 
 		let vertex_input_info = vk::PipelineVertexInputStateCreateInfo::default()
@@ -169,7 +202,7 @@ impl VulkanPipeline {
 
 		// ===== INPUT ASSEMBLY =====
 		let input_assembly = vk::PipelineInputAssemblyStateCreateInfo::default()
-			.topology(vk::PrimitiveTopology::TRIANGLE_LIST)
+			.topology(topology)
 			.primitive_restart_enable(false);
 
 		// ===== VIEWPORT & SCISSORS =====
@@ -220,11 +253,15 @@ impl VulkanPipeline {
 			.attachments(std::slice::from_ref(&color_blend_attachment));
 
 		// ===== DYNAMIC STATE =====
-		// Viewport & scissor (resize)
-		let dynamic_states = [vk::DynamicState::VIEWPORT, vk::DynamicState::SCISSOR];
+		// Viewport & scissor (resize), line width (user setting)
+		let dynamic_states: &[vk::DynamicState] = if topology == vk::PrimitiveTopology::LINE_LIST {
+			&[vk::DynamicState::VIEWPORT, vk::DynamicState::SCISSOR, vk::DynamicState::LINE_WIDTH]
+		} else {
+			&[vk::DynamicState::VIEWPORT, vk::DynamicState::SCISSOR]
+		};
 
 		let dynamic_state = vk::PipelineDynamicStateCreateInfo::default()
-			.dynamic_states(&dynamic_states);
+			.dynamic_states(dynamic_states);
 
 		// ===== DEPTH STENCIL =====
 		let depth_stencil = vk::PipelineDepthStencilStateCreateInfo::default()
@@ -258,7 +295,7 @@ impl VulkanPipeline {
 				.map_err(|e| format!("Failed to create graphics pipeline: {:?}", e.1))?
 		};
 
-		println!("✓ Graphics pipeline created");
+		println!("✓ Graphics pipeline created ({:?})", topology);
 
 		Ok(pipelines[0])
 	}
@@ -266,6 +303,8 @@ impl VulkanPipeline {
 	pub fn cleanup(&self, device: &ash::Device) {
 		unsafe {
 			device.destroy_pipeline(self.pipeline, None);
+			device.destroy_pipeline(self.line_pipeline, None);
+			device.destroy_pipeline(self.point_pipeline, None);
 			device.destroy_pipeline_layout(self.pipeline_layout, None);
 			device.destroy_descriptor_set_layout(self.descriptor_set_layout, None);
 		}
@@ -4,6 +4,7 @@ use crate::renderer::{
 	Descriptors, MeshBuffers, MixFactorBuffers, Texture, UniformBufferObject, UniformBuffers, VulkanCommands, VulkanDevice, VulkanPipeline, VulkanRenderPass, VulkanSwapchain, VulkanSync
 };
 use crate::mesh::{DominantAxis, Mesh};
+use crate::mesh::lod::{LodLevel, select_lod};
 use crate::math::{Matrix, Vector, Transform};
 
 pub struct Renderer {
@@ -18,8 +19,26 @@ pub struct Renderer {
 	texture: Option<Texture>,
 	mix_target: f32,
 	last_frame_time: Instant,
+	line_width: f32,
+	point_size: f32,
+	/// Camera distance relative to the default one.
+	zoom: f32,
 }
 
+/// Line widths and point sizes stepped through at runtime, in pixels.
+const PRIMITIVE_SIZES: [f32; 4] = [1.0, 2.0, 4.0, 8.0];
+
+/// Vertical field of view of the camera.
+const FOV_Y: f32 = std::f32::consts::FRAC_PI_6;
+const NEAR_PLANE: f32 = 0.1;
+
+/// Largest on-screen error, in pixels, allowed for a level of detail.
+const LOD_MAX_PIXELS: f32 = 1.0;
+
+/// Camera distance factor of one zoom step, and its limits.
+const ZOOM_STEP: f32 = 1.2;
+const ZOOM_RANGE: (f32, f32) = (0.2, 8.0);
+
 impl Renderer {
 	pub fn new(
 		instance: &ash::Instance,
@@ -53,6 +72,9 @@ impl Renderer {
 			texture: Some(tex),
 			mix_target: 0.0,
 			last_frame_time: Instant::now(),
+			line_width: PRIMITIVE_SIZES[0],
+			point_size: PRIMITIVE_SIZES[2],
+			zoom: 1.0,
 		})
 	}
 
@@ -64,11 +86,31 @@ impl Renderer {
 		}
 	}
 
+	/// Switches to the next line width, returning it.
+	pub fn cycle_line_width(&mut self) -> f32 {
+		self.line_width = next_size(self.line_width);
+		self.line_width
+	}
+
+	/// Switches to the next point size, returning it.
+	pub fn cycle_point_size(&mut self) -> f32 {
+		self.point_size = next_size(self.point_size);
+		self.point_size
+	}
+
+	/// Moves the camera `steps` zoom steps closer, or farther for negative
+	/// steps, returning the new zoom factor.
+	pub fn zoom(&mut self, steps: f32) -> f32 {
+		self.zoom = (self.zoom * ZOOM_STEP.powf(-steps)).clamp(ZOOM_RANGE.0, ZOOM_RANGE.1);
+		self.zoom
+	}
+
 	pub fn load_mesh(
 		&mut self,
 		instance: &ash::Instance,
 		device: &VulkanDevice,
 		mesh: &Mesh,
+		lods: &[LodLevel],
 	) -> Result<(), String> {
 		if let Some(old_mesh) = &self.mesh_buffers {
 			old_mesh.cleanup(&device.device);
@@ -78,7 +120,8 @@ impl Renderer {
 			instance,
 			device,
 			self.commands.command_pool,
-			mesh
+			mesh,
+			lods
 		)?;
 
 		self.mesh_buffers = Some(mesh_buffers);
@@ -86,6 +129,15 @@ impl Renderer {
 		Ok(())
 	}
 
+	/// Restricts drawing to the parts of `mesh` currently marked visible.
+	pub fn set_visible_parts(&mut self, mesh: &Mesh) {
+		if let Some(mesh_buffers) = &mut self.mesh_buffers {
+			mesh_buffers.visible_parts = mesh.parts.iter().map(|part| part.visible).collect();
+		}
+	}
+
+	/// Uploads the transforms of this frame, returning them as
+	/// `(model, view, proj)`.
 	fn update_uniform_buffer(
 		&mut self,
 		device: &VulkanDevice,
@@ -93,7 +145,7 @@ impl Renderer {
 		extent: Extent2D,
 		centroid: [f32; 3],
 		dominant_axis: DominantAxis,
-	) -> Result<(), String> {
+	) -> Result<(Matrix, Matrix, Matrix), String> {
 		let now = Instant::now();
 		let delta = now.duration_since(self.last_frame_time).as_secs_f32();
 		self.last_frame_time = now;
@@ -106,12 +158,10 @@ impl Renderer {
 
 		let model = Transform::rotation_y(angle);
 
-		let (eye, up) = compute_eye_and_up(dominant_axis);
-		let target = Vector::new(vec![centroid[0], centroid[1], centroid[2]]);
-		let view = Transform::look_at(&eye, &target, &up);
+		let view = view_matrix(centroid, dominant_axis, self.zoom);
 
 		let aspect = extent.width as f32 / extent.height as f32;
-		let proj = crate::math::projection(std::f32::consts::FRAC_PI_6, aspect, 0.1, 100.0);
+		let proj = crate::math::projection(FOV_Y, aspect, NEAR_PLANE, 100.0);
 
 		let ubo = UniformBufferObject {
 			model: matrix_to_array(&model),
@@ -122,7 +172,7 @@ impl Renderer {
 		self.uniform_buffers.update(&device.device, current_frame, &ubo)?;
 		self.mix_factor_buffers.update(&device.device, current_frame, self.mix_value)?;
 
-		Ok(())
+		Ok((model, view, proj))
 	}
 
 	pub fn draw_frame(
@@ -170,9 +220,20 @@ impl Renderer {
 		}
 
 		// 4. Update uniforms
-		self.update_uniform_buffer(device, current_frame, swapchain.extent, centroid, dominant_axis)?;
+		let (model, view, proj) = self.update_uniform_buffer(device, current_frame, swapchain.extent, centroid, dominant_axis)?;
+
+		// 5. Pick the level of detail from the size of the mesh on screen
+		if let Some(mesh_buffers) = &mut self.mesh_buffers {
+			let pixels_per_unit = pixels_per_unit(&view.mul_mat(&model), &proj, swapchain.extent, mesh_buffers.radius);
+			let errors: Vec<f32> = mesh_buffers.lods.iter().map(|lod| lod.error).collect();
+			let lod = select_lod(&errors, pixels_per_unit, LOD_MAX_PIXELS);
+			if lod != mesh_buffers.current_lod {
+				println!("Level of detail: {} (error {:.2} px)", lod, errors[lod] * pixels_per_unit);
+				mesh_buffers.current_lod = lod;
+			}
+		}
 
-		// 5. Register commands
+		// 6. Register commands
 		let command_buffer = self.commands.command_buffers[current_frame];
 
 		unsafe {
@@ -189,12 +250,12 @@ impl Renderer {
 				render_pass.framebuffers[image_index as usize],
 				render_pass.render_pass,
 				swapchain.extent,
-				pipeline.pipeline,
-				pipeline.pipeline_layout,
-				mesh_buffers.vertex_buffer.buffer,
-				mesh_buffers.index_buffer.buffer,
-				mesh_buffers.index_count,
+				pipeline,
+				mesh_buffers,
 				self.descriptors.descriptor_sets[current_frame],
+				// Sizes the device cannot draw are clamped rather than rejected
+				self.line_width.clamp(device.line_width_range[0], device.line_width_range[1]),
+				self.point_size.clamp(device.point_size_range[0], device.point_size_range[1]),
 			)?;
 		} else {
 			self.commands.record_command_buffer(
@@ -207,7 +268,7 @@ impl Renderer {
 			)?;
 		}
 
-		// 6. Submit command buffer
+		// 7. Submit command buffer
 		let wait_semaphores = [self.sync.image_available_semaphores[current_frame]];
 		let wait_stages = [vk::PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT];
 		let command_buffers = [command_buffer];
@@ -230,7 +291,7 @@ impl Renderer {
 				.map_err(|e| format!("Failed to submit queue: {}", e))?;
 		}
 
-		// 7. Present image
+		// 8. Present image
 		let swapchains = [swapchain.swapchain];
 		let image_indices = [image_index];
 
@@ -246,7 +307,7 @@ impl Renderer {
 				.map_err(|e| format!("Failed to present: {}", e))?;
 		}
 
-		// 7. Move to next frame
+		// 9. Move to next frame
 		self.sync.current_frame = (current_frame + 1) % VulkanSync::max_frames_in_flight();
 
 		Ok(())
@@ -273,6 +334,39 @@ impl Drop for Renderer {
 	}
 }
 
+fn next_size(current: f32) -> f32 {
+	PRIMITIVE_SIZES.iter()
+		.copied()
+		.find(|&size| size > current)
+		.unwrap_or(PRIMITIVE_SIZES[0])
+}
+
+/// Camera looking at `centroid` along the dominant axis, `zoom` times its
+/// default distance away.
+fn view_matrix(centroid: [f32; 3], dominant_axis: DominantAxis, zoom: f32) -> Matrix {
+	let (eye, up) = compute_eye_and_up(dominant_axis);
+	let target = Vector::new(centroid.to_vec());
+	let mut offset = eye.sub_vec(&target);
+	offset.scl(zoom);
+	let mut eye = target.clone();
+	eye.add(&offset);
+	Transform::look_at(&eye, &target, &up)
+}
+
+/// Size on screen, in pixels, of one model unit at the nearest point of a
+/// mesh of `radius` around the model origin, drawn with `model_view` and
+/// `proj`.
+fn pixels_per_unit(model_view: &Matrix, proj: &Matrix, extent: Extent2D, radius: f32) -> f32 {
+	// The view looks down +z, and the model may scale the mesh
+	let depth = model_view.get(2, 3);
+	let scale = (0..3)
+		.map(|c| (0..3).map(|r| model_view.get(r, c).powi(2)).sum::<f32>().sqrt())
+		.fold(0.0, f32::max);
+	let nearest = (depth - radius * scale).max(NEAR_PLANE);
+
+	scale * proj.get(1, 1).abs() * extent.height as f32 / (2.0 * nearest)
+}
+
 fn matrix_to_array(m: &Matrix) -> [[f32; 4]; 4] {
     let data = m.as_slice();
     [
@@ -298,4 +392,24 @@ fn compute_eye_and_up(dominant_axis: DominantAxis) -> (Vector, Vector) {
 			Vector::new(vec![0.0, 1.0, 0.0])
 		)
 	}
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn closer_camera_picks_finer_levels() {
+        let extent = Extent2D { width: 1280, height: 720 };
+        let proj = crate::math::projection(FOV_Y, 16.0 / 9.0, NEAR_PLANE, 100.0);
+        let model = Transform::rotation_y(1.0);
+        let errors = [0.0, 0.001, 0.004, 0.012];
+
+        let level_at = |zoom: f32| {
+            let model_view = view_matrix([0.0; 3], DominantAxis::Z, zoom).mul_mat(&model);
+            select_lod(&errors, pixels_per_unit(&model_view, &proj, extent, 1.0), LOD_MAX_PIXELS)
+        };
+
+        let levels: Vec<usize> = [0.3, 1.0, 4.0].into_iter().map(level_at).collect();
+        assert_eq!(levels, vec![0, 1, 3]);
+    }
+}
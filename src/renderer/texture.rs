@@ -1,5 +1,8 @@
 use ash::vk;
 use crate::renderer::{VulkanDevice, Buffer};
+use crate::parser::error::TextureError;
+use crate::parser::texture::bc::BlockFormat;
+use crate::parser::texture::{CompressedTexture, LoadedTexture, load_builtin_texture};
 
 #[derive(Clone, Copy)]
 pub struct Texture {
@@ -11,6 +14,14 @@ pub struct Texture {
 	height: u32,
 }
 
+/// Pixel data ready for upload, one buffer per mip level from full size down.
+struct ImageSource {
+	format: vk::Format,
+	width: u32,
+	height: u32,
+	levels: Vec<Vec<u8>>,
+}
+
 impl Texture {
 	pub fn new(
 		path: &str,
@@ -18,13 +29,29 @@ impl Texture {
 		device: &VulkanDevice,
 		command_pool: vk::CommandPool,
 	) -> Result<Self, String> {
-		let img = image::open(path)
-			.map_err(|e| format!("Failed to open texture: {}: {}", path, e))?
-			.to_rgba8();
-
-		let (width, height) = img.dimensions();
-		let pixels = img.into_raw();
-		let size = (width as vk::DeviceSize) * (height as vk::DeviceSize) * 4;
+		let builtin = load_builtin_texture(path)
+			.map_err(|e| format!("Failed to open texture: {}: {}", path, e))?;
+
+		let ImageSource { format, width, height, levels } = match builtin {
+			Some(LoadedTexture::Compressed(texture)) => Self::compressed_source(instance, device, texture)
+				.map_err(|e| format!("Failed to decode texture: {}: {}", path, e))?,
+			Some(LoadedTexture::Decoded(data)) => ImageSource {
+				format: vk::Format::R8G8B8A8_SRGB,
+				width: data.width,
+				height: data.height,
+				levels: vec![data.pixels],
+			},
+			None => {
+				let img = image::open(path)
+					.map_err(|e| format!("Failed to open texture: {}: {}", path, e))?
+					.to_rgba8();
+				let (width, height) = img.dimensions();
+				ImageSource { format: vk::Format::R8G8B8A8_SRGB, width, height, levels: vec![img.into_raw()] }
+			}
+		};
+		let mip_levels = levels.len() as u32;
+		let pixels = levels.concat();
+		let size = pixels.len() as vk::DeviceSize;
 
 		let staging = Buffer::new(
 			instance, device, size,
@@ -33,18 +60,19 @@ impl Texture {
 		)?;
 		staging.upload_data(&device.device, &pixels)?;
 
-		let (image, image_memory) = Self::create_image(instance, device, width, height)?;
+		let (image, image_memory) = Self::create_image(instance, device, width, height, format, mip_levels)?;
 
 		Self::transition_layout(
 			&device.device, command_pool, device.graphics_queue,
 			image,
 			vk::ImageLayout::UNDEFINED,
 			vk::ImageLayout::TRANSFER_DST_OPTIMAL,
+			mip_levels,
 		)?;
 
 		Self::copy_buffer_to_image(
 			&device.device, command_pool, device.graphics_queue,
-			staging.buffer, image, width, height,
+			staging.buffer, image, &Self::level_regions(width, height, &levels),
 		)?;
 
 		Self::transition_layout(
@@ -52,14 +80,15 @@ impl Texture {
 			image,
 			vk::ImageLayout::TRANSFER_DST_OPTIMAL,
 			vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL,
+			mip_levels,
 		)?;
 
 		staging.cleanup(&device.device);
 
-		let image_view = Self::create_image_view(&device.device, image)?;
-		let sampler = Self::create_sampler(&device.device)?;
+		let image_view = Self::create_image_view(&device.device, image, format, mip_levels)?;
+		let sampler = Self::create_sampler(&device.device, mip_levels)?;
 
-		println!("✓ Texture loaded: {} ({}x{})", path, width, height);
+		println!("✓ Texture loaded: {} ({}x{}, {:?}, {} mip levels)", path, width, height, format, mip_levels);
 
 		Ok(Self { image, image_memory, image_view, sampler, width, height })
 	}
@@ -73,18 +102,88 @@ impl Texture {
 		}
 	}
 
+	/// Keeps the blocks when the device can sample their format, otherwise
+	/// decodes every level to RGBA8 on the CPU.
+	fn compressed_source(
+		instance: &ash::Instance,
+		device: &VulkanDevice,
+		texture: CompressedTexture,
+	) -> Result<ImageSource, TextureError> {
+		let format = Self::block_vk_format(texture.format, texture.srgb);
+		let properties = unsafe {
+			instance.get_physical_device_format_properties(device.physical_device, format)
+		};
+		let sampled = properties.optimal_tiling_features.contains(vk::FormatFeatureFlags::SAMPLED_IMAGE_FILTER_LINEAR);
+
+		if device.texture_compression_bc && sampled {
+			return Ok(ImageSource { format, width: texture.width, height: texture.height, levels: texture.levels });
+		}
+
+		eprintln!("Warning: {:?} is not supported by the device, decoding on the CPU", format);
+		let levels = (0..texture.levels.len() as u32)
+			.map(|level| texture.decode_level(level).map(|data| data.pixels))
+			.collect::<Result<_, _>>()?;
+		let format = if texture.srgb { vk::Format::R8G8B8A8_SRGB } else { vk::Format::R8G8B8A8_UNORM };
+
+		Ok(ImageSource { format, width: texture.width, height: texture.height, levels })
+	}
+
+	fn block_vk_format(format: BlockFormat, srgb: bool) -> vk::Format {
+		match (format, srgb) {
+			(BlockFormat::Bc1, true) => vk::Format::BC1_RGBA_SRGB_BLOCK,
+			(BlockFormat::Bc1, false) => vk::Format::BC1_RGBA_UNORM_BLOCK,
+			(BlockFormat::Bc2, true) => vk::Format::BC2_SRGB_BLOCK,
+			(BlockFormat::Bc2, false) => vk::Format::BC2_UNORM_BLOCK,
+			(BlockFormat::Bc3, true) => vk::Format::BC3_SRGB_BLOCK,
+			(BlockFormat::Bc3, false) => vk::Format::BC3_UNORM_BLOCK,
+			(BlockFormat::Bc4, _) => vk::Format::BC4_UNORM_BLOCK,
+			(BlockFormat::Bc5, _) => vk::Format::BC5_UNORM_BLOCK,
+			(BlockFormat::Bc6h { signed: false }, _) => vk::Format::BC6H_UFLOAT_BLOCK,
+			(BlockFormat::Bc6h { signed: true }, _) => vk::Format::BC6H_SFLOAT_BLOCK,
+			(BlockFormat::Bc7, true) => vk::Format::BC7_SRGB_BLOCK,
+			(BlockFormat::Bc7, false) => vk::Format::BC7_UNORM_BLOCK,
+		}
+	}
+
+	/// One copy per mip level, reading the levels packed one after another.
+	fn level_regions(width: u32, height: u32, levels: &[Vec<u8>]) -> Vec<vk::BufferImageCopy> {
+		let mut offset = 0;
+		levels.iter().enumerate().map(|(level, data)| {
+			let region = vk::BufferImageCopy::default()
+				.buffer_offset(offset)
+				.buffer_row_length(0)
+				.buffer_image_height(0)
+				.image_subresource(vk::ImageSubresourceLayers {
+					aspect_mask: vk::ImageAspectFlags::COLOR,
+					mip_level: level as u32,
+					base_array_layer: 0,
+					layer_count: 1,
+				})
+				.image_offset(vk::Offset3D { x: 0, y: 0, z: 0 })
+				.image_extent(vk::Extent3D {
+					width: (width >> level).max(1),
+					height: (height >> level).max(1),
+					depth: 1,
+				});
+			offset += data.len() as vk::DeviceSize;
+			region
+		}).collect()
+	}
+
 	fn create_image(
 		instance: &ash::Instance,
 		device: &VulkanDevice,
 		width: u32,
 		height: u32,
+		format: vk::Format,
+		mip_levels: u32,
 	) -> Result<(vk::Image, vk::DeviceMemory), String> {
 		let image_info = vk::ImageCreateInfo::default()
 			.image_type(vk::ImageType::TYPE_2D)
 			.extent(vk::Extent3D { width, height, depth: 1 })
-			.mip_levels(1)
+			.mip_levels(mip_levels)
 			.array_layers(1)
-			.format(vk::Format::R8G8B8A8_SRGB)
+			.format(format)
 			.tiling(vk::ImageTiling::OPTIMAL)
 			.initial_layout(vk::ImageLayout::UNDEFINED)
 			.usage(vk::ImageUsageFlags::TRANSFER_DST | vk::ImageUsageFlags::SAMPLED)
@@ -131,6 +230,7 @@ impl Texture {
 		image: vk::Image,
 		old_layout: vk::ImageLayout,
 		new_layout: vk::ImageLayout,
+		mip_levels: u32,
 	) -> Result<(), String> {
 		let cmd = Self::begin_single_time_commands(device, command_pool)?;
 
@@ -159,7 +259,7 @@ impl Texture {
 			.subresource_range(vk::ImageSubresourceRange {
 				aspect_mask: vk::ImageAspectFlags::COLOR,
 				base_mip_level: 0,
-				level_count: 1,
+				level_count: mip_levels,
 				base_array_layer: 0,
 				layer_count: 1,
 			})
@@ -185,31 +285,17 @@ impl Texture {
 		queue: vk::Queue,
 		buffer: vk::Buffer,
 		image: vk::Image,
-		width: u32,
-		height: u32,
+		regions: &[vk::BufferImageCopy],
 	) -> Result<(), String> {
 		let cmd = Self::begin_single_time_commands(device, command_pool)?;
 
-		let region = vk::BufferImageCopy::default()
-			.buffer_offset(0)
-			.buffer_row_length(0)
-			.buffer_image_height(0)
-			.image_subresource(vk::ImageSubresourceLayers {
-				aspect_mask: vk::ImageAspectFlags::COLOR,
-				mip_level: 0,
-				base_array_layer: 0,
-				layer_count: 1,
-			})
-			.image_offset(vk::Offset3D { x: 0, y: 0, z: 0 })
-			.image_extent(vk::Extent3D { width, height, depth: 1 });
-
 		unsafe {
 			device.cmd_copy_buffer_to_image(
 				cmd,
 				buffer,
 				image,
 				vk::ImageLayout::TRANSFER_DST_OPTIMAL,
-				std::slice::from_ref(&region),
+				regions,
 			);
 		}
 
@@ -219,15 +305,17 @@ impl Texture {
 	fn create_image_view(
 		device: &ash::Device,
 		image: vk::Image,
+		format: vk::Format,
+		mip_levels: u32,
 	) -> Result<vk::ImageView, String> {
 		let view_info = vk::ImageViewCreateInfo::default()
 			.image(image)
 			.view_type(vk::ImageViewType::TYPE_2D)
-			.format(vk::Format::R8G8B8A8_SRGB)
+			.format(format)
 			.subresource_range(vk::ImageSubresourceRange {
 				aspect_mask: vk::ImageAspectFlags::COLOR,
 				base_mip_level: 0,
-				level_count: 1,
+				level_count: mip_levels,
 				base_array_layer: 0,
 				layer_count: 1,
 			});
@@ -238,7 +326,7 @@ impl Texture {
 		}
 	}
 
-	fn create_sampler(device: &ash::Device) -> Result<vk::Sampler, String> {
+	fn create_sampler(device: &ash::Device, mip_levels: u32) -> Result<vk::Sampler, String> {
 		let sampler_info = vk::SamplerCreateInfo::default()
 			.mag_filter(vk::Filter::LINEAR)
 			.min_filter(vk::Filter::LINEAR)
@@ -249,7 +337,9 @@ impl Texture {
 			.border_color(vk::BorderColor::INT_OPAQUE_BLACK)
 			.unnormalized_coordinates(false)
 			.compare_enable(false)
-			.mipmap_mode(vk::SamplerMipmapMode::LINEAR);
+			.mipmap_mode(vk::SamplerMipmapMode::LINEAR)
+			.min_lod(0.0)
+			.max_lod(mip_levels as f32);
 
 		unsafe {
 			device.create_sampler(&sampler_info, None)
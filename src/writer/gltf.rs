@@ -0,0 +1,412 @@
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+
+use crate::mesh::Mesh;
+use crate::parser::gltf::GLB_MAGIC;
+use crate::parser::mtl::Material;
+
+const GLB_VERSION: u32 = 2;
+const CHUNK_JSON: u32 = 0x4E4F_534A;
+const CHUNK_BIN: u32 = 0x004E_4942;
+
+const FLOAT: u32 = 5126;
+const UNSIGNED_INT: u32 = 5125;
+const ARRAY_BUFFER: u32 = 34962;
+const ELEMENT_ARRAY_BUFFER: u32 = 34963;
+
+const MODE_POINTS: u32 = 0;
+const MODE_LINES: u32 = 1;
+const MODE_TRIANGLES: u32 = 4;
+
+pub fn save_glb(mesh: &Mesh, path: &str) -> io::Result<()> {
+	let mut file = BufWriter::new(File::create(path)?);
+	write_glb(&mut file, mesh)?;
+	file.flush()
+}
+
+/// Writes `mesh` as a binary glTF container.
+///
+/// Each mesh part becomes a node of its own, triangles outside every part
+/// going to a node named `default`, and each node gets one primitive per
+/// material. Materials are written as metallic-roughness materials with
+/// their diffuse color and opacity as base color; PNG and JPEG diffuse maps
+/// are embedded. Vertex colors are only written for triangles without a
+/// material, since glTF multiplies them with the base color. Lines and
+/// points go to nodes named `lines` and `points`.
+pub fn write_glb<W: Write>(writer: &mut W, mesh: &Mesh) -> io::Result<()> {
+	let mut builder = GlbBuilder::default();
+
+	let materials: Vec<String> = mesh.materials.iter()
+		.map(|material| builder.material(material))
+		.collect();
+
+	for (name, triangles) in node_triangles(mesh) {
+		let mut groups: Vec<(Option<usize>, Vec<u32>)> = Vec::new();
+		for triangle in triangles {
+			let material = mesh.face_materials.get(triangle).copied().flatten()
+				.filter(|&m| m < mesh.materials.len());
+			let corners = &mesh.indices[triangle * 3..triangle * 3 + 3];
+
+			match groups.iter_mut().find(|(m, _)| *m == material) {
+				Some((_, indices)) => indices.extend(corners),
+				None => groups.push((material, corners.to_vec())),
+			}
+		}
+
+		let primitives = groups.iter()
+			.map(|(material, indices)| builder.primitive(mesh, indices, MODE_TRIANGLES, *material))
+			.collect();
+		builder.node(&name, primitives);
+	}
+
+	for (name, indices, mode) in [("lines", &mesh.line_indices, MODE_LINES), ("points", &mesh.point_indices, MODE_POINTS)] {
+		if !indices.is_empty() {
+			let primitive = builder.primitive(mesh, indices, mode, None);
+			builder.node(name, vec![primitive]);
+		}
+	}
+
+	builder.finish(writer, &materials)
+}
+
+/// Triangle ranges of the nodes to write, named after the mesh parts.
+fn node_triangles(mesh: &Mesh) -> Vec<(String, Vec<usize>)> {
+	let triangle_count = mesh.indices.len() / 3;
+	if mesh.parts.is_empty() {
+		return vec![("mesh".to_string(), (0..triangle_count).collect())];
+	}
+
+	let mut covered = vec![false; triangle_count];
+	let mut nodes = Vec::new();
+
+	for part in &mesh.parts {
+		let first = part.first_index as usize / 3;
+		let triangles: Vec<usize> = (first..first + part.index_count as usize / 3)
+			.filter(|&t| t < triangle_count && !covered[t])
+			.collect();
+		for &t in &triangles {
+			covered[t] = true;
+		}
+		nodes.push((part.name.clone(), triangles));
+	}
+
+	let rest: Vec<usize> = (0..triangle_count).filter(|&t| !covered[t]).collect();
+	if !rest.is_empty() {
+		nodes.push(("default".to_string(), rest));
+	}
+
+	nodes.retain(|(_, triangles)| !triangles.is_empty());
+	nodes
+}
+
+/// Accumulates the binary chunk and the JSON objects describing it.
+#[derive(Default)]
+struct GlbBuilder {
+	bin: Vec<u8>,
+	buffer_views: Vec<String>,
+	accessors: Vec<String>,
+	images: Vec<String>,
+	textures: Vec<String>,
+	meshes: Vec<String>,
+	nodes: Vec<String>,
+}
+
+impl GlbBuilder {
+	/// Appends `bytes` on a 4-byte boundary, returning the buffer view id.
+	fn buffer_view(&mut self, bytes: &[u8], target: Option<u32>) -> usize {
+		while !self.bin.len().is_multiple_of(4) {
+			self.bin.push(0);
+		}
+
+		let target = target.map(|t| format!(",\"target\":{}", t)).unwrap_or_default();
+		self.buffer_views.push(format!(
+			"{{\"buffer\":0,\"byteOffset\":{},\"byteLength\":{}{}}}",
+			self.bin.len(), bytes.len(), target,
+		));
+		self.bin.extend_from_slice(bytes);
+		self.buffer_views.len() - 1
+	}
+
+	fn float_accessor<const N: usize>(&mut self, values: &[[f32; N]], with_bounds: bool) -> usize {
+		let bytes: Vec<u8> = values.iter().flatten().flat_map(|v| v.to_le_bytes()).collect();
+		let view = self.buffer_view(&bytes, Some(ARRAY_BUFFER));
+		let ty = ["SCALAR", "VEC2", "VEC3", "VEC4"][N - 1];
+
+		// POSITION accessors must declare their bounds
+		let bounds = if with_bounds {
+			let fold = |pick: fn(f32, f32) -> f32, start: f32| -> Vec<String> {
+				(0..N).map(|k| json_number(values.iter().map(|v| v[k]).fold(start, pick))).collect()
+			};
+			format!(
+				",\"min\":[{}],\"max\":[{}]",
+				fold(f32::min, f32::INFINITY).join(","),
+				fold(f32::max, f32::NEG_INFINITY).join(","),
+			)
+		} else {
+			String::new()
+		};
+
+		self.accessors.push(format!(
+			"{{\"bufferView\":{},\"componentType\":{},\"count\":{},\"type\":\"{}\"{}}}",
+			view, FLOAT, values.len(), ty, bounds,
+		));
+		self.accessors.len() - 1
+	}
+
+	fn index_accessor(&mut self, indices: &[u32]) -> usize {
+		let bytes: Vec<u8> = indices.iter().flat_map(|i| i.to_le_bytes()).collect();
+		let view = self.buffer_view(&bytes, Some(ELEMENT_ARRAY_BUFFER));
+
+		self.accessors.push(format!(
+			"{{\"bufferView\":{},\"componentType\":{},\"count\":{},\"type\":\"SCALAR\"}}",
+			view, UNSIGNED_INT, indices.len(),
+		));
+		self.accessors.len() - 1
+	}
+
+	/// A primitive drawing `indices` of `mesh`, with its own copy of the
+	/// vertices it uses so readers get exactly those vertices back.
+	fn primitive(&mut self, mesh: &Mesh, indices: &[u32], mode: u32, material: Option<usize>) -> String {
+		let mut remap = vec![u32::MAX; mesh.vertices.len()];
+		let mut used = Vec::new();
+		let local: Vec<u32> = indices.iter()
+			.map(|&i| {
+				if remap[i as usize] == u32::MAX {
+					remap[i as usize] = used.len() as u32;
+					used.push(&mesh.vertices[i as usize]);
+				}
+				remap[i as usize]
+			})
+			.collect();
+
+		let positions: Vec<[f32; 3]> = used.iter().map(|v| v.position).collect();
+		let normals: Vec<[f32; 3]> = used.iter().map(|v| v.normal).collect();
+		// glTF puts the UV origin at the top left, OBJ at the bottom left
+		let tex_coords: Vec<[f32; 2]> = used.iter().map(|v| [v.tex_coords[0], 1.0 - v.tex_coords[1]]).collect();
+
+		let mut attributes = vec![
+			format!("\"POSITION\":{}", self.float_accessor(&positions, true)),
+			format!("\"TEXCOORD_0\":{}", self.float_accessor(&tex_coords, false)),
+		];
+		// Zero normals mark unlit lines and points, which glTF has no use for
+		if normals.iter().any(|n| n.iter().any(|&c| c != 0.0)) {
+			attributes.push(format!("\"NORMAL\":{}", self.float_accessor(&normals, false)));
+		}
+		if material.is_none() {
+			let colors: Vec<[f32; 3]> = used.iter().map(|v| v.color).collect();
+			attributes.push(format!("\"COLOR_0\":{}", self.float_accessor(&colors, false)));
+		}
+
+		let material = material.map(|m| format!(",\"material\":{}", m)).unwrap_or_default();
+		format!(
+			"{{\"attributes\":{{{}}},\"indices\":{},\"mode\":{}{}}}",
+			attributes.join(","), self.index_accessor(&local), mode, material,
+		)
+	}
+
+	fn node(&mut self, name: &str, primitives: Vec<String>) {
+		self.meshes.push(format!("{{\"name\":{},\"primitives\":[{}]}}", json_string(name), primitives.join(",")));
+		self.nodes.push(format!("{{\"name\":{},\"mesh\":{}}}", json_string(name), self.meshes.len() - 1));
+	}
+
+	fn material(&mut self, material: &Material) -> String {
+		let [r, g, b] = material.diffuse;
+		let alpha = material.dissolve.clamp(0.0, 1.0);
+
+		let texture = material.diffuse_map.as_deref()
+			.and_then(|path| self.embed_texture(path))
+			.map(|texture| format!(",\"baseColorTexture\":{{\"index\":{}}}", texture))
+			.unwrap_or_default();
+		let blend = if alpha < 1.0 { ",\"alphaMode\":\"BLEND\"" } else { "" };
+
+		format!(
+			"{{\"name\":{},\"pbrMetallicRoughness\":{{\"baseColorFactor\":[{},{},{},{}],\"metallicFactor\":0,\"roughnessFactor\":1{}}}{}}}",
+			json_string(&material.name),
+			json_number(r), json_number(g), json_number(b), json_number(alpha),
+			texture, blend,
+		)
+	}
+
+	/// Embeds a PNG or JPEG file, returning its texture id. Other formats
+	/// and unreadable files are left out.
+	fn embed_texture(&mut self, path: &str) -> Option<usize> {
+		let extension = Path::new(path).extension()?.to_string_lossy().to_lowercase();
+		let mime_type = match extension.as_str() {
+			"png" => "image/png",
+			"jpg" | "jpeg" => "image/jpeg",
+			_ => return None,
+		};
+		let data = std::fs::read(path).ok()?;
+
+		let view = self.buffer_view(&data, None);
+		self.images.push(format!("{{\"bufferView\":{},\"mimeType\":\"{}\"}}", view, mime_type));
+		self.textures.push(format!("{{\"source\":{}}}", self.images.len() - 1));
+		Some(self.textures.len() - 1)
+	}
+
+	fn finish<W: Write>(mut self, writer: &mut W, materials: &[String]) -> io::Result<()> {
+		while !self.bin.len().is_multiple_of(4) {
+			self.bin.push(0);
+		}
+
+		let mut json = String::from("{\"asset\":{\"version\":\"2.0\",\"generator\":\"scop\"}");
+		json.push_str(&format!(",\"scene\":0,\"scenes\":[{{\"nodes\":[{}]}}]",
+			(0..self.nodes.len()).map(|n| n.to_string()).collect::<Vec<_>>().join(",")));
+		for (key, items) in [
+			("nodes", &self.nodes),
+			("meshes", &self.meshes),
+			("materials", &materials.to_vec()),
+			("textures", &self.textures),
+			("images", &self.images),
+			("accessors", &self.accessors),
+			("bufferViews", &self.buffer_views),
+		] {
+			if !items.is_empty() {
+				json.push_str(&format!(",\"{}\":[{}]", key, items.join(",")));
+			}
+		}
+		if !self.bin.is_empty() {
+			json.push_str(&format!(",\"buffers\":[{{\"byteLength\":{}}}]", self.bin.len()));
+		}
+		json.push('}');
+
+		// The JSON chunk is padded with spaces, the binary one with zeros
+		let mut json = json.into_bytes();
+		while !json.len().is_multiple_of(4) {
+			json.push(b' ');
+		}
+
+		let bin_chunk = if self.bin.is_empty() { 0 } else { 8 + self.bin.len() };
+		let total = 12 + 8 + json.len() + bin_chunk;
+		let total = u32::try_from(total)
+			.map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, "mesh too large for GLB"))?;
+
+		writer.write_all(GLB_MAGIC)?;
+		writer.write_all(&GLB_VERSION.to_le_bytes())?;
+		writer.write_all(&total.to_le_bytes())?;
+
+		writer.write_all(&(json.len() as u32).to_le_bytes())?;
+		writer.write_all(&CHUNK_JSON.to_le_bytes())?;
+		writer.write_all(&json)?;
+
+		if !self.bin.is_empty() {
+			writer.write_all(&(self.bin.len() as u32).to_le_bytes())?;
+			writer.write_all(&CHUNK_BIN.to_le_bytes())?;
+			writer.write_all(&self.bin)?;
+		}
+
+		Ok(())
+	}
+}
+
+/// JSON has no representation for infinities and NaN, which become zero.
+fn json_number(value: f32) -> String {
+	if value.is_finite() { value.to_string() } else { "0".to_string() }
+}
+
+fn json_string(text: &str) -> String {
+	let mut out = String::with_capacity(text.len() + 2);
+	out.push('"');
+	for c in text.chars() {
+		match c {
+			'"' => out.push_str("\\\""),
+			'\\' => out.push_str("\\\\"),
+			'\n' => out.push_str("\\n"),
+			'\r' => out.push_str("\\r"),
+			'\t' => out.push_str("\\t"),
+			c if c < ' ' => out.push_str(&format!("\\u{:04x}", c as u32)),
+			c => out.push(c),
+		}
+	}
+	out.push('"');
+	out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::gltf::{AlphaMode, gltf_to_mesh, load_gltf, parse_gltf_from_bytes};
+    use crate::parser::obj::{obj_to_mesh, parse_obj_from_string};
+
+    const MODEL: &str = "
+v 0 0 0
+v 1 0 0
+v 1 1 0
+v 0 1 0
+v 0 0 1
+o body
+usemtl red
+f 1 2 3 4
+o \"fin\"
+f 1 2 5
+l 1 5
+";
+
+    fn model() -> Mesh {
+        let mut mesh = obj_to_mesh(parse_obj_from_string(MODEL).unwrap());
+        mesh.materials[0].dissolve = 0.5;
+        mesh
+    }
+
+    #[test]
+    fn glb_round_trip() {
+        let mesh = model();
+        let mut bytes = Vec::new();
+        write_glb(&mut bytes, &mesh).unwrap();
+        assert_eq!(bytes.len() % 4, 0);
+
+        let document = parse_gltf_from_bytes(&bytes, Path::new("")).unwrap();
+        assert_eq!(document.materials[0].name, "red");
+        assert_eq!(document.materials[0].base_color_factor, [0.8, 0.8, 0.8, 0.5]);
+        assert_eq!(document.materials[0].alpha_mode, AlphaMode::Blend);
+
+        let reloaded = gltf_to_mesh(&document);
+        let names: Vec<&str> = reloaded.parts.iter().map(|p| p.name.as_str()).collect();
+        assert_eq!(names, ["body", "\"fin\""]);
+        assert_eq!(reloaded.indices.len(), mesh.indices.len());
+        assert_eq!(reloaded.face_materials, mesh.face_materials);
+        assert_eq!(reloaded.line_indices.len(), 2);
+
+        // Same triangles in the same order, by position
+        let corners = |m: &Mesh| -> Vec<[f32; 3]> { m.indices.iter().map(|&i| m.vertices[i as usize].position).collect() };
+        assert_eq!(corners(&reloaded), corners(&mesh));
+        for (a, b) in reloaded.indices.iter().zip(&mesh.indices) {
+            let (a, b) = (&reloaded.vertices[*a as usize], &mesh.vertices[*b as usize]);
+            assert_eq!(a.normal, b.normal);
+            assert_eq!(a.tex_coords, b.tex_coords);
+            assert_eq!(a.color, b.color);
+        }
+    }
+
+    #[test]
+    fn tex_coords_are_written_from_the_top_left() {
+        let mut mesh = model();
+        for vertex in &mut mesh.vertices {
+            vertex.tex_coords = [0.25, 0.125];
+        }
+        let mut bytes = Vec::new();
+        write_glb(&mut bytes, &mesh).unwrap();
+
+        let document = parse_gltf_from_bytes(&bytes, Path::new("")).unwrap();
+        let primitive = &document.meshes[0].primitives[0];
+        assert!(primitive.tex_coords.as_ref().unwrap().iter().all(|&uv| uv == [0.25, 0.875]));
+    }
+
+    #[test]
+    fn saved_file_loads_back() {
+        let mesh = model();
+        let path = std::env::temp_dir().join(format!("scop_write_glb_{}.glb", std::process::id()));
+        save_glb(&mesh, path.to_str().unwrap()).unwrap();
+
+        let reloaded = load_gltf(path.to_str().unwrap()).unwrap();
+        assert_eq!(reloaded.indices.len(), mesh.indices.len());
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn strings_are_escaped() {
+        assert_eq!(json_string("a\"b\\c\n\u{1}"), r#""a\"b\\c\n\u0001""#);
+    }
+}
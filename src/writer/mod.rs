@@ -0,0 +1,96 @@
+pub mod gltf;
+pub mod mtl;
+pub mod obj;
+pub mod ply;
+pub mod stl;
+
+use std::io;
+use std::path::{Component, Path, PathBuf};
+
+use crate::mesh::Mesh;
+
+/// Saves `mesh` in the format named by the extension of `path`, with
+/// default options.
+pub fn save_mesh(mesh: &Mesh, path: &str) -> io::Result<()> {
+	let extension = Path::new(path)
+		.extension()
+		.map(|ext| ext.to_string_lossy().to_lowercase());
+
+	match extension.as_deref() {
+		Some("obj") => obj::save_obj(mesh, path, &obj::ObjWriteOptions::default()),
+		Some("stl") => stl::save_stl(mesh, path),
+		Some("ply") => ply::save_ply(mesh, path, &ply::PlyWriteOptions::default()),
+		Some("glb") => gltf::save_glb(mesh, path),
+		_ => Err(io::Error::new(io::ErrorKind::InvalidInput, format!("no writer for {}", path))),
+	}
+}
+
+/// Formats `value` with at most `precision` decimals, without trailing zeros.
+pub(crate) fn format_float(value: f32, precision: usize) -> String {
+	let mut text = format!("{:.*}", precision, value);
+
+	if text.contains('.') {
+		let trimmed = text.trim_end_matches('0').trim_end_matches('.').len();
+		text.truncate(trimmed);
+	}
+
+	if text == "-0" {
+		text.remove(0);
+	}
+
+	text
+}
+
+/// Expresses `path` relative to `base_dir`, going up with `..` as needed.
+///
+/// Both are made absolute against the working directory first. Paths on
+/// different roots are returned absolute.
+pub(crate) fn relative_path(path: &str, base_dir: &Path) -> String {
+	let (Ok(path), Ok(base)) = (std::path::absolute(path), std::path::absolute(base_dir)) else {
+		return path.to_string();
+	};
+
+	let path: Vec<Component> = path.components().collect();
+	let base: Vec<Component> = base.components().collect();
+
+	if path.first() != base.first() {
+		return path.iter().collect::<PathBuf>().to_string_lossy().into_owned();
+	}
+
+	let common = path.iter().zip(&base).take_while(|(a, b)| a == b).count();
+
+	let mut relative = PathBuf::new();
+	for _ in common..base.len() {
+		relative.push("..");
+	}
+	relative.extend(&path[common..]);
+
+	// OBJ and MTL files use forward slashes on every platform
+	relative.to_string_lossy().replace('\\', "/")
+}
+
+/// Makes `path` absolute against the working directory.
+pub(crate) fn absolute_path(path: &str) -> String {
+	std::path::absolute(path)
+		.map(|p| p.to_string_lossy().into_owned())
+		.unwrap_or_else(|_| path.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn floats_are_trimmed() {
+        assert_eq!(format_float(1.0, 6), "1");
+        assert_eq!(format_float(0.126, 2), "0.13");
+        assert_eq!(format_float(-0.00001, 3), "0");
+        assert_eq!(format_float(-2.5, 4), "-2.5");
+    }
+
+    #[test]
+    fn paths_are_made_relative() {
+        assert_eq!(relative_path("/data/models/tex/a.png", Path::new("/data/models")), "tex/a.png");
+        assert_eq!(relative_path("/data/textures/a.png", Path::new("/data/models")), "../textures/a.png");
+    }
+}
@@ -0,0 +1,86 @@
+use std::io::{self, Write};
+use std::path::Path;
+
+use crate::parser::mtl::Material;
+use crate::writer::{absolute_path, format_float, relative_path};
+
+/// Writes `materials` as an MTL library.
+///
+/// Texture paths are written relative to `texture_dir` when given, which
+/// should be the directory the library is saved in, and absolute otherwise.
+pub fn write_mtl<W: Write>(
+	writer: &mut W,
+	materials: &[Material],
+	precision: usize,
+	texture_dir: Option<&Path>,
+) -> io::Result<()> {
+	let color = |c: [f32; 3]| {
+		format!(
+			"{} {} {}",
+			format_float(c[0], precision),
+			format_float(c[1], precision),
+			format_float(c[2], precision),
+		)
+	};
+
+	for (i, material) in materials.iter().enumerate() {
+		if i > 0 {
+			writeln!(writer)?;
+		}
+
+		writeln!(writer, "newmtl {}", material.name)?;
+		writeln!(writer, "Ka {}", color(material.ambient))?;
+		writeln!(writer, "Kd {}", color(material.diffuse))?;
+		writeln!(writer, "Ks {}", color(material.specular))?;
+		writeln!(writer, "Ns {}", format_float(material.shininess, precision))?;
+		writeln!(writer, "d {}", format_float(material.dissolve, precision))?;
+		writeln!(writer, "illum {}", material.illum)?;
+
+		let maps = [
+			("map_Ka", &material.ambient_map),
+			("map_Kd", &material.diffuse_map),
+			("map_Ks", &material.specular_map),
+			("map_Ns", &material.shininess_map),
+			("map_d", &material.dissolve_map),
+			("map_Bump", &material.bump_map),
+		];
+
+		for (statement, map) in maps {
+			if let Some(map) = map {
+				let path = match texture_dir {
+					Some(dir) => relative_path(map, dir),
+					None => absolute_path(map),
+				};
+				writeln!(writer, "{} {}", statement, path)?;
+			}
+		}
+	}
+
+	Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::mtl::parse_mtl_from_string;
+
+    #[test]
+    fn materials_round_trip() {
+        let mut red = Material::new("Red");
+        red.diffuse = [0.9, 0.1, 0.1];
+        red.shininess = 32.0;
+        red.dissolve = 0.5;
+        red.illum = 2;
+        red.diffuse_map = Some("/assets/textures/red.png".to_string());
+
+        let mut out = Vec::new();
+        write_mtl(&mut out, &[red.clone(), Material::new("Plain")], 6, Some(Path::new("/assets/models"))).unwrap();
+        let text = String::from_utf8(out).unwrap();
+        assert!(text.contains("map_Kd ../textures/red.png"));
+
+        let materials = parse_mtl_from_string(&text, &mut Vec::new()).unwrap();
+        assert_eq!(materials.len(), 2);
+        assert_eq!(materials[0], Material { diffuse_map: Some("../textures/red.png".to_string()), ..red });
+        assert_eq!(materials[1], Material::new("Plain"));
+    }
+}
@@ -0,0 +1,349 @@
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+
+use crate::mesh::{Mesh, MeshPart};
+use crate::writer::{absolute_path, format_float, mtl};
+
+/// Options controlling how a `Mesh` is written as OBJ.
+#[derive(Debug, Clone)]
+pub struct ObjWriteOptions {
+	/// Maximum number of decimals written for coordinates and colors.
+	pub precision: usize,
+	/// Write each distinct position, texture coordinate and normal once and
+	/// share it between faces, instead of one of each per mesh vertex.
+	pub deduplicate: bool,
+	/// Reference the material library and textures relative to the written
+	/// file, instead of by absolute path.
+	pub relative_paths: bool,
+	/// Append each vertex color to its `v` line, as `v x y z r g b`.
+	pub vertex_colors: bool,
+}
+
+impl Default for ObjWriteOptions {
+	fn default() -> Self {
+		Self {
+			precision: 6,
+			deduplicate: true,
+			relative_paths: true,
+			vertex_colors: false,
+		}
+	}
+}
+
+/// Saves `mesh` to `path`, with its materials in an MTL library of the same
+/// name next to it.
+pub fn save_obj(mesh: &Mesh, path: &str, options: &ObjWriteOptions) -> io::Result<()> {
+	let path = Path::new(path);
+	let dir = match path.parent() {
+		Some(dir) if !dir.as_os_str().is_empty() => dir,
+		_ => Path::new("."),
+	};
+
+	let mut mtllib = None;
+
+	if !mesh.materials.is_empty() {
+		let mtl_path = path.with_extension("mtl");
+		let texture_dir = options.relative_paths.then_some(dir);
+
+		let mut file = BufWriter::new(File::create(&mtl_path)?);
+		mtl::write_mtl(&mut file, &mesh.materials, options.precision, texture_dir)?;
+		file.flush()?;
+
+		let mtl_path = mtl_path.to_string_lossy();
+		mtllib = Some(if options.relative_paths {
+			mtl_path.rsplit(['/', '\\']).next().unwrap_or(&mtl_path).to_string()
+		} else {
+			absolute_path(&mtl_path)
+		});
+	}
+
+	let mut file = BufWriter::new(File::create(path)?);
+	write_obj(&mut file, mesh, mtllib.as_deref(), options)?;
+	file.flush()
+}
+
+/// Writes `mesh` as OBJ text, referencing `mtllib` for its materials.
+///
+/// Parts become `o`/`g` statements, a part named `object/group` being written
+/// as group `group` of object `object`, so the parser gives back the same
+/// names. Triangles following a part but outside any go to an object named
+/// `default`. OBJ cannot clear the current material, so triangles without one
+/// keep the previous material.
+pub fn write_obj<W: Write>(
+	writer: &mut W,
+	mesh: &Mesh,
+	mtllib: Option<&str>,
+	options: &ObjWriteOptions,
+) -> io::Result<()> {
+	writeln!(writer, "# {} vertices, {} triangles", mesh.vertices.len(), mesh.indices.len() / 3)?;
+	if let Some(mtllib) = mtllib {
+		writeln!(writer, "mtllib {}", mtllib)?;
+	}
+
+	let positions = if options.vertex_colors {
+		let colored = mesh.vertices.iter().map(|v| {
+			let (p, c) = (v.position, v.color);
+			[p[0], p[1], p[2], c[0], c[1], c[2]]
+		});
+		write_elements(writer, "v", colored, options)?
+	} else {
+		write_elements(writer, "v", mesh.vertices.iter().map(|v| v.position), options)?
+	};
+	let tex_coords = write_elements(writer, "vt", mesh.vertices.iter().map(|v| v.tex_coords), options)?;
+	let normals = write_elements(writer, "vn", mesh.vertices.iter().map(|v| v.normal), options)?;
+
+	let part_starts: HashMap<u32, &MeshPart> = mesh.parts.iter()
+		.filter(|p| p.index_count > 0)
+		.map(|p| (p.first_index, p))
+		.collect();
+	let mut part_end = None;
+	let mut current_object = None;
+	let mut current_material = None;
+
+	for (triangle, corners) in mesh.indices.chunks_exact(3).enumerate() {
+		let first_index = (triangle * 3) as u32;
+
+		if let Some(part) = part_starts.get(&first_index) {
+			write_part(writer, &part.name, &mut current_object)?;
+			part_end = Some(part.first_index + part.index_count);
+		} else if part_end == Some(first_index) {
+			write_part(writer, "default", &mut current_object)?;
+			part_end = None;
+		}
+
+		let material = mesh.face_materials.get(triangle).copied().flatten();
+		if material.is_some() && material != current_material {
+			current_material = material;
+			if let Some(material) = material.and_then(|m| mesh.materials.get(m)) {
+				writeln!(writer, "usemtl {}", material.name)?;
+			}
+		}
+
+		write!(writer, "f")?;
+		for &corner in corners {
+			let corner = corner as usize;
+			write!(writer, " {}/{}/{}", positions[corner], tex_coords[corner], normals[corner])?;
+		}
+		writeln!(writer)?;
+	}
+
+	write_polylines(writer, &mesh.line_indices, &positions)?;
+
+	if !mesh.point_indices.is_empty() {
+		write!(writer, "p")?;
+		for &vertex in &mesh.point_indices {
+			write!(writer, " {}", positions[vertex as usize])?;
+		}
+		writeln!(writer)?;
+	}
+
+	Ok(())
+}
+
+/// Writes line segments as `l` statements, joining segments that continue
+/// one another into a single polyline.
+fn write_polylines<W: Write>(writer: &mut W, line_indices: &[u32], positions: &[usize]) -> io::Result<()> {
+	let mut last = None;
+
+	for segment in line_indices.chunks_exact(2) {
+		let (start, end) = (segment[0] as usize, segment[1] as usize);
+
+		if last != Some(start) {
+			if last.is_some() {
+				writeln!(writer)?;
+			}
+			write!(writer, "l {}", positions[start])?;
+		}
+		write!(writer, " {}", positions[end])?;
+		last = Some(end);
+	}
+
+	if last.is_some() {
+		writeln!(writer)?;
+	}
+
+	Ok(())
+}
+
+/// Writes one `directive` line per value, or per distinct value when
+/// deduplicating, and returns the 1-based OBJ index of each value.
+///
+/// Values are compared once formatted, so values equal at the output
+/// precision are merged too.
+fn write_elements<W: Write, V: AsRef<[f32]>>(
+	writer: &mut W,
+	directive: &str,
+	values: impl ExactSizeIterator<Item = V>,
+	options: &ObjWriteOptions,
+) -> io::Result<Vec<usize>> {
+	let mut ids = Vec::with_capacity(values.len());
+	let mut seen: HashMap<String, usize> = HashMap::new();
+
+	for value in values {
+		let mut line = directive.to_string();
+		for &component in value.as_ref() {
+			line.push(' ');
+			line.push_str(&format_float(component, options.precision));
+		}
+
+		if options.deduplicate {
+			if let Some(&id) = seen.get(&line) {
+				ids.push(id);
+				continue;
+			}
+			seen.insert(line.clone(), seen.len() + 1);
+		}
+
+		writeln!(writer, "{}", line)?;
+		ids.push(if options.deduplicate { seen.len() } else { ids.len() + 1 });
+	}
+
+	Ok(ids)
+}
+
+/// Writes the `o`/`g` statements opening the part named `name`.
+fn write_part<W: Write>(writer: &mut W, name: &str, current_object: &mut Option<String>) -> io::Result<()> {
+	match name.split_once('/') {
+		Some((object, group)) => {
+			if current_object.as_deref() != Some(object) {
+				writeln!(writer, "o {}", object)?;
+				*current_object = Some(object.to_string());
+			}
+			writeln!(writer, "g {}", group)
+		}
+		None => {
+			writeln!(writer, "o {}", name)?;
+			*current_object = Some(name.to_string());
+			Ok(())
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::obj::{load_obj, obj_to_mesh, parse_obj_from_string};
+
+    const MODEL: &str = "
+v 0.0 0.0 0.0
+v 1.0 0.0 0.0
+v 1.0 1.0 0.0
+v 0.0 1.0 0.0
+v 0.5 0.5 1.0
+vt 0.0 0.0
+vt 1.0 0.0
+vt 1.0 1.0
+vt 0.0 1.0
+vn 0.0 0.0 1.0
+o Base
+usemtl Stone
+f 1/1/1 2/2/1 3/3/1 4/4/1
+o Roof
+g North
+usemtl Slate
+f 3/3/1 4/4/1 5/1/1
+g South
+f 1/1/1 2/2/1 5/3/1
+";
+
+    fn write_to_string(mesh: &Mesh, options: &ObjWriteOptions) -> String {
+        let mut out = Vec::new();
+        write_obj(&mut out, mesh, None, options).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn mesh_round_trips_through_parser() {
+        let mesh = obj_to_mesh(parse_obj_from_string(MODEL).unwrap());
+        let text = write_to_string(&mesh, &ObjWriteOptions::default());
+        let reloaded = obj_to_mesh(parse_obj_from_string(&text).unwrap());
+
+        assert_eq!(reloaded.indices, mesh.indices);
+        assert_eq!(reloaded.face_materials, mesh.face_materials);
+        assert_eq!(reloaded.vertices.len(), mesh.vertices.len());
+        for (a, b) in reloaded.vertices.iter().zip(&mesh.vertices) {
+            assert_eq!(a.position, b.position);
+            assert_eq!(a.tex_coords, b.tex_coords);
+            assert_eq!(a.normal, b.normal);
+        }
+
+        let names = |m: &Mesh| m.parts.iter().map(|p| (p.name.clone(), p.first_index, p.index_count)).collect::<Vec<_>>();
+        assert_eq!(names(&reloaded), names(&mesh));
+
+        let materials = |m: &Mesh| m.materials.iter().map(|m| m.name.clone()).collect::<Vec<_>>();
+        assert_eq!(materials(&reloaded), materials(&mesh));
+    }
+
+    #[test]
+    fn deduplication_shares_elements() {
+        let mesh = obj_to_mesh(parse_obj_from_string(MODEL).unwrap());
+        let count = |text: &str, directive: &str| text.lines().filter(|l| l.starts_with(directive)).count();
+
+        let shared = write_to_string(&mesh, &ObjWriteOptions::default());
+        assert_eq!(count(&shared, "v "), 5);
+        assert_eq!(count(&shared, "vn "), 1);
+
+        let options = ObjWriteOptions { deduplicate: false, ..Default::default() };
+        let full = write_to_string(&mesh, &options);
+        assert_eq!(count(&full, "v "), mesh.vertices.len());
+        assert_eq!(count(&full, "vn "), mesh.vertices.len());
+    }
+
+    #[test]
+    fn vertex_colors_round_trip() {
+        let mesh = obj_to_mesh(parse_obj_from_string("v 0 0 0 1 0 0\nv 1 0 0 0 1 0\nv 0 1 0 0 0 1\nf 1 2 3\n").unwrap());
+        let options = ObjWriteOptions { vertex_colors: true, ..Default::default() };
+        let reloaded = obj_to_mesh(parse_obj_from_string(&write_to_string(&mesh, &options)).unwrap());
+
+        let colors = |m: &Mesh| m.vertices.iter().map(|v| v.color).collect::<Vec<_>>();
+        assert_eq!(colors(&reloaded), colors(&mesh));
+        assert_eq!(colors(&mesh)[2], [0.0, 0.0, 1.0]);
+    }
+
+    #[test]
+    fn lines_and_points_round_trip() {
+        let content = "v 0 0 0\nv 1 0 0\nv 1 1 0\nv 0 1 0\nf 1 2 3\nl 1 2 3\nl 4 1\np 3 4\n";
+        let mesh = obj_to_mesh(parse_obj_from_string(content).unwrap());
+        let text = write_to_string(&mesh, &ObjWriteOptions::default());
+        assert!(text.contains("\nl 1 2 3\nl 4 1\np 3 4\n"), "{}", text);
+
+        let reloaded = obj_to_mesh(parse_obj_from_string(&text).unwrap());
+        assert_eq!(reloaded.line_indices.len(), mesh.line_indices.len());
+        let positions = |m: &Mesh, indices: &[u32]| indices.iter().map(|&i| m.vertices[i as usize].position).collect::<Vec<_>>();
+        assert_eq!(positions(&reloaded, &reloaded.line_indices), positions(&mesh, &mesh.line_indices));
+        assert_eq!(positions(&reloaded, &reloaded.point_indices), positions(&mesh, &mesh.point_indices));
+    }
+
+    #[test]
+    fn precision_limits_decimals() {
+        let mesh = obj_to_mesh(parse_obj_from_string("v 0.123456 0 0\nv 1 0 0\nv 0 1 0\nf 1 2 3\n").unwrap());
+        let options = ObjWriteOptions { precision: 2, ..Default::default() };
+        assert!(write_to_string(&mesh, &options).contains("v 0.12 0 0\n"));
+    }
+
+    #[test]
+    fn saved_model_keeps_materials_and_textures() {
+        let dir = std::env::temp_dir().join(format!("scop_write_obj_{}", std::process::id()));
+        std::fs::create_dir_all(dir.join("in")).unwrap();
+        std::fs::write(dir.join("in/model.mtl"), "newmtl Stone\nKd 0.5 0.5 0.5\nmap_Kd stone.png\n").unwrap();
+        std::fs::write(dir.join("in/model.obj"), format!("mtllib model.mtl\n{}", MODEL)).unwrap();
+        std::fs::write(dir.join("in/stone.png"), []).unwrap();
+
+        let mesh = load_obj(dir.join("in/model.obj").to_str().unwrap()).unwrap();
+        std::fs::create_dir_all(dir.join("out")).unwrap();
+        save_obj(&mesh, dir.join("out/copy.obj").to_str().unwrap(), &ObjWriteOptions::default()).unwrap();
+
+        let mtl = std::fs::read_to_string(dir.join("out/copy.mtl")).unwrap();
+        let reloaded = load_obj(dir.join("out/copy.obj").to_str().unwrap()).unwrap();
+        let texture = reloaded.materials[0].diffuse_map.as_deref().unwrap();
+        let same_texture = std::fs::canonicalize(texture).unwrap() == std::fs::canonicalize(dir.join("in/stone.png")).unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert!(mtl.contains("map_Kd ../in/stone.png"));
+        assert_eq!(reloaded.materials[0].diffuse, [0.5, 0.5, 0.5]);
+        assert_eq!(reloaded.vertices[0].color, mesh.vertices[0].color);
+        assert!(same_texture);
+    }
+}
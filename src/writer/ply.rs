@@ -0,0 +1,197 @@
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+
+use crate::mesh::Mesh;
+use crate::parser::ply::PlyFormat;
+use crate::writer::format_float;
+
+/// Options controlling how a `Mesh` is written as PLY.
+#[derive(Debug, Clone)]
+pub struct PlyWriteOptions {
+	pub format: PlyFormat,
+	/// Maximum number of decimals of ASCII values.
+	pub precision: usize,
+}
+
+impl Default for PlyWriteOptions {
+	fn default() -> Self {
+		Self {
+			format: PlyFormat::BinaryLittleEndian,
+			precision: 6,
+		}
+	}
+}
+
+pub fn save_ply(mesh: &Mesh, path: &str, options: &PlyWriteOptions) -> io::Result<()> {
+	let mut file = BufWriter::new(File::create(path)?);
+	write_ply(&mut file, mesh, options)?;
+	file.flush()
+}
+
+/// One value of a PLY element line.
+enum Value {
+	Float(f32),
+	UChar(u8),
+	Int(u32),
+}
+
+/// Writes `mesh` as PLY.
+///
+/// Vertices carry position, normal, 8-bit color, texture coordinates and
+/// every mesh attribute as a float property. Triangles become the `face`
+/// element and line segments the `edge` element; a mesh with neither is a
+/// point cloud. PLY has no materials, so those are dropped.
+pub fn write_ply<W: Write>(writer: &mut W, mesh: &Mesh, options: &PlyWriteOptions) -> io::Result<()> {
+	let format = match options.format {
+		PlyFormat::Ascii => "ascii",
+		PlyFormat::BinaryLittleEndian => "binary_little_endian",
+		PlyFormat::BinaryBigEndian => "binary_big_endian",
+	};
+
+	writeln!(writer, "ply")?;
+	writeln!(writer, "format {} 1.0", format)?;
+	writeln!(writer, "comment written by scop")?;
+	writeln!(writer, "element vertex {}", mesh.vertices.len())?;
+	for name in ["x", "y", "z", "nx", "ny", "nz"] {
+		writeln!(writer, "property float {}", name)?;
+	}
+	for name in ["red", "green", "blue"] {
+		writeln!(writer, "property uchar {}", name)?;
+	}
+	for name in ["u", "v"] {
+		writeln!(writer, "property float {}", name)?;
+	}
+	for attribute in &mesh.attributes {
+		writeln!(writer, "property float {}", attribute.name)?;
+	}
+	if !mesh.indices.is_empty() {
+		writeln!(writer, "element face {}", mesh.indices.len() / 3)?;
+		writeln!(writer, "property list uchar int vertex_indices")?;
+	}
+	if !mesh.line_indices.is_empty() {
+		writeln!(writer, "element edge {}", mesh.line_indices.len() / 2)?;
+		writeln!(writer, "property int vertex1")?;
+		writeln!(writer, "property int vertex2")?;
+	}
+	writeln!(writer, "end_header")?;
+
+	let mut element = Vec::new();
+	for (i, vertex) in mesh.vertices.iter().enumerate() {
+		element.clear();
+		element.extend(vertex.position.iter().chain(&vertex.normal).map(|&v| Value::Float(v)));
+		element.extend(vertex.color.iter().map(|&c| Value::UChar((c.clamp(0.0, 1.0) * 255.0).round() as u8)));
+		element.extend(vertex.tex_coords.iter().map(|&t| Value::Float(t)));
+		element.extend(mesh.attributes.iter().map(|a| Value::Float(a.values.get(i).copied().unwrap_or(0.0))));
+		write_element(writer, &element, options)?;
+	}
+
+	for triangle in mesh.indices.chunks_exact(3) {
+		element.clear();
+		element.push(Value::UChar(3));
+		element.extend(triangle.iter().map(|&i| Value::Int(i)));
+		write_element(writer, &element, options)?;
+	}
+
+	for segment in mesh.line_indices.chunks_exact(2) {
+		element.clear();
+		element.extend(segment.iter().map(|&i| Value::Int(i)));
+		write_element(writer, &element, options)?;
+	}
+
+	Ok(())
+}
+
+fn write_element<W: Write>(writer: &mut W, values: &[Value], options: &PlyWriteOptions) -> io::Result<()> {
+	let big_endian = options.format == PlyFormat::BinaryBigEndian;
+
+	if options.format == PlyFormat::Ascii {
+		let line: Vec<String> = values.iter()
+			.map(|value| match value {
+				Value::Float(v) => format_float(*v, options.precision),
+				Value::UChar(v) => v.to_string(),
+				Value::Int(v) => v.to_string(),
+			})
+			.collect();
+		return writeln!(writer, "{}", line.join(" "));
+	}
+
+	for value in values {
+		match *value {
+			Value::Float(v) if big_endian => writer.write_all(&v.to_be_bytes())?,
+			Value::Float(v) => writer.write_all(&v.to_le_bytes())?,
+			Value::UChar(v) => writer.write_all(&[v])?,
+			Value::Int(v) if big_endian => writer.write_all(&(v as i32).to_be_bytes())?,
+			Value::Int(v) => writer.write_all(&(v as i32).to_le_bytes())?,
+		}
+	}
+
+	Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mesh::{Vertex, VertexAttribute};
+    use crate::parser::obj::{obj_to_mesh, parse_obj_from_string};
+    use crate::parser::ply::{load_ply, parse_ply_from_bytes, ply_to_mesh};
+
+    const MODEL: &str = "
+v 0 0 0 1 0 0
+v 1 0 0 0 1 0
+v 1 1 0 0 0 1
+v 0 1 0 1 1 1
+vt 0 0
+vt 1 0
+vt 1 1
+vt 0 1
+f 1/1 2/2 3/3 4/4
+l 1 3
+";
+
+    fn assert_same_vertices(a: &[Vertex], b: &[Vertex]) {
+        assert_eq!(a.len(), b.len());
+        for (a, b) in a.iter().zip(b) {
+            assert_eq!(a.position, b.position);
+            assert_eq!(a.normal, b.normal);
+            assert_eq!(a.tex_coords, b.tex_coords);
+            for k in 0..3 {
+                assert!((a.color[k] - b.color[k]).abs() <= 0.5 / 255.0, "{:?} != {:?}", a.color, b.color);
+            }
+        }
+    }
+
+    #[test]
+    fn every_format_round_trips() {
+        let mut mesh = obj_to_mesh(parse_obj_from_string(MODEL).unwrap());
+        let values = (0..mesh.vertices.len()).map(|i| i as f32 * 0.5).collect();
+        mesh.attributes.push(VertexAttribute { name: "confidence".to_string(), values });
+
+        for format in [PlyFormat::Ascii, PlyFormat::BinaryLittleEndian, PlyFormat::BinaryBigEndian] {
+            let mut bytes = Vec::new();
+            write_ply(&mut bytes, &mesh, &PlyWriteOptions { format, ..Default::default() }).unwrap();
+
+            let ply = parse_ply_from_bytes(&bytes).unwrap();
+            assert_eq!(ply.format, format);
+            let reloaded = ply_to_mesh(&ply);
+
+            assert_same_vertices(&reloaded.vertices, &mesh.vertices);
+            assert_eq!(reloaded.indices, mesh.indices);
+            assert_eq!(reloaded.line_indices, mesh.line_indices);
+            assert_eq!(reloaded.attributes, mesh.attributes);
+        }
+    }
+
+    #[test]
+    fn point_cloud_is_saved_without_faces() {
+        let mesh = obj_to_mesh(parse_obj_from_string("v 0 0 0\nv 1 2 3\np 1 2\n").unwrap());
+        let path = std::env::temp_dir().join(format!("scop_write_ply_{}.ply", std::process::id()));
+        save_ply(&mesh, path.to_str().unwrap(), &PlyWriteOptions::default()).unwrap();
+
+        let reloaded = load_ply(path.to_str().unwrap()).unwrap();
+        assert!(reloaded.indices.is_empty());
+        assert_eq!(reloaded.point_indices, vec![0, 1]);
+        assert_eq!(reloaded.vertices[1].position, [1.0, 2.0, 3.0]);
+
+        std::fs::remove_file(&path).unwrap();
+    }
+}
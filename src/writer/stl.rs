@@ -0,0 +1,100 @@
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+
+use crate::mesh::Mesh;
+
+/// Text at the start of the 80-byte header. It must not begin with `solid`,
+/// which readers take as the mark of an ASCII file.
+const HEADER_TEXT: &[u8] = b"Binary STL written by scop";
+
+pub fn save_stl(mesh: &Mesh, path: &str) -> io::Result<()> {
+	let mut file = BufWriter::new(File::create(path)?);
+	write_stl(&mut file, mesh)?;
+	file.flush()
+}
+
+/// Writes the triangles of `mesh` as binary STL.
+///
+/// STL only stores geometry: each triangle gets its face normal, and colors,
+/// texture coordinates, materials, lines and points are dropped.
+pub fn write_stl<W: Write>(writer: &mut W, mesh: &Mesh) -> io::Result<()> {
+	let triangle_count = u32::try_from(mesh.indices.len() / 3)
+		.map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, "too many triangles for STL"))?;
+
+	let mut header = [0u8; 80];
+	header[..HEADER_TEXT.len()].copy_from_slice(HEADER_TEXT);
+	writer.write_all(&header)?;
+	writer.write_all(&triangle_count.to_le_bytes())?;
+
+	for triangle in mesh.indices.chunks_exact(3) {
+		let corners = [0, 1, 2].map(|k| mesh.vertices[triangle[k] as usize].position);
+
+		for value in face_normal(corners).into_iter().chain(corners.into_iter().flatten()) {
+			writer.write_all(&value.to_le_bytes())?;
+		}
+		writer.write_all(&0_u16.to_le_bytes())?;
+	}
+
+	Ok(())
+}
+
+/// Unit normal of a counter-clockwise triangle, zero when degenerate.
+fn face_normal([a, b, c]: [[f32; 3]; 3]) -> [f32; 3] {
+	let e1 = [b[0] - a[0], b[1] - a[1], b[2] - a[2]];
+	let e2 = [c[0] - a[0], c[1] - a[1], c[2] - a[2]];
+	let n = [
+		e1[1] * e2[2] - e1[2] * e2[1],
+		e1[2] * e2[0] - e1[0] * e2[2],
+		e1[0] * e2[1] - e1[1] * e2[0],
+	];
+	let len = (n[0] * n[0] + n[1] * n[1] + n[2] * n[2]).sqrt();
+
+	if len > 1e-12 { [n[0] / len, n[1] / len, n[2] / len] } else { [0.0; 3] }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::obj::{obj_to_mesh, parse_obj_from_string};
+    use crate::parser::stl::{load_stl, parse_stl_from_bytes};
+
+    const MODEL: &str = "
+v 0 0 0
+v 1 0 0
+v 1 1 0
+v 0 1 0
+v 0 0 1
+f 1 2 3 4
+f 1 2 5
+";
+
+    #[test]
+    fn binary_stl_round_trip() {
+        let mesh = obj_to_mesh(parse_obj_from_string(MODEL).unwrap());
+        let mut bytes = Vec::new();
+        write_stl(&mut bytes, &mesh).unwrap();
+
+        assert_eq!(bytes.len(), 84 + 3 * 50);
+        let stl = parse_stl_from_bytes(&bytes).unwrap();
+        assert_eq!(stl.facets.len(), 3);
+        for (facet, triangle) in stl.facets.iter().zip(mesh.indices.chunks_exact(3)) {
+            let expected = [0, 1, 2].map(|k| mesh.vertices[triangle[k] as usize].position);
+            assert_eq!(facet.vertices, expected);
+        }
+        assert_eq!(stl.facets[0].normal, [0.0, 0.0, 1.0]);
+        assert_eq!(stl.facets[2].normal, [0.0, -1.0, 0.0]);
+    }
+
+    #[test]
+    fn saved_file_loads_back_welded() {
+        let mesh = obj_to_mesh(parse_obj_from_string(MODEL).unwrap());
+        let path = std::env::temp_dir().join(format!("scop_write_stl_{}.stl", std::process::id()));
+        save_stl(&mesh, path.to_str().unwrap()).unwrap();
+
+        let reloaded = load_stl(path.to_str().unwrap()).unwrap();
+        assert_eq!(reloaded.indices.len(), 9);
+        assert!(reloaded.vertices.len() < 9);
+
+        std::fs::remove_file(&path).unwrap();
+    }
+}